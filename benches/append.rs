@@ -0,0 +1,35 @@
+//! Appends many small chunks at increasing addresses, the pattern of trace recorders.
+//!
+//! Run with `cargo bench --bench append`.
+
+use std::time::Instant;
+
+use sparse_vec::SparseVec;
+
+const APPENDS: u64 = 1_000_000;
+const CHUNK: u64 = 16;
+
+fn chunk(i: u64) -> Vec<u8> {
+    Vec::from_iter((0..CHUNK).map(|j| (i * CHUNK + j) as u8))
+}
+
+fn main() {
+    let start = Instant::now();
+    let mut appended = SparseVec::new();
+    // An unrelated block below keeps the appended one from being the only block
+    appended.insert(vec![0xff; 16], 0);
+    for i in 0..APPENDS {
+        appended.insert(chunk(i), 0x1000 + i * CHUNK);
+    }
+    let append_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut inserted = SparseVec::new();
+    inserted.insert(vec![0xff; 16], 0);
+    inserted.insert(Vec::from_iter((0..APPENDS).flat_map(chunk)), 0x1000);
+    let insert_time = start.elapsed();
+
+    assert!(appended.blocks().eq(inserted.blocks()));
+    println!("{APPENDS} appends: {append_time:?}");
+    println!("single insert:  {insert_time:?}");
+}
@@ -0,0 +1,35 @@
+//! Compares `SparseVecBuilder` with inserting the same sorted blocks one by one.
+//!
+//! Run with `cargo bench --bench builder`.
+
+use std::time::Instant;
+
+use sparse_vec::{SparseVec, SparseVecBuilder};
+
+const BLOCKS: u64 = 5000;
+
+fn blocks() -> impl Iterator<Item = (u64, Vec<u8>)> {
+    // Every third block is adjacent to the previous one
+    (0..BLOCKS).map(|i| (i * 64 - (i % 3 == 2) as u64 * 32, vec![i as u8; 32]))
+}
+
+fn main() {
+    let start = Instant::now();
+    let mut inserted = SparseVec::new();
+    for (addr, data) in blocks() {
+        inserted.insert(data, addr);
+    }
+    let insert_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut builder = SparseVecBuilder::new();
+    for (addr, data) in blocks() {
+        builder.push(addr, data).unwrap();
+    }
+    let built = builder.finish();
+    let build_time = start.elapsed();
+
+    assert!(built.blocks().eq(inserted.blocks()));
+    println!("insert:  {insert_time:?}");
+    println!("builder: {build_time:?}");
+}
@@ -0,0 +1,41 @@
+//! Compares `SparseVec::gather` with calling `get` for every range, for many lookups
+//! clustered in a few blocks.
+//!
+//! Run with `cargo bench --bench gather`.
+
+use std::time::Instant;
+
+use sparse_vec::SparseVec;
+
+const BLOCKS: u64 = 1000;
+const LOOKUPS: u64 = 1_000_000;
+
+fn main() {
+    let mut map = SparseVec::new();
+    for i in 0..BLOCKS {
+        map.insert(vec![i as u8; 0x1000], i * 0x2000);
+    }
+    // Pointers into 8 hot blocks, in no particular order
+    let ranges = Vec::from_iter((0..LOOKUPS).map(|i| {
+        let addr = (i * 7919 % 8) * 0x2000 * 97 % (BLOCKS * 0x2000) + i * 31 % 0x1000;
+        addr..addr + 8
+    }));
+
+    let start = Instant::now();
+    let naive = Vec::from_iter(ranges.iter().map(|range| map.get(range.clone())));
+    let get_time = start.elapsed();
+
+    let start = Instant::now();
+    let gathered = map.gather(&ranges);
+    let gather_time = start.elapsed();
+
+    let start = Instant::now();
+    let found = map.gather_iter(ranges.iter().cloned()).flatten().count();
+    let iter_time = start.elapsed();
+
+    assert_eq!(gathered, naive);
+    assert_eq!(found, naive.iter().flatten().count());
+    println!("get:         {get_time:?}");
+    println!("gather:      {gather_time:?}");
+    println!("gather_iter: {iter_time:?}");
+}
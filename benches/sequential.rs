@@ -0,0 +1,53 @@
+//! Sequential 16 byte reads over a large block among many small ones, as when disassembling
+//! or hashing an image, next to random reads over the small blocks. Consecutive sequential
+//! reads hit the same block, so only the first one searches the block map. Random reads
+//! mostly miss and pay for checking the last hit first.
+//!
+//! Run with `cargo bench --bench sequential`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use sparse_vec::SparseVec;
+
+const PAGES: u64 = 4096;
+const PAGE: u64 = 0x1000;
+const LARGE: u64 = 0x100_0000;
+const READ: u64 = 16;
+const PASSES: usize = 8;
+
+fn main() {
+    let mut map = SparseVec::new();
+    // Every other page, so that no pages merge, then the large block past them
+    for page in 0..PAGES {
+        map.insert(vec![page as u8; PAGE as usize], page * 2 * PAGE);
+    }
+    let base = PAGES * 2 * PAGE;
+    map.insert(Vec::from_iter((0..LARGE).map(|i| i as u8)), base);
+    let reads = PASSES * (LARGE / READ) as usize;
+
+    let start = Instant::now();
+    let mut sequential = 0u64;
+    for _ in 0..PASSES {
+        for addr in (base..base + LARGE).step_by(READ as usize) {
+            sequential += map.get(addr..addr + READ).unwrap()[0] as u64;
+        }
+    }
+    let sequential_time = start.elapsed();
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(189);
+    let addrs = Vec::from_iter(
+        (0..reads).map(|_| rng.gen_range(0..PAGES) * 2 * PAGE + rng.gen_range(0..PAGE - READ)),
+    );
+    let start = Instant::now();
+    let mut random = 0u64;
+    for &addr in &addrs {
+        random += map.get(addr..addr + READ).unwrap()[0] as u64;
+    }
+    let random_time = start.elapsed();
+
+    black_box((sequential, random));
+    println!("{reads} sequential reads: {sequential_time:?}");
+    println!("{reads} random reads:     {random_time:?}");
+}
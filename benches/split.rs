@@ -0,0 +1,27 @@
+//! Inserts that split existing blocks, which makes most of the block map lookups.
+//!
+//! Run with `cargo bench --bench split`.
+
+use std::time::Instant;
+
+use sparse_vec::SparseVec;
+
+const BLOCKS: u64 = 2000;
+const INSERTS: u64 = 20_000;
+
+fn main() {
+    let mut map = SparseVec::new();
+    for i in 0..BLOCKS {
+        map.insert(vec![0u8; 0x100], i * 0x200);
+    }
+    let start = Instant::now();
+    for i in 0..INSERTS {
+        // Land inside a block, splitting it in three
+        let block = i * 7919 % BLOCKS;
+        let offset = 1 + i * 31 % 0xf0;
+        map.insert(vec![i as u8; 4], block * 0x200 + offset);
+    }
+    let split_time = start.elapsed();
+    assert_eq!(map.ranges().len(), BLOCKS as usize);
+    println!("{INSERTS} splitting inserts into {BLOCKS} blocks: {split_time:?}");
+}
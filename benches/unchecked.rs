@@ -0,0 +1,45 @@
+//! Random single element reads from mapped pages, checked and unchecked. Both look up the
+//! block, which dominates, so the difference is only the checks.
+//!
+//! Run with `cargo bench --bench unchecked`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rand::{Rng, SeedableRng};
+use sparse_vec::SparseVec;
+
+const PAGES: u64 = 256;
+const PAGE: u64 = 0x1000;
+const READS: usize = 10_000_000;
+
+fn main() {
+    let mut map = SparseVec::new();
+    // Every other page, so that no pages merge
+    for page in 0..PAGES {
+        map.insert(vec![page as u8; PAGE as usize], page * 2 * PAGE);
+    }
+    let mut rng = rand::rngs::StdRng::seed_from_u64(184);
+    let addrs = Vec::from_iter(
+        (0..READS).map(|_| rng.gen_range(0..PAGES) * 2 * PAGE + rng.gen_range(0..PAGE)),
+    );
+
+    let start = Instant::now();
+    let mut checked = 0u64;
+    for &addr in &addrs {
+        checked += map.get(addr..addr + 1).unwrap()[0] as u64;
+    }
+    let checked_time = start.elapsed();
+
+    let start = Instant::now();
+    let mut unchecked = 0u64;
+    for &addr in &addrs {
+        // SAFETY: every address is inside a page inserted above
+        unchecked += unsafe { *map.get_value_unchecked(addr) } as u64;
+    }
+    let unchecked_time = start.elapsed();
+
+    assert_eq!(black_box(checked), unchecked);
+    println!("{READS} checked reads:   {checked_time:?}");
+    println!("{READS} unchecked reads: {unchecked_time:?}");
+}
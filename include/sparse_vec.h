@@ -0,0 +1,83 @@
+/* C interface of the `ffi` feature, kept in sync with `cbindgen --config cbindgen.toml`. */
+
+#ifndef SPARSE_VEC_H
+#define SPARSE_VEC_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/**
+ * Opaque handle owning a `SparseVec<u8>`.
+ */
+typedef struct SparseVecHandle SparseVecHandle;
+
+/**
+ * A stored range, `end` exclusive.
+ */
+typedef struct SparseVecRange {
+  uint64_t start;
+  uint64_t end;
+} SparseVecRange;
+
+typedef void (*SparseVecRangeCallback)(uint64_t start, uint64_t end, void *user);
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates an empty `SparseVec<u8>`. Free it with [`sparse_vec_free`].
+ */
+SparseVecHandle *sparse_vec_new(void);
+
+/**
+ * Frees a handle. Null is ignored.
+ */
+void sparse_vec_free(SparseVecHandle *handle);
+
+/**
+ * Copies `len` bytes from `data` to `addr`, overwriting stored bytes. Returns 0 or -1.
+ */
+int sparse_vec_insert(SparseVecHandle *handle, uint64_t addr, const uint8_t *data, size_t len);
+
+/**
+ * Copies the bytes stored contiguously from `addr` into `buf`, at most `len`. Returns the
+ * number of bytes copied, 0 if `addr` is unmapped, or -1.
+ */
+intptr_t sparse_vec_read(const SparseVecHandle *handle, uint64_t addr, uint8_t *buf, size_t len);
+
+/**
+ * Whether any byte in `start..end` is stored. Returns 1, 0 or -1.
+ */
+int sparse_vec_overlaps(const SparseVecHandle *handle, uint64_t start, uint64_t end);
+
+/**
+ * Whether every byte in `start..end` is stored. Returns 1, 0 or -1.
+ */
+int sparse_vec_contains(const SparseVecHandle *handle, uint64_t start, uint64_t end);
+
+/**
+ * Calls `callback` with every stored range in address order. Returns the number of ranges
+ * or -1. The handle must not be modified from the callback.
+ */
+intptr_t sparse_vec_ranges(const SparseVecHandle *handle,
+                           SparseVecRangeCallback callback,
+                           void *user);
+
+/**
+ * Writes up to `cap` stored ranges in address order to `out`. Returns the total number of
+ * ranges, which may exceed `cap`, or -1.
+ */
+intptr_t sparse_vec_ranges_into(const SparseVecHandle *handle, SparseVecRange *out, size_t cap);
+
+/**
+ * Description of the last failure on this thread, or null. Valid until the next failing
+ * call on the same thread.
+ */
+const char *sparse_vec_last_error(void);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif // SPARSE_VEC_H
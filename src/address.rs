@@ -0,0 +1,65 @@
+use core::fmt;
+use core::hash::Hash;
+use core::ops::{Add, Sub};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// Integer types usable as addresses of a [`SparseVec`](crate::SparseVec).
+///
+/// Implemented for `u16`, `u32`, `u64` and `usize`.
+pub trait Address:
+    private::Sealed
+    + Copy
+    + Ord
+    + Hash
+    + fmt::Debug
+    + fmt::LowerHex
+    + Add<Output = Self>
+    + Sub<Output = Self>
+{
+    const ZERO: Self;
+    const MAX: Self;
+
+    /// Panics if `v` does not fit.
+    fn from_usize(v: usize) -> Self;
+    /// Panics if `self` does not fit.
+    fn to_usize(self) -> usize;
+    fn try_from_u64(v: u64) -> Option<Self>;
+    fn to_u64(self) -> u64;
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+}
+
+macro_rules! impl_address {
+    ($($t:ty),*) => {$(
+        impl private::Sealed for $t {}
+
+        impl Address for $t {
+            const ZERO: Self = 0;
+            const MAX: Self = <$t>::MAX;
+
+            fn from_usize(v: usize) -> Self {
+                v.try_into().expect("length does not fit the address type")
+            }
+
+            fn to_usize(self) -> usize {
+                self.try_into().expect("address does not fit usize")
+            }
+
+            fn try_from_u64(v: u64) -> Option<Self> {
+                v.try_into().ok()
+            }
+
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+
+            fn checked_add(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_add(self, rhs)
+            }
+        }
+    )*};
+}
+
+impl_address!(u16, u32, u64, usize);
@@ -0,0 +1,401 @@
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+const WORD: u64 = u64::BITS as u64;
+
+// Bits packed into words, bit `i` at `words[i / 64] >> (i % 64)`. Bits past `len` are zero.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bits {
+    words: Vec<u64>,
+    len: u64,
+}
+
+fn word_count(len: u64) -> usize {
+    len.div_ceil(WORD) as usize
+}
+
+// Mask of the bits `from..to` within one word, `to <= 64`
+fn mask(from: u64, to: u64) -> u64 {
+    let high = if to == WORD { !0 } else { (1 << to) - 1 };
+    high & !((1 << from) - 1)
+}
+
+impl Bits {
+    fn filled(len: u64, value: bool) -> Self {
+        let mut bits = Bits {
+            words: vec![if value { !0 } else { 0 }; word_count(len)],
+            len,
+        };
+        bits.clear_tail();
+        bits
+    }
+
+    fn from_bools(bools: &[bool]) -> Self {
+        let mut words = vec![0u64; word_count(bools.len() as u64)];
+        for (i, &bit) in bools.iter().enumerate() {
+            words[i / WORD as usize] |= (bit as u64) << (i as u64 % WORD);
+        }
+        Bits {
+            words,
+            len: bools.len() as u64,
+        }
+    }
+
+    fn clear_tail(&mut self) {
+        let used = self.len % WORD;
+        if used != 0 {
+            *self.words.last_mut().unwrap() &= mask(0, used);
+        }
+    }
+
+    fn get(&self, i: u64) -> bool {
+        self.words[(i / WORD) as usize] >> (i % WORD) & 1 != 0
+    }
+
+    // The 64 bits starting at bit `pos`, zero past the end
+    fn word_at(&self, pos: u64) -> u64 {
+        let (index, shift) = ((pos / WORD) as usize, pos % WORD);
+        let low = self.words.get(index).map_or(0, |w| w >> shift);
+        if shift == 0 {
+            return low;
+        }
+        let high = self.words.get(index + 1).map_or(0, |w| w << (WORD - shift));
+        low | high
+    }
+
+    fn extract(&self, range: Range<u64>) -> Bits {
+        let len = range.end - range.start;
+        let mut bits = Bits {
+            words: Vec::from_iter(
+                (0..word_count(len) as u64).map(|i| self.word_at(range.start + i * WORD)),
+            ),
+            len,
+        };
+        bits.clear_tail();
+        bits
+    }
+
+    fn truncate(&mut self, len: u64) {
+        self.len = len;
+        self.words.truncate(word_count(len));
+        self.clear_tail();
+    }
+
+    fn append(&mut self, other: &Bits) {
+        let shift = self.len % WORD;
+        if shift == 0 {
+            self.words.extend_from_slice(&other.words);
+        } else {
+            for &word in &other.words {
+                *self.words.last_mut().unwrap() |= word << shift;
+                self.words.push(word >> (WORD - shift));
+            }
+        }
+        self.len += other.len;
+        self.words.truncate(word_count(self.len));
+    }
+
+    // Sets the bits `range` to `value`, a word at a time
+    fn fill(&mut self, range: Range<u64>, value: bool) {
+        let mut pos = range.start;
+        while pos < range.end {
+            let index = (pos / WORD) as usize;
+            let from = pos % WORD;
+            let to = (range.end - pos + from).min(WORD);
+            let mask = mask(from, to);
+            if value {
+                self.words[index] |= mask;
+            } else {
+                self.words[index] &= !mask;
+            }
+            pos += to - from;
+        }
+    }
+
+    // Copies all of `src` to bit `at`
+    fn copy_from(&mut self, at: u64, src: &Bits) {
+        for i in 0..src.words.len() as u64 {
+            let pos = at + i * WORD;
+            let len = (src.len - i * WORD).min(WORD);
+            let word = src.words[i as usize];
+            let (index, shift) = ((pos / WORD) as usize, pos % WORD);
+            let low_len = len.min(WORD - shift);
+            self.words[index] = self.words[index] & !mask(shift, shift + low_len) | word << shift;
+            if low_len < len {
+                let high_len = len - low_len;
+                let rest = word >> low_len;
+                self.words[index + 1] = self.words[index + 1] & !mask(0, high_len) | rest;
+            }
+        }
+    }
+}
+
+/// A sparse set of bits, like `SparseVec<bool>` with 8 bits per byte.
+///
+/// Adjacent stored ranges are always merged.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparseBitVec {
+    blocks: BTreeMap<u64, Bits>,
+}
+
+impl SparseBitVec {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Stores `bits` at `addr..addr + bits.len()`, overwriting stored bits.
+    ///
+    /// Panics if the bits do not fit in the address space, which ends at `u64::MAX` like the
+    /// ranges of a [`crate::SparseVec`].
+    pub fn insert(&mut self, addr: u64, bits: &[bool]) {
+        if !bits.is_empty() {
+            if addr.checked_add(bits.len() as u64).is_none() {
+                panic!("{} bits at {addr:#x} exceed the address space", bits.len());
+            }
+            self.insert_bits(addr, Bits::from_bools(bits));
+        }
+    }
+
+    /// Stores `value` at every address in `range`, mapping gaps.
+    pub fn fill(&mut self, range: Range<u64>, value: bool) {
+        if range.is_empty() {
+            return;
+        }
+        if let Some((start, block)) = self.block_containing_mut(&range) {
+            block.fill(range.start - start..range.end - start, value);
+        } else {
+            self.insert_bits(range.start, Bits::filled(range.end - range.start, value));
+        }
+    }
+
+    /// Unmaps every address in `range`.
+    pub fn remove(&mut self, range: Range<u64>) {
+        if range.is_empty() {
+            return;
+        }
+        let overlapping = Vec::from_iter(
+            self.blocks
+                .range(..range.end)
+                .rev()
+                .take_while(|(&start, block)| start + block.len > range.start)
+                .map(|(&start, _)| start),
+        );
+        for start in overlapping {
+            let mut block = self.blocks.remove(&start).unwrap();
+            let end = start + block.len;
+            if end > range.end {
+                let tail = block.extract(range.end - start..block.len);
+                self.blocks.insert(range.end, tail);
+            }
+            if start < range.start {
+                block.truncate(range.start - start);
+                self.blocks.insert(start, block);
+            }
+        }
+    }
+
+    /// The bit at `addr`, `None` if unmapped.
+    pub fn get_bit(&self, addr: u64) -> Option<bool> {
+        let (start, block) = self.block_at(addr)?;
+        Some(block.get(addr - start))
+    }
+
+    /// The bits of `range` packed into words, bit `i` of the range at `words[i / 64] >> (i %
+    /// 64)`. `None` if any part of the range is unmapped or it is reversed, like
+    /// [`crate::SparseVec::get`].
+    pub fn get(&self, range: Range<u64>) -> Option<Vec<u64>> {
+        if range.start > range.end {
+            return None;
+        }
+        let (start, block) = self.block_at(range.start)?;
+        if range.end > start + block.len {
+            return None;
+        }
+        Some(block.extract(range.start - start..range.end - start).words)
+    }
+
+    /// Stored bits within `range` with their addresses. Gaps are skipped.
+    pub fn iter_range(&self, range: Range<u64>) -> impl Iterator<Item = (u64, bool)> + '_ {
+        self.ranges_in(range).flat_map(move |clipped| {
+            let (start, block) = self.block_at(clipped.start).unwrap();
+            clipped.map(move |addr| (addr, block.get(addr - start)))
+        })
+    }
+
+    /// Number of set bits within `range`.
+    pub fn count_ones(&self, range: Range<u64>) -> u64 {
+        self.ranges_in(range)
+            .map(|clipped| {
+                let (start, block) = self.block_at(clipped.start).unwrap();
+                let bits = block.extract(clipped.start - start..clipped.end - start);
+                bits.words
+                    .iter()
+                    .map(|w| w.count_ones() as u64)
+                    .sum::<u64>()
+            })
+            .sum()
+    }
+
+    /// Whether every address in `range` is stored. True for empty ranges.
+    pub fn contains_range(&self, range: &Range<u64>) -> bool {
+        range.is_empty()
+            || self
+                .block_at(range.start)
+                .is_some_and(|(start, block)| range.end <= start + block.len)
+    }
+
+    /// Stored ranges in address order.
+    pub fn ranges(&self) -> impl DoubleEndedIterator<Item = Range<u64>> + '_ {
+        self.blocks
+            .iter()
+            .map(|(&start, block)| start..start + block.len)
+    }
+
+    // Reversed ranges count as empty
+    fn ranges_in(&self, range: Range<u64>) -> impl Iterator<Item = Range<u64>> + '_ {
+        let range = range.start..range.end.max(range.start);
+        let first = self
+            .block_at(range.start)
+            .map_or(range.start, |(start, _)| start);
+        self.blocks
+            .range(first..range.end)
+            .map(move |(&start, block)| start.max(range.start)..(start + block.len).min(range.end))
+    }
+
+    fn block_at(&self, addr: u64) -> Option<(u64, &Bits)> {
+        let (&start, block) = self.blocks.range(..=addr).next_back()?;
+        (addr < start + block.len).then_some((start, block))
+    }
+
+    fn block_containing_mut(&mut self, range: &Range<u64>) -> Option<(u64, &mut Bits)> {
+        let (&start, block) = self.blocks.range_mut(..=range.start).next_back()?;
+        (range.end <= start + block.len).then_some((start, block))
+    }
+
+    fn insert_bits(&mut self, addr: u64, bits: Bits) {
+        let range = addr..addr + bits.len;
+        if let Some((start, block)) = self.block_containing_mut(&range) {
+            block.copy_from(addr - start, &bits);
+            return;
+        }
+        self.remove(range.clone());
+
+        // Merge with the neighbours
+        let mut start = addr;
+        let mut block = bits;
+        if let Some((&left, left_bits)) = self.blocks.range(..addr).next_back() {
+            if left + left_bits.len == addr {
+                let mut merged = self.blocks.remove(&left).unwrap();
+                merged.append(&block);
+                (start, block) = (left, merged);
+            }
+        }
+        if let Some(right) = self.blocks.remove(&range.end) {
+            block.append(&right);
+        }
+        self.blocks.insert(start, block);
+    }
+}
+
+#[test]
+fn sparsevec_bits() {
+    let mut bits = SparseBitVec::new();
+    bits.fill(3..200, true);
+    bits.fill(70..130, false);
+    bits.insert(5, &[false, true, false]);
+    assert_eq!(Vec::from_iter(bits.ranges()), vec![3..200]);
+    assert_eq!(bits.get_bit(5), Some(false));
+    assert_eq!(bits.get_bit(6), Some(true));
+    assert_eq!(bits.get_bit(200), None);
+    assert_eq!(bits.count_ones(0..1000), 197 - 60 - 2);
+    assert_eq!(bits.get(4..8), Some(vec![0b0101]));
+    assert_eq!(bits.get(60..200).unwrap()[0], 0x3ff);
+    assert_eq!(bits.get(2..5), None);
+
+    // Splitting at unaligned offsets
+    bits.remove(67..133);
+    assert_eq!(Vec::from_iter(bits.ranges()), vec![3..67, 133..200]);
+    assert!(bits.contains_range(&(133..200)));
+    assert!(!bits.contains_range(&(66..134)));
+    assert_eq!(bits.get(133..200), Some(vec![!0, 0b111]));
+    bits.fill(67..133, true);
+    assert_eq!(bits.count_ones(0..1000), 197 - 2);
+    assert_eq!(
+        Vec::from_iter(bits.iter_range(1..8)),
+        vec![(3, true), (4, true), (5, false), (6, true), (7, false)]
+    );
+
+    // Reversed ranges and the end of the address space
+    let reversed = Range { start: 10, end: 2 };
+    assert_eq!(bits.get(reversed.clone()), None);
+    assert_eq!(bits.count_ones(reversed.clone()), 0);
+    assert_eq!(bits.iter_range(reversed).count(), 0);
+    bits.insert(u64::MAX - 3, &[true; 3]);
+    bits.fill(u64::MAX - 1..u64::MAX, false);
+    assert_eq!(bits.get(u64::MAX - 3..u64::MAX), Some(vec![0b011]));
+    assert_eq!(bits.count_ones(u64::MAX - 10..u64::MAX), 2);
+    assert_eq!(bits.get_bit(u64::MAX), None);
+    bits.remove(u64::MAX - 2..u64::MAX);
+    assert_eq!(
+        Vec::from_iter(bits.ranges()).last(),
+        Some(&(u64::MAX - 3..u64::MAX - 2))
+    );
+}
+
+#[test]
+#[should_panic(expected = "2 bits at 0xffffffffffffffff exceed the address space")]
+fn sparsevec_bits_insert_overflow() {
+    SparseBitVec::new().insert(u64::MAX, &[true; 2]);
+}
+
+#[test]
+fn sparsevec_bits_model() {
+    use crate::SparseVec;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(158);
+    let mut bits = SparseBitVec::new();
+    let mut model = SparseVec::<bool>::new();
+    for _ in 0..20_000 {
+        let start = rng.gen_range(0..2000);
+        let len = rng.gen_range(0..300);
+        let range = start..start + len;
+        match rng.gen_range(0..4) {
+            0 => {
+                let value = rng.gen();
+                bits.fill(range.clone(), value);
+                model.fill(range, value);
+            }
+            1 => {
+                let data = Vec::from_iter((0..len).map(|_| rng.gen::<bool>()));
+                bits.insert(start, &data);
+                model.insert(data, start);
+            }
+            2 => {
+                bits.remove(range.clone());
+                let mut rest = model.clone_range(0..range.start);
+                for (start, data) in model.clone_range(range.end..u64::MAX) {
+                    rest.insert(data, start);
+                }
+                model = rest;
+            }
+            _ => {
+                let iter = bits.iter_range(range.clone());
+                assert!(iter.eq(model.iter_range(range.clone()).map(|(a, v)| (a, *v))));
+                let expected = model.get(range.clone()).map(|data| {
+                    let mut words = vec![0; word_count(len)];
+                    for (i, &bit) in data.iter().enumerate() {
+                        words[i / 64] |= (bit as u64) << (i % 64);
+                    }
+                    words
+                });
+                assert_eq!(bits.get(range.clone()), expected);
+                let ones = model.iter_range(range.clone()).filter(|(_, v)| **v).count();
+                assert_eq!(bits.count_ones(range), ones as u64);
+            }
+        }
+        assert!(bits.ranges().eq(model.ranges()));
+    }
+}
@@ -0,0 +1,126 @@
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+/// Handle of a block that stays valid while other data is inserted or removed, see
+/// [`SparseVec::block_id_at`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BlockId(usize);
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Id of the block containing `addr`, `None` if `addr` is unmapped. Ids are never reused
+    /// by a `SparseVec`, so once [`SparseVec::resolve`] returns `None` for one it always
+    /// will. They are meaningless for other `SparseVec`s, except for the blocks that
+    /// [`SparseVec::map_values`] copies.
+    ///
+    /// A block keeps its id while its range changes:
+    /// - Trimmed by an insert or removal overlapping either end, it keeps the id.
+    /// - Split in two, by an insert or removal in its middle or by
+    ///   [`SparseVec::split_blocks_at`], the lower part keeps the id and the upper part gets
+    ///   a new one.
+    /// - Merged with the block after it, the lower block's id wins and the upper one's no
+    ///   longer resolves.
+    ///
+    /// Inserted data gets a new id and is then merged with adjacent blocks, unless
+    /// [auto merging](SparseVec::set_auto_merge) is off. So data inserted at the end of a
+    /// block joins it under its id, while data inserted at or across its start ends its id.
+    /// Overwriting a whole block ends its id as well, even with data of the same range.
+    pub fn block_id_at(&self, addr: A) -> Option<BlockId> {
+        self.map.get(&addr).map(|key| BlockId(*key))
+    }
+
+    /// The current range and data of the block with `id`, `None` if it no longer exists.
+    /// Block ranges are only indexed by address, so this searches all blocks for `id`.
+    pub fn resolve(&self, id: BlockId) -> Option<(Range<A>, &[T])> {
+        let (range, _) = self.map.iter().find(|(_, key)| **key == id.0)?;
+        Some((range.clone(), &self.data[&id.0]))
+    }
+}
+
+#[test]
+fn sparsevec_block_id() {
+    let mut map = crate::sparse_vec! {
+        0x100 => [1u8; 0x10],
+        0x200 => [2; 0x10],
+    };
+    let first = map.block_id_at(0x108).unwrap();
+    let second = map.block_id_at(0x200).unwrap();
+    assert_ne!(first, second);
+    assert_eq!(map.block_id_at(0x110), None);
+    assert_eq!(map.resolve(first), Some((0x100..0x110, &[1; 0x10][..])));
+
+    // Unrelated inserts keep the id, data inserted across the end joins the block
+    map.insert(vec![3; 4], 0x180);
+    map.insert(vec![5; 2], 0x20f);
+    assert_eq!(map.resolve(second).unwrap().0, 0x200..0x211);
+    assert_eq!(map.block_id_at(0x210), Some(second));
+
+    // Data inserted across the start takes over the block
+    map.insert(vec![4; 4], 0xfe);
+    assert_eq!(map.resolve(first), None);
+    let first = map.block_id_at(0x100).unwrap();
+    assert_eq!(map.resolve(first).unwrap().0, 0xfe..0x110);
+
+    // Splitting leaves the id with the lower part
+    map.set_auto_merge(false);
+    map.insert(vec![6; 2], 0x104);
+    assert_eq!(map.resolve(first).unwrap().0, 0xfe..0x104);
+    let upper = map.block_id_at(0x106).unwrap();
+    assert_eq!(map.resolve(upper).unwrap().0, 0x106..0x110);
+    map.split_blocks_at(&[0x102]);
+    assert_eq!(map.resolve(first).unwrap().0, 0xfe..0x102);
+
+    // Without merging, trimming either end keeps the id
+    map.insert(vec![6; 2], 0x1fe);
+    map.insert(vec![6; 2], 0x210);
+    assert_eq!(map.resolve(second).unwrap().0, 0x200..0x210);
+
+    // Merging keeps the id of the lower block
+    map.set_auto_merge(true);
+    map.insert(vec![7; 2], 0x102);
+    assert_eq!(map.resolve(first).unwrap().0, 0xfe..0x110);
+    assert_eq!(map.resolve(upper), None);
+    assert_eq!(map.block_id_at(0x10f), Some(first));
+
+    // Overwritten blocks end their id, also for data at the same range
+    map.insert(vec![8; 4], 0x180);
+    let overwritten = map.block_id_at(0x180).unwrap();
+    map.insert(vec![9; 4], 0x180);
+    assert_eq!(map.resolve(overwritten), None);
+    assert_eq!(map.get(0x180..0x184).unwrap(), &[9; 4]);
+    map.retain_blocks(|range, _| range.start != 0x200);
+    assert_eq!(map.resolve(second), None);
+}
+
+#[test]
+fn sparsevec_block_id_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(197);
+    let mut map = SparseVec::new();
+    let mut live = alloc::vec::Vec::new();
+    let mut dead = alloc::vec::Vec::new();
+    for i in 0..2000u32 {
+        let addr = rng.gen_range(0..0x400);
+        match rng.gen_range(0..4) {
+            0 | 1 => map.insert(vec![i; rng.gen_range(1..0x40)], addr),
+            2 => map.split_blocks_at(&[addr]),
+            _ => map.retain_blocks(|range, _| !range.contains(&addr)),
+        }
+        live.extend(map.block_id_at(rng.gen_range(0..0x400)));
+        live.sort();
+        live.dedup();
+        live.retain(|&id| {
+            let Some((range, data)) = map.resolve(id) else {
+                dead.push(id);
+                return false;
+            };
+            // Resolving agrees with looking the block up
+            assert_eq!(map.block_id_at(range.start), Some(id));
+            assert_eq!(map.get_block(range.start), Some((range, data)));
+            true
+        });
+        assert!(dead.iter().all(|&id| map.resolve(id).is_none()));
+    }
+    assert!(!live.is_empty() && !dead.is_empty());
+}
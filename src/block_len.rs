@@ -0,0 +1,113 @@
+use core::ops::Range;
+
+#[cfg(test)]
+use itertools::Itertools;
+
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Empty `SparseVec` whose blocks never cross an address that is a multiple of `max`, so
+    /// no block is longer than `max` elements. Adjacent data is merged up to those
+    /// boundaries and longer inserts are split at them, which bounds the cost of copying and
+    /// partially overwriting a block.
+    ///
+    /// [`SparseVec::ranges`] and [`SparseVec::blocks`] expose the boundaries as separate
+    /// ranges. Reads spanning several blocks work like everywhere else, e.g.
+    /// [`SparseVec::slices`], [`SparseVec::read_into_exact`] and [`SparseVec::gaps`], but
+    /// [`SparseVec::get`] and [`SparseVec::get_mut`] return single slices and therefore
+    /// `None` for ranges crossing a boundary, and [`SparseVec::get_mut_or_fill`] panics.
+    ///
+    /// Panics if `max` is zero.
+    pub fn with_max_block_len(max: u64) -> Self {
+        assert!(max > 0, "maximum block length must not be zero");
+        Self {
+            max_block_len: Some(max),
+            ..Self::default()
+        }
+    }
+
+    /// The limit passed to [`SparseVec::with_max_block_len`], if any.
+    pub fn max_block_len(&self) -> Option<u64> {
+        self.max_block_len
+    }
+
+    // Whether the non-empty `start..end` can be a single block
+    pub(crate) fn same_window(&self, start: A, end: A) -> bool {
+        self.max_block_len
+            .is_none_or(|max| start.to_u64() / max == (end.to_u64() - 1) / max)
+    }
+
+    /// Stored ranges with the boundaries of [`SparseVec::with_max_block_len`] coalesced,
+    /// in address order.
+    pub fn coalesced_ranges(&self) -> impl Iterator<Item = Range<A>> + '_ {
+        let mut ranges = self.ranges().peekable();
+        core::iter::from_fn(move || {
+            let mut range = ranges.next()?;
+            while let Some(next) = ranges.next_if(|next| next.start == range.end) {
+                range.end = next.end;
+            }
+            Some(range)
+        })
+    }
+}
+
+#[test]
+fn sparsevec_max_block_len() {
+    use rand::{Rng, SeedableRng};
+
+    let max = 0x40;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(147);
+    let mut map = SparseVec::with_max_block_len(max);
+    let mut expected = SparseVec::new();
+    for i in 0..2000u32 {
+        let start = rng.gen_range(0..0xe00u64);
+        let len = rng.gen_range(0..0x200);
+        let range = start..start + len as u64;
+        match rng.gen_range(0..4) {
+            0 => {
+                map.fill(range.clone(), i);
+                expected.fill(range, i);
+            }
+            1 => {
+                map.fill_gaps(range.clone(), i);
+                expected.fill_gaps(range, i);
+            }
+            2 if expected.contains_range(&range) => {
+                map.write(start, &vec![i; len]).unwrap();
+                expected.write(start, &vec![i; len]).unwrap();
+            }
+            _ => {
+                map.insert(vec![i; len], start);
+                expected.insert(vec![i; len], start);
+            }
+        }
+
+        for ((range, slice), (next, _)) in map.blocks().tuple_windows() {
+            assert!(slice.len() as u64 <= max, "block {range:x?}");
+            // Split only where needed
+            assert!(range.end < next.start || range.end % max == 0);
+        }
+        assert!(map.coalesced_ranges().eq(expected.ranges()));
+    }
+
+    // Reads across the boundaries see the same data
+    for (range, data) in expected.blocks() {
+        let mut buf = vec![0; data.len()];
+        map.read_into_exact(range.start, &mut buf).unwrap();
+        assert_eq!(buf, data);
+        assert_eq!(map.gaps(range.clone()).count(), 0);
+        assert!(map.iter_range(range).map(|(_, v)| v).eq(data));
+    }
+    assert_eq!(map.stored_len(), expected.stored_len());
+    assert_eq!(map.max_block_len(), Some(max));
+
+    let mut map = SparseVec::<u8>::with_max_block_len(0x10);
+    map.insert(vec![1u8; 0x30], 0x08);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x08..0x10, 0x10..0x20, 0x20..0x30, 0x30..0x38]
+    );
+    assert_eq!(Vec::from_iter(map.coalesced_ranges()), vec![0x08..0x38]);
+    assert_eq!(map.get(0x10..0x20).unwrap(), &[1; 0x10]);
+    assert_eq!(map.get(0x0f..0x11), None);
+}
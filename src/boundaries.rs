@@ -0,0 +1,119 @@
+use crate::{cast_range, sub_range, Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Whether inserts merge adjacent blocks, which is the default. Without it, every insert
+    /// stays a block of its own unless it overwrites whole blocks, and block boundaries only
+    /// disappear when the data on one side is overwritten.
+    ///
+    /// Turning it back on merges all adjacent blocks on the next insert.
+    pub fn set_auto_merge(&mut self, merge: bool) {
+        self.auto_merge = merge;
+    }
+
+    pub fn auto_merge(&self) -> bool {
+        self.auto_merge
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Splits every block containing one of `addrs` into two at that address, without
+    /// changing the stored data. Addresses in gaps or at the start of a block are ignored.
+    ///
+    /// As with [`SparseVec::with_max_block_len`], reads spanning a boundary work except
+    /// for [`SparseVec::get`] and [`SparseVec::get_mut`]. Unless
+    /// [auto merging](SparseVec::set_auto_merge) is off, the next insert merges the blocks
+    /// again.
+    pub fn split_blocks_at(&mut self, addrs: &[A]) {
+        for &addr in addrs {
+            self.split_block(addr);
+        }
+        #[cfg(debug_assertions)]
+        self.assert_invariants();
+    }
+
+    // Makes `addr` the start of a block if it is inside one
+    pub(crate) fn split_block(&mut self, addr: A) {
+        let Some((block, &key)) = self.map.get_key_value(&addr) else {
+            return;
+        };
+        let block = block.clone();
+        if block.start == addr {
+            return;
+        }
+        let (lower, upper) = (block.start..addr, addr..block.end);
+        let data =
+            self.data[&key].slice(cast_range(sub_range(&upper, block.start)), self.block_align);
+        self.map.insert(upper, self.key_counter);
+        self.data.insert(self.key_counter, data);
+        self.key_counter += 1;
+        Self::resize_block(&mut self.data, &key, &block, &lower);
+    }
+}
+
+#[test]
+fn sparsevec_split_blocks_at() {
+    let mut map = SparseVec::new();
+    map.insert(Vec::from_iter(0..0x20u8), 0x100);
+    map.insert(vec![0xff; 4], 0x200);
+    map.set_auto_merge(false);
+    map.split_blocks_at(&[0x108, 0x100, 0x180, 0x110, 0x108, 0x202, 0x204]);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![
+            0x100..0x108,
+            0x108..0x110,
+            0x110..0x120,
+            0x200..0x202,
+            0x202..0x204
+        ]
+    );
+
+    // Reads across the new boundaries still see the same data
+    let mut buf = [0; 0x20];
+    map.read_into_exact(0x100, &mut buf).unwrap();
+    assert!(buf.iter().copied().eq(0..0x20));
+    assert!(map.iter_range(0x104..0x114).map(|(_, v)| *v).eq(4..0x14));
+    assert!(map.contains_range(&(0x100..0x120)));
+    assert_eq!(map.get(0x108..0x110).unwrap(), &Vec::from_iter(8..0x10)[..]);
+    assert_eq!(map.get(0x106..0x10a), None);
+    assert_eq!(Vec::from_iter(map.gaps(0x100..0x204)), vec![0x120..0x200]);
+
+    // Boundaries survive inserts until auto merging is back on
+    map.write(0x107, &[0xaa, 0xbb]).unwrap();
+    map.insert(vec![1; 2], 0x120);
+    assert_eq!(map.ranges().len(), 6);
+    assert_eq!(map.get(0x107..0x108).unwrap(), &[0xaa]);
+    assert!(!map.auto_merge());
+    map.set_auto_merge(true);
+    map.insert(vec![2; 2], 0x300);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x122, 0x200..0x204, 0x300..0x302]
+    );
+}
+
+#[test]
+fn sparsevec_split_blocks_at_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(176);
+    let mut map = SparseVec::new();
+    map.set_auto_merge(false);
+    let mut model = [None; 0x200];
+    for i in 0..500u32 {
+        if rng.gen_bool(0.3) {
+            let addrs = Vec::from_iter((0..4).map(|_| rng.gen_range(0..0x200)));
+            map.split_blocks_at(&addrs);
+        } else {
+            let start = rng.gen_range(0..0x1c0);
+            let len = rng.gen_range(0..0x40);
+            map.insert(vec![i; len], start as u64);
+            model[start..][..len].fill(Some(i));
+        }
+        let stored = Vec::from_iter(map.iter_range(0..0x200).map(|(addr, v)| (addr, *v)));
+        let expected = Vec::from_iter(
+            (0..0x200u64).filter_map(|addr| model[addr as usize].map(|v| (addr, v))),
+        );
+        assert_eq!(stored, expected);
+    }
+}
@@ -0,0 +1,113 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Fills every gap between two blocks that is shorter than `max_gap` with `default`, so
+    /// that runs of blocks separated by small holes become single blocks. Nothing is added
+    /// before the first or after the last block. Returns the number of elements added.
+    ///
+    /// Like [`SparseVec::fill_gaps`] over each such run, so the stored values are kept and
+    /// bridging a frozen gap panics.
+    pub fn bridge_gaps(&mut self, max_gap: u64, default: T) -> u64 {
+        // Runs of blocks with the length of the gaps within them
+        let mut runs: Vec<(Range<A>, u64)> = Vec::new();
+        for range in self.ranges() {
+            match runs.last_mut() {
+                Some((run, bridged)) if (range.start - run.end).to_u64() < max_gap => {
+                    *bridged += (range.start - run.end).to_u64();
+                    run.end = range.end;
+                }
+                _ => runs.push((range, 0)),
+            }
+        }
+        let mut added = 0;
+        for (run, bridged) in runs {
+            if bridged != 0 {
+                self.fill_gaps(run, default);
+                added += bridged;
+            }
+        }
+        added
+    }
+}
+
+#[test]
+fn sparsevec_bridge_gaps() {
+    let mut map = crate::sparse_vec! {
+        0x100 => [1u8; 4],
+        0x106 => [2; 2],
+        0x109 => [3; 2],
+        0x200 => [4; 4],
+        0x204 => [5; 4],
+        0x20c => [6; 4],
+    };
+    map.set_auto_merge(false);
+    map.insert(vec![7; 2], 0x20a);
+    map.set_auto_merge(true);
+
+    // Gaps of exactly `max_gap` stay
+    assert_eq!(map.bridge_gaps(0, 0), 0);
+    assert_eq!(map.bridge_gaps(1, 0), 0);
+    assert_eq!(map.bridge_gaps(2, 0), 1);
+    map.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x104, 0x106..0x10b, 0x200..0x208, 0x20a..0x210]
+    );
+    assert_eq!(map.get(0x106..0x10b).unwrap(), &[2, 2, 0, 3, 3]);
+
+    // Consecutive gaps join several blocks, nothing is added around them
+    assert_eq!(map.bridge_gaps(3, 0xff), 4);
+    map.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x10b, 0x200..0x210]
+    );
+    assert_eq!(
+        map.get(0x100..0x10b).unwrap(),
+        &[1, 1, 1, 1, 0xff, 0xff, 2, 2, 0, 3, 3]
+    );
+    assert_eq!(map.get(0x206..0x20c).unwrap(), &[5, 5, 0xff, 0xff, 7, 7]);
+    assert_eq!(map.bridge_gaps(u64::MAX, 0), 0xf5);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x100..0x210]);
+
+    let mut empty = SparseVec::<u8>::new();
+    assert_eq!(empty.bridge_gaps(u64::MAX, 0), 0);
+    assert_eq!(empty.bounds(), None);
+}
+
+#[test]
+fn sparsevec_bridge_gaps_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(198);
+    for _ in 0..100 {
+        let mut map = SparseVec::<u16>::new();
+        for _ in 0..rng.gen_range(0..20) {
+            let len = rng.gen_range(1..8);
+            map.insert(vec![1; len], rng.gen_range(0..0x100));
+        }
+        let before = map.clone_range(0..u64::MAX);
+        let max_gap = rng.gen_range(0..6);
+        let added = map.bridge_gaps(max_gap, 0);
+        map.assert_invariants();
+
+        let gaps = Vec::from_iter(before.bounds().into_iter().flat_map(|b| before.gaps(b)));
+        let bridged = gaps
+            .iter()
+            .map(|gap| gap.end - gap.start)
+            .filter(|&len| len < max_gap);
+        assert_eq!(added, bridged.sum::<u64>());
+        assert_eq!(map.bounds(), before.bounds());
+        assert_eq!(map.stored_len() as u64, before.stored_len() as u64 + added);
+        for gap in gaps {
+            let filled = gap.end - gap.start < max_gap;
+            assert_eq!(map.contains_range(&gap), filled);
+        }
+        for (range, data) in before.blocks() {
+            assert!(map.iter_range(range).map(|(_, v)| v).eq(data));
+        }
+    }
+}
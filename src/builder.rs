@@ -0,0 +1,130 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+/// Builds a [`SparseVec`] from blocks pushed in address order, without the cost of
+/// [`SparseVec::insert`].
+pub struct SparseVecBuilder<T, A = u64> {
+    blocks: Vec<(Range<A>, Vec<T>)>,
+}
+
+impl<T, A: Address> Default for SparseVecBuilder<T, A> {
+    fn default() -> Self {
+        Self { blocks: Vec::new() }
+    }
+}
+
+impl<T> SparseVecBuilder<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, A: Address> SparseVecBuilder<T, A> {
+    /// Appends `data` at `start`, which must not be below the end of the previous block.
+    /// Adjacent blocks are merged. Empty data is ignored.
+    pub fn push(&mut self, start: A, data: Vec<T>) -> Result<(), BuildError<A>> {
+        if let Some((last, _)) = self.blocks.last() {
+            if start < last.end {
+                return Err(BuildError {
+                    start,
+                    previous_end: last.end,
+                });
+            }
+        }
+        if data.is_empty() {
+            return Ok(());
+        }
+        let end = start + A::from_usize(data.len());
+        match self.blocks.last_mut() {
+            Some((last, last_data)) if last.end == start => {
+                last.end = end;
+                last_data.extend(data);
+            }
+            _ => self.blocks.push((start..end, data)),
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> SparseVec<T, A> {
+        let mut vec = SparseVec::default();
+        for block in self.blocks {
+            vec.push_block(block);
+        }
+        vec
+    }
+}
+
+/// A block pushed to a [`SparseVecBuilder`] starts before the end of the previous one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildError<A = u64> {
+    pub start: A,
+    pub previous_end: A,
+}
+
+impl<A: Address> fmt::Display for BuildError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "block at {:#x} starts before the end of the previous block at {:#x}",
+            self.start, self.previous_end
+        )
+    }
+}
+
+impl<A: Address> core::error::Error for BuildError<A> {}
+
+#[test]
+fn sparsevec_builder() {
+    let mut builder = SparseVecBuilder::new();
+    builder.push(0x10, vec![1u8, 2]).unwrap();
+    builder.push(0x12, vec![3]).unwrap();
+    builder.push(0x13, Vec::new()).unwrap();
+    builder.push(0x20, vec![4; 4]).unwrap();
+    assert_eq!(
+        builder.push(0x23, vec![5]),
+        Err(BuildError {
+            start: 0x23,
+            previous_end: 0x24
+        })
+    );
+    assert_eq!(builder.push(0x10, vec![5]).unwrap_err().start, 0x10);
+    builder.push(0x24, vec![5]).unwrap();
+
+    let map = builder.finish();
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x10..0x13, 0x20..0x25]);
+    assert_eq!(map.get(0x10..0x13).unwrap(), &[1, 2, 3]);
+    assert_eq!(map.get(0x20..0x25).unwrap(), &[4, 4, 4, 4, 5]);
+    assert!(SparseVecBuilder::<u8>::new().finish().bounds().is_none());
+}
+
+#[test]
+fn sparsevec_builder_matches_insert() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(5);
+    for _ in 0..50 {
+        let mut builder = SparseVecBuilder::new();
+        let mut inserted = SparseVec::new();
+        let mut addr = 0u64;
+        for _ in 0..rng.gen_range(0..30) {
+            // Gaps of zero make adjacent blocks
+            addr += rng.gen_range(0..3) * rng.gen_range(1..10);
+            let data = Vec::from_iter((0..rng.gen_range(0..8)).map(|_| rng.gen::<u8>()));
+            let len = data.len() as u64;
+            builder.push(addr, data.clone()).unwrap();
+            inserted.insert(data, addr);
+            addr += len;
+        }
+        let built = builder.finish();
+        assert!(built.blocks().eq(inserted.blocks()));
+
+        // The result behaves like any other `SparseVec`
+        let mut built = built;
+        built.insert(vec![0; 5], 3);
+        inserted.insert(vec![0; 5], 3);
+        assert!(built.blocks().eq(inserted.blocks()));
+    }
+}
@@ -0,0 +1,161 @@
+use core::fmt;
+
+use crate::{Frozen, SparseVec, Unmapped, WriteError};
+
+/// Byte addressed memory as seen by an emulated CPU or device.
+pub trait MemoryBus {
+    /// Fills `buf` with the bytes at `addr..addr + buf.len()`.
+    fn read(&self, addr: u64, buf: &mut [u8]) -> Result<(), MemFault>;
+    /// Stores `data` at `addr..addr + data.len()`.
+    fn write(&mut self, addr: u64, data: &[u8]) -> Result<(), MemFault>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Read,
+    Write,
+}
+
+/// A bus access touched memory that is not mapped, or wrote to frozen memory. Nothing was read
+/// or written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemFault {
+    /// The first unmapped or frozen address of the access.
+    pub addr: u64,
+    pub access: Access,
+}
+
+impl fmt::Display for MemFault {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access = match self.access {
+            Access::Read => "read",
+            Access::Write => "write",
+        };
+        write!(f, "{access} fault at {:#x}", self.addr)
+    }
+}
+
+impl core::error::Error for MemFault {}
+
+impl SparseVec<u8> {
+    /// Bus that maps unmapped memory on write instead of faulting.
+    pub fn auto_map(&mut self) -> AutoMap<'_> {
+        AutoMap { vec: self }
+    }
+
+    // Accesses that wrap around the address space fault at the first address that is not
+    // stored, which is at the latest `u64::MAX`.
+    fn check_wrap(&self, addr: u64, len: usize, access: Access) -> Result<(), MemFault> {
+        match addr.checked_add(len as u64) {
+            Some(_) => Ok(()),
+            None => Err(MemFault {
+                addr: self.first_unmapped(&(addr..u64::MAX)).unwrap_or(u64::MAX),
+                access,
+            }),
+        }
+    }
+}
+
+/// Reads and writes fault on gaps.
+impl MemoryBus for SparseVec<u8> {
+    fn read(&self, addr: u64, buf: &mut [u8]) -> Result<(), MemFault> {
+        self.check_wrap(addr, buf.len(), Access::Read)?;
+        self.read_into_exact(addr, buf)
+            .map_err(|Unmapped { addr }| MemFault {
+                addr,
+                access: Access::Read,
+            })
+    }
+
+    fn write(&mut self, addr: u64, data: &[u8]) -> Result<(), MemFault> {
+        self.check_wrap(addr, data.len(), Access::Write)?;
+        self.try_write(addr, data).map_err(|err| {
+            let (WriteError::Unmapped { addr } | WriteError::Frozen { addr }) = err;
+            MemFault {
+                addr,
+                access: Access::Write,
+            }
+        })
+    }
+}
+
+/// [`MemoryBus`] over a [`SparseVec<u8>`] where writes into gaps map the written bytes.
+///
+/// Reads still fault on gaps.
+pub struct AutoMap<'a> {
+    vec: &'a mut SparseVec<u8>,
+}
+
+impl MemoryBus for AutoMap<'_> {
+    fn read(&self, addr: u64, buf: &mut [u8]) -> Result<(), MemFault> {
+        self.vec.read(addr, buf)
+    }
+
+    fn write(&mut self, addr: u64, data: &[u8]) -> Result<(), MemFault> {
+        self.vec.check_wrap(addr, data.len(), Access::Write)?;
+        self.vec
+            .try_insert(data.to_vec(), addr)
+            .map_err(|Frozen { addr }| MemFault {
+                addr,
+                access: Access::Write,
+            })
+    }
+}
+
+#[test]
+fn sparsevec_memory_bus() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1, 2, 3, 4], 0x100);
+    map.insert(vec![5, 6], 0x200);
+
+    let mut buf = [0; 4];
+    assert_eq!(MemoryBus::read(&map, 0x100, &mut buf), Ok(()));
+    assert_eq!(buf, [1, 2, 3, 4]);
+    let fault = |addr, access| Err(MemFault { addr, access });
+    assert_eq!(
+        MemoryBus::read(&map, 0x101, &mut buf),
+        fault(0x104, Access::Read)
+    );
+    assert_eq!(
+        MemoryBus::read(&map, 0xff, &mut buf[..1]),
+        fault(0xff, Access::Read)
+    );
+    assert_eq!(MemoryBus::read(&map, 0x1ff, &mut [0; 0]), Ok(()));
+    assert_eq!(
+        MemoryBus::read(&map, u64::MAX - 1, &mut buf),
+        fault(u64::MAX - 1, Access::Read)
+    );
+    assert_eq!(buf, [1, 2, 3, 4]);
+
+    assert_eq!(MemoryBus::write(&mut map, 0x202, &[]), Ok(()));
+    assert_eq!(
+        MemoryBus::write(&mut map, 0x200, &[9, 9, 9]),
+        fault(0x202, Access::Write)
+    );
+    assert_eq!(
+        MemoryBus::write(&mut map, 0x1ff, &[9]),
+        fault(0x1ff, Access::Write)
+    );
+    assert_eq!(MemoryBus::write(&mut map, 0x103, &[9]), Ok(()));
+    assert_eq!(map.get(0x200..0x202).unwrap(), &[5, 6]);
+
+    let mut bus = map.auto_map();
+    assert_eq!(bus.write(0x1fe, &[7, 7, 7, 7, 7]), Ok(()));
+    assert_eq!(bus.read(0x1fd, &mut buf), fault(0x1fd, Access::Read));
+    assert_eq!(bus.write(u64::MAX, &[0]), fault(u64::MAX, Access::Write));
+    assert_eq!(map.get(0x100..0x104).unwrap(), &[1, 2, 3, 9]);
+    assert_eq!(map.get(0x1fe..0x203).unwrap(), &[7, 7, 7, 7, 7]);
+
+    // Frozen memory behaves like ROM
+    map.freeze(0x100..0x102);
+    assert_eq!(
+        MemoryBus::write(&mut map, 0x101, &[9, 9]),
+        fault(0x101, Access::Write)
+    );
+    assert_eq!(
+        map.auto_map().write(0xfe, &[9; 4]),
+        fault(0x100, Access::Write)
+    );
+    assert_eq!(MemoryBus::read(&map, 0x100, &mut buf), Ok(()));
+    assert_eq!(buf, [1, 2, 3, 9]);
+}
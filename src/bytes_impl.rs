@@ -0,0 +1,69 @@
+use core::ops::Range;
+
+use bytes::Bytes;
+
+use crate::storage::Storage;
+use crate::{Address, SparseVec, WatchOp};
+
+impl<A: Address> SparseVec<u8, A> {
+    /// Like [`SparseVec::insert_shared`], but references a [`Bytes`]. Splitting and trimming
+    /// the block slices `data` instead of copying it.
+    pub fn insert_bytes(&mut self, data: Bytes, addr: A) {
+        if data.is_empty() {
+            return;
+        }
+        let range = addr..addr + A::from_usize(data.len());
+        self.assert_thawed(&range);
+        self.watch.notify(&range, WatchOp::Insert);
+        self.record_history(&range);
+        let storage = match self.block_align {
+            Some(align) => Storage::new(data.to_vec(), Some(align)),
+            None => Storage::bytes(data),
+        };
+        self.insert_storage(storage, addr);
+        self.enforce_capacity(&range);
+    }
+
+    /// Like [`SparseVec::get_block`], as a [`Bytes`]. Cheap for blocks that still reference
+    /// data passed to [`SparseVec::insert_bytes`], others are copied.
+    pub fn block_bytes(&self, addr: A) -> Option<(Range<A>, Bytes)> {
+        let (range, key) = self.map.get_key_value(&addr)?;
+        Some((range.clone(), self.data[key].to_bytes()))
+    }
+}
+
+#[test]
+fn sparsevec_insert_bytes() {
+    let image = Bytes::from(Vec::from_iter(0..0x40u8));
+    let mut map = SparseVec::new();
+    map.insert_bytes(image.clone(), 0x10);
+    assert!(map.is_shared(0x10));
+
+    // Splitting slices the same buffer
+    map.insert(vec![0xee; 4], 0x20);
+    let (range, tail) = map.block_bytes(0x30).unwrap();
+    assert_eq!(range, 0x24..0x50);
+    assert_eq!(&tail[..], &image[0x14..]);
+    assert_eq!(tail.as_ptr(), image[0x14..].as_ptr());
+    let (range, head) = map.block_bytes(0x10).unwrap();
+    assert_eq!(range, 0x10..0x20);
+    assert_eq!(head.as_ptr(), image.as_ptr());
+    assert_eq!(map.get(0x1e..0x22), None);
+
+    // Mutation copies the block
+    map.write(0x11, &[0xcc]).unwrap();
+    assert!(!map.is_shared(0x10));
+    assert_eq!(map.get(0x10..0x13).unwrap(), &[0, 0xcc, 2]);
+    assert_eq!(image[1], 1);
+    let (range, copied) = map.block_bytes(0x12).unwrap();
+    assert_eq!(range, 0x10..0x20);
+    assert_ne!(copied.as_ptr(), image.as_ptr());
+    assert_eq!(map.block_bytes(0x50), None);
+
+    let blocks = Vec::from_iter(map);
+    assert_eq!(blocks[2], (0x24, Vec::from_iter(0x14..0x40)));
+
+    let mut aligned = SparseVec::<u8>::with_block_alignment(64);
+    aligned.insert_bytes(image, 0);
+    assert!(!aligned.is_shared(0));
+}
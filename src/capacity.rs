@@ -0,0 +1,215 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::panic::{RefUnwindSafe, UnwindSafe};
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::journal::JournalOp;
+use crate::keys::KeyMap;
+use crate::{trace, Address, SparseVec};
+
+// Unwind safe so that `SparseVec` keeps its auto traits
+type EvictHook<T, A> = Box<dyn FnMut(Range<A>, Vec<T>) + Send + Sync + UnwindSafe + RefUnwindSafe>;
+
+// Set by `SparseVec::with_capacity_limit`. Reads only take `&self`, so the access order is
+// kept in atomics
+pub(crate) struct Capacity<T, A> {
+    max_bytes: usize,
+    clock: AtomicUsize,
+    // Last access of every block key
+    accessed: KeyMap<AtomicUsize>,
+    on_evict: Option<EvictHook<T, A>>,
+}
+
+impl<T, A> Capacity<T, A> {
+    pub(crate) fn touch(&self, key: usize) {
+        if let Some(accessed) = self.accessed.get(&key) {
+            accessed.store(
+                self.clock.fetch_add(1, Ordering::Relaxed),
+                Ordering::Relaxed,
+            );
+        }
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    // Counts an access of the block for the capacity limit and compression
+    pub(crate) fn touch(&self, key: usize) {
+        if let Some(capacity) = &self.capacity {
+            capacity.touch(key);
+        }
+        #[cfg(feature = "compress")]
+        if let Some(compression) = &self.compression {
+            compression.touch(key);
+        }
+    }
+
+    /// Empty `SparseVec` holding at most `max_bytes` of elements, e.g. as a cache of remote
+    /// memory. Inserts that exceed it evict whole blocks, least recently accessed first. An
+    /// access is a [`SparseVec::get`], [`SparseVec::get_mut`] or [`SparseVec::get_block`] of
+    /// a block, or an insert into it.
+    ///
+    /// Blocks overlapping the inserted range and frozen blocks are never evicted, so the
+    /// limit is only exceeded if they are larger than it on their own.
+    pub fn with_capacity_limit(max_bytes: usize) -> Self {
+        Self {
+            capacity: Some(Capacity {
+                max_bytes,
+                clock: AtomicUsize::new(0),
+                accessed: KeyMap::default(),
+                on_evict: None,
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// The limit passed to [`SparseVec::with_capacity_limit`], if any.
+    pub fn capacity_limit(&self) -> Option<usize> {
+        self.capacity.as_ref().map(|capacity| capacity.max_bytes)
+    }
+
+    /// Calls `f` with the range and data of every block evicted from now on, replacing the
+    /// previous hook.
+    ///
+    /// Panics without a [`SparseVec::with_capacity_limit`].
+    pub fn on_evict(
+        &mut self,
+        f: impl FnMut(Range<A>, Vec<T>) + Send + Sync + UnwindSafe + RefUnwindSafe + 'static,
+    ) {
+        let capacity = self.capacity.as_mut().expect("no capacity limit set");
+        capacity.on_evict = Some(Box::new(f));
+    }
+
+    // Evicts blocks not overlapping `inserted` until the limit holds
+    pub(crate) fn enforce_capacity(&mut self, inserted: &Range<A>) {
+        let Some(capacity) = &mut self.capacity else {
+            return;
+        };
+        let size = core::mem::size_of::<T>();
+        let stored: usize = self.data.values().map(|data| data.len() * size).sum();
+        let overlaps = |range: &Range<A>| range.start < inserted.end && inserted.start < range.end;
+        // Blocks created since the last insert count as accessed now
+        let now = capacity.clock.fetch_add(1, Ordering::Relaxed);
+        capacity
+            .accessed
+            .retain(|key, _| self.data.contains_key(key));
+        for (range, key) in self.map.iter() {
+            let accessed = capacity
+                .accessed
+                .entry(*key)
+                .or_insert_with(|| AtomicUsize::new(now));
+            if overlaps(range) {
+                *accessed.get_mut() = now;
+            }
+        }
+        if stored <= capacity.max_bytes {
+            return;
+        }
+
+        let mut candidates = Vec::from_iter(
+            self.map
+                .iter()
+                .filter(|(range, _)| !overlaps(range) && !self.frozen.overlaps(range))
+                .map(|(range, key)| {
+                    (
+                        capacity.accessed[key].load(Ordering::Relaxed),
+                        range.clone(),
+                        *key,
+                    )
+                }),
+        );
+        candidates.sort_unstable_by_key(|(accessed, _, _)| *accessed);
+        let mut stored = stored;
+        let mut evicted = Vec::new();
+        for (_, range, key) in candidates {
+            if stored <= capacity.max_bytes {
+                break;
+            }
+            trace::event!(
+                DEBUG,
+                start = %trace::Hex(range.start),
+                end = %trace::Hex(range.end),
+                "evict"
+            );
+            self.map.remove(range.clone());
+            let data = self.data.remove(&key).unwrap();
+            capacity.accessed.remove(&key);
+            stored -= data.len() * size;
+            evicted.push(range.clone());
+            if let Some(on_evict) = &mut capacity.on_evict {
+                on_evict(range, data.into_vec());
+            }
+        }
+        for range in evicted {
+            let len = (range.end - range.start).to_u64();
+            self.journal(JournalOp::Remove, range.start, len, &[]);
+        }
+    }
+}
+
+#[test]
+fn sparsevec_capacity_limit() {
+    use alloc::sync::Arc;
+    use std::sync::Mutex;
+
+    let mut map = SparseVec::<u32>::with_capacity_limit(0x100);
+    let evicted = Arc::new(Mutex::new(Vec::new()));
+    let log = evicted.clone();
+    map.on_evict(move |range, data| log.lock().unwrap().push((range, data)));
+
+    for i in 0..4 {
+        map.insert(vec![i; 0x10], i as u64 * 0x100);
+    }
+    assert_eq!(map.stored_len(), 0x40);
+    // Reading the oldest block keeps it
+    assert_eq!(map.get(0x00..0x04).unwrap(), &[0; 4]);
+    map.insert(vec![4; 0x10], 0x400);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x000..0x010, 0x200..0x210, 0x300..0x310, 0x400..0x410]
+    );
+    assert_eq!(
+        *evicted.lock().unwrap(),
+        vec![(0x100..0x110, vec![1; 0x10])]
+    );
+    assert!(!map.contains_range(&(0x100..0x101)));
+
+    // Inserting into a block accesses it, the inserted block is never evicted
+    map.get_mut(0x200..0x201).unwrap()[0] = 9;
+    map.insert(vec![5; 0x20], 0x308);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x200..0x210, 0x300..0x328]
+    );
+    assert_eq!(evicted.lock().unwrap().len(), 3);
+    map.insert(vec![6; 0x80], 0x1000);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x1000..0x1080]);
+    assert_eq!(map.stored_len(), 0x80);
+    assert_eq!(evicted.lock().unwrap().len(), 5);
+    assert_eq!(map.capacity_limit(), Some(0x100));
+
+    // Hooks keep `SparseVec` usable across `catch_unwind`
+    fn unwind_safe<T: UnwindSafe + RefUnwindSafe>(_: &T) {}
+    unwind_safe(&map);
+}
+
+#[test]
+fn sparsevec_capacity_limit_random() {
+    use rand::{Rng, SeedableRng};
+
+    let max_bytes = 0x1000;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(174);
+    let mut map = SparseVec::<u16>::with_capacity_limit(max_bytes);
+    let hot = 0x50000..0x50040;
+    map.insert(vec![7; 0x40], hot.start);
+    for i in 0..2000u64 {
+        let addr = rng.gen_range(0..0x4000) * 0x10;
+        map.insert(vec![i as u16; rng.gen_range(1..0x40)], addr);
+        map.assert_invariants();
+        assert!(map.stored_len() * 2 <= max_bytes);
+        // Read every few inserts, so it never becomes the least recently used
+        if i % 8 == 0 {
+            assert_eq!(map.get(hot.clone()).unwrap(), &[7; 0x40]);
+        }
+    }
+}
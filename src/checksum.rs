@@ -0,0 +1,172 @@
+use core::fmt;
+use core::ops::Range;
+
+use crate::SparseVec;
+
+/// How operations over a range treat addresses that are not stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GapPolicy {
+    /// Fail at the first unmapped address.
+    Error,
+    /// Leave unmapped addresses out, as if the stored data was contiguous.
+    Skip,
+    /// Use this value for every unmapped address.
+    TreatAsValue(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgo {
+    /// CRC-32 as used by zlib, PNG and Ethernet.
+    Crc32,
+    /// Sum of all bytes, wrapping at `u64::MAX`.
+    Sum,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumError {
+    /// The range has a gap at `addr` and the policy is [`GapPolicy::Error`].
+    Unmapped { addr: u64 },
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChecksumError::Unmapped { addr } => write!(f, "address {addr:#x} is unmapped"),
+        }
+    }
+}
+
+impl core::error::Error for ChecksumError {}
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                crc >> 1 ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+enum State {
+    Crc32(u32),
+    Sum(u64),
+}
+
+impl State {
+    fn update(&mut self, bytes: &[u8]) {
+        match self {
+            State::Crc32(crc) => {
+                for b in bytes {
+                    *crc = CRC32_TABLE[((*crc ^ *b as u32) & 0xff) as usize] ^ *crc >> 8;
+                }
+            }
+            State::Sum(sum) => {
+                *sum = bytes
+                    .iter()
+                    .fold(*sum, |sum, b| sum.wrapping_add(*b as u64));
+            }
+        }
+    }
+
+    fn update_gap(&mut self, value: u8, len: u64) {
+        let chunk = [value; 256];
+        let mut left = len;
+        while left > 0 {
+            let n = left.min(chunk.len() as u64);
+            self.update(&chunk[..n as usize]);
+            left -= n;
+        }
+    }
+
+    fn finish(self) -> u64 {
+        match self {
+            State::Crc32(crc) => !crc as u64,
+            State::Sum(sum) => sum,
+        }
+    }
+}
+
+// CRC-32 of `bytes`, as `ChecksumAlgo::Crc32` computes it
+#[cfg(feature = "std")]
+pub(crate) fn crc32(bytes: &[u8]) -> u32 {
+    let mut state = State::Crc32(!0);
+    state.update(bytes);
+    state.finish() as u32
+}
+
+impl SparseVec<u8> {
+    /// Checksum of the bytes in `range`, computed over the stored slices directly.
+    pub fn checksum(
+        &self,
+        range: Range<u64>,
+        algo: ChecksumAlgo,
+        gaps: GapPolicy,
+    ) -> Result<u64, ChecksumError> {
+        let mut state = match algo {
+            ChecksumAlgo::Crc32 => State::Crc32(!0),
+            ChecksumAlgo::Sum => State::Sum(0),
+        };
+        let mut pos = range.start;
+        let gap = |state: &mut State, from: u64, to: u64| match gaps {
+            _ if from >= to => Ok(()),
+            GapPolicy::Error => Err(ChecksumError::Unmapped { addr: from }),
+            GapPolicy::Skip => Ok(()),
+            GapPolicy::TreatAsValue(value) => {
+                state.update_gap(value, to - from);
+                Ok(())
+            }
+        };
+        for (slice_range, data) in self.slices(range.clone()) {
+            gap(&mut state, pos, slice_range.start)?;
+            state.update(data);
+            pos = slice_range.end;
+        }
+        gap(&mut state, pos, range.end)?;
+        Ok(state.finish())
+    }
+}
+
+#[test]
+fn sparsevec_checksum() {
+    let mut map = SparseVec::new();
+    map.insert(b"123456789".to_vec(), 0x100);
+    map.insert(b"abc".to_vec(), 0x10);
+    map.insert(b"def".to_vec(), 0x15);
+
+    let crc = |range, gaps| map.checksum(range, ChecksumAlgo::Crc32, gaps);
+    assert_eq!(crc(0x100..0x109, GapPolicy::Error), Ok(0xcbf4_3926));
+    assert_eq!(crc(0x10..0x18, GapPolicy::Skip), Ok(0x4b8e_39ef));
+    assert_eq!(
+        crc(0x10..0x18, GapPolicy::TreatAsValue(0xff)),
+        Ok(0x0cce_a67a)
+    );
+    assert_eq!(
+        crc(0x10..0x18, GapPolicy::Error),
+        Err(ChecksumError::Unmapped { addr: 0x13 })
+    );
+    assert_eq!(crc(0xf..0x1a, GapPolicy::Skip), Ok(0x4b8e_39ef));
+    assert_eq!(
+        crc(0xf..0x1a, GapPolicy::Error),
+        Err(ChecksumError::Unmapped { addr: 0xf })
+    );
+    assert_eq!(crc(0x20..0x20, GapPolicy::Error), Ok(0));
+
+    let sum = |range, gaps| map.checksum(range, ChecksumAlgo::Sum, gaps);
+    assert_eq!(sum(0x10..0x18, GapPolicy::Skip), Ok(597));
+    assert_eq!(sum(0x10..0x18, GapPolicy::TreatAsValue(0xff)), Ok(1107));
+    assert_eq!(
+        sum(0x0..0x100_0000, GapPolicy::TreatAsValue(0)),
+        sum(0x10..0x200, GapPolicy::Skip)
+    );
+}
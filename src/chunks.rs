@@ -0,0 +1,103 @@
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Fully stored chunks of `chunk` elements starting at `range.start + k * chunk`, within
+    /// `range`. Chunks that overlap a gap or the end of `range` are skipped.
+    ///
+    /// # Panics
+    ///
+    /// If `chunk` is zero.
+    pub fn chunks(&self, range: Range<A>, chunk: A) -> impl Iterator<Item = (A, &[T])> + '_ {
+        self.chunks_impl(range, chunk, false)
+    }
+
+    /// Like [`SparseVec::chunks`], but also yields chunks whose start is stored and that are
+    /// cut short by a gap or the end of `range`, truncated to the stored prefix.
+    pub fn chunks_partial(
+        &self,
+        range: Range<A>,
+        chunk: A,
+    ) -> impl Iterator<Item = (A, &[T])> + '_ {
+        self.chunks_impl(range, chunk, true)
+    }
+
+    fn chunks_impl(
+        &self,
+        range: Range<A>,
+        chunk: A,
+        partial: bool,
+    ) -> impl Iterator<Item = (A, &[T])> + '_ {
+        let chunk = chunk.to_u64();
+        assert!(chunk != 0, "chunk size must not be zero");
+        let origin = range.start.to_u64();
+        self.slices(range).flat_map(move |(clipped, slice)| {
+            let (start, end) = (clipped.start.to_u64(), clipped.end.to_u64());
+            let first = origin + (start - origin).div_ceil(chunk) * chunk;
+            // Chunk starts within the slice, stepping stops at the end of the address space
+            let starts = core::iter::successors(Some(first), move |c| c.checked_add(chunk))
+                .take_while(move |c| *c < end);
+            starts.filter_map(move |c| {
+                let chunk_end = c.saturating_add(chunk).min(end);
+                if !partial && chunk_end - c != chunk {
+                    return None;
+                }
+                let offset = (c - start) as usize;
+                Some((
+                    A::try_from_u64(c).unwrap(),
+                    &slice[offset..offset + (chunk_end - c) as usize],
+                ))
+            })
+        })
+    }
+}
+
+#[test]
+fn sparsevec_chunks() {
+    let mut map = SparseVec::new();
+    map.insert(Vec::from_iter(0u8..20), 0x100);
+    map.insert(Vec::from_iter(100u8..110), 0x118);
+
+    let starts = |iter: &mut dyn Iterator<Item = (u64, &[u8])>| {
+        Vec::from_iter(iter.map(|(addr, chunk)| (addr, chunk.len())))
+    };
+    assert_eq!(
+        starts(&mut map.chunks(0x100..0x200, 8)),
+        vec![(0x100, 8), (0x108, 8), (0x118, 8)]
+    );
+    assert_eq!(
+        Vec::from_iter(map.chunks(0x100..0x200, 8)).last(),
+        Some(&(0x118, &[100u8, 101, 102, 103, 104, 105, 106, 107][..]))
+    );
+    // Alignment follows the start of the range, not absolute addresses
+    assert_eq!(starts(&mut map.chunks(0xfe..0x200, 8)), vec![(0x106, 8)]);
+    assert_eq!(
+        starts(&mut map.chunks_partial(0xfe..0x200, 8)),
+        vec![(0x106, 8), (0x10e, 6), (0x11e, 4)]
+    );
+    // The end of the range cuts chunks
+    assert_eq!(
+        starts(&mut map.chunks(0x100..0x10c, 4)),
+        vec![(0x100, 4), (0x104, 4), (0x108, 4)]
+    );
+    assert_eq!(
+        starts(&mut map.chunks(0x100..0x10b, 4)),
+        vec![(0x100, 4), (0x104, 4)]
+    );
+    assert_eq!(
+        starts(&mut map.chunks_partial(0x100..0x10b, 4)),
+        vec![(0x100, 4), (0x104, 4), (0x108, 3)]
+    );
+
+    let mut top = SparseVec::new();
+    top.insert(vec![1u8; 6], u64::MAX - 6);
+    assert_eq!(
+        starts(&mut top.chunks(u64::MAX - 6..u64::MAX, 4)),
+        vec![(u64::MAX - 6, 4)]
+    );
+    assert_eq!(
+        starts(&mut top.chunks_partial(u64::MAX - 6..u64::MAX, 4)),
+        vec![(u64::MAX - 6, 4), (u64::MAX - 2, 2)]
+    );
+}
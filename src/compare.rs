@@ -0,0 +1,233 @@
+use core::ops::Range;
+
+use crate::{cast_range, sub_range, Address, CompareDiff, CompareError, SparseVec};
+
+impl<T: PartialEq + Clone, A: Address> SparseVec<T, A> {
+    /// Checks that `expected` is stored at `addr`, comparing against the block slices
+    /// directly. Reports the lowest address that is unmapped or differs, which is `A::MAX`
+    /// if `expected` runs past the end of the address space.
+    pub fn compare_range(&self, addr: A, expected: &[T]) -> Result<(), CompareError<T, A>> {
+        let Some(end) =
+            A::try_from_u64(expected.len() as u64).and_then(|len| addr.checked_add(len))
+        else {
+            let (expected, last) = expected.split_at((A::MAX - addr).to_usize());
+            self.compare_range(addr, expected)?;
+            // Only the element at `A::MAX` itself is left to compare
+            return match (&self.top, last) {
+                (Some(found), [expected]) if found != expected => Err(CompareError::Mismatch {
+                    addr: A::MAX,
+                    expected: expected.clone(),
+                    found: found.clone(),
+                }),
+                (Some(_), [_]) => Ok(()),
+                _ => Err(CompareError::Unmapped { addr: A::MAX }),
+            };
+        };
+        let range = addr..end;
+        let mut pos = addr;
+        for (clipped, slice) in self.slices(range.clone()) {
+            if clipped.start > pos {
+                return Err(CompareError::Unmapped { addr: pos });
+            }
+            let expected = &expected[cast_range(sub_range(&clipped, addr))];
+            if let Some(i) = slice.iter().zip(expected).position(|(a, b)| a != b) {
+                return Err(CompareError::Mismatch {
+                    addr: clipped.start + A::from_usize(i),
+                    expected: expected[i].clone(),
+                    found: slice[i].clone(),
+                });
+            }
+            pos = clipped.end;
+        }
+        if pos < range.end {
+            return Err(CompareError::Unmapped { addr: pos });
+        }
+        Ok(())
+    }
+}
+
+impl<T: PartialEq, A: Address> SparseVec<T, A> {
+    /// Checks that `other` stores the same as `my_range` at the same offsets from
+    /// `other_addr`, e.g. the same code loaded at two bases. Unmapped elements on both sides
+    /// count as equal. Reports the lowest offset where the coverage or contents differ,
+    /// regardless of how either side is split into blocks.
+    ///
+    /// Panics if the range at `other_addr` does not fit in the address space.
+    pub fn compare_with(
+        &self,
+        my_range: Range<A>,
+        other: &SparseVec<T, A>,
+        other_addr: A,
+    ) -> Result<(), CompareDiff<A>> {
+        if my_range.is_empty() {
+            return Ok(());
+        }
+        let len = my_range.end - my_range.start;
+        let other_range = other_addr
+            ..other_addr
+                .checked_add(len)
+                .expect("compare_with range does not fit in the address space");
+        // Both sides as slices at offsets from the range starts
+        let mut mine = self
+            .slices(my_range.clone())
+            .map(|(range, slice)| (sub_range(&range, my_range.start), slice));
+        let mut theirs = other
+            .slices(other_range)
+            .map(|(range, slice)| (sub_range(&range, other_addr), slice));
+        let (mut a, mut b) = (mine.next(), theirs.next());
+        let mut pos = A::ZERO;
+        loop {
+            let a_here = a.as_ref().filter(|(range, _)| range.start <= pos);
+            let b_here = b.as_ref().filter(|(range, _)| range.start <= pos);
+            match (a_here, b_here) {
+                (Some((a_range, a_slice)), Some((b_range, b_slice))) => {
+                    let end = a_range.end.min(b_range.end);
+                    let a_part = &a_slice[cast_range(sub_range(&(pos..end), a_range.start))];
+                    let b_part = &b_slice[cast_range(sub_range(&(pos..end), b_range.start))];
+                    if let Some(i) = a_part.iter().zip(b_part).position(|(a, b)| a != b) {
+                        return Err(CompareDiff::Content {
+                            offset: pos + A::from_usize(i),
+                        });
+                    }
+                    if a_range.end == end {
+                        a = mine.next();
+                    }
+                    if b_range.end == end {
+                        b = theirs.next();
+                    }
+                    pos = end;
+                }
+                (Some(_), None) => {
+                    return Err(CompareDiff::Coverage {
+                        offset: pos,
+                        in_self: true,
+                    })
+                }
+                (None, Some(_)) => {
+                    return Err(CompareDiff::Coverage {
+                        offset: pos,
+                        in_self: false,
+                    })
+                }
+                // Skip the gap both sides share
+                (None, None) => match (&a, &b) {
+                    (Some((a_range, _)), Some((b_range, _))) => {
+                        pos = a_range.start.min(b_range.start)
+                    }
+                    (Some((range, _)), None) | (None, Some((range, _))) => pos = range.start,
+                    (None, None) => return Ok(()),
+                },
+            }
+        }
+    }
+}
+
+#[test]
+fn sparsevec_compare_range() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8, 2, 3, 4], 0x10);
+    map.insert(vec![5u8, 6], 0x14);
+    map.insert(vec![9u8; 4], 0x20);
+
+    assert_eq!(map.compare_range(0x11, &[2, 3, 4, 5, 6]), Ok(()));
+    assert_eq!(map.compare_range(0x30, &[]), Ok(()));
+    // At the boundary of the two merged inserts
+    assert_eq!(
+        map.compare_range(0x10, &[1, 2, 3, 4, 7, 6]),
+        Err(CompareError::Mismatch {
+            addr: 0x14,
+            expected: 7,
+            found: 5
+        })
+    );
+    // Holes are reported before later mismatches
+    let mut expected = vec![5, 6];
+    expected.extend_from_slice(&[0; 10]);
+    expected.extend_from_slice(&[9, 9, 8, 9]);
+    assert_eq!(
+        map.compare_range(0x14, &expected),
+        Err(CompareError::Unmapped { addr: 0x16 })
+    );
+    assert_eq!(
+        map.compare_range(0x1e, &[9; 4]),
+        Err(CompareError::Unmapped { addr: 0x1e })
+    );
+    assert_eq!(
+        map.compare_range(0x22, &[9; 4]),
+        Err(CompareError::Unmapped { addr: 0x24 })
+    );
+    assert_eq!(
+        map.compare_range(0x20, &[9, 9, 8]).unwrap_err().to_string(),
+        "expected 8 at 0x22, found 9"
+    );
+
+    // Past the end of the address space
+    map.insert(vec![1u8; 4], u64::MAX - 4);
+    assert_eq!(
+        map.compare_range(u64::MAX - 4, &[1; 5]),
+        Err(CompareError::Unmapped { addr: u64::MAX })
+    );
+    assert_eq!(
+        map.compare_range(u64::MAX - 4, &[1, 2, 1, 1, 1]),
+        Err(CompareError::Mismatch {
+            addr: u64::MAX - 3,
+            expected: 2,
+            found: 1
+        })
+    );
+}
+
+#[test]
+fn sparsevec_compare_with() {
+    let code = Vec::from_iter(0..0x40u8);
+    let mut a = SparseVec::new();
+    a.insert(code[..0x10].to_vec(), 0x8000);
+    a.insert(code[0x10..0x18].to_vec(), 0x8010);
+    a.insert(code[0x20..].to_vec(), 0x8020);
+    a.insert(vec![0xff; 4], 0x9000);
+    let mut b = SparseVec::new();
+    b.set_auto_merge(false);
+    for (start, end) in [(0, 6), (6, 0x15), (0x15, 0x18), (0x20, 0x21), (0x21, 0x40)] {
+        b.insert(code[start..end].to_vec(), start as u64);
+    }
+
+    // Same contents and gaps, split differently
+    assert_eq!(a.compare_with(0x8000..0x8040, &b, 0), Ok(()));
+    assert_eq!(b.compare_with(0x04..0x30, &a, 0x8004), Ok(()));
+    assert_eq!(a.compare_with(0x8018..0x8020, &b, 0x18), Ok(()));
+    assert_eq!(a.compare_with(0x8000..0x8000, &b, 0x1000), Ok(()));
+
+    b.write(0x22, &[0]).unwrap();
+    assert_eq!(
+        a.compare_with(0x8000..0x8040, &b, 0),
+        Err(CompareDiff::Content { offset: 0x22 })
+    );
+    assert_eq!(
+        a.compare_with(0x8000..0x8040, &b, 0)
+            .unwrap_err()
+            .to_string(),
+        "contents differ at offset 0x22"
+    );
+    // Coverage differs before the contents do
+    b.insert(vec![0x1c], 0x1c);
+    assert_eq!(
+        a.compare_with(0x8000..0x8040, &b, 0),
+        Err(CompareDiff::Coverage {
+            offset: 0x1c,
+            in_self: false
+        })
+    );
+    assert_eq!(
+        a.compare_with(0x8030..0x9010, &b, 0x30),
+        Err(CompareDiff::Coverage {
+            offset: 0xfd0,
+            in_self: true
+        })
+    );
+    assert_eq!(
+        a.compare_with(0x8ff0..0x9004, &b, 0x1000)
+            .unwrap_err()
+            .to_string(),
+        "offset 0x10 is only mapped on this side"
+    );
+}
@@ -0,0 +1,226 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+use crate::keys::KeyMap;
+use crate::storage::Storage;
+use crate::{Address, SparseVec};
+
+// Set by `SparseVec::with_compression`
+pub(crate) struct Compression {
+    threshold: usize,
+    // Whether each block that existed at the last `compress_cold_blocks` was accessed since.
+    // Blocks created after it are missing and count as accessed.
+    accessed: KeyMap<AtomicBool>,
+}
+
+impl Compression {
+    pub(crate) fn touch(&self, key: usize) {
+        if let Some(accessed) = self.accessed.get(&key) {
+            accessed.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Data of a block compressed by [`SparseVec::compress_cold_blocks`]. Reads go through a
+/// decompressed copy made on the first of them, writes decompress the block for good.
+pub(crate) struct Compressed<T> {
+    data: Box<[u8]>,
+    len: usize,
+    cache: OnceLock<Vec<T>>,
+    // Only built for `Compressed<u8>`, like `Storage::Bytes`
+    decompress: fn(&[u8], usize) -> Vec<T>,
+}
+
+impl<T> Compressed<T> {
+    pub(crate) fn get(&self) -> &[T] {
+        self.cache
+            .get_or_init(|| (self.decompress)(&self.data, self.len))
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<T> {
+        let Self {
+            data,
+            len,
+            cache,
+            decompress,
+        } = self;
+        cache.into_inner().unwrap_or_else(|| decompress(&data, len))
+    }
+}
+
+/// What [`SparseVec::compression_stats`] found.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionStats {
+    /// Size of the data of all blocks.
+    pub raw_bytes: usize,
+    /// Size of the data as held in memory, compressed blocks counting their compressed data
+    /// and their decompressed copy if they have one.
+    pub stored_bytes: usize,
+    pub compressed_blocks: usize,
+    /// Compressed blocks with a decompressed copy made by a read since they were compressed.
+    pub cached_blocks: usize,
+}
+
+impl<A: Address> SparseVec<u8, A> {
+    /// Empty `SparseVec` that [`SparseVec::compress_cold_blocks`] keeps LZ4 compressed, for
+    /// large and mostly cold captures. Only blocks of at least `threshold` bytes that
+    /// compress to fewer bytes are compressed.
+    ///
+    /// Compressed blocks are transparent: reading one decompresses it into a copy that is
+    /// kept until the next `compress_cold_blocks` finds the block cold again, so repeated
+    /// reads decompress it once. Mutating a compressed block, including by an overlapping
+    /// insert, decompresses it for good.
+    pub fn with_compression(threshold: usize) -> Self {
+        Self {
+            compression: Some(Compression {
+                threshold,
+                accessed: KeyMap::default(),
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// The threshold passed to [`SparseVec::with_compression`], if any.
+    pub fn compression_threshold(&self) -> Option<usize> {
+        self.compression
+            .as_ref()
+            .map(|compression| compression.threshold)
+    }
+
+    /// Compresses the blocks not accessed since the last call, and drops the decompressed
+    /// copies of compressed blocks not accessed since then. An access is a
+    /// [`SparseVec::get`], [`SparseVec::get_mut`] or [`SparseVec::get_block`] of a block, as
+    /// for [`SparseVec::with_capacity_limit`], and blocks created since the last call count
+    /// as accessed.
+    ///
+    /// Does nothing without [`SparseVec::with_compression`]. Shared blocks are never
+    /// compressed.
+    pub fn compress_cold_blocks(&mut self) {
+        let Some(compression) = &mut self.compression else {
+            return;
+        };
+        for (key, storage) in self.data.iter_mut() {
+            let accessed = compression
+                .accessed
+                .get(key)
+                .is_none_or(|accessed| accessed.load(Ordering::Relaxed));
+            if accessed {
+                continue;
+            }
+            match storage {
+                Storage::Vec(data) if data.len() >= compression.threshold => {
+                    let compressed = lz4_flex::compress(data);
+                    if compressed.len() < data.len() {
+                        *storage = Storage::Compressed(Box::new(Compressed {
+                            data: compressed.into(),
+                            len: data.len(),
+                            cache: OnceLock::new(),
+                            decompress: |data, len| {
+                                lz4_flex::decompress(data, len).expect("corrupt compressed block")
+                            },
+                        }));
+                    }
+                }
+                Storage::Compressed(data) => drop(data.cache.take()),
+                _ => {}
+            }
+        }
+        compression.accessed = self
+            .data
+            .keys()
+            .map(|key| (*key, AtomicBool::new(false)))
+            .collect();
+    }
+
+    pub fn compression_stats(&self) -> CompressionStats {
+        let mut stats = CompressionStats::default();
+        for storage in self.data.values() {
+            let (raw, stored) = match storage {
+                Storage::Compressed(data) => {
+                    stats.compressed_blocks += 1;
+                    let cached = data.cache.get().map_or(0, Vec::len);
+                    stats.cached_blocks += usize::from(data.cache.get().is_some());
+                    (data.len, data.data.len() + cached)
+                }
+                _ => (storage.len(), storage.len()),
+            };
+            stats.raw_bytes += raw;
+            stats.stored_bytes += stored;
+        }
+        stats
+    }
+}
+
+#[test]
+fn sparsevec_compress_cold_blocks() {
+    let page = |i: u8| Vec::from_iter((0..0x1000).map(|j| if j % 64 == 0 { i } else { 0 }));
+    let mut map = SparseVec::<u8>::with_compression(0x100);
+    map.insert(page(1), 0x0000);
+    map.insert(page(2), 0x2000);
+    map.insert(vec![0; 0x10], 0x4000);
+    assert_eq!(map.compression_threshold(), Some(0x100));
+
+    // New blocks count as accessed
+    map.compress_cold_blocks();
+    assert_eq!(map.compression_stats().compressed_blocks, 0);
+    map.compress_cold_blocks();
+    let stats = map.compression_stats();
+    assert_eq!(stats.compressed_blocks, 2);
+    assert_eq!(stats.raw_bytes, 0x2010);
+    assert!(stats.stored_bytes < 0x200);
+
+    // Reads decompress into a copy that is kept while the block is accessed
+    assert_eq!(map.get(0x1f0..0x1000).unwrap(), &page(1)[0x1f0..]);
+    assert_eq!(map.compression_stats().cached_blocks, 1);
+    map.compress_cold_blocks();
+    assert_eq!(map.compression_stats().cached_blocks, 1);
+    map.compress_cold_blocks();
+    assert_eq!(map.compression_stats().cached_blocks, 0);
+    assert_eq!(map.compression_stats().raw_bytes, 0x2010);
+
+    // Iterating reads without counting as an access
+    assert!(map
+        .blocks()
+        .map(|(_, data)| data)
+        .eq([&page(1)[..], &page(2), &[0; 0x10]]));
+    assert_eq!(map.compression_stats().cached_blocks, 2);
+    map.compress_cold_blocks();
+    assert_eq!(map.compression_stats().cached_blocks, 0);
+
+    // Writes and overlapping inserts decompress for good
+    map.write(0x2001, &[7]).unwrap();
+    map.insert(vec![9; 4], 0xffe);
+    map.assert_invariants();
+    let stats = map.compression_stats();
+    assert_eq!((stats.compressed_blocks, stats.cached_blocks), (0, 0));
+    assert_eq!(stats.stored_bytes, stats.raw_bytes);
+    assert_eq!(map.get(0x2000..0x2002).unwrap(), &[2, 7]);
+    assert_eq!(map.get(0xffc..0x1002).unwrap(), &[0, 0, 9, 9, 9, 9]);
+}
+
+#[test]
+fn sparsevec_compress_incompressible() {
+    use rand::{RngCore, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(196);
+    let mut noise = vec![0; 0x1000];
+    rng.fill_bytes(&mut noise);
+    let mut map = SparseVec::<u8>::with_compression(0x100);
+    map.insert(noise.clone(), 0);
+    map.insert_shared(alloc::sync::Arc::from(vec![0; 0x1000]), 0x2000);
+    map.compress_cold_blocks();
+    map.compress_cold_blocks();
+    assert_eq!(map.compression_stats().compressed_blocks, 0);
+    assert!(map.is_shared(0x2000));
+    assert_eq!(map.get(0..0x1000).unwrap(), &noise);
+
+    // Not enabled
+    let mut plain = SparseVec::new();
+    plain.insert(vec![0u8; 0x1000], 0);
+    plain.compress_cold_blocks();
+    plain.compress_cold_blocks();
+    assert_eq!(plain.compression_threshold(), None);
+    assert_eq!(plain.compression_stats().compressed_blocks, 0);
+}
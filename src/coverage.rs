@@ -0,0 +1,389 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::journal::JournalOp;
+use crate::{cast_range, sub_range, Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Addresses covered by `self` or `other`, as sorted and coalesced ranges.
+    pub fn coverage_union<U>(&self, other: &SparseVec<U, A>) -> Vec<Range<A>> {
+        let mut left = self.map.iter().map(|(r, _)| r).peekable();
+        let mut right = other.map.iter().map(|(r, _)| r).peekable();
+        let mut result: Vec<Range<A>> = Vec::new();
+        loop {
+            let next = match (left.peek(), right.peek()) {
+                (Some(a), Some(b)) if a.start <= b.start => left.next(),
+                (Some(_), Some(_)) => right.next(),
+                (Some(_), None) => left.next(),
+                (None, _) => right.next(),
+            };
+            let Some(next) = next else {
+                break;
+            };
+            match result.last_mut() {
+                Some(last) if last.end >= next.start => last.end = last.end.max(next.end),
+                _ => result.push(next.clone()),
+            }
+        }
+        result
+    }
+
+    /// Addresses covered by both `self` and `other`.
+    pub fn coverage_intersection<U>(&self, other: &SparseVec<U, A>) -> Vec<Range<A>> {
+        let mut left = self.map.iter().map(|(r, _)| r).peekable();
+        let mut right = other.map.iter().map(|(r, _)| r).peekable();
+        let mut result = Vec::new();
+        while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+            let common = a.start.max(b.start)..a.end.min(b.end);
+            if !common.is_empty() {
+                result.push(common);
+            }
+            if a.end <= b.end {
+                left.next();
+            } else {
+                right.next();
+            }
+        }
+        result
+    }
+
+    /// Addresses covered by `self` but not by `other`.
+    pub fn coverage_difference<U>(&self, other: &SparseVec<U, A>) -> Vec<Range<A>> {
+        let mut right = other.map.iter().map(|(r, _)| r).peekable();
+        let mut result = Vec::new();
+        for (range, _) in self.map.iter() {
+            let mut start = range.start;
+            while let Some(b) = right.peek() {
+                if b.end <= start {
+                    right.next();
+                    continue;
+                }
+                if b.start >= range.end {
+                    break;
+                }
+                if b.start > start {
+                    result.push(start..b.start);
+                }
+                start = b.end;
+                if b.end > range.end {
+                    break;
+                }
+                right.next();
+            }
+            if start < range.end {
+                result.push(start..range.end);
+            }
+        }
+        result
+    }
+
+    /// Whether every address stored in `other` is also stored in `self`.
+    pub fn covers<U>(&self, other: &SparseVec<U, A>) -> bool {
+        other
+            .map
+            .iter()
+            .all(|(range, _)| self.contains_range(range))
+    }
+
+    /// Whether every address stored in `self` is also stored in `other`.
+    pub fn covered_by<U>(&self, other: &SparseVec<U, A>) -> bool {
+        other.covers(self)
+    }
+
+    /// Parts of `other` that `self` does not cover. Empty exactly when `self.covers(other)`.
+    pub fn missing_from<U>(&self, other: &SparseVec<U, A>) -> Vec<Range<A>> {
+        other.coverage_difference(self)
+    }
+
+    /// Whether each address of `range` is stored, in address order.
+    ///
+    /// Panics if the length of `range` does not fit `usize`.
+    pub fn coverage_mask(&self, range: Range<A>) -> Vec<bool> {
+        let mut mask = vec![false; window_len(&range)];
+        for covered in self.ranges_in(range.clone()) {
+            mask[cast_range(sub_range(&covered, range.start))].fill(true);
+        }
+        mask
+    }
+
+    /// [`SparseVec::coverage_mask`] packed into words. Bit `i % 64` of word `i / 64`, counting
+    /// from the least significant bit, is set if `range.start + i` is stored. Bits past the
+    /// end of the range are clear.
+    ///
+    /// Panics if the length of `range` does not fit `usize`.
+    pub fn coverage_bits(&self, range: Range<A>) -> Vec<u64> {
+        let mut bits = vec![0u64; window_len(&range).div_ceil(64)];
+        for covered in self.ranges_in(range.clone()) {
+            let Range { start, end } = cast_range(sub_range(&covered, range.start));
+            let (first, last) = (start / 64, (end - 1) / 64);
+            // Ones from bit `start % 64` up, and below bit `end % 64` in the last word
+            let head = !0u64 << (start % 64);
+            let tail = !0u64 >> (63 - (end - 1) % 64);
+            if first == last {
+                bits[first] |= head & tail;
+            } else {
+                bits[first] |= head;
+                bits[first + 1..last].fill(!0);
+                bits[last] |= tail;
+            }
+        }
+        bits
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Copy of the data at the addresses `coverage` also stores, e.g. the sections of a
+    /// manifest cut from a full dump. Only the coverage of `coverage` matters, the values are
+    /// those of `self`.
+    pub fn mask<U>(&self, coverage: &SparseVec<U, A>) -> Self {
+        Self::from_sorted_blocks(
+            self.coverage_intersection(coverage)
+                .into_iter()
+                .map(|range| (range.start, self.get(range).unwrap().to_vec())),
+        )
+    }
+
+    /// Like [`SparseVec::mask`], but unmaps the other addresses in place, splitting the blocks
+    /// `coverage` starts or ends in.
+    ///
+    /// Panics if an unmapped address is frozen.
+    pub fn mask_in_place<U>(&mut self, coverage: &SparseVec<U, A>) {
+        let removed = self.coverage_difference(coverage);
+        for range in &removed {
+            self.assert_thawed(range);
+        }
+        for range in removed {
+            let len = (range.end - range.start).to_u64();
+            self.journal(JournalOp::Remove, range.start, len, &[]);
+            self.record_history(&range);
+            self.remove_unwatched(range);
+        }
+    }
+}
+
+fn window_len<A: Address>(range: &Range<A>) -> usize {
+    let len = if range.is_empty() {
+        0
+    } else {
+        (range.end - range.start).to_u64()
+    };
+    usize::try_from(len)
+        .unwrap_or_else(|_| panic!("window of {len:#x} addresses does not fit usize"))
+}
+
+#[cfg(test)]
+fn coverage_test_vec(ranges: &[Range<u64>]) -> SparseVec<()> {
+    let mut map = SparseVec::new();
+    for range in ranges {
+        map.insert(vec![(); (range.end - range.start) as usize], range.start);
+    }
+    map
+}
+
+#[test]
+fn sparsevec_coverage_algebra() {
+    // Interleaved
+    let a = coverage_test_vec(&[0..10, 20..30, 40..50]);
+    let b = coverage_test_vec(&[5..25, 30..40, 45..60]);
+    assert_eq!(a.coverage_union(&b), vec![0..60]);
+    assert_eq!(a.coverage_intersection(&b), vec![5..10, 20..25, 45..50]);
+    assert_eq!(a.coverage_difference(&b), vec![0..5, 25..30, 40..45]);
+    assert_eq!(b.coverage_difference(&a), vec![10..20, 30..40, 50..60]);
+
+    // Nested
+    let outer = coverage_test_vec(&[0..100, 200..300]);
+    let inner = coverage_test_vec(&[10..20, 30..40, 250..260]);
+    assert_eq!(outer.coverage_union(&inner), vec![0..100, 200..300]);
+    assert_eq!(
+        outer.coverage_intersection(&inner),
+        vec![10..20, 30..40, 250..260]
+    );
+    assert_eq!(
+        outer.coverage_difference(&inner),
+        vec![0..10, 20..30, 40..100, 200..250, 260..300]
+    );
+    assert!(inner.coverage_difference(&outer).is_empty());
+
+    // Identical, with different element types
+    let mut bytes = SparseVec::new();
+    bytes.insert(vec![0u8; 10], 0);
+    bytes.insert(vec![0u8; 10], 20);
+    let units = coverage_test_vec(&[0..10, 20..30]);
+    assert_eq!(bytes.coverage_union(&units), vec![0..10, 20..30]);
+    assert_eq!(bytes.coverage_intersection(&units), vec![0..10, 20..30]);
+    assert!(bytes.coverage_difference(&units).is_empty());
+
+    let empty = SparseVec::<u8>::new();
+    assert_eq!(bytes.coverage_union(&empty), vec![0..10, 20..30]);
+    assert!(bytes.coverage_intersection(&empty).is_empty());
+    assert_eq!(bytes.coverage_difference(&empty), vec![0..10, 20..30]);
+    assert!(empty.coverage_difference(&bytes).is_empty());
+}
+
+#[test]
+fn sparsevec_coverage_algebra_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    for _ in 0..100 {
+        let mut random = || {
+            let ranges = Vec::from_iter((0..rng.gen_range(0..20)).map(|_| {
+                let start = rng.gen_range(0..200u64);
+                start..start + rng.gen_range(1..20)
+            }));
+            coverage_test_vec(&ranges)
+        };
+        let (a, b) = (random(), random());
+        let covered = |ranges: &[Range<u64>], addr| ranges.iter().any(|r| r.contains(&addr));
+        let a_ranges = Vec::from_iter(a.ranges());
+        let b_ranges = Vec::from_iter(b.ranges());
+        let union = a.coverage_union(&b);
+        let intersection = a.coverage_intersection(&b);
+        let difference = a.coverage_difference(&b);
+        for result in [&union, &intersection, &difference] {
+            assert!(result.windows(2).all(|w| w[0].end < w[1].start));
+            assert!(result.iter().all(|r| !r.is_empty()));
+        }
+        for addr in 0..250 {
+            let (in_a, in_b) = (covered(&a_ranges, addr), covered(&b_ranges, addr));
+            assert_eq!(covered(&union, addr), in_a || in_b);
+            assert_eq!(covered(&intersection, addr), in_a && in_b);
+            assert_eq!(covered(&difference, addr), in_a && !in_b);
+        }
+    }
+}
+
+#[test]
+fn sparsevec_covers() {
+    let target = coverage_test_vec(&[0..10, 20..30]);
+
+    let exact = coverage_test_vec(&[0..10, 20..30]);
+    assert!(target.covers(&exact) && target.covered_by(&exact));
+    assert!(target.missing_from(&exact).is_empty());
+
+    let inside = coverage_test_vec(&[2..4, 9..10, 20..30]);
+    assert!(target.covers(&inside));
+    assert!(!target.covered_by(&inside));
+    assert!(inside.covered_by(&target));
+    assert_eq!(inside.missing_from(&target), vec![0..2, 4..9]);
+
+    // Interleaved, crossing the gap between the target's blocks
+    let patch = coverage_test_vec(&[5..12, 18..22, 29..31]);
+    assert!(!target.covers(&patch));
+    assert!(!patch.covers(&target));
+    assert_eq!(target.missing_from(&patch), vec![10..12, 18..20, 30..31]);
+    assert_eq!(patch.missing_from(&target), vec![0..5, 22..29]);
+
+    let mut bytes = SparseVec::new();
+    bytes.insert(vec![0u8; 4], 21);
+    assert!(target.covers(&bytes));
+    assert!(target.covers(&SparseVec::<u8>::new()));
+    assert!(!SparseVec::<u8>::new().covers(&target));
+}
+
+#[test]
+fn sparsevec_coverage_mask() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(175);
+    let mut map = SparseVec::new();
+    for _ in 0..60 {
+        let start = rng.gen_range(0..0x800);
+        map.insert(vec![0u8; rng.gen_range(1..0x90)], start);
+    }
+    for _ in 0..500 {
+        let start = rng.gen_range(0..0x900);
+        let window = start..start + rng.gen_range(0..0x180);
+        let expected = Vec::from_iter(
+            window
+                .clone()
+                .map(|addr| map.contains_range(&(addr..addr + 1))),
+        );
+        assert_eq!(map.coverage_mask(window.clone()), expected);
+
+        let bits = map.coverage_bits(window.clone());
+        assert_eq!(bits.len(), expected.len().div_ceil(64));
+        for i in 0..bits.len() * 64 {
+            let set = bits[i / 64] >> (i % 64) & 1 == 1;
+            assert_eq!(
+                set,
+                expected.get(i).copied().unwrap_or(false),
+                "bit {i} of {window:x?}"
+            );
+        }
+    }
+    assert!(map.coverage_mask(0x10..0x10).is_empty());
+    assert!(map.coverage_bits(0x10..0x10).is_empty());
+}
+
+#[test]
+fn sparsevec_mask() {
+    let mut map = crate::sparse_vec! {
+        0x100 => Vec::from_iter(0..0x20u8),
+        0x200 => [0xff; 8],
+        0x300 => [0xee; 4],
+    };
+    // Boundaries in the middle of blocks, spanning gaps and past the stored data
+    let manifest = coverage_test_vec(&[0x0f0..0x104, 0x108..0x110, 0x11c..0x204, 0x400..0x500]);
+    let masked = map.mask(&manifest);
+    masked.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(masked.ranges()),
+        vec![0x100..0x104, 0x108..0x110, 0x11c..0x120, 0x200..0x204]
+    );
+    assert_eq!(
+        masked.get(0x108..0x110).unwrap(),
+        &Vec::from_iter(8..0x10)[..]
+    );
+    assert_eq!(masked.get(0x11c..0x120).unwrap(), &[0x1c, 0x1d, 0x1e, 0x1f]);
+    assert_eq!(masked.get(0x200..0x204).unwrap(), &[0xff; 4]);
+    assert_eq!(map.ranges().len(), 3);
+
+    map.mask_in_place(&manifest);
+    map.assert_invariants();
+    assert!(map
+        .iter_range(0..u64::MAX)
+        .eq(masked.iter_range(0..u64::MAX)));
+    assert_eq!(map.mask(&SparseVec::<u8>::new()).ranges().len(), 0);
+    map.mask_in_place(&SparseVec::<u8>::new());
+    assert_eq!(map.ranges().len(), 0);
+}
+
+#[test]
+fn sparsevec_mask_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(192);
+    for _ in 0..100 {
+        let mut map = SparseVec::new();
+        map.set_auto_merge(rng.gen_bool(0.5));
+        for i in 0..rng.gen_range(0..12u32) {
+            let start = rng.gen_range(0..0x1c0);
+            map.insert(vec![i; rng.gen_range(1..0x40)], start);
+        }
+        let ranges = Vec::from_iter((0..rng.gen_range(0..12)).map(|_| {
+            let start = rng.gen_range(0..0x1c0);
+            start..start + rng.gen_range(1..0x40)
+        }));
+        let coverage = coverage_test_vec(&ranges);
+
+        let expected = Vec::from_iter(
+            map.iter_range(0..0x200)
+                .filter(|(addr, _)| coverage.contains_range(&(*addr..addr + 1)))
+                .map(|(addr, v)| (addr, *v)),
+        );
+        let masked = map.mask(&coverage);
+        masked.assert_invariants();
+        assert_eq!(
+            Vec::from_iter(masked.iter_range(0..0x200).map(|(addr, v)| (addr, *v))),
+            expected
+        );
+        map.mask_in_place(&coverage);
+        map.assert_invariants();
+        assert_eq!(
+            Vec::from_iter(map.iter_range(0..0x200).map(|(addr, v)| (addr, *v))),
+            expected
+        );
+    }
+}
@@ -0,0 +1,144 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Address, SparseVec};
+
+/// Why [`SparseVec::read_cstr`] found no string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CStrError<A = u64> {
+    /// Nothing is stored at the start of the string.
+    Unmapped { addr: A },
+    /// The bytes from the start run into a gap at `gap` before a NUL.
+    Unterminated { gap: A },
+    /// No NUL within the first `max_len` bytes.
+    TooLong { max_len: usize },
+}
+
+impl<A: Address> fmt::Display for CStrError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CStrError::Unmapped { addr } => write!(f, "address {addr:#x} is unmapped"),
+            CStrError::Unterminated { gap } => {
+                write!(f, "string runs into unmapped address {gap:#x} before a NUL")
+            }
+            CStrError::TooLong { max_len } => {
+                write!(f, "string is longer than {max_len} bytes")
+            }
+        }
+    }
+}
+
+impl<A: Address> core::error::Error for CStrError<A> {}
+
+impl<A: Address> SparseVec<u8, A> {
+    /// The bytes from `addr` up to, not including, the first NUL, which must come within
+    /// `max_len` bytes. Borrowed unless the string spans several blocks.
+    pub fn read_cstr(&self, addr: A, max_len: usize) -> Result<Cow<'_, [u8]>, CStrError<A>> {
+        // Up to `max_len` bytes and the NUL
+        let end = A::try_from_u64((max_len as u64).saturating_add(1))
+            .and_then(|len| addr.checked_add(len))
+            .unwrap_or(A::MAX);
+        let mut pieces: Vec<&[u8]> = Vec::new();
+        let mut next = addr;
+        for (clipped, slice) in self.slices(addr..end) {
+            if clipped.start != next {
+                break;
+            }
+            next = clipped.end;
+            match slice.iter().position(|&b| b == 0) {
+                Some(nul) if pieces.is_empty() => return Ok(Cow::Borrowed(&slice[..nul])),
+                // The NUL starts the next block
+                Some(0) if pieces.len() == 1 => return Ok(Cow::Borrowed(pieces[0])),
+                Some(nul) => {
+                    pieces.push(&slice[..nul]);
+                    return Ok(Cow::Owned(pieces.concat()));
+                }
+                None => pieces.push(slice),
+            }
+        }
+        if next == addr {
+            Err(CStrError::Unmapped { addr })
+        } else if (next - addr).to_u64() > max_len as u64 {
+            Err(CStrError::TooLong { max_len })
+        } else {
+            Err(CStrError::Unterminated { gap: next })
+        }
+    }
+
+    /// [`SparseVec::read_cstr`] converted to UTF-8, replacing invalid sequences.
+    pub fn read_cstr_lossy(&self, addr: A, max_len: usize) -> Option<String> {
+        let bytes = self.read_cstr(addr, max_len).ok()?;
+        Some(String::from_utf8_lossy(&bytes).into_owned())
+    }
+}
+
+#[test]
+fn sparsevec_read_cstr() {
+    let mut map = SparseVec::new();
+    map.insert(b"hello\0world".to_vec(), 0x100);
+    map.insert(b"gap".to_vec(), 0x200);
+    // NUL is the last stored byte
+    map.insert(b"end\0".to_vec(), 0x300);
+
+    assert_eq!(map.read_cstr(0x100, 0x40).unwrap(), &b"hello"[..]);
+    assert!(matches!(
+        map.read_cstr(0x100, 5),
+        Ok(Cow::Borrowed(b"hello"))
+    ));
+    assert_eq!(map.read_cstr(0x105, 0).unwrap(), &b""[..]);
+    assert_eq!(map.read_cstr(0x300, 3).unwrap(), &b"end"[..]);
+    assert_eq!(
+        map.read_cstr(0x100, 4),
+        Err(CStrError::TooLong { max_len: 4 })
+    );
+    assert_eq!(
+        map.read_cstr(0x106, 0x40),
+        Err(CStrError::Unterminated { gap: 0x10b })
+    );
+    assert_eq!(
+        map.read_cstr(0x203, 0x40),
+        Err(CStrError::Unmapped { addr: 0x203 })
+    );
+    // The limit is reached before the gap
+    assert_eq!(
+        map.read_cstr(0x200, 2),
+        Err(CStrError::TooLong { max_len: 2 })
+    );
+    assert_eq!(
+        map.read_cstr(0x200, 3),
+        Err(CStrError::Unterminated { gap: 0x203 })
+    );
+    assert_eq!(
+        map.read_cstr(0x200, usize::MAX).unwrap_err().to_string(),
+        "string runs into unmapped address 0x203 before a NUL"
+    );
+
+    map.insert(vec![b'a', 0xff, 0], 0x400);
+    assert_eq!(map.read_cstr_lossy(0x400, 8).unwrap(), "a\u{fffd}");
+    assert_eq!(map.read_cstr_lossy(0x100, 2), None);
+}
+
+#[test]
+fn sparsevec_read_cstr_block_boundary() {
+    let mut map = SparseVec::<u8>::with_max_block_len(4);
+    map.insert(b"abcd\0efgh".to_vec(), 0x10);
+    assert_eq!(map.ranges().len(), 3);
+
+    // NUL at the start of the next block
+    assert!(matches!(map.read_cstr(0x10, 8), Ok(Cow::Borrowed(b"abcd"))));
+    assert_eq!(map.read_cstr(0x12, 8).unwrap(), &b"cd"[..]);
+    // String spanning blocks, ending at the end of the last one
+    assert_eq!(
+        map.read_cstr(0x15, 8),
+        Err(CStrError::Unterminated { gap: 0x19 })
+    );
+    map.insert(vec![0], 0x19);
+    assert!(matches!(map.read_cstr(0x15, 4), Ok(Cow::Owned(_))));
+    assert_eq!(map.read_cstr(0x15, 4).unwrap(), &b"efgh"[..]);
+    assert_eq!(
+        map.read_cstr(0x15, 3),
+        Err(CStrError::TooLong { max_len: 3 })
+    );
+}
@@ -0,0 +1,141 @@
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Cursor over the stored data and the gaps between it, starting at `addr`.
+    pub fn cursor_at(&self, addr: A) -> Cursor<'_, T, A> {
+        Cursor {
+            vec: self,
+            pos: addr,
+        }
+    }
+}
+
+/// Piece of a [`SparseVec`] returned by [`Cursor::next_segment`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorSegment<'a, T, A = u64> {
+    /// Stored data from the position to the end of its block.
+    Data(&'a [T]),
+    /// Number of unmapped addresses from the position to the next block.
+    Gap(A),
+}
+
+/// Seekable read position in a [`SparseVec`], created by [`SparseVec::cursor_at`].
+///
+/// Unlike the `std::io` cursors this works for any element type and reports gaps as
+/// segments instead of errors.
+pub struct Cursor<'a, T, A = u64> {
+    vec: &'a SparseVec<T, A>,
+    pos: A,
+}
+
+// Not derived, that would require `T: Clone`
+impl<T, A: Address> Clone for Cursor<'_, T, A> {
+    fn clone(&self) -> Self {
+        Self {
+            vec: self.vec,
+            pos: self.pos,
+        }
+    }
+}
+
+impl<'a, T, A: Address> Cursor<'a, T, A> {
+    pub fn position(&self) -> A {
+        self.pos
+    }
+
+    /// Moves to `addr`, which may be before the current position.
+    pub fn seek(&mut self, addr: A) {
+        self.pos = addr;
+    }
+
+    /// Moves forward by `n` addresses, stopping at the end of the address space.
+    pub fn advance(&mut self, n: A) {
+        self.pos = self.pos.checked_add(n).unwrap_or(A::MAX);
+    }
+
+    /// Stored data from the position to the end of its block, empty inside a gap.
+    pub fn remaining_in_block(&self) -> &'a [T] {
+        match self.vec.map.get_key_value(&self.pos) {
+            Some((range, key)) => &self.vec.data[key][(self.pos - range.start).to_usize()..],
+            None => &[],
+        }
+    }
+
+    /// The data or gap at the position, moving past it. `None` once no data is stored at or
+    /// after the position.
+    pub fn next_segment(&mut self) -> Option<CursorSegment<'a, T, A>> {
+        let data = self.remaining_in_block();
+        if !data.is_empty() {
+            self.pos = self.pos + A::from_usize(data.len());
+            return Some(CursorSegment::Data(data));
+        }
+        let (next, _) = self.vec.map.overlapping(self.pos..A::MAX).next()?;
+        let gap = next.start - self.pos;
+        self.pos = next.start;
+        Some(CursorSegment::Gap(gap))
+    }
+}
+
+#[test]
+fn sparsevec_cursor() {
+    let mut map = SparseVec::new();
+    map.insert(vec!['a', 'b', 'c'], 0x10);
+    map.insert(vec!['d'], 0x20);
+    map.insert(vec!['e', 'f'], 0x26);
+
+    let mut cursor = map.cursor_at(0);
+    assert!(cursor.remaining_in_block().is_empty());
+    assert_eq!(cursor.next_segment(), Some(CursorSegment::Gap(0x10)));
+    assert_eq!(cursor.position(), 0x10);
+    assert_eq!(cursor.remaining_in_block(), &['a', 'b', 'c']);
+    cursor.advance(1);
+    assert_eq!(
+        cursor.next_segment(),
+        Some(CursorSegment::Data(&['b', 'c'][..]))
+    );
+    assert_eq!(cursor.next_segment(), Some(CursorSegment::Gap(0xd)));
+    assert_eq!(cursor.next_segment(), Some(CursorSegment::Data(&['d'][..])));
+    assert_eq!(cursor.next_segment(), Some(CursorSegment::Gap(5)));
+    assert_eq!(
+        cursor.next_segment(),
+        Some(CursorSegment::Data(&['e', 'f'][..]))
+    );
+    assert_eq!(cursor.position(), 0x28);
+    assert_eq!(cursor.next_segment(), None);
+    assert_eq!(cursor.position(), 0x28);
+
+    // Rewind into the middle of a block and to its last element
+    cursor.seek(0x11);
+    assert_eq!(cursor.remaining_in_block(), &['b', 'c']);
+    cursor.seek(0x12);
+    assert_eq!(cursor.next_segment(), Some(CursorSegment::Data(&['c'][..])));
+    assert_eq!(cursor.position(), 0x13);
+    cursor.seek(0x1f);
+    assert_eq!(cursor.next_segment(), Some(CursorSegment::Gap(1)));
+
+    // Segments cover every address up to the end of the stored data exactly once
+    let mut cursor = map.cursor_at(0x5);
+    let mut covered = 0x5;
+    while let Some(segment) = cursor.clone().next_segment() {
+        covered += match segment {
+            CursorSegment::Data(data) => data.len() as u64,
+            CursorSegment::Gap(len) => len,
+        };
+        cursor.next_segment();
+        assert_eq!(cursor.position(), covered);
+    }
+    assert_eq!(covered, map.bounds().unwrap().end);
+
+    let mut top = SparseVec::new();
+    top.insert(vec![1u8, 2], u64::MAX - 2);
+    let mut cursor = top.cursor_at(u64::MAX - 1);
+    assert_eq!(cursor.next_segment(), Some(CursorSegment::Data(&[2u8][..])));
+    assert_eq!(cursor.position(), u64::MAX);
+    assert_eq!(cursor.next_segment(), None);
+    cursor.seek(0);
+    cursor.advance(u64::MAX - 3);
+    assert_eq!(cursor.next_segment(), Some(CursorSegment::Gap(1)));
+    cursor.advance(u64::MAX);
+    assert_eq!(cursor.position(), u64::MAX);
+    assert!(cursor.remaining_in_block().is_empty());
+}
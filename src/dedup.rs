@@ -0,0 +1,200 @@
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use core::hash::{BuildHasher, Hash};
+
+use hashbrown::{DefaultHashBuilder, HashMap};
+
+use crate::storage::Storage;
+use crate::{Address, SparseVec};
+
+/// What [`SparseVec::dedup_blocks`] changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupStats {
+    /// Blocks now referencing the data of another block.
+    pub shared_blocks: usize,
+    /// Size of the data these blocks owned before.
+    pub bytes_saved: usize,
+}
+
+// Set by `SparseVec::set_insert_dedup`. `hash` and `eq` are those of `[T]`, so that
+// inserting does not need `T: Eq + Hash` everywhere.
+pub(crate) struct DedupIndex<T> {
+    hasher: DefaultHashBuilder,
+    hash: fn(&DefaultHashBuilder, &[T]) -> u64,
+    eq: fn(&[T], &[T]) -> bool,
+    // Data of deduplicated blocks by hash
+    buffers: HashMap<u64, Vec<Weak<[T]>>>,
+}
+
+impl<T> DedupIndex<T> {
+    fn add(&mut self, data: &Arc<[T]>) {
+        let hash = (self.hash)(&self.hasher, data);
+        self.buffers
+            .entry(hash)
+            .or_default()
+            .push(Arc::downgrade(data));
+    }
+}
+
+impl<T: Copy + Eq + Hash, A: Address> SparseVec<T, A> {
+    /// Makes blocks with identical contents share one allocation, like blocks inserted with
+    /// [`SparseVec::insert_shared`]. The first write to a sharing block copies it again.
+    /// Shared blocks are not merged with adjacent data.
+    ///
+    /// Does nothing with [`SparseVec::with_block_alignment`].
+    pub fn dedup_blocks(&mut self) -> DedupStats {
+        let mut stats = DedupStats::default();
+        if self.block_align.is_some() {
+            return stats;
+        }
+        let hasher = DefaultHashBuilder::default();
+        let mut by_hash: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (_, key) in self.map.iter() {
+            let hash = hasher.hash_one(&self.data[key][..]);
+            by_hash.entry(hash).or_default().push(*key);
+        }
+
+        for keys in by_hash.into_values().filter(|keys| keys.len() > 1) {
+            // Equal hashes do not mean equal contents
+            let mut groups: Vec<Vec<usize>> = Vec::new();
+            for key in keys {
+                let data = &self.data[&key][..];
+                match groups
+                    .iter_mut()
+                    .find(|group| self.data[&group[0]][..] == *data)
+                {
+                    Some(group) => group.push(key),
+                    None => groups.push(Vec::from_iter([key])),
+                }
+            }
+            for group in groups.into_iter().filter(|group| group.len() > 1) {
+                let (first, rest) = group.split_first().unwrap();
+                let storage = self.data.get_mut(first).unwrap();
+                let shared = match storage {
+                    Storage::Shared { data, range, .. } if *range == (0..data.len()) => {
+                        data.clone()
+                    }
+                    _ => {
+                        let shared: Arc<[T]> = Arc::from(&storage[..]);
+                        *storage = Storage::shared(shared.clone());
+                        shared
+                    }
+                };
+                for key in rest {
+                    let storage = self.data.get_mut(key).unwrap();
+                    if let Storage::Shared { data, range, .. } = storage {
+                        if Arc::ptr_eq(data, &shared) && *range == (0..shared.len()) {
+                            continue;
+                        }
+                    }
+                    if !storage.is_shared() {
+                        stats.bytes_saved += core::mem::size_of_val(&storage[..]);
+                    }
+                    *storage = Storage::shared(shared.clone());
+                    stats.shared_blocks += 1;
+                }
+                if let Some(index) = &mut self.dedup {
+                    index.add(&shared);
+                }
+            }
+        }
+        stats
+    }
+
+    /// With `enabled`, [`SparseVec::insert`] stores data like [`SparseVec::insert_shared`],
+    /// and data equal to that of an earlier such insert or of a deduplicated block
+    /// references it instead of being stored again.
+    ///
+    /// Does nothing with [`SparseVec::with_block_alignment`].
+    pub fn set_insert_dedup(&mut self, enabled: bool) {
+        self.dedup = enabled.then(|| DedupIndex {
+            hasher: DefaultHashBuilder::default(),
+            hash: |hasher, data| hasher.hash_one(data),
+            eq: |a, b| a == b,
+            buffers: HashMap::new(),
+        });
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    // `insert` with `set_insert_dedup`
+    pub(crate) fn insert_deduped(&mut self, data: Vec<T>, addr: A) {
+        let range = addr..addr + A::from_usize(data.len());
+        let index = self.dedup.as_mut().unwrap();
+        let hash = (index.hash)(&index.hasher, &data);
+        let candidates = index.buffers.entry(hash).or_default();
+        candidates.retain(|buffer| buffer.strong_count() > 0);
+        let found = candidates
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|buffer| (index.eq)(buffer, &data));
+        let shared = found.unwrap_or_else(|| {
+            let shared: Arc<[T]> = data.into();
+            candidates.push(Arc::downgrade(&shared));
+            shared
+        });
+        self.insert_storage(Storage::shared(shared), addr);
+        self.enforce_capacity(&range);
+    }
+}
+
+#[test]
+fn sparsevec_dedup_blocks() {
+    let page = |v: u8| Vec::from_iter((0..0x100).map(|i| v ^ i as u8));
+    let mut map = SparseVec::new();
+    for (i, v) in [0xff, 1, 0xff, 2, 1, 0xff].into_iter().enumerate() {
+        map.insert(page(v), i as u64 * 0x1000);
+    }
+    let before = map.clone_range(0..u64::MAX);
+
+    let stats = map.dedup_blocks();
+    assert_eq!(
+        stats,
+        DedupStats {
+            shared_blocks: 3,
+            bytes_saved: 0x300
+        }
+    );
+    assert!(map.blocks().eq(before.blocks()));
+    let ptr = |map: &SparseVec<u8>, addr| map.get_block(addr).unwrap().1.as_ptr();
+    assert_eq!(ptr(&map, 0), ptr(&map, 0x5000));
+    assert_eq!(ptr(&map, 0x1000), ptr(&map, 0x4000));
+    assert!(!map.is_shared(0x3000));
+    assert_eq!(map.dedup_blocks(), DedupStats::default());
+
+    // Writes copy the written block only
+    map.write(0x2010, &[0]).unwrap();
+    assert_eq!(map.get(0x0010..0x0011).unwrap(), &[0xef]);
+    assert_eq!(map.get(0x5010..0x5011).unwrap(), &[0xef]);
+    assert_eq!(map.get(0x2010..0x2011).unwrap(), &[0]);
+    assert!(!map.is_shared(0x2000) && map.is_shared(0x5000));
+}
+
+#[test]
+fn sparsevec_insert_dedup() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 0x10], 0x100);
+    map.set_insert_dedup(true);
+    map.insert(vec![0xff; 0x40], 0x1000);
+    map.insert(vec![0xff; 0x40], 0x2000);
+    map.insert(vec![0xfe; 0x40], 0x3000);
+    let ptr = |map: &SparseVec<u8>, addr| map.get_block(addr).unwrap().1.as_ptr();
+    assert_eq!(ptr(&map, 0x1000), ptr(&map, 0x2000));
+    assert_ne!(ptr(&map, 0x1000), ptr(&map, 0x3000));
+
+    // Deduplicated blocks are found too
+    map.insert(vec![0u8; 0x10], 0x200);
+    map.dedup_blocks();
+    map.insert(vec![0u8; 0x10], 0x400);
+    assert_eq!(ptr(&map, 0x100), ptr(&map, 0x400));
+
+    // Overwritten data can be stored again
+    map.insert(vec![2; 0x40], 0x1000);
+    map.insert(vec![2; 0x40], 0x2000);
+    map.insert(vec![0xff; 0x40], 0x5000);
+    assert!(map.is_shared(0x5000));
+    assert_eq!(map.get(0x5000..0x5040).unwrap(), &[0xff; 0x40]);
+    map.set_insert_dedup(false);
+    map.insert(vec![0xfe; 0x40], 0x6000);
+    assert!(!map.is_shared(0x6000));
+}
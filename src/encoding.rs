@@ -0,0 +1,287 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{Address, SparseVec};
+
+const MAGIC: [u8; 4] = *b"SPVC";
+const VERSION: u8 = 1;
+
+/// Element types that can be stored in the [`SparseVec::to_bytes`] format.
+pub trait LeBytes: Copy {
+    const SIZE: usize;
+
+    fn write_le(self, out: &mut Vec<u8>);
+    /// `bytes` is exactly `SIZE` long.
+    fn read_le(bytes: &[u8]) -> Self;
+}
+
+macro_rules! impl_le_bytes {
+    ($($t:ty),*) => {$(
+        impl LeBytes for $t {
+            const SIZE: usize = core::mem::size_of::<$t>();
+
+            fn write_le(self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+
+            fn read_le(bytes: &[u8]) -> Self {
+                <$t>::from_le_bytes(bytes.try_into().unwrap())
+            }
+        }
+    )*};
+}
+
+impl_le_bytes!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeError {
+    BadMagic,
+    UnsupportedVersion(u8),
+    ElementSize {
+        expected: usize,
+        found: u8,
+    },
+    /// The input ended while reading the item starting at `offset`.
+    Truncated {
+        offset: usize,
+    },
+    /// The record at `start` overlaps a previous record ending at `prev_end`.
+    Overlap {
+        start: u64,
+        prev_end: u64,
+    },
+    /// The record at `start` with `len` elements does not fit in the address space.
+    Overflow {
+        start: u64,
+        len: u64,
+    },
+    /// Unused input after the last record.
+    TrailingData {
+        offset: usize,
+    },
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::BadMagic => write!(f, "bad magic number"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "unsupported version {v}"),
+            DecodeError::ElementSize { expected, found } => {
+                write!(f, "element size is {found}, expected {expected}")
+            }
+            DecodeError::Truncated { offset } => write!(f, "input truncated at offset {offset}"),
+            DecodeError::Overlap { start, prev_end } => write!(
+                f,
+                "record at {start:#x} overlaps previous record ending at {prev_end:#x}"
+            ),
+            DecodeError::Overflow { start, len } => write!(
+                f,
+                "record at {start:#x} with length {len} overflows the address space"
+            ),
+            DecodeError::TrailingData { offset } => {
+                write!(f, "trailing data at offset {offset}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        let truncated = DecodeError::Truncated {
+            offset: self.offset,
+        };
+        let end = self.offset.checked_add(len).ok_or(truncated.clone())?;
+        let slice = self.bytes.get(self.offset..end).ok_or(truncated)?;
+        self.offset = end;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::read_le(self.take(8)?))
+    }
+}
+
+impl<T: LeBytes, A: Address> SparseVec<T, A> {
+    /// Encodes into a compact binary format: magic `SPVC`, version byte, element size byte,
+    /// block count (u64 LE), then `(start: u64 LE, len: u64 LE, data)` records in address order.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(14 + self.map.len() * 16 + self.stored_len() * T::SIZE);
+        out.extend_from_slice(&MAGIC);
+        out.push(VERSION);
+        out.push(T::SIZE as u8);
+        out.extend_from_slice(&(self.map.len() as u64).to_le_bytes());
+        for (range, data) in self.blocks() {
+            out.extend_from_slice(&range.start.to_u64().to_le_bytes());
+            out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+            for v in data {
+                v.write_le(&mut out);
+            }
+        }
+        out
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.take(4).map_err(|_| DecodeError::BadMagic)? != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(DecodeError::UnsupportedVersion(version));
+        }
+        let size = reader.u8()?;
+        if size as usize != T::SIZE {
+            return Err(DecodeError::ElementSize {
+                expected: T::SIZE,
+                found: size,
+            });
+        }
+
+        let count = reader.u64()?;
+        let mut blocks = Vec::new();
+        for _ in 0..count {
+            let start = reader.u64()?;
+            let len = reader.u64()?;
+            let range = start
+                .checked_add(len)
+                .and_then(A::try_from_u64)
+                .and_then(|end| Some(A::try_from_u64(start)?..end))
+                .ok_or(DecodeError::Overflow { start, len })?;
+            let offset = reader.offset;
+            let byte_len = usize::try_from(len)
+                .ok()
+                .and_then(|len| len.checked_mul(T::SIZE))
+                .ok_or(DecodeError::Truncated { offset })?;
+            let data = Vec::from_iter(reader.take(byte_len)?.chunks_exact(T::SIZE).map(T::read_le));
+            if !data.is_empty() {
+                blocks.push((range.start, data));
+            }
+        }
+        if reader.offset != bytes.len() {
+            return Err(DecodeError::TrailingData {
+                offset: reader.offset,
+            });
+        }
+
+        blocks.sort_by_key(|(start, _)| *start);
+        for ((start, data), (next_start, _)) in blocks.iter().zip(blocks.iter().skip(1)) {
+            let prev_end = *start + A::from_usize(data.len());
+            if prev_end > *next_start {
+                return Err(DecodeError::Overlap {
+                    start: next_start.to_u64(),
+                    prev_end: prev_end.to_u64(),
+                });
+            }
+        }
+        Ok(Self::from_sorted_blocks(blocks))
+    }
+}
+
+#[test]
+fn sparsevec_bytes_roundtrip() {
+    let empty = SparseVec::<u8>::new();
+    let bytes = empty.to_bytes();
+    assert_eq!(bytes, b"SPVC\x01\x01\0\0\0\0\0\0\0\0");
+    assert_eq!(SparseVec::<u8>::from_bytes(&bytes).unwrap().stored_len(), 0);
+
+    let map = crate::sparse_vec! {
+        0x10 => [1u8, 2, 3],
+        u64::MAX - 2 => [4; 2],
+    };
+    let bytes = map.to_bytes();
+    assert_eq!(bytes.len(), 14 + 2 * 16 + 5);
+    assert_eq!(&bytes[14..30], b"\x10\0\0\0\0\0\0\0\x03\0\0\0\0\0\0\0");
+    let decoded = SparseVec::<u8>::from_bytes(&bytes).unwrap();
+    decoded.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(decoded.blocks()),
+        Vec::from_iter(map.blocks())
+    );
+
+    let mut wide = SparseVec::new();
+    wide.insert(vec![0x1234_5678u32, 0x9abc_def0], 0x100);
+    let decoded = SparseVec::<u32>::from_bytes(&wide.to_bytes()).unwrap();
+    assert_eq!(
+        decoded.get(0x100..0x102).unwrap(),
+        &[0x1234_5678, 0x9abc_def0]
+    );
+    assert_eq!(
+        SparseVec::<u16>::from_bytes(&wide.to_bytes()).unwrap_err(),
+        DecodeError::ElementSize {
+            expected: 2,
+            found: 4
+        }
+    );
+}
+
+#[test]
+fn sparsevec_bytes_corrupted() {
+    fn record(start: u64, data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::from_iter(start.to_le_bytes());
+        out.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        out.extend_from_slice(data);
+        out
+    }
+    fn encode(records: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = b"SPVC\x01\x01".to_vec();
+        out.extend_from_slice(&(records.len() as u64).to_le_bytes());
+        out.extend(records.concat());
+        out
+    }
+    let decode = |bytes: &[u8]| SparseVec::<u8>::from_bytes(bytes).unwrap_err();
+
+    assert_eq!(decode(b"SPV"), DecodeError::BadMagic);
+    assert_eq!(decode(b"XPVC\x01\x01"), DecodeError::BadMagic);
+    assert_eq!(decode(b"SPVC\x02\x01"), DecodeError::UnsupportedVersion(2));
+
+    let valid = encode(&[record(0, &[1, 2, 3]), record(10, &[4])]);
+    for len in 4..valid.len() {
+        assert!(matches!(
+            decode(&valid[..len]),
+            DecodeError::Truncated { .. }
+        ));
+    }
+    assert_eq!(
+        decode(&valid[..valid.len() - 1]),
+        DecodeError::Truncated { offset: 49 }
+    );
+    let mut trailing = valid.clone();
+    trailing.push(0);
+    assert_eq!(
+        decode(&trailing),
+        DecodeError::TrailingData {
+            offset: valid.len()
+        }
+    );
+
+    assert_eq!(
+        decode(&encode(&[record(0, &[1, 2, 3]), record(2, &[4])])),
+        DecodeError::Overlap {
+            start: 2,
+            prev_end: 3
+        }
+    );
+    assert_eq!(
+        decode(&encode(&[record(u64::MAX - 1, &[1, 2])])),
+        DecodeError::Overflow {
+            start: u64::MAX - 1,
+            len: 2
+        }
+    );
+
+    let mut huge = b"SPVC\x01\x01\x01\0\0\0\0\0\0\0".to_vec();
+    huge.extend_from_slice(&0u64.to_le_bytes());
+    huge.extend_from_slice(&(u64::MAX / 2).to_le_bytes());
+    assert_eq!(decode(&huge), DecodeError::Truncated { offset: 30 });
+}
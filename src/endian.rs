@@ -0,0 +1,82 @@
+use crate::{Address, SparseVec, Unmapped};
+
+macro_rules! int_accessors {
+    ($($t:ty => $read_le:ident, $read_be:ident, $write_le:ident, $write_be:ident;)*) => {
+        impl<A: Address> SparseVec<u8, A> {
+            $(
+                #[doc = concat!("Reads a little endian `", stringify!($t), "`, `None` if any byte is unmapped.")]
+                pub fn $read_le(&self, addr: A) -> Option<$t> {
+                    let mut bytes = [0; core::mem::size_of::<$t>()];
+                    self.read_into_exact(addr, &mut bytes).ok()?;
+                    Some(<$t>::from_le_bytes(bytes))
+                }
+
+                #[doc = concat!("Reads a big endian `", stringify!($t), "`, `None` if any byte is unmapped.")]
+                pub fn $read_be(&self, addr: A) -> Option<$t> {
+                    let mut bytes = [0; core::mem::size_of::<$t>()];
+                    self.read_into_exact(addr, &mut bytes).ok()?;
+                    Some(<$t>::from_be_bytes(bytes))
+                }
+
+                #[doc = concat!("Writes a little endian `", stringify!($t), "` through existing coverage.")]
+                pub fn $write_le(&mut self, addr: A, value: $t) -> Result<(), Unmapped<A>> {
+                    self.write(addr, &value.to_le_bytes())
+                }
+
+                #[doc = concat!("Writes a big endian `", stringify!($t), "` through existing coverage.")]
+                pub fn $write_be(&mut self, addr: A, value: $t) -> Result<(), Unmapped<A>> {
+                    self.write(addr, &value.to_be_bytes())
+                }
+            )*
+        }
+
+        #[test]
+        fn sparsevec_endian_accessors() {
+            $({
+                const SIZE: usize = core::mem::size_of::<$t>();
+                let value = <$t>::from_le_bytes(core::array::from_fn(|i| 0x81 + i as u8));
+
+                let mut map = SparseVec::new();
+                map.insert(vec![0xee; 0x10], 0x100);
+                map.insert(vec![0xee; 0x10], 0x200);
+
+                // Exactly at the end of a block
+                let at = 0x110 - SIZE as u64;
+                map.$write_le(at, value).unwrap();
+                assert_eq!(map.$read_le(at), Some(value));
+                assert_eq!(map.get(at..0x110).unwrap(), &value.to_le_bytes());
+                map.$write_be(at, value).unwrap();
+                assert_eq!(map.$read_be(at), Some(value));
+                assert_eq!(map.get(at..0x110).unwrap(), &value.to_be_bytes());
+
+                // One byte past the end of a block
+                assert_eq!(map.$read_le(at + 1), None);
+                assert_eq!(map.$read_be(at + 1), None);
+                assert_eq!(map.$write_le(at + 1, value), Err(Unmapped { addr: 0x110 }));
+                assert_eq!(map.$write_be(0x1ff, value), Err(Unmapped { addr: 0x1ff }));
+                assert_eq!(map.get(0x200..0x201).unwrap(), &[0xee]);
+                assert_eq!(Vec::from_iter(map.ranges()), vec![0x100..0x110, 0x200..0x210]);
+
+                // Past the end of the address space
+                let at = u64::MAX - SIZE as u64 + 1;
+                map.insert(vec![0xee; SIZE - 1], at);
+                assert_eq!(map.$read_le(at), None);
+                assert_eq!(map.$read_be(at), None);
+                let mut buf = [0; SIZE + 1];
+                let result = map.read_into_exact(at, &mut buf[1..]);
+                assert_eq!(result, Err(Unmapped { addr: u64::MAX }));
+                let result = map.read_into_exact(at - 1, &mut buf);
+                assert_eq!(result, Err(Unmapped { addr: at - 1 }));
+            })*
+        }
+    };
+}
+
+int_accessors! {
+    u16 => read_u16_le, read_u16_be, write_u16_le, write_u16_be;
+    u32 => read_u32_le, read_u32_be, write_u32_le, write_u32_be;
+    u64 => read_u64_le, read_u64_be, write_u64_le, write_u64_be;
+    i16 => read_i16_le, read_i16_be, write_i16_le, write_i16_be;
+    i32 => read_i32_le, read_i32_be, write_i32_le, write_i32_be;
+    i64 => read_i64_le, read_i64_be, write_i64_le, write_i64_be;
+}
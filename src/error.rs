@@ -0,0 +1,524 @@
+use alloc::boxed::Box;
+use core::fmt;
+use core::ops::Range;
+
+#[cfg(feature = "object")]
+use crate::LoadError;
+use crate::{
+    Address, BuildError, CStrError, ChecksumError, DecodeError, IhexError, MapError, MemFault,
+    RawPartsError, RebaseError, RunLimitExceeded, SignatureError, SrecError, ViewError,
+};
+#[cfg(feature = "std")]
+use crate::{ImportError, ReplayError};
+
+/// An operation needed data at `addr`, which is not stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Unmapped<A = u64> {
+    pub addr: A,
+}
+
+impl<A: Address> fmt::Display for Unmapped<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "address {:#x} is unmapped", self.addr)
+    }
+}
+
+impl<A: Address> core::error::Error for Unmapped<A> {}
+
+/// Why a read of a range failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReadError<A = u64> {
+    /// The read starts in a gap. `gap` is its unmapped part, from the start of the read up
+    /// to the next stored data or the end of the read.
+    StartUnmapped { gap: Range<A> },
+    /// Only `available` elements are stored from the start of the read, `first_missing` is the
+    /// first address after them.
+    TruncatedAt { first_missing: A, available: A },
+}
+
+impl<A: Address> fmt::Display for ReadError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::StartUnmapped { gap } => {
+                write!(f, "range {:#x}..{:#x} is unmapped", gap.start, gap.end)
+            }
+            ReadError::TruncatedAt {
+                first_missing,
+                available,
+            } => write!(
+                f,
+                "address {first_missing:#x} is unmapped after {available:?} readable elements"
+            ),
+        }
+    }
+}
+
+impl<A: Address> core::error::Error for ReadError<A> {}
+
+/// A mutation would have touched `addr`, which is frozen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frozen<A = u64> {
+    pub addr: A,
+}
+
+impl<A: Address> fmt::Display for Frozen<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "address {:#x} is frozen", self.addr)
+    }
+}
+
+impl<A: Address> core::error::Error for Frozen<A> {}
+
+/// Why [`SparseVec::try_write`](crate::SparseVec::try_write) wrote nothing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteError<A = u64> {
+    Unmapped { addr: A },
+    Frozen { addr: A },
+}
+
+impl<A> From<Unmapped<A>> for WriteError<A> {
+    fn from(Unmapped { addr }: Unmapped<A>) -> Self {
+        WriteError::Unmapped { addr }
+    }
+}
+
+impl<A> From<Frozen<A>> for WriteError<A> {
+    fn from(Frozen { addr }: Frozen<A>) -> Self {
+        WriteError::Frozen { addr }
+    }
+}
+
+impl<A: Address> fmt::Display for WriteError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Unmapped { addr } => write!(f, "address {addr:#x} is unmapped"),
+            WriteError::Frozen { addr } => write!(f, "address {addr:#x} is frozen"),
+        }
+    }
+}
+
+impl<A: Address> core::error::Error for WriteError<A> {}
+
+/// Where [`SparseVec::compare_range`](crate::SparseVec::compare_range) found a difference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompareError<T, A = u64> {
+    Unmapped { addr: A },
+    Mismatch { addr: A, expected: T, found: T },
+}
+
+impl<T: fmt::Debug, A: Address> fmt::Display for CompareError<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompareError::Unmapped { addr } => write!(f, "address {addr:#x} is unmapped"),
+            CompareError::Mismatch {
+                addr,
+                expected,
+                found,
+            } => write!(f, "expected {expected:?} at {addr:#x}, found {found:?}"),
+        }
+    }
+}
+
+impl<T: fmt::Debug, A: Address> core::error::Error for CompareError<T, A> {}
+
+/// Where [`SparseVec::compare_with`](crate::SparseVec::compare_with) found the two regions
+/// to differ, as an offset from the start of each.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareDiff<A = u64> {
+    /// Only one side stores an element at `offset`. `in_self` tells which.
+    Coverage { offset: A, in_self: bool },
+    /// Both sides store an element at `offset`, but different ones.
+    Content { offset: A },
+}
+
+impl<A: Address> fmt::Display for CompareDiff<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompareDiff::Coverage { offset, in_self } => {
+                let side = if *in_self {
+                    "this side"
+                } else {
+                    "the other side"
+                };
+                write!(f, "offset {offset:#x} is only mapped on {side}")
+            }
+            CompareDiff::Content { offset } => write!(f, "contents differ at offset {offset:#x}"),
+        }
+    }
+}
+
+impl<A: Address> core::error::Error for CompareDiff<A> {}
+
+/// Any error of this crate, for callers that do not need the error type of a specific
+/// operation. Every error type of the crate converts into it, so that `?` works across
+/// operations. Errors without a variant of their own are kept as [`Error::Invalid`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error<A = u64> {
+    /// Addresses computed by the operation do not fit in the address space.
+    AddressOverflow,
+    /// Nothing is stored at `addr`. `gap` is the unmapped range from `addr`, if known.
+    Unmapped { addr: A, gap: Option<Range<A>> },
+    /// Data is only stored up to `first_missing`.
+    Truncated { first_missing: A },
+    /// New data overlaps `existing`.
+    Overlap { existing: Range<A> },
+    /// A length exceeds what the operation supports.
+    LengthOverflow,
+    /// A mutation would have touched `addr`, which is frozen.
+    Frozen { addr: A },
+    /// Invalid input, like a malformed file. Displays as the wrapped error.
+    Invalid(Box<dyn core::error::Error + Send + Sync>),
+    /// An I/O error of the `std::io` integrations. Displays as the wrapped error.
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl<A: Address> fmt::Display for Error<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::AddressOverflow => write!(f, "addresses do not fit in the address space"),
+            Error::Unmapped { gap: Some(gap), .. } => {
+                write!(f, "range {:#x}..{:#x} is unmapped", gap.start, gap.end)
+            }
+            Error::Unmapped { addr, gap: None } => write!(f, "address {addr:#x} is unmapped"),
+            Error::Truncated { first_missing } => {
+                write!(f, "data is truncated at {first_missing:#x}")
+            }
+            Error::Overlap { existing } => write!(
+                f,
+                "overlaps existing data at {:#x}..{:#x}",
+                existing.start, existing.end
+            ),
+            Error::LengthOverflow => write!(f, "length exceeds the supported maximum"),
+            Error::Frozen { addr } => write!(f, "address {addr:#x} is frozen"),
+            Error::Invalid(err) => err.fmt(f),
+            #[cfg(feature = "std")]
+            Error::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl<A: Address> core::error::Error for Error<A> {
+    // The wrapped errors are displayed in place, so their sources come next in the chain
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            Error::Invalid(err) => err.source(),
+            #[cfg(feature = "std")]
+            Error::Io(err) => err.source(),
+            _ => None,
+        }
+    }
+}
+
+impl<A> Error<A> {
+    fn invalid(err: impl core::error::Error + Send + Sync + 'static) -> Self {
+        Error::Invalid(Box::new(err))
+    }
+}
+
+impl<A> From<Unmapped<A>> for Error<A> {
+    fn from(Unmapped { addr }: Unmapped<A>) -> Self {
+        Error::Unmapped { addr, gap: None }
+    }
+}
+
+impl<A: Address> From<ReadError<A>> for Error<A> {
+    fn from(err: ReadError<A>) -> Self {
+        match err {
+            ReadError::StartUnmapped { gap } => Error::Unmapped {
+                addr: gap.start,
+                gap: Some(gap),
+            },
+            ReadError::TruncatedAt { first_missing, .. } => Error::Truncated { first_missing },
+        }
+    }
+}
+
+impl<A> From<Frozen<A>> for Error<A> {
+    fn from(Frozen { addr }: Frozen<A>) -> Self {
+        Error::Frozen { addr }
+    }
+}
+
+impl<A> From<WriteError<A>> for Error<A> {
+    fn from(err: WriteError<A>) -> Self {
+        match err {
+            WriteError::Unmapped { addr } => Error::Unmapped { addr, gap: None },
+            WriteError::Frozen { addr } => Error::Frozen { addr },
+        }
+    }
+}
+
+impl<T, A> From<CompareError<T, A>> for Error<A>
+where
+    T: fmt::Debug + Send + Sync + 'static,
+    A: Address + Send + Sync + 'static,
+{
+    fn from(err: CompareError<T, A>) -> Self {
+        match err {
+            CompareError::Unmapped { addr } => Error::Unmapped { addr, gap: None },
+            err => Error::invalid(err),
+        }
+    }
+}
+
+impl<A: Address + Send + Sync + 'static> From<CompareDiff<A>> for Error<A> {
+    fn from(err: CompareDiff<A>) -> Self {
+        Error::invalid(err)
+    }
+}
+
+impl<A> From<BuildError<A>> for Error<A> {
+    fn from(err: BuildError<A>) -> Self {
+        Error::Overlap {
+            existing: err.start..err.previous_end,
+        }
+    }
+}
+
+impl<A: Address + Send + Sync + 'static> From<CStrError<A>> for Error<A> {
+    fn from(err: CStrError<A>) -> Self {
+        match err {
+            CStrError::Unmapped { addr } => Error::Unmapped { addr, gap: None },
+            CStrError::Unterminated { gap } => Error::Truncated { first_missing: gap },
+            err => Error::invalid(err),
+        }
+    }
+}
+
+impl<A: Address + Send + Sync + 'static> From<ViewError<A>> for Error<A> {
+    fn from(err: ViewError<A>) -> Self {
+        match err {
+            ViewError::Unmapped { addr } => Error::Unmapped { addr, gap: None },
+            err => Error::invalid(err),
+        }
+    }
+}
+
+impl<A: Address + Send + Sync + 'static> From<RawPartsError<A>> for Error<A> {
+    fn from(err: RawPartsError<A>) -> Self {
+        match err {
+            RawPartsError::Overlap {
+                start,
+                previous_end,
+                ..
+            } => Error::Overlap {
+                existing: start..previous_end,
+            },
+            err => Error::invalid(err),
+        }
+    }
+}
+
+impl<A> From<RunLimitExceeded<A>> for Error<A> {
+    fn from(_: RunLimitExceeded<A>) -> Self {
+        Error::LengthOverflow
+    }
+}
+
+impl<E, A> From<MapError<E, A>> for Error<A>
+where
+    E: core::error::Error + Send + Sync + 'static,
+    A: Address + Send + Sync + 'static,
+{
+    fn from(err: MapError<E, A>) -> Self {
+        Error::invalid(err)
+    }
+}
+
+impl From<ChecksumError> for Error {
+    fn from(ChecksumError::Unmapped { addr }: ChecksumError) -> Self {
+        Error::Unmapped { addr, gap: None }
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(err: DecodeError) -> Self {
+        match err {
+            DecodeError::Overlap { start, prev_end } => Error::Overlap {
+                existing: start..prev_end,
+            },
+            DecodeError::Overflow { .. } => Error::AddressOverflow,
+            err => Error::invalid(err),
+        }
+    }
+}
+
+impl From<RebaseError> for Error {
+    fn from(err: RebaseError) -> Self {
+        match err {
+            RebaseError::OutOfRange { .. } => Error::AddressOverflow,
+            err => Error::invalid(err),
+        }
+    }
+}
+
+impl From<MemFault> for Error {
+    fn from(err: MemFault) -> Self {
+        Error::invalid(err)
+    }
+}
+
+impl From<SignatureError> for Error {
+    fn from(err: SignatureError) -> Self {
+        Error::invalid(err)
+    }
+}
+
+impl From<IhexError> for Error {
+    fn from(err: IhexError) -> Self {
+        Error::invalid(err)
+    }
+}
+
+impl From<SrecError> for Error {
+    fn from(err: SrecError) -> Self {
+        Error::invalid(err)
+    }
+}
+
+#[cfg(feature = "object")]
+impl From<LoadError> for Error {
+    fn from(err: LoadError) -> Self {
+        match err {
+            LoadError::Overflow { .. } => Error::AddressOverflow,
+            err => Error::invalid(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ImportError> for Error {
+    fn from(err: ImportError) -> Self {
+        match err {
+            ImportError::Io { source, .. } => Error::Io(source),
+            ImportError::Overflow { .. } => Error::AddressOverflow,
+            err => Error::invalid(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ReplayError> for Error {
+    fn from(err: ReplayError) -> Self {
+        match err {
+            ReplayError::Io { source, .. } => Error::Io(source),
+            ReplayError::Overflow { .. } => Error::AddressOverflow,
+            ReplayError::Unmapped { addr, .. } => Error::Unmapped { addr, gap: None },
+            err => Error::invalid(err),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<A> From<std::io::Error> for Error<A> {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+#[test]
+fn sparsevec_error() {
+    use crate::SparseVec;
+
+    fn roundtrip(map: &mut SparseVec<u8>) -> Result<u8, Error> {
+        map.try_write(0x10, &[1, 2])?;
+        map.try_get(0x10..0x14)?;
+        Ok(map.read_cstr(0x10, 4)?.len() as u8)
+    }
+    let mut map = crate::sparse_vec! { 0x10 => [0u8; 3] };
+    assert_eq!(
+        roundtrip(&mut map).unwrap_err().to_string(),
+        "data is truncated at 0x13"
+    );
+    map.insert(vec![0], 0x13);
+    assert_eq!(roundtrip(&mut map).unwrap(), 2);
+    map.freeze(0x10..0x11);
+    assert_eq!(
+        roundtrip(&mut map).unwrap_err().to_string(),
+        "address 0x10 is frozen"
+    );
+
+    let messages = [
+        (
+            Error::AddressOverflow,
+            "addresses do not fit in the address space",
+        ),
+        (
+            Error::Unmapped {
+                addr: 0x10,
+                gap: Some(0x10..0x20),
+            },
+            "range 0x10..0x20 is unmapped",
+        ),
+        (
+            Error::Unmapped {
+                addr: 0x10,
+                gap: None,
+            },
+            "address 0x10 is unmapped",
+        ),
+        (
+            Error::Truncated {
+                first_missing: 0x18,
+            },
+            "data is truncated at 0x18",
+        ),
+        (
+            Error::Overlap {
+                existing: 0x20..0x28,
+            },
+            "overlaps existing data at 0x20..0x28",
+        ),
+        (
+            Error::LengthOverflow,
+            "length exceeds the supported maximum",
+        ),
+        (Error::Frozen { addr: 0x30 }, "address 0x30 is frozen"),
+        (
+            DecodeError::BadMagic.into(),
+            &DecodeError::BadMagic.to_string(),
+        ),
+    ];
+    for (err, message) in messages {
+        assert_eq!(err.to_string(), message);
+    }
+    assert!(matches!(
+        Error::from(BuildError {
+            start: 0x10,
+            previous_end: 0x18
+        }),
+        Error::Overlap { existing } if existing == (0x10..0x18)
+    ));
+}
+
+#[test]
+fn sparsevec_error_source() {
+    use core::error::Error as _;
+    use std::io;
+
+    use crate::SparseVec;
+
+    // Wrapped errors display in place, with their sources following
+    let map = crate::sparse_vec! { 0x10 => [1u8, 2] };
+    let mapped = map.try_map_values(|addr, _| match addr {
+        0x11 => Err(io::Error::other("bad value")),
+        _ => Ok(0u8),
+    });
+    let err = Error::from(mapped.unwrap_err());
+    assert_eq!(err.to_string(), "at address 0x11: bad value");
+    assert_eq!(err.source().unwrap().to_string(), "bad value");
+
+    let err = Error::<u64>::from(io::Error::other(Unmapped { addr: 4u64 }));
+    assert_eq!(err.to_string(), "address 0x4 is unmapped");
+    assert!(err.source().is_none());
+    let err = Error::from(ImportError::Io {
+        offset: 0,
+        source: io::ErrorKind::UnexpectedEof.into(),
+    });
+    assert!(matches!(err, Error::Io(ref io) if io.kind() == io::ErrorKind::UnexpectedEof));
+    assert!(SparseVec::<u8>::read_sparse_file("/nonexistent/sparse_vec")
+        .map_err(Error::<u64>::from)
+        .is_err_and(|err| matches!(err, Error::Io(_))));
+}
@@ -0,0 +1,144 @@
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, SparseVec, WatchOp};
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Like [`SparseVec::insert`], but returns an error instead of aborting if the block can
+    /// not be allocated. Nothing is modified on error.
+    ///
+    /// The stored data is allocated up front. Bookkeeping of the block index and history
+    /// snapshots may still allocate afterwards, as does copying the block into its aligned
+    /// buffer with [`SparseVec::with_block_alignment`].
+    pub fn try_insert_alloc(&mut self, data: &[T], addr: A) -> Result<(), TryReserveError> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let range = addr..addr + A::from_usize(data.len());
+        let (start, merged) = self.try_merged(&range, |_, vec| vec.extend_from_slice(data))?;
+        self.assert_thawed(&range);
+        self.watch.notify(&range, WatchOp::Insert);
+        self.record_history(&range);
+        self.insert_unwatched(merged, start);
+        Ok(())
+    }
+
+    /// Fallible variant of [`SparseVec::fill`], see [`SparseVec::try_insert_alloc`].
+    pub fn try_fill_alloc(&mut self, range: Range<A>, value: T) -> Result<(), TryReserveError> {
+        if range.is_empty() {
+            return Ok(());
+        }
+        let len = (range.end - range.start).to_usize();
+        let (start, merged) = self.try_merged(&range, |_, vec| {
+            vec.extend(core::iter::repeat_n(value, len));
+        })?;
+        self.assert_thawed(&range);
+        self.watch.notify(&range, WatchOp::Fill);
+        self.record_history(&range);
+        self.insert_unwatched(merged, start);
+        Ok(())
+    }
+
+    /// Fallible variant of [`SparseVec::fill_gaps`], see [`SparseVec::try_insert_alloc`].
+    pub fn try_fill_gaps_alloc(
+        &mut self,
+        range: Range<A>,
+        value: T,
+    ) -> Result<(), TryReserveError> {
+        if self.contains_range(&range) {
+            return Ok(());
+        }
+        let (start, merged) = self.try_merged(&range, |vec_self, vec| {
+            let offset = vec.len();
+            for (clipped, slice) in vec_self.slices(range.clone()) {
+                vec.resize(offset + (clipped.start - range.start).to_usize(), value);
+                vec.extend_from_slice(slice);
+            }
+            vec.resize(offset + (range.end - range.start).to_usize(), value);
+        })?;
+        let gaps = Vec::from_iter(self.gaps(range.clone()));
+        for gap in &gaps {
+            self.assert_thawed(gap);
+        }
+        for gap in gaps {
+            self.watch.notify(&gap, WatchOp::Fill);
+        }
+        self.record_history(&range);
+        self.insert_unwatched(merged, start);
+        Ok(())
+    }
+
+    // The block that `range` becomes part of once inserted, including the stored data right
+    // before and after it, in a single fallible allocation. `contents` appends exactly the
+    // new data of `range`. Inserting the result replaces its neighbours without splitting or
+    // merging.
+    fn try_merged(
+        &self,
+        range: &Range<A>,
+        contents: impl FnOnce(&Self, &mut Vec<T>),
+    ) -> Result<(A, Vec<T>), TryReserveError> {
+        let before = (range.start > A::ZERO)
+            .then(|| {
+                let last = range.start - A::from_usize(1);
+                self.map.get_key_value(&last)
+            })
+            .flatten()
+            .map(|(block, key)| {
+                let slice = &self.data[key][..(range.start - block.start).to_usize()];
+                (block.start, slice)
+            });
+        let after = self
+            .map
+            .get_key_value(&range.end)
+            .map(|(block, key)| &self.data[key][(range.end - block.start).to_usize()..]);
+        let (start, before) = before.unwrap_or((range.start, &[]));
+        let after = after.unwrap_or(&[]);
+
+        let len = (range.end - range.start).to_usize();
+        let mut merged = Vec::new();
+        merged.try_reserve_exact(before.len() + len + after.len())?;
+        merged.extend_from_slice(before);
+        contents(self, &mut merged);
+        debug_assert_eq!(merged.len(), before.len() + len);
+        merged.extend_from_slice(after);
+        Ok((start, merged))
+    }
+}
+
+#[test]
+fn sparsevec_try_alloc() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u32; 4], 0x10);
+    map.insert(vec![2u32; 4], 0x20);
+
+    // Too large for the address space of the allocator
+    let huge = 0..u64::MAX / 2;
+    assert!(map.try_fill_alloc(huge.clone(), 0).is_err());
+    assert!(map.try_fill_gaps_alloc(huge.clone(), 0).is_err());
+    let mut bytes = SparseVec::new();
+    bytes.insert(vec![1u8; 4], 0x10);
+    assert!(bytes.try_fill_alloc(huge.clone(), 0).is_err());
+    assert!(bytes.try_fill_gaps_alloc(huge, 0).is_err());
+    assert_eq!(Vec::from_iter(bytes.ranges()), vec![0x10..0x14]);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x10..0x14, 0x20..0x24]);
+    assert_eq!(map.version(), 2);
+
+    // Successful calls match the infallible ones, including splits and merges
+    let mut expected = map.clone_range(0..u64::MAX);
+    map.try_insert_alloc(&[3, 3], 0x11).unwrap();
+    expected.insert(vec![3, 3], 0x11);
+    map.try_fill_alloc(0x14..0x18, 4).unwrap();
+    expected.fill(0x14..0x18, 4);
+    map.try_fill_gaps_alloc(0x0c..0x22, 5).unwrap();
+    expected.fill_gaps(0x0c..0x22, 5);
+    map.try_insert_alloc(&[6; 3], 0x30).unwrap();
+    expected.insert(vec![6; 3], 0x30);
+    map.try_insert_alloc(&[], 0x40).unwrap();
+    assert!(map.blocks().eq(expected.blocks()));
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x0c..0x24, 0x30..0x33]);
+    assert_eq!(
+        map.get(0x0f..0x19).unwrap(),
+        &[5, 1, 3, 3, 1, 4, 4, 4, 4, 5]
+    );
+}
@@ -0,0 +1,323 @@
+//! C interface for `SparseVec<u8>`, declared in `include/sparse_vec.h`.
+//!
+//! Functions returning `int` or `intptr_t` report failure with `-1` and leave a description
+//! for [`sparse_vec_last_error`]. Panics are caught at the boundary and reported the same way,
+//! unless the library is built with `panic = "abort"`.
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, c_void, CString};
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::SparseVec;
+
+/// Opaque handle owning a `SparseVec<u8>`.
+pub struct SparseVecHandle(SparseVec<u8>);
+
+/// A stored range, `end` exclusive.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SparseVecRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+pub type SparseVecRangeCallback = extern "C" fn(start: u64, end: u64, user: *mut c_void);
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap();
+    LAST_ERROR.with(|last| *last.borrow_mut() = Some(message));
+}
+
+// Runs `f`, turning errors and panics into `on_error` and the thread's last error
+fn guard<R>(on_error: R, f: impl FnOnce() -> Result<R, String>) -> R {
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(result)) => result,
+        Ok(Err(message)) => {
+            set_last_error(message);
+            on_error
+        }
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".into());
+            set_last_error(format!("panic: {message}"));
+            on_error
+        }
+    }
+}
+
+unsafe fn vec_ref<'a>(handle: *const SparseVecHandle) -> Result<&'a SparseVec<u8>, String> {
+    unsafe { handle.as_ref() }
+        .map(|handle| &handle.0)
+        .ok_or_else(|| "null handle".into())
+}
+
+unsafe fn vec_mut<'a>(handle: *mut SparseVecHandle) -> Result<&'a mut SparseVec<u8>, String> {
+    unsafe { handle.as_mut() }
+        .map(|handle| &mut handle.0)
+        .ok_or_else(|| "null handle".into())
+}
+
+unsafe fn slice<'a>(data: *const u8, len: usize) -> Result<&'a [u8], String> {
+    match (data.is_null(), len) {
+        (_, 0) => Ok(&[]),
+        (true, _) => Err("null data pointer".into()),
+        (false, _) => Ok(unsafe { std::slice::from_raw_parts(data, len) }),
+    }
+}
+
+fn range(start: u64, end: u64) -> Result<Range<u64>, String> {
+    if start > end {
+        return Err(format!("invalid range {start:#x}..{end:#x}"));
+    }
+    Ok(start..end)
+}
+
+fn end(addr: u64, len: usize) -> Result<u64, String> {
+    addr.checked_add(len as u64)
+        .ok_or_else(|| format!("{len:#x} bytes at {addr:#x} exceed the address space"))
+}
+
+/// Creates an empty `SparseVec<u8>`. Free it with [`sparse_vec_free`].
+#[no_mangle]
+pub extern "C" fn sparse_vec_new() -> *mut SparseVecHandle {
+    guard(ptr::null_mut(), || {
+        Ok(Box::into_raw(Box::new(SparseVecHandle(SparseVec::new()))))
+    })
+}
+
+/// Frees a handle. Null is ignored.
+///
+/// # Safety
+/// `handle` must be null or come from [`sparse_vec_new`] and not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn sparse_vec_free(handle: *mut SparseVecHandle) {
+    if !handle.is_null() {
+        guard((), || {
+            drop(unsafe { Box::from_raw(handle) });
+            Ok(())
+        })
+    }
+}
+
+/// Copies `len` bytes from `data` to `addr`, overwriting stored bytes. Returns 0 or -1.
+///
+/// # Safety
+/// `handle` must be a live handle and `data` must be readable for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sparse_vec_insert(
+    handle: *mut SparseVecHandle,
+    addr: u64,
+    data: *const u8,
+    len: usize,
+) -> c_int {
+    guard(-1, || {
+        let vec = unsafe { vec_mut(handle) }?;
+        let data = unsafe { slice(data, len) }?;
+        end(addr, len)?;
+        vec.insert(data.to_vec(), addr);
+        Ok(0)
+    })
+}
+
+/// Copies the bytes stored contiguously from `addr` into `buf`, at most `len`. Returns the
+/// number of bytes copied, 0 if `addr` is unmapped, or -1.
+///
+/// # Safety
+/// `handle` must be a live handle and `buf` must be writable for `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn sparse_vec_read(
+    handle: *const SparseVecHandle,
+    addr: u64,
+    buf: *mut u8,
+    len: usize,
+) -> isize {
+    guard(-1, || {
+        let vec = unsafe { vec_ref(handle) }?;
+        if len == 0 {
+            return Ok(0);
+        }
+        if buf.is_null() {
+            return Err("null buffer".into());
+        }
+        let end = addr.saturating_add(len as u64);
+        let Some((_, data)) = vec
+            .slices(addr..end)
+            .next()
+            .filter(|(clipped, _)| clipped.start == addr)
+        else {
+            return Ok(0);
+        };
+        unsafe { ptr::copy_nonoverlapping(data.as_ptr(), buf, data.len()) };
+        Ok(data.len() as isize)
+    })
+}
+
+/// Whether any byte in `start..end` is stored. Returns 1, 0 or -1.
+///
+/// # Safety
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn sparse_vec_overlaps(
+    handle: *const SparseVecHandle,
+    start: u64,
+    end: u64,
+) -> c_int {
+    guard(-1, || {
+        let vec = unsafe { vec_ref(handle) }?;
+        Ok(vec.overlaps(&range(start, end)?) as c_int)
+    })
+}
+
+/// Whether every byte in `start..end` is stored. Returns 1, 0 or -1.
+///
+/// # Safety
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn sparse_vec_contains(
+    handle: *const SparseVecHandle,
+    start: u64,
+    end: u64,
+) -> c_int {
+    guard(-1, || {
+        let vec = unsafe { vec_ref(handle) }?;
+        Ok(vec.contains_range(&range(start, end)?) as c_int)
+    })
+}
+
+/// Calls `callback` with every stored range in address order. Returns the number of ranges
+/// or -1. The handle must not be modified from the callback.
+///
+/// # Safety
+/// `handle` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn sparse_vec_ranges(
+    handle: *const SparseVecHandle,
+    callback: Option<SparseVecRangeCallback>,
+    user: *mut c_void,
+) -> isize {
+    guard(-1, || {
+        let vec = unsafe { vec_ref(handle) }?;
+        let callback = callback.ok_or("null callback")?;
+        for range in vec.ranges() {
+            callback(range.start, range.end, user);
+        }
+        Ok(vec.ranges().len() as isize)
+    })
+}
+
+/// Writes up to `cap` stored ranges in address order to `out`. Returns the total number of
+/// ranges, which may exceed `cap`, or -1.
+///
+/// # Safety
+/// `handle` must be a live handle and `out` must be writable for `cap` ranges.
+#[no_mangle]
+pub unsafe extern "C" fn sparse_vec_ranges_into(
+    handle: *const SparseVecHandle,
+    out: *mut SparseVecRange,
+    cap: usize,
+) -> isize {
+    guard(-1, || {
+        let vec = unsafe { vec_ref(handle) }?;
+        if cap > 0 && out.is_null() {
+            return Err("null output array".into());
+        }
+        for (i, range) in vec.ranges().take(cap).enumerate() {
+            let range = SparseVecRange {
+                start: range.start,
+                end: range.end,
+            };
+            unsafe { out.add(i).write(range) };
+        }
+        Ok(vec.ranges().len() as isize)
+    })
+}
+
+/// Description of the last failure on this thread, or null. Valid until the next failing
+/// call on the same thread.
+#[no_mangle]
+pub extern "C" fn sparse_vec_last_error() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+#[test]
+fn sparsevec_ffi() {
+    use std::ffi::CStr;
+
+    let last_error = || {
+        unsafe { CStr::from_ptr(sparse_vec_last_error()) }
+            .to_str()
+            .unwrap()
+    };
+    unsafe {
+        let handle = sparse_vec_new();
+        assert_eq!(sparse_vec_insert(handle, 0x10, [1u8; 8].as_ptr(), 8), 0);
+        assert_eq!(sparse_vec_insert(handle, 0x18, [2u8; 8].as_ptr(), 8), 0);
+        assert_eq!(sparse_vec_insert(handle, 0x40, [3u8; 4].as_ptr(), 4), 0);
+        assert_eq!(sparse_vec_insert(handle, 0x50, ptr::null(), 0), 0);
+
+        let mut buf = [0u8; 0x20];
+        assert_eq!(
+            sparse_vec_read(handle, 0x14, buf.as_mut_ptr(), buf.len()),
+            0x0c
+        );
+        assert_eq!(buf[..0x0c], [1, 1, 1, 1, 2, 2, 2, 2, 2, 2, 2, 2]);
+        assert_eq!(sparse_vec_read(handle, 0x40, buf.as_mut_ptr(), 2), 2);
+        assert_eq!(
+            sparse_vec_read(handle, 0x20, buf.as_mut_ptr(), buf.len()),
+            0
+        );
+
+        assert_eq!(sparse_vec_overlaps(handle, 0x1c, 0x44), 1);
+        assert_eq!(sparse_vec_overlaps(handle, 0x20, 0x40), 0);
+        assert_eq!(sparse_vec_contains(handle, 0x10, 0x20), 1);
+        assert_eq!(sparse_vec_contains(handle, 0x10, 0x21), 0);
+
+        extern "C" fn collect(start: u64, end: u64, user: *mut c_void) {
+            unsafe { &mut *user.cast::<Vec<Range<u64>>>() }.push(start..end);
+        }
+        let mut ranges = Vec::<Range<u64>>::new();
+        let user = ptr::from_mut(&mut ranges).cast();
+        assert_eq!(sparse_vec_ranges(handle, Some(collect), user), 2);
+        assert_eq!(ranges, [0x10..0x20, 0x40..0x44]);
+
+        let mut out = [SparseVecRange { start: 0, end: 0 }; 1];
+        assert_eq!(sparse_vec_ranges_into(handle, out.as_mut_ptr(), 1), 2);
+        assert_eq!(
+            out[0],
+            SparseVecRange {
+                start: 0x10,
+                end: 0x20
+            }
+        );
+        assert_eq!(sparse_vec_ranges_into(handle, ptr::null_mut(), 0), 2);
+
+        // Failures never reach the caller as panics
+        assert_eq!(sparse_vec_overlaps(handle, 0x20, 0x10), -1);
+        assert_eq!(last_error(), "invalid range 0x20..0x10");
+        assert_eq!(
+            sparse_vec_insert(handle, u64::MAX, [0u8; 2].as_ptr(), 2),
+            -1
+        );
+        assert_eq!(
+            last_error(),
+            "0x2 bytes at 0xffffffffffffffff exceed the address space"
+        );
+        assert_eq!(sparse_vec_read(ptr::null(), 0, buf.as_mut_ptr(), 1), -1);
+        assert_eq!(last_error(), "null handle");
+        (*handle).0.freeze(0x10..0x11);
+        assert_eq!(sparse_vec_insert(handle, 0x10, [0u8].as_ptr(), 1), -1);
+        assert_eq!(last_error(), "panic: address 0x10 is frozen");
+
+        sparse_vec_free(handle);
+        sparse_vec_free(ptr::null_mut());
+    }
+}
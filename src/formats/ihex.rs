@@ -0,0 +1,270 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{decode_hex, push_hex};
+use crate::SparseVec;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IhexError {
+    /// 1-based line number of the offending record.
+    pub line: usize,
+    pub kind: IhexErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IhexErrorKind {
+    MissingColon,
+    InvalidHex,
+    /// The record is shorter or longer than its byte count says.
+    Length,
+    Checksum {
+        expected: u8,
+        found: u8,
+    },
+    UnsupportedRecord(u8),
+    /// An address record with a payload of the wrong size.
+    InvalidAddressRecord,
+    /// The input ended without an EOF (01) record.
+    MissingEof,
+}
+
+impl fmt::Display for IhexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            IhexErrorKind::MissingColon => write!(f, "record does not start with ':'"),
+            IhexErrorKind::InvalidHex => write!(f, "invalid hex digit"),
+            IhexErrorKind::Length => write!(f, "record length does not match byte count"),
+            IhexErrorKind::Checksum { expected, found } => {
+                write!(f, "checksum is {found:#04x}, expected {expected:#04x}")
+            }
+            IhexErrorKind::UnsupportedRecord(ty) => write!(f, "unsupported record type {ty:02x}"),
+            IhexErrorKind::InvalidAddressRecord => write!(f, "malformed address record"),
+            IhexErrorKind::MissingEof => write!(f, "missing end of file record"),
+        }
+    }
+}
+
+impl core::error::Error for IhexError {}
+
+fn parse_record(line: &str) -> Result<(u8, u16, Vec<u8>), IhexErrorKind> {
+    let hex = line.strip_prefix(':').ok_or(IhexErrorKind::MissingColon)?;
+    if hex.len() % 2 != 0 {
+        return Err(IhexErrorKind::Length);
+    }
+    let bytes = decode_hex(hex).ok_or(IhexErrorKind::InvalidHex)?;
+    if bytes.len() < 5 || bytes.len() != bytes[0] as usize + 5 {
+        return Err(IhexErrorKind::Length);
+    }
+
+    let (body, checksum) = bytes.split_at(bytes.len() - 1);
+    let expected = checksum_of(body);
+    if expected != checksum[0] {
+        return Err(IhexErrorKind::Checksum {
+            expected,
+            found: checksum[0],
+        });
+    }
+    let offset = u16::from_be_bytes([body[1], body[2]]);
+    Ok((body[3], offset, body[4..].to_vec()))
+}
+
+fn checksum_of(bytes: &[u8]) -> u8 {
+    bytes
+        .iter()
+        .fold(0u8, |sum, b| sum.wrapping_add(*b))
+        .wrapping_neg()
+}
+
+fn push_record(out: &mut String, ty: u8, offset: u16, data: &[u8]) {
+    let mut body = Vec::with_capacity(data.len() + 4);
+    body.push(data.len() as u8);
+    body.extend_from_slice(&offset.to_be_bytes());
+    body.push(ty);
+    body.extend_from_slice(data);
+    out.push(':');
+    push_hex(out, &body);
+    push_hex(out, &[checksum_of(&body)]);
+    out.push('\n');
+}
+
+impl SparseVec<u8> {
+    /// Parses Intel HEX records 00 (data), 01 (EOF), 02 (extended segment address) and
+    /// 04 (extended linear address). Start address records (03, 05) are ignored.
+    /// Overlapping data records are inserted in order, so the last one wins.
+    pub fn from_ihex(text: &str) -> Result<Self, IhexError> {
+        let mut vec = Self::new();
+        let mut base = 0u64;
+        // Consecutive records are collected before inserting
+        let mut pending: Option<(u64, Vec<u8>)> = None;
+        let mut line_count = 0;
+        for (i, line) in text.lines().enumerate() {
+            line_count = i + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |kind| IhexError { line: i + 1, kind };
+            let (ty, offset, data) = parse_record(line).map_err(error)?;
+            match ty {
+                0x00 => {
+                    let addr = base + offset as u64;
+                    match &mut pending {
+                        Some((start, buf)) if *start + buf.len() as u64 == addr => {
+                            buf.extend_from_slice(&data)
+                        }
+                        _ => {
+                            if let Some((start, buf)) = pending.replace((addr, data)) {
+                                vec.insert(buf, start);
+                            }
+                        }
+                    }
+                }
+                0x01 => {
+                    if let Some((start, buf)) = pending {
+                        vec.insert(buf, start);
+                    }
+                    return Ok(vec);
+                }
+                0x02 | 0x04 => {
+                    let [hi, lo] = data[..] else {
+                        return Err(error(IhexErrorKind::InvalidAddressRecord));
+                    };
+                    let value = u16::from_be_bytes([hi, lo]) as u64;
+                    base = if ty == 0x02 { value << 4 } else { value << 16 };
+                }
+                0x03 | 0x05 => {}
+                ty => return Err(error(IhexErrorKind::UnsupportedRecord(ty))),
+            }
+        }
+        Err(IhexError {
+            line: line_count + 1,
+            kind: IhexErrorKind::MissingEof,
+        })
+    }
+
+    /// Encodes as Intel HEX with at most `record_len` data bytes per record, using extended
+    /// linear address records where needed and a terminating EOF record.
+    ///
+    /// # Panics
+    ///
+    /// If `record_len` is not in `1..=255` or data is stored above 4 GiB.
+    pub fn to_ihex(&self, record_len: usize) -> String {
+        assert!(
+            (1..=255).contains(&record_len),
+            "record length must be in 1..=255"
+        );
+        let mut out = String::new();
+        let mut upper = 0u16;
+        for (range, data) in self.blocks() {
+            assert!(
+                range.end <= 1 << 32,
+                "Intel HEX cannot address data above 4 GiB"
+            );
+            let mut addr = range.start;
+            let mut data = data;
+            while !data.is_empty() {
+                let block_upper = (addr >> 16) as u16;
+                if block_upper != upper {
+                    push_record(&mut out, 0x04, 0, &block_upper.to_be_bytes());
+                    upper = block_upper;
+                }
+                // Records never cross a 64 KiB boundary
+                let to_boundary = (0x1_0000 - (addr & 0xffff)) as usize;
+                let len = record_len.min(to_boundary).min(data.len());
+                push_record(&mut out, 0x00, addr as u16, &data[..len]);
+                addr += len as u64;
+                data = &data[len..];
+            }
+        }
+        push_record(&mut out, 0x01, 0, &[]);
+        out
+    }
+}
+
+#[test]
+fn sparsevec_ihex_parse() {
+    let text = "\
+:0B0010006164647265737320676170A7
+:020000021000EC
+:0401000001020304F1
+:020000040001F9
+:03FFFF00AABBCCCE
+:00000001FF
+";
+    let map = SparseVec::from_ihex(text).unwrap();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x10..0x1b, 0x10100..0x10104, 0x1ffff..0x20002]
+    );
+    assert_eq!(map.get(0x10..0x1b).unwrap(), b"address gap");
+    assert_eq!(map.get(0x1ffff..0x20002).unwrap(), &[0xaa, 0xbb, 0xcc]);
+
+    // Last wins
+    let map = SparseVec::from_ihex(":0400000001020304F2\n:02000100AAAAA9\n:00000001FF").unwrap();
+    assert_eq!(map.get(0..4).unwrap(), &[1, 0xaa, 0xaa, 4]);
+}
+
+#[test]
+fn sparsevec_ihex_errors() {
+    let err = |text| SparseVec::from_ihex(text).unwrap_err();
+    assert_eq!(
+        err(":0400000001020304F2\n:0400040001020304F3\n"),
+        IhexError {
+            line: 2,
+            kind: IhexErrorKind::Checksum {
+                expected: 0xee,
+                found: 0xf3
+            }
+        }
+    );
+    assert_eq!(err("\n0400000001\n").line, 2);
+    assert_eq!(err("0400000001\n").kind, IhexErrorKind::MissingColon);
+    assert_eq!(err(":04000000010203F2\n").kind, IhexErrorKind::Length);
+    assert_eq!(err(":0G00000001020304F2").kind, IhexErrorKind::InvalidHex);
+    assert_eq!(err(":01000000+1FE").kind, IhexErrorKind::InvalidHex);
+    assert_eq!(err(":00000006FA").kind, IhexErrorKind::UnsupportedRecord(6));
+    assert_eq!(
+        err(":0100000400FB").kind,
+        IhexErrorKind::InvalidAddressRecord
+    );
+    assert_eq!(
+        err(":0400000001020304F2\n"),
+        IhexError {
+            line: 2,
+            kind: IhexErrorKind::MissingEof
+        }
+    );
+}
+
+#[test]
+fn sparsevec_ihex_roundtrip() {
+    let mut map = SparseVec::new();
+    map.insert(Vec::from_iter(0..=255u8), 0x0);
+    // Crosses the boundary between the first and second 64 KiB page
+    map.insert(Vec::from_iter((0..100u8).rev()), 0xffd0);
+    map.insert(vec![0x5a; 40], 0x3_0000);
+    map.insert(vec![0xa5; 3], 0x1234_fffe);
+
+    let text = map.to_ihex(32);
+    assert!(text.starts_with(":20000000000102"));
+    assert!(text.ends_with(":00000001FF\n"));
+    assert!(text.contains(":020000040001F9\n"));
+    assert!(text.lines().all(|line| line.len() <= 11 + 64));
+
+    let decoded = SparseVec::from_ihex(&text).unwrap();
+    assert_eq!(
+        Vec::from_iter(decoded.blocks()),
+        Vec::from_iter(map.blocks())
+    );
+    for record_len in [1, 7, 255] {
+        let decoded = SparseVec::from_ihex(&map.to_ihex(record_len)).unwrap();
+        assert_eq!(
+            Vec::from_iter(decoded.blocks()),
+            Vec::from_iter(map.blocks())
+        );
+    }
+
+    assert_eq!(SparseVec::new().to_ihex(16), ":00000001FF\n");
+}
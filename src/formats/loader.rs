@@ -0,0 +1,107 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use object::{Object, ObjectSegment};
+
+use crate::SparseVec;
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The input is not a supported object file or is malformed.
+    Parse(object::Error),
+    /// A segment does not fit the address space.
+    Overflow { address: u64, size: u64 },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Parse(err) => write!(f, "invalid object file: {err}"),
+            LoadError::Overflow { address, size } => {
+                write!(f, "segment at {address:#x} with size {size:#x} overflows")
+            }
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
+
+impl From<object::Error> for LoadError {
+    fn from(err: object::Error) -> Self {
+        LoadError::Parse(err)
+    }
+}
+
+impl SparseVec<u8> {
+    /// Loads the loadable segments of an ELF file or the sections of a PE file at their virtual
+    /// addresses and returns the entry point.
+    ///
+    /// Memory beyond the file bytes of a segment is zero-filled. Segments are inserted in file
+    /// order, so later segments overwrite earlier ones where they overlap.
+    pub fn from_object(data: &[u8]) -> Result<(Self, u64), LoadError> {
+        let file = object::File::parse(data)?;
+        let mut vec = Self::new();
+        for segment in file.segments() {
+            let (address, size) = (segment.address(), segment.size());
+            let len = address
+                .checked_add(size)
+                .and_then(|_| usize::try_from(size).ok())
+                .ok_or(LoadError::Overflow { address, size })?;
+            if len == 0 {
+                continue;
+            }
+            let bytes = segment.data()?;
+            let mut buf = Vec::from(&bytes[..bytes.len().min(len)]);
+            buf.resize(len, 0);
+            vec.insert(buf, address);
+        }
+        Ok((vec, file.entry()))
+    }
+}
+
+#[test]
+fn sparsevec_from_object_elf() {
+    let (map, entry) = SparseVec::from_object(include_bytes!("../../tests/data/tiny.elf")).unwrap();
+    assert_eq!(entry, 0x400002);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x400000..0x400008, 0x401000..0x401010]
+    );
+    assert_eq!(
+        map.get(0x400000..0x400008).unwrap(),
+        &[1, 2, 3, 4, 0xee, 0xee, 7, 8]
+    );
+    assert_eq!(
+        map.get(0x401000..0x401010).unwrap(),
+        &[0xaa, 0xbb, 0xcc, 0xdd, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+    );
+}
+
+#[test]
+fn sparsevec_from_object_pe() {
+    let (map, entry) = SparseVec::from_object(include_bytes!("../../tests/data/tiny.exe")).unwrap();
+    let base = 0x1_4000_0000;
+    assert_eq!(entry, base + 0x1004);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![
+            base + 0x1000..base + 0x1008,
+            base + 0x2000..base + 0x2010,
+            base + 0x3000..base + 0x3004
+        ]
+    );
+    assert_eq!(
+        map.get(base + 0x1000..base + 0x1008).unwrap(),
+        &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17]
+    );
+    assert_eq!(map.get(base + 0x2000..base + 0x2010).unwrap(), &[0; 16]);
+    assert_eq!(
+        map.get(base + 0x3000..base + 0x3004).unwrap(),
+        &[0x99, 0x88, 0x77, 0x66]
+    );
+
+    assert!(matches!(
+        SparseVec::from_object(b"not an object file"),
+        Err(LoadError::Parse(_))
+    ));
+}
@@ -0,0 +1,34 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+mod ihex;
+#[cfg(feature = "object")]
+mod loader;
+mod srec;
+
+pub use ihex::{IhexError, IhexErrorKind};
+#[cfg(feature = "object")]
+pub use loader::LoadError;
+pub use srec::{SrecError, SrecErrorKind, SrecKind};
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            // `from_str_radix` alone would take a sign, e.g. `+1`
+            hex.get(i..i + 2)
+                .filter(|byte| byte.bytes().all(|c| c.is_ascii_hexdigit()))
+                .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+        })
+        .collect()
+}
+
+fn push_hex(out: &mut String, bytes: &[u8]) {
+    for b in bytes {
+        write!(out, "{b:02X}").unwrap();
+    }
+}
@@ -0,0 +1,306 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use super::{decode_hex, push_hex};
+use crate::SparseVec;
+
+/// Address width of the data records written by [`SparseVec::to_srec`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SrecKind {
+    /// S1 records with 16 bit addresses, terminated by S9.
+    S19,
+    /// S2 records with 24 bit addresses, terminated by S8.
+    S28,
+    /// S3 records with 32 bit addresses, terminated by S7.
+    S37,
+    /// The smallest kind that can address `bounds()`.
+    Auto,
+}
+
+impl SrecKind {
+    fn address_len(self) -> usize {
+        match self {
+            SrecKind::S19 => 2,
+            SrecKind::S28 => 3,
+            SrecKind::S37 | SrecKind::Auto => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SrecError {
+    /// 1-based line number of the offending record.
+    pub line: usize,
+    pub kind: SrecErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SrecErrorKind {
+    MissingS,
+    InvalidHex,
+    /// The byte count does not match the record length or is too small for the record type.
+    ByteCount,
+    Checksum {
+        expected: u8,
+        found: u8,
+    },
+    UnsupportedRecord(u8),
+    /// An S5/S6 record disagrees with the number of data records seen.
+    RecordCount {
+        expected: u32,
+        found: u32,
+    },
+    /// The input ended without an S7, S8 or S9 record.
+    MissingTermination,
+}
+
+impl fmt::Display for SrecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            SrecErrorKind::MissingS => write!(f, "record does not start with 'S'"),
+            SrecErrorKind::InvalidHex => write!(f, "invalid hex digit"),
+            SrecErrorKind::ByteCount => write!(f, "byte count does not match record"),
+            SrecErrorKind::Checksum { expected, found } => {
+                write!(f, "checksum is {found:#04x}, expected {expected:#04x}")
+            }
+            SrecErrorKind::UnsupportedRecord(ty) => write!(f, "unsupported record type S{ty}"),
+            SrecErrorKind::RecordCount { expected, found } => {
+                write!(f, "record count is {found}, expected {expected}")
+            }
+            SrecErrorKind::MissingTermination => write!(f, "missing termination record"),
+        }
+    }
+}
+
+impl core::error::Error for SrecError {}
+
+fn checksum_of(bytes: &[u8]) -> u8 {
+    !bytes.iter().fold(0u8, |sum, b| sum.wrapping_add(*b))
+}
+
+// Returns the record type, address and data
+fn parse_record(line: &str) -> Result<(u8, u32, Vec<u8>), SrecErrorKind> {
+    let rest = line.strip_prefix('S').ok_or(SrecErrorKind::MissingS)?;
+    let mut chars = rest.chars();
+    let ty = chars
+        .next()
+        .and_then(|c| c.to_digit(10))
+        .ok_or(SrecErrorKind::InvalidHex)? as u8;
+    let bytes = decode_hex(chars.as_str()).ok_or(SrecErrorKind::InvalidHex)?;
+    let address_len = match ty {
+        0 | 1 | 5 | 9 => 2,
+        2 | 6 | 8 => 3,
+        3 | 7 => 4,
+        ty => return Err(SrecErrorKind::UnsupportedRecord(ty)),
+    };
+    if bytes.len() < address_len + 2 || bytes[0] as usize != bytes.len() - 1 {
+        return Err(SrecErrorKind::ByteCount);
+    }
+
+    let (body, checksum) = bytes.split_at(bytes.len() - 1);
+    let expected = checksum_of(body);
+    if expected != checksum[0] {
+        return Err(SrecErrorKind::Checksum {
+            expected,
+            found: checksum[0],
+        });
+    }
+    let address = body[1..=address_len]
+        .iter()
+        .fold(0u32, |addr, b| addr << 8 | *b as u32);
+    Ok((ty, address, body[address_len + 1..].to_vec()))
+}
+
+fn push_record(out: &mut String, ty: u8, address_len: usize, address: u32, data: &[u8]) {
+    let mut body = Vec::with_capacity(data.len() + address_len + 1);
+    body.push((address_len + data.len() + 1) as u8);
+    body.extend_from_slice(&address.to_be_bytes()[4 - address_len..]);
+    body.extend_from_slice(data);
+    out.push('S');
+    out.push(char::from(b'0' + ty));
+    push_hex(out, &body);
+    push_hex(out, &[checksum_of(&body)]);
+    out.push('\n');
+}
+
+impl SparseVec<u8> {
+    /// Parses Motorola S-records. S0 headers are ignored, S1–S3 data records are inserted in
+    /// order (the last one wins on overlap), S5/S6 counts are checked and S7–S9 end the input.
+    pub fn from_srec(text: &str) -> Result<Self, SrecError> {
+        let mut vec = Self::new();
+        let mut data_records = 0u32;
+        let mut pending: Option<(u64, Vec<u8>)> = None;
+        let mut line_count = 0;
+        for (i, line) in text.lines().enumerate() {
+            line_count = i + 1;
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |kind| SrecError { line: i + 1, kind };
+            let (ty, address, data) = parse_record(line).map_err(error)?;
+            match ty {
+                0 => {}
+                1..=3 => {
+                    data_records += 1;
+                    let addr = address as u64;
+                    match &mut pending {
+                        Some((start, buf)) if *start + buf.len() as u64 == addr => {
+                            buf.extend_from_slice(&data)
+                        }
+                        _ => {
+                            if let Some((start, buf)) = pending.replace((addr, data)) {
+                                vec.insert(buf, start);
+                            }
+                        }
+                    }
+                }
+                5 | 6 => {
+                    if address != data_records {
+                        return Err(error(SrecErrorKind::RecordCount {
+                            expected: data_records,
+                            found: address,
+                        }));
+                    }
+                }
+                _ => {
+                    if let Some((start, buf)) = pending {
+                        vec.insert(buf, start);
+                    }
+                    return Ok(vec);
+                }
+            }
+        }
+        Err(SrecError {
+            line: line_count + 1,
+            kind: SrecErrorKind::MissingTermination,
+        })
+    }
+
+    /// Encodes as S-records with at most `record_len` data bytes per record, in address order.
+    ///
+    /// # Panics
+    ///
+    /// If `record_len` is zero or too long for the record type, or the data does not fit the
+    /// address width of `kind`.
+    pub fn to_srec(&self, kind: SrecKind, record_len: usize) -> String {
+        let end = self.bounds().map_or(0, |bounds| bounds.end);
+        let kind = match kind {
+            SrecKind::Auto if end <= 1 << 16 => SrecKind::S19,
+            SrecKind::Auto if end <= 1 << 24 => SrecKind::S28,
+            SrecKind::Auto => SrecKind::S37,
+            kind => kind,
+        };
+        let address_len = kind.address_len();
+        assert!(
+            end <= 1 << (8 * address_len),
+            "data does not fit the address width of {kind:?}"
+        );
+        assert!(
+            (1..=254 - address_len).contains(&record_len),
+            "record length must be in 1..={}",
+            254 - address_len
+        );
+
+        let mut out = String::new();
+        push_record(&mut out, 0, 2, 0, &[]);
+        for (range, data) in self.blocks() {
+            for (i, chunk) in data.chunks(record_len).enumerate() {
+                let address = (range.start + (i * record_len) as u64) as u32;
+                push_record(&mut out, address_len as u8 - 1, address_len, address, chunk);
+            }
+        }
+        push_record(&mut out, 11 - address_len as u8, address_len, 0, &[]);
+        out
+    }
+}
+
+#[test]
+fn sparsevec_srec_parse() {
+    let text = "\
+S00600004844521B
+S108001068656C6C6FD3
+S20601234501028D
+S307FFFFFFFEAABB98
+S5030003F9
+S9030000FC
+";
+    let map = SparseVec::from_srec(text).unwrap();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x10..0x15, 0x12345..0x12347, 0xffff_fffe..0x1_0000_0000]
+    );
+    assert_eq!(map.get(0x10..0x15).unwrap(), b"hello");
+
+    let map = SparseVec::from_srec("S107000001020304EE\nS104000209F0\nS9030000FC").unwrap();
+    assert_eq!(map.get(0..4).unwrap(), &[1, 2, 9, 4]);
+}
+
+#[test]
+fn sparsevec_srec_errors() {
+    let err = |text| SparseVec::from_srec(text).unwrap_err();
+    assert_eq!(
+        err("S00600004844521B\n\nS108001068656C6C6FD4\nS9030000FC"),
+        SrecError {
+            line: 3,
+            kind: SrecErrorKind::Checksum {
+                expected: 0xd3,
+                found: 0xd4
+            }
+        }
+    );
+    assert_eq!(err("S109001068656C6C6FD3").kind, SrecErrorKind::ByteCount);
+    assert_eq!(err("S1020010ED").kind, SrecErrorKind::ByteCount);
+    assert_eq!(err("X104000209F0").kind, SrecErrorKind::MissingS);
+    assert_eq!(err("S10400020GF0").kind, SrecErrorKind::InvalidHex);
+    assert_eq!(err("S1040002+9F0").kind, SrecErrorKind::InvalidHex);
+    assert_eq!(err("S4030000FC").kind, SrecErrorKind::UnsupportedRecord(4));
+    assert_eq!(
+        err("S104000209F0\nS5030003F9"),
+        SrecError {
+            line: 2,
+            kind: SrecErrorKind::RecordCount {
+                expected: 1,
+                found: 3
+            }
+        }
+    );
+    assert_eq!(
+        err("S104000209F0\n").kind,
+        SrecErrorKind::MissingTermination
+    );
+}
+
+#[test]
+fn sparsevec_srec_roundtrip() {
+    let mut map = SparseVec::new();
+    map.insert(Vec::from_iter(0..=255u8), 0x100);
+    map.insert(vec![0x5a; 40], 0xf000);
+
+    let text = map.to_srec(SrecKind::Auto, 16);
+    assert!(text.starts_with("S0030000FC\nS1130100000102"));
+    assert!(text.ends_with("S9030000FC\n"));
+    let decoded = SparseVec::from_srec(&text).unwrap();
+    assert_eq!(
+        Vec::from_iter(decoded.blocks()),
+        Vec::from_iter(map.blocks())
+    );
+
+    map.insert(vec![1, 2, 3], 0xff_fffd);
+    let text = map.to_srec(SrecKind::Auto, 250);
+    assert!(text.contains("\nS207FFFFFD010203F7\n"));
+    assert!(text.ends_with("S804000000FB\n"));
+
+    map.insert(vec![4; 8], 0xffff_fff8);
+    for kind in [SrecKind::Auto, SrecKind::S37] {
+        let text = map.to_srec(kind, 7);
+        assert!(text.ends_with("S70500000000FA\n"));
+        let decoded = SparseVec::from_srec(&text).unwrap();
+        assert_eq!(
+            Vec::from_iter(decoded.blocks()),
+            Vec::from_iter(map.blocks())
+        );
+    }
+}
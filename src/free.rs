@@ -0,0 +1,119 @@
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Lowest address in `search` where `len` consecutive addresses are unmapped and which is a
+    /// multiple of `align`. `align` does not have to be a power of two. Reserved addresses
+    /// count as unmapped, see [`SparseVec::find_unreserved_range`].
+    ///
+    /// # Panics
+    ///
+    /// If `align` is zero.
+    pub fn find_free_range(&self, len: A, align: A, search: Range<A>) -> Option<A> {
+        let (len, align) = (len.to_u64(), align.to_u64());
+        assert!(align != 0, "alignment must not be zero");
+        let fit = |gap: Range<A>| {
+            let (start, end) = (gap.start.to_u64(), gap.end.to_u64());
+            let addr = start.checked_add((align - start % align) % align)?;
+            (addr.checked_add(len)? <= end).then(|| A::try_from_u64(addr).unwrap())
+        };
+
+        let mut pos = search.start;
+        for (block, _) in self.map.overlapping(&search) {
+            if let Some(addr) = fit(pos..block.start) {
+                return Some(addr);
+            }
+            pos = pos.max(block.end);
+        }
+        fit(pos..search.end)
+    }
+
+    /// Like [`SparseVec::find_free_range`], but returns the highest such address.
+    pub fn find_free_range_last(&self, len: A, align: A, search: Range<A>) -> Option<A> {
+        let (len, align) = (len.to_u64(), align.to_u64());
+        assert!(align != 0, "alignment must not be zero");
+        let fit = |gap: Range<A>| {
+            let (start, end) = (gap.start.to_u64(), gap.end.to_u64());
+            let last = end.checked_sub(len)?;
+            let addr = last - last % align;
+            (addr >= start).then(|| A::try_from_u64(addr).unwrap())
+        };
+
+        let mut end = search.end;
+        for (block, _) in self.map.overlapping(&search).rev() {
+            if let Some(addr) = fit(block.end..end) {
+                return Some(addr);
+            }
+            end = end.min(block.start);
+        }
+        fit(search.start..end)
+    }
+}
+
+#[test]
+fn sparsevec_find_free_range() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 0x100], 0x1000);
+    map.insert(vec![0u8; 0x10], 0x1180);
+    map.insert(vec![0u8; 0x80], 0x2000);
+
+    assert_eq!(map.find_free_range(0x10, 0x10, 0..u64::MAX), Some(0));
+    assert_eq!(
+        map.find_free_range(0x80, 0x80, 0x1000..0x3000),
+        Some(0x1100)
+    );
+    assert_eq!(
+        map.find_free_range(0x81, 0x80, 0x1000..0x3000),
+        Some(0x1200)
+    );
+    assert_eq!(
+        map.find_free_range(0x20, 0x100, 0x1080..0x3000),
+        Some(0x1100)
+    );
+    assert_eq!(
+        map.find_free_range(0x10, 0x30, 0x1001..0x1200),
+        Some(0x1110)
+    );
+    assert_eq!(map.find_free_range(0x10, 1, 0x1190..0x1195), None);
+    assert_eq!(map.find_free_range(0x10, 1, 0x1050..0x1195), Some(0x1100));
+    assert_eq!(map.find_free_range(0x10, 1, 0x2010..0x208f), None);
+    assert_eq!(
+        map.find_free_range(1, 1, u64::MAX - 1..u64::MAX),
+        Some(u64::MAX - 1)
+    );
+    assert_eq!(map.find_free_range(2, 1, u64::MAX - 1..u64::MAX), None);
+    assert_eq!(map.find_free_range(1, 3, u64::MAX - 1..u64::MAX), None);
+
+    assert_eq!(
+        map.find_free_range_last(0x10, 0x10, 0..0x3000),
+        Some(0x2ff0)
+    );
+    assert_eq!(
+        map.find_free_range_last(0x10, 0x10, 0..0x2050),
+        Some(0x1ff0)
+    );
+    assert_eq!(
+        map.find_free_range_last(0x80, 0x80, 0x1000..0x1fff),
+        Some(0x1f00)
+    );
+    assert_eq!(
+        map.find_free_range_last(0x70, 7, 0x1100..0x1180),
+        Some(0x1110)
+    );
+    assert_eq!(
+        map.find_free_range_last(0x70, 0x10, 0x1000..0x1180),
+        Some(0x1110)
+    );
+    assert_eq!(map.find_free_range_last(0x81, 0x10, 0x1000..0x1180), None);
+    assert_eq!(map.find_free_range_last(0x1001, 1, 0..0x1000), None);
+    assert_eq!(map.find_free_range_last(0x1000, 0x1000, 0..0x1000), Some(0));
+
+    let mut small = SparseVec::<u8, u16>::default();
+    small.insert(vec![0; 0x10], 0);
+    assert_eq!(small.find_free_range(0x10, 0x10, 0..u16::MAX), Some(0x10));
+    assert_eq!(
+        small.find_free_range_last(0x10, 0x10, 0..u16::MAX),
+        Some(0xffe0)
+    );
+}
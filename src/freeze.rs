@@ -0,0 +1,153 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, Frozen, SparseVec, WriteError};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Protects `range` against mutation, whether or not data is stored there. `insert`,
+    /// `fill`, `fill_gaps`, `get_mut` and `write` panic if they would touch a frozen address;
+    /// their `try_` variants return [`Frozen`] instead.
+    pub fn freeze(&mut self, range: Range<A>) {
+        if !range.is_empty() {
+            self.frozen.insert(range);
+        }
+    }
+
+    /// Removes the protection of `range`.
+    pub fn thaw(&mut self, range: Range<A>) {
+        if !range.is_empty() {
+            self.frozen.remove(range);
+        }
+    }
+
+    pub fn is_frozen(&self, addr: A) -> bool {
+        self.frozen.contains(&addr)
+    }
+
+    /// Frozen ranges in address order, coalesced.
+    pub fn frozen_ranges(&self) -> impl Iterator<Item = Range<A>> + '_ {
+        self.frozen.iter().cloned()
+    }
+
+    // The first frozen address in `range`
+    pub(crate) fn check_frozen(&self, range: &Range<A>) -> Result<(), Frozen<A>> {
+        if self.frozen.is_empty() || range.is_empty() {
+            return Ok(());
+        }
+        match self.frozen.overlapping(range).next() {
+            Some(frozen) => Err(Frozen {
+                addr: frozen.start.max(range.start),
+            }),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn assert_thawed(&self, range: &Range<A>) {
+        if let Err(err) = self.check_frozen(range) {
+            panic!("{err}");
+        }
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Like [`SparseVec::insert`], but fails without changes if the range is frozen.
+    pub fn try_insert(&mut self, data: Vec<T>, addr: A) -> Result<(), Frozen<A>> {
+        self.check_frozen(&(addr..addr + A::from_usize(data.len())))?;
+        self.insert(data, addr);
+        Ok(())
+    }
+
+    /// Like [`SparseVec::fill`], but fails without changes if the range is frozen.
+    pub fn try_fill(&mut self, range: Range<A>, value: T) -> Result<(), Frozen<A>> {
+        self.check_frozen(&range)?;
+        self.fill(range, value);
+        Ok(())
+    }
+
+    /// Like [`SparseVec::fill_gaps`], but fails without changes if a gap in the range is
+    /// frozen. Frozen stored data is not touched and does not fail.
+    pub fn try_fill_gaps(&mut self, range: Range<A>, value: T) -> Result<(), Frozen<A>> {
+        if !self.frozen.is_empty() {
+            for gap in self.gaps(range.clone()) {
+                self.check_frozen(&gap)?;
+            }
+        }
+        self.fill_gaps(range, value);
+        Ok(())
+    }
+
+    /// Like [`SparseVec::get_mut`], but fails if the stored range is frozen.
+    pub fn try_get_mut(&mut self, range: Range<A>) -> Result<Option<&mut [T]>, Frozen<A>> {
+        if self.get(range.clone()).is_some() {
+            self.check_frozen(&range)?;
+        }
+        Ok(self.get_mut(range))
+    }
+
+    /// Like [`SparseVec::write`], but also fails without changes if the range is frozen.
+    pub fn try_write(&mut self, addr: A, data: &[T]) -> Result<(), WriteError<A>> {
+        self.check_frozen(&(addr..addr + A::from_usize(data.len())))?;
+        Ok(self.write(addr, data)?)
+    }
+}
+
+#[test]
+fn sparsevec_freeze() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 0x10], 0x10);
+    map.freeze(0x18..0x20);
+    // Protection is independent of stored data
+    map.freeze(0x30..0x38);
+    map.freeze(0x38..0x40);
+    assert_eq!(
+        Vec::from_iter(map.frozen_ranges()),
+        vec![0x18..0x20, 0x30..0x40]
+    );
+    assert!(map.is_frozen(0x18) && !map.is_frozen(0x17) && !map.is_frozen(0x40));
+
+    // Partial overlaps are rejected without writing anything
+    let frozen = |addr| Frozen { addr };
+    assert_eq!(
+        map.try_write(0x14, &[2; 8]),
+        Err(WriteError::Frozen { addr: 0x18 })
+    );
+    assert_eq!(map.try_insert(vec![2; 8], 0x2c), Err(frozen(0x30)));
+    assert_eq!(map.try_fill(0x1c..0x24, 2), Err(frozen(0x1c)));
+    assert_eq!(map.try_fill_gaps(0x10..0x34, 2), Err(frozen(0x30)));
+    assert_eq!(
+        map.try_get_mut(0x1f..0x20).map(|s| s.is_some()),
+        Err(frozen(0x1f))
+    );
+    assert_eq!(map.get(0x10..0x20).unwrap(), &[1; 0x10]);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x10..0x20]);
+
+    // Everything outside works as usual
+    map.try_write(0x10, &[3; 8]).unwrap();
+    assert_eq!(
+        map.try_write(0x20, &[3]),
+        Err(WriteError::Unmapped { addr: 0x20 })
+    );
+    map.try_fill_gaps(0x10..0x30, 4).unwrap();
+    map.try_get_mut(0x10..0x18).unwrap().unwrap().fill(5);
+    assert_eq!(map.try_get_mut(0x50..0x54), Ok(None));
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x10..0x30]);
+    assert_eq!(map.get(0x16..0x1a).unwrap(), &[5, 5, 1, 1]);
+
+    map.thaw(0x18..0x1c);
+    map.write(0x18, &[6; 4]).unwrap();
+    assert_eq!(
+        map.try_write(0x18, &[6; 5]),
+        Err(WriteError::Frozen { addr: 0x1c })
+    );
+    map.thaw(0..u64::MAX);
+    assert_eq!(map.frozen_ranges().count(), 0);
+    map.fill(0..0x40, 7);
+}
+
+#[test]
+#[should_panic(expected = "address 0x1c is frozen")]
+fn sparsevec_freeze_panics() {
+    let mut map = SparseVec::new();
+    map.freeze(0x1c..0x20);
+    map.insert(vec![1u8; 0x10], 0x10);
+}
@@ -0,0 +1,116 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{cast_range, sub_range, Address, SparseVec};
+
+// Number of recently found blocks `gather` remembers
+const CACHED_BLOCKS: usize = 16;
+
+// Recently found blocks, replaced round-robin
+struct BlockCache<'a, T, A> {
+    blocks: Vec<(Range<A>, &'a [T])>,
+    next: usize,
+}
+
+impl<T, A> BlockCache<'_, T, A> {
+    fn new() -> Self {
+        Self {
+            blocks: Vec::with_capacity(CACHED_BLOCKS),
+            next: 0,
+        }
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// [`SparseVec::get`] for every range, in the given order. The last few blocks found are
+    /// remembered, so lookups clustered in a few blocks rarely have to search the map.
+    pub fn gather<'a>(&'a self, ranges: &[Range<A>]) -> Vec<Option<&'a [T]>> {
+        let mut cache = BlockCache::new();
+        Vec::from_iter(
+            ranges
+                .iter()
+                .map(|range| self.lookup(&mut cache, range.clone())),
+        )
+    }
+
+    /// Like [`SparseVec::gather`], but lazily.
+    pub fn gather_iter<'a>(
+        &'a self,
+        ranges: impl IntoIterator<Item = Range<A>> + 'a,
+    ) -> impl Iterator<Item = Option<&'a [T]>> + 'a {
+        let mut cache = BlockCache::new();
+        ranges
+            .into_iter()
+            .map(move |range| self.lookup(&mut cache, range))
+    }
+
+    fn lookup<'a>(&'a self, cache: &mut BlockCache<'a, T, A>, range: Range<A>) -> Option<&'a [T]> {
+        let range = if self.mirrors.is_empty() {
+            range
+        } else {
+            self.unmirror(range)?
+        };
+        let cached = cache
+            .blocks
+            .iter()
+            .find(|(block, _)| block.contains(&range.start));
+        let (block, data) = match cached {
+            Some(found) => found.clone(),
+            None => {
+                let (block, key) = self.map.get_key_value(&range.start)?;
+                let found = (block.clone(), &self.data[key][..]);
+                if cache.blocks.len() < CACHED_BLOCKS {
+                    cache.blocks.push(found.clone());
+                } else {
+                    cache.blocks[cache.next] = found.clone();
+                    cache.next = (cache.next + 1) % CACHED_BLOCKS;
+                }
+                found
+            }
+        };
+        data.get(cast_range(sub_range(&range, block.start)))
+    }
+}
+
+#[test]
+fn sparsevec_gather() {
+    let mut map = SparseVec::new();
+    map.insert(Vec::from_iter(0..0x40u8), 0x100);
+    map.insert(vec![0xff; 4], 0x200);
+    map.add_mirror(0x200..0x204, 0x300, 0x10);
+    let ranges = [
+        0x130..0x134,
+        0x100..0x102,
+        0x13e..0x142,
+        0x200..0x204,
+        0x0ff..0x101,
+        0x140..0x140,
+        0x110..0x110,
+        0x30a..0x30c,
+        0x104..0x108,
+    ];
+    let expected = Vec::from_iter(ranges.iter().map(|range| map.get(range.clone())));
+    assert_eq!(map.gather(&ranges), expected);
+    assert!(map.gather_iter(ranges.iter().cloned()).eq(expected));
+    assert_eq!(map.gather(&ranges)[0], Some(&[0x30, 0x31, 0x32, 0x33][..]));
+    assert_eq!(map.gather(&[]), Vec::<Option<&[u8]>>::new());
+}
+
+#[test]
+fn sparsevec_gather_many_blocks() {
+    use rand::{Rng, SeedableRng};
+
+    // More blocks than are cached
+    let mut rng = rand::rngs::StdRng::seed_from_u64(166);
+    let mut map = SparseVec::new();
+    for i in 0..40u64 {
+        map.insert(vec![i as u8; 0x80], i * 0x100);
+    }
+    let ranges = Vec::from_iter((0..5000).map(|_| {
+        let start = rng.gen_range(0..0x2900);
+        start..start + rng.gen_range(0..0x90)
+    }));
+    let expected = Vec::from_iter(ranges.iter().map(|range| map.get(range.clone())));
+    assert_eq!(map.gather(&ranges), expected);
+    assert!(map.gather_iter(ranges).eq(expected));
+}
@@ -0,0 +1,198 @@
+use alloc::vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::SparseVec;
+
+impl SparseVec<u8> {
+    /// `hexdump -C` style dump of `range`.
+    pub fn hexdump(&self, range: Range<u64>) -> HexDump<'_> {
+        HexDump {
+            vec: self,
+            range,
+            bytes_per_line: 16,
+            uppercase: false,
+            unmapped: "..",
+            collapse: true,
+        }
+    }
+}
+
+/// Lines of address, hex bytes and ASCII, aligned to multiples of the line width.
+///
+/// Addresses outside of the dumped range are left blank. Repeated lines, including lines that
+/// are entirely unmapped, are collapsed into a single `*` line unless disabled. The last line
+/// holds the end address.
+pub struct HexDump<'a> {
+    vec: &'a SparseVec<u8>,
+    range: Range<u64>,
+    bytes_per_line: usize,
+    uppercase: bool,
+    unmapped: &'a str,
+    collapse: bool,
+}
+
+impl<'a> HexDump<'a> {
+    /// Defaults to 16.
+    ///
+    /// # Panics
+    ///
+    /// If `n` is zero.
+    pub fn bytes_per_line(mut self, n: usize) -> Self {
+        assert!(n > 0, "bytes per line must not be zero");
+        self.bytes_per_line = n;
+        self
+    }
+
+    pub fn uppercase(mut self, uppercase: bool) -> Self {
+        self.uppercase = uppercase;
+        self
+    }
+
+    /// Printed in place of the hex digits of unmapped bytes, should be two characters wide.
+    /// Defaults to `..`.
+    pub fn unmapped(mut self, marker: &'a str) -> Self {
+        self.unmapped = marker;
+        self
+    }
+
+    /// Collapse repeated lines into `*`. Enabled by default.
+    pub fn collapse(mut self, collapse: bool) -> Self {
+        self.collapse = collapse;
+        self
+    }
+
+    fn write_line(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+        start: u64,
+        cells: &[Option<u8>],
+    ) -> fmt::Result {
+        self.write_addr(f, start)?;
+        let inside = |i: usize| self.range.contains(&(start + i as u64));
+        for (i, cell) in cells.iter().enumerate() {
+            f.write_str(if i == 0 { "  " } else { " " })?;
+            match cell {
+                _ if !inside(i) => f.write_str("  ")?,
+                Some(b) if self.uppercase => write!(f, "{b:02X}")?,
+                Some(b) => write!(f, "{b:02x}")?,
+                None => f.write_str(self.unmapped)?,
+            }
+        }
+        f.write_str("  |")?;
+        for (i, cell) in cells.iter().enumerate() {
+            let c = match cell {
+                Some(b) if inside(i) && (b.is_ascii_graphic() || *b == b' ') => *b as char,
+                Some(_) if inside(i) => '.',
+                _ => ' ',
+            };
+            write!(f, "{c}")?;
+        }
+        f.write_str("|\n")
+    }
+
+    fn write_addr(&self, f: &mut fmt::Formatter<'_>, addr: u64) -> fmt::Result {
+        if self.uppercase {
+            write!(f, "{addr:08X}")
+        } else {
+            write!(f, "{addr:08x}")
+        }
+    }
+}
+
+impl fmt::Display for HexDump<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Range { start, end } = self.range;
+        if start >= end {
+            return Ok(());
+        }
+        let width = self.bytes_per_line as u64;
+        let mut line = start - start % width;
+        let mut cells = vec![None; self.bytes_per_line];
+        let mut prev = None;
+        let mut starred = false;
+        while line < end {
+            let line_end = line.saturating_add(width);
+            cells.fill(None);
+            for (range, data) in self.vec.slices(line.max(start)..line_end.min(end)) {
+                let offset = (range.start - line) as usize;
+                for (cell, b) in cells[offset..].iter_mut().zip(data) {
+                    *cell = Some(*b);
+                }
+            }
+
+            let full = line >= start && line_end <= end;
+            if self.collapse && full && prev.as_ref() == Some(&cells) {
+                if !starred {
+                    f.write_str("*\n")?;
+                    starred = true;
+                }
+                if cells.iter().all(Option::is_none) {
+                    // Skip straight to the line with the next stored byte
+                    let next = self
+                        .vec
+                        .map
+                        .overlapping(line_end..end)
+                        .next()
+                        .map_or(end, |(range, _)| range.start.max(line_end));
+                    line = line_end.max(next - next % width);
+                    continue;
+                }
+            } else {
+                self.write_line(f, line, &cells)?;
+                starred = false;
+                prev = full.then(|| cells.clone());
+            }
+            line = line_end;
+        }
+        self.write_addr(f, end)?;
+        f.write_str("\n")
+    }
+}
+
+#[test]
+fn sparsevec_hexdump() {
+    let mut map = SparseVec::new();
+    map.insert(b"Hello, sparse world!".to_vec(), 0x1005);
+    map.insert(vec![0; 64], 0x1020);
+    map.insert(vec![0xff, 0x41], 0x10_0000);
+
+    assert_eq!(
+        map.hexdump(0x1003..0x1068).to_string(),
+        "\
+00001000           .. .. 48 65 6c 6c 6f 2c 20 73 70 61 72  |     Hello, spar|
+00001010  73 65 20 77 6f 72 6c 64 21 .. .. .. .. .. .. ..  |se world!       |
+00001020  00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00  |................|
+*
+00001060  .. .. .. .. .. .. .. ..                          |                |
+00001068
+"
+    );
+
+    assert_eq!(
+        map.hexdump(0x1060..0x10_0002).to_string(),
+        "\
+00001060  .. .. .. .. .. .. .. .. .. .. .. .. .. .. .. ..  |                |
+*
+00100000  ff 41                                            |.A              |
+00100002
+"
+    );
+
+    assert_eq!(
+        map.hexdump(0xffffe..0x100002)
+            .bytes_per_line(4)
+            .uppercase(true)
+            .unmapped("--")
+            .to_string(),
+        "\
+000FFFFC        -- --  |    |
+00100000  FF 41        |.A  |
+00100002
+"
+    );
+
+    let lines = map.hexdump(0x1020..0x1040).collapse(false).to_string();
+    assert_eq!(lines.lines().count(), 3);
+    assert_eq!(map.hexdump(5..5).to_string(), "");
+}
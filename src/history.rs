@@ -0,0 +1,276 @@
+use alloc::borrow::Cow;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{cast_range, clip_range, sub_range, Address, SparseVec};
+
+// Data overwritten by the mutation that produced `version`
+struct Undo<T, A> {
+    version: u64,
+    range: Range<A>,
+    old: Vec<(A, Vec<T>)>,
+}
+
+pub(crate) struct History<T, A> {
+    // Number of retained versions, `None` while history is disabled
+    keep: Option<u64>,
+    version: u64,
+    oldest: u64,
+    undo: Vec<Undo<T, A>>,
+}
+
+impl<T, A> Default for History<T, A> {
+    fn default() -> Self {
+        Self {
+            keep: None,
+            version: 0,
+            oldest: 0,
+            undo: Vec::new(),
+        }
+    }
+}
+
+impl<T, A> History<T, A> {
+    fn prune(&mut self, keep_last_n: u64) {
+        self.oldest = self.oldest.max(self.version.saturating_sub(keep_last_n));
+        let oldest = self.oldest;
+        self.undo.retain(|undo| undo.version > oldest);
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Starts preserving overwritten data so that [`SparseVec::get_at_version`] can read the
+    /// last `keep_versions` versions. Older versions are pruned automatically.
+    ///
+    /// Changes made through [`SparseVec::blocks_mut`] are not recorded.
+    pub fn enable_history(&mut self, keep_versions: u64) {
+        if self.history.keep.is_none() {
+            self.history.oldest = self.history.version;
+        }
+        self.history.keep = Some(keep_versions);
+        self.history.prune(keep_versions);
+    }
+
+    /// Stops recording and drops all preserved data.
+    pub fn disable_history(&mut self) {
+        self.history = History {
+            version: self.history.version,
+            oldest: self.history.version,
+            ..Default::default()
+        };
+    }
+
+    /// Number of mutations so far. Every `insert`, `fill`, `fill_gaps`, `write` and `get_mut`
+    /// that may change data creates a new version, as does every block removed by
+    /// `retain_blocks`.
+    pub fn version(&self) -> u64 {
+        self.history.version
+    }
+
+    /// Drops preserved data so that only the last `keep_last_n` versions before the current
+    /// one stay readable.
+    pub fn prune_history(&mut self, keep_last_n: u64) {
+        self.history.prune(keep_last_n);
+    }
+
+    /// What `range` contained at `version`. `None` if any part of it was unmapped then, or if
+    /// that version is not retained.
+    pub fn get_at_version(&self, range: Range<A>, version: u64) -> Option<Cow<'_, [T]>> {
+        if version > self.history.version || version < self.history.oldest {
+            return None;
+        }
+        let newer = self
+            .history
+            .undo
+            .iter()
+            .rev()
+            .take_while(|undo| undo.version > version)
+            .filter(|undo| !clip_range(&undo.range, &range).is_empty());
+        if newer.clone().next().is_none() {
+            return self.get(range).map(Cow::Borrowed);
+        }
+
+        // Undo newer mutations on a copy of the range, newest first
+        let mut values = vec![None; (range.end - range.start).to_usize()];
+        for (clipped, slice) in self.slices(range.clone()) {
+            let offset = (clipped.start - range.start).to_usize();
+            for (value, v) in values[offset..].iter_mut().zip(slice) {
+                *value = Some(*v);
+            }
+        }
+        for undo in newer {
+            let clipped = clip_range(&undo.range, &range);
+            values[cast_range(sub_range(&clipped, range.start))].fill(None);
+            for (start, old) in &undo.old {
+                let old_range = *start..*start + A::from_usize(old.len());
+                let common = clip_range(&old_range, &clipped);
+                if common.is_empty() {
+                    continue;
+                }
+                values[cast_range(sub_range(&common, range.start))]
+                    .iter_mut()
+                    .zip(&old[cast_range(sub_range(&common, *start))])
+                    .for_each(|(value, v)| *value = Some(*v));
+            }
+        }
+        values
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .map(Cow::Owned)
+    }
+
+    // Called before every mutation of `range`
+    pub(crate) fn record_history(&mut self, range: &Range<A>) {
+        self.record_history_in(core::slice::from_ref(range));
+    }
+
+    // Like `record_history` for a single mutation of several ranges
+    pub(crate) fn record_history_in(&mut self, ranges: &[Range<A>]) {
+        for range in ranges {
+            self.marks.touch(range);
+        }
+        self.history.version += 1;
+        let Some(keep) = self.history.keep else {
+            self.history.oldest = self.history.version;
+            return;
+        };
+        for range in ranges {
+            let old = Vec::from_iter(
+                self.slices(range.clone())
+                    .map(|(clipped, slice)| (clipped.start, slice.to_vec())),
+            );
+            self.history.undo.push(Undo {
+                version: self.history.version,
+                range: range.clone(),
+                old,
+            });
+        }
+        self.history.prune(keep);
+    }
+}
+
+#[test]
+fn sparsevec_history() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 4], 0x10);
+    assert_eq!(map.version(), 1);
+    assert_eq!(map.get_at_version(0x10..0x14, 0), None);
+
+    map.enable_history(8);
+    map.write(0x11, &[2, 2]).unwrap();
+    map.insert(vec![3; 4], 0x13);
+    map.fill(0x0e..0x10, 4);
+    assert_eq!(map.version(), 4);
+    assert_eq!(map.get(0x0e..0x17).unwrap(), &[4, 4, 1, 2, 2, 3, 3, 3, 3]);
+
+    assert_eq!(map.get_at_version(0x10..0x14, 0), None);
+    assert_eq!(map.get_at_version(0x10..0x14, 1).unwrap(), &[1; 4][..]);
+    assert_eq!(
+        map.get_at_version(0x10..0x14, 2).unwrap(),
+        &[1, 2, 2, 1][..]
+    );
+    assert_eq!(map.get_at_version(0x10..0x15, 2), None);
+    assert_eq!(
+        map.get_at_version(0x10..0x17, 3).unwrap(),
+        &[1, 2, 2, 3, 3, 3, 3][..]
+    );
+    assert_eq!(map.get_at_version(0x0e..0x10, 3), None);
+    assert!(matches!(
+        map.get_at_version(0x0e..0x10, 4),
+        Some(Cow::Borrowed(&[4, 4]))
+    ));
+    // Ranges untouched since a version are borrowed
+    assert!(matches!(
+        map.get_at_version(0x14..0x17, 3),
+        Some(Cow::Borrowed(_))
+    ));
+    assert_eq!(map.get_at_version(0x10..0x14, 5), None);
+
+    map.prune_history(1);
+    assert_eq!(map.get_at_version(0x10..0x14, 2), None);
+    assert_eq!(
+        map.get_at_version(0x10..0x14, 3).unwrap(),
+        &[1, 2, 2, 3][..]
+    );
+
+    map.enable_history(2);
+    map.get_mut(0x10..0x12).unwrap().fill(5);
+    map.fill_gaps(0x0c..0x18, 6);
+    assert_eq!(map.version(), 6);
+    assert_eq!(map.get_at_version(0x0e..0x12, 3), None);
+    assert_eq!(
+        map.get_at_version(0x0e..0x12, 4).unwrap(),
+        &[4, 4, 1, 2][..]
+    );
+    assert_eq!(map.get_at_version(0x0c..0x10, 5), None);
+    assert_eq!(
+        map.get_at_version(0x0c..0x10, 6).unwrap(),
+        &[6, 6, 4, 4][..]
+    );
+
+    map.disable_history();
+    assert_eq!(map.get_at_version(0x0e..0x12, 5), None);
+    assert!(map.get_at_version(0x0e..0x12, 6).is_some());
+}
+
+#[test]
+fn sparsevec_history_model() {
+    use alloc::collections::BTreeMap;
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(3);
+    for _ in 0..20 {
+        let mut map = SparseVec::new();
+        map.enable_history(10);
+        // Contents after every version
+        let mut model = vec![BTreeMap::new()];
+        for _ in 0..40 {
+            let start = rng.gen_range(0..64u64);
+            let len = rng.gen_range(1..16u64);
+            let range = start..start + len;
+            let value = rng.gen::<u8>();
+            let mut state = model.last().unwrap().clone();
+            let mapped = range.clone().all(|addr| state.contains_key(&addr));
+            let op = rng.gen_range(0..5);
+            match op {
+                0 => map.insert(vec![value; len as usize], start),
+                1 => map.fill(range.clone(), value),
+                2 if mapped => continue,
+                2 => map.fill_gaps(range.clone(), value),
+                3 if !mapped => continue,
+                3 => map.write(start, &vec![value; len as usize]).unwrap(),
+                _ if !mapped => continue,
+                _ => map.get_mut(range.clone()).unwrap().fill(value),
+            }
+            for addr in range {
+                if op == 2 {
+                    state.entry(addr).or_insert(value);
+                } else {
+                    state.insert(addr, value);
+                }
+            }
+            model.push(state);
+            assert_eq!(map.version() as usize, model.len() - 1);
+        }
+        let current = map.iter_range(0..u64::MAX).map(|(addr, v)| (addr, *v));
+        assert!(current.eq(model.last().unwrap().clone()));
+
+        let version = map.version();
+        for _ in 0..200 {
+            let start = rng.gen_range(0..80u64);
+            let range = start..start + rng.gen_range(1..20u64);
+            let v = rng.gen_range(0..=version);
+            let expected = range
+                .clone()
+                .map(|addr| model[v as usize].get(&addr).copied())
+                .collect::<Option<Vec<_>>>();
+            let actual = map.get_at_version(range, v).map(|cow| cow.into_owned());
+            if v < version.saturating_sub(10) {
+                assert_eq!(actual, None);
+            } else {
+                assert_eq!(actual, expected);
+            }
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+// Set by `SparseVec::with_holes`. `eq` is `T::eq`, so that inserting does not need
+// `T: PartialEq` everywhere.
+pub(crate) struct Holes<T> {
+    value: T,
+    threshold: usize,
+    eq: fn(&T, &T) -> bool,
+}
+
+impl<T: PartialEq, A: Address> SparseVec<T, A> {
+    /// Empty `SparseVec` that does not store runs of `value`, e.g. zeroes meaning "nothing"
+    /// in a capture. Inserts and fills leave leading and trailing runs of `value` unmapped,
+    /// and runs in between that are longer than `threshold` elements. Data stored there
+    /// before is removed, as if `value` had been written. [`SparseVec::read_or`] with `value`
+    /// as the default reads the data as inserted.
+    ///
+    /// Data passed to [`SparseVec::insert_shared`] is stored as is.
+    pub fn with_holes(value: T, threshold: usize) -> Self {
+        Self {
+            holes: Some(Holes {
+                value,
+                threshold,
+                eq: T::eq,
+            }),
+            ..Self::default()
+        }
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// The value and threshold passed to [`SparseVec::with_holes`], if any.
+    pub fn holes(&self) -> Option<(T, usize)> {
+        self.holes
+            .as_ref()
+            .map(|holes| (holes.value, holes.threshold))
+    }
+
+    // Parts of `data` to leave unmapped
+    pub(crate) fn hole_runs(&self, data: &[T]) -> Vec<Range<usize>> {
+        let Some(Holes {
+            value,
+            threshold,
+            eq,
+        }) = &self.holes
+        else {
+            return Vec::new();
+        };
+        let mut runs = Vec::new();
+        let mut i = 0;
+        while i < data.len() {
+            if !eq(&data[i], value) {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < data.len() && eq(&data[i], value) {
+                i += 1;
+            }
+            if start == 0 || i == data.len() || i - start > *threshold {
+                runs.push(start..i);
+            }
+        }
+        runs
+    }
+
+    // Unmaps `range`, keeping the rest of the blocks it cuts
+    pub(crate) fn remove_unwatched(&mut self, range: Range<A>) {
+        if range.is_empty() || !self.overlaps(&range) {
+            return;
+        }
+        self.split_block(range.start);
+        self.split_block(range.end);
+        let removed = Vec::from_iter(self.map.overlapping(&range).map(|(block, _)| block.clone()));
+        for block in removed {
+            self.remove_block(block);
+        }
+    }
+}
+
+#[test]
+fn sparsevec_holes() {
+    let mut map = SparseVec::<u8>::with_holes(0, 2);
+    map.insert(vec![0, 0, 1, 0, 0, 2, 0, 0, 0, 3, 0], 0x10);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x12..0x16, 0x19..0x1a]);
+    assert_eq!(map.get(0x12..0x16).unwrap(), &[1, 0, 0, 2]);
+    assert_eq!(
+        map.read_or(0x10..0x1b, 0),
+        vec![0, 0, 1, 0, 0, 2, 0, 0, 0, 3, 0]
+    );
+    assert_eq!(map.holes(), Some((0, 2)));
+
+    // Holes remove what was stored there
+    map.fill(0x13..0x15, 0);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x12..0x13, 0x15..0x16, 0x19..0x1a]
+    );
+    map.insert(vec![4; 0x20], 0x100);
+    map.insert(vec![0; 4], 0x104);
+    map.insert(vec![0, 5, 0], 0x11e);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![
+            0x12..0x13,
+            0x15..0x16,
+            0x19..0x1a,
+            0x100..0x104,
+            0x108..0x11e,
+            0x11f..0x120
+        ]
+    );
+    map.fill(0..0x200, 0);
+    assert_eq!(map.ranges().len(), 0);
+}
+
+#[test]
+fn sparsevec_holes_merge() {
+    let mut map = SparseVec::<u8>::with_holes(0, 0);
+    map.insert(vec![1; 4], 0x10);
+    // Trimmed inserts right next to stored data still merge with it
+    map.insert(vec![0, 2, 2], 0x0d);
+    map.insert(vec![3, 0, 0], 0x14);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x0e..0x15]);
+    assert_eq!(map.get(0x0e..0x15).unwrap(), &[2, 2, 1, 1, 1, 1, 3]);
+
+    // Threshold zero leaves no run of the value stored
+    map.insert(vec![5, 0, 5], 0x20);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x0e..0x15, 0x20..0x21, 0x22..0x23]
+    );
+    let mut kept = SparseVec::<u8>::with_holes(0, 1);
+    kept.insert(vec![5, 0, 5], 0x20);
+    assert_eq!(Vec::from_iter(kept.ranges()), vec![0x20..0x23]);
+}
+
+#[test]
+fn sparsevec_holes_threshold() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(173);
+    for threshold in [0, 1, 3, 8] {
+        let mut map = SparseVec::<u8>::with_holes(0, threshold);
+        let mut model = [0u8; 0x200];
+        for _ in 0..300 {
+            let addr = rng.gen_range(0..0x1c0);
+            let data = Vec::from_iter((0..rng.gen_range(0..0x40)).map(|_| {
+                if rng.gen_bool(0.6) {
+                    0
+                } else {
+                    rng.gen()
+                }
+            }));
+            model[addr..][..data.len()].copy_from_slice(&data);
+            map.insert(data, addr as u64);
+            map.assert_invariants();
+            assert_eq!(map.read_or(0..0x200, 0), model);
+        }
+        // No block holds a run of the value longer than the threshold
+        for range in map.ranges() {
+            let data = map.get(range.clone()).unwrap();
+            let longest = data.split(|&v| v != 0).map(<[u8]>::len).max().unwrap();
+            assert!(longest <= threshold);
+        }
+    }
+}
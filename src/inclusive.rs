@@ -0,0 +1,205 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::RangeInclusive;
+
+use itertools::Itertools;
+
+use crate::journal::JournalOp;
+use crate::{Address, SparseVec, Unmapped};
+
+// Whether `len` elements at `addr` end exactly at `A::MAX`, so that all but the last one fit
+// in a range
+fn ends_at_max<A: Address>(addr: A, len: usize) -> bool {
+    let len = len as u64 - 1;
+    A::try_from_u64(len).and_then(|len| addr.checked_add(len)) == Some(A::MAX)
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Like [`SparseVec::get`], for ranges given by their last address. Ranges including
+    /// `A::MAX` are copied, as it is stored apart from the block before it. `None` for empty
+    /// ranges and ranges with any part unmapped.
+    pub fn get_inclusive(&self, range: RangeInclusive<A>) -> Option<Cow<'_, [T]>> {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return None;
+        }
+        if end < A::MAX {
+            return self.get(start..end + A::from_usize(1)).map(Cow::Borrowed);
+        }
+        let top = self.top?;
+        let mut data = if start == A::MAX {
+            Vec::new()
+        } else {
+            self.get(start..A::MAX)?.to_vec()
+        };
+        data.push(top);
+        Some(Cow::Owned(data))
+    }
+
+    /// Whether every address in `range` is stored.
+    pub fn contains_inclusive(&self, range: RangeInclusive<A>) -> bool {
+        let (start, end) = range.into_inner();
+        match end.checked_add(A::from_usize(1)) {
+            Some(end) => self.contains_range(&(start..end)),
+            None => self.top.is_some() && self.contains_range(&(start..A::MAX)),
+        }
+    }
+
+    /// Stored ranges as inclusive ranges, in address order. `A::MAX` extends the range right
+    /// before it.
+    pub fn ranges_inclusive(&self) -> impl Iterator<Item = RangeInclusive<A>> + '_ {
+        let one = A::from_usize(1);
+        self.ranges()
+            .map(move |range| range.start..=range.end - one)
+            .chain(self.top.map(|_| A::MAX..=A::MAX))
+            .coalesce(move |range, next| {
+                if *next.start() == A::MAX && *range.end() == A::MAX - one {
+                    Ok(*range.start()..=A::MAX)
+                } else {
+                    Err((range, next))
+                }
+            })
+    }
+
+    // `insert` of `data` running past the end of the address space
+    pub(crate) fn insert_through_max(&mut self, mut data: Vec<T>, addr: A) {
+        if !ends_at_max(addr, data.len()) {
+            panic!(
+                "{} elements at {addr:#x} exceed the address space",
+                data.len()
+            );
+        }
+        let hole = self
+            .hole_runs(&data)
+            .last()
+            .is_some_and(|run| run.end == data.len());
+        let top = data.pop().unwrap();
+        self.insert(data, addr);
+        self.journal(JournalOp::Insert, A::MAX, 1, &[top]);
+        self.top = (!hole).then_some(top);
+    }
+
+    // `read_into_exact` of `buf` running past the end of the address space
+    pub(crate) fn read_through_max(&self, addr: A, buf: &mut [T]) -> Result<(), Unmapped<A>> {
+        let top = match self.top {
+            Some(top) if ends_at_max(addr, buf.len()) => top,
+            _ => return Err(self.unmapped_past_end(addr)),
+        };
+        let (last, rest) = buf.split_last_mut().unwrap();
+        self.read_into_exact(addr, rest)?;
+        *last = top;
+        Ok(())
+    }
+
+    // `write` of `data` running past the end of the address space
+    pub(crate) fn write_through_max(&mut self, addr: A, data: &[T]) -> Result<(), Unmapped<A>> {
+        if self.top.is_none() || !ends_at_max(addr, data.len()) {
+            return Err(self.unmapped_past_end(addr));
+        }
+        let (last, rest) = data.split_last().unwrap();
+        self.write(addr, rest)?;
+        self.journal(JournalOp::Write, A::MAX, 1, &[*last]);
+        self.top = Some(*last);
+        Ok(())
+    }
+}
+
+#[test]
+fn sparsevec_inclusive() {
+    let mut map = SparseVec::new();
+    let data = Vec::from_iter(0..15u8);
+    map.insert(data.clone(), u64::MAX - 15);
+    assert_eq!(
+        map.get_inclusive(u64::MAX - 15..=u64::MAX - 1).unwrap(),
+        &data[..]
+    );
+    assert_eq!(
+        map.get_inclusive(u64::MAX - 4..=u64::MAX - 2).unwrap(),
+        &[11, 12, 13][..]
+    );
+    assert_eq!(map.get_inclusive(u64::MAX - 15..=u64::MAX), None);
+    assert_eq!(map.get_inclusive(u64::MAX..=u64::MAX), None);
+    assert_eq!(
+        map.get_inclusive(RangeInclusive::new(u64::MAX - 1, u64::MAX - 2)),
+        None
+    );
+    assert!(map.contains_inclusive(u64::MAX - 15..=u64::MAX - 1));
+    assert!(!map.contains_inclusive(u64::MAX - 15..=u64::MAX));
+    assert!(!map.contains_inclusive(u64::MAX - 16..=u64::MAX - 1));
+    map.insert(vec![1, 2], 0x10);
+    assert_eq!(
+        Vec::from_iter(map.ranges_inclusive()),
+        vec![0x10..=0x11, u64::MAX - 15..=u64::MAX - 1]
+    );
+
+    let mut small = SparseVec::<u8, u16>::default();
+    small.insert(vec![7; 2], u16::MAX - 2);
+    assert_eq!(
+        Vec::from_iter(small.ranges_inclusive()),
+        vec![u16::MAX - 2..=u16::MAX - 1]
+    );
+    assert_eq!(small.get_inclusive(0..=u16::MAX), None);
+}
+
+#[test]
+fn sparsevec_insert_at_max() {
+    let mut map = SparseVec::new();
+    let data = Vec::from_iter(0..16u8);
+    map.insert(data.clone(), u64::MAX - 15);
+    assert_eq!(
+        map.get_inclusive(u64::MAX - 15..=u64::MAX).unwrap(),
+        &data[..]
+    );
+    assert_eq!(map.get_inclusive(u64::MAX..=u64::MAX).unwrap(), &[15][..]);
+    assert!(map.contains_inclusive(u64::MAX - 15..=u64::MAX));
+    assert!(!map.contains_inclusive(u64::MAX - 16..=u64::MAX));
+    assert_eq!(
+        Vec::from_iter(map.ranges_inclusive()),
+        vec![u64::MAX - 15..=u64::MAX]
+    );
+    // Only the inclusive methods reach the last element
+    assert_eq!(Vec::from_iter(map.ranges()), vec![u64::MAX - 15..u64::MAX]);
+    let mut buf = [0; 2];
+    map.read_into_exact(u64::MAX - 1, &mut buf).unwrap();
+    assert_eq!(buf, [14, 15]);
+    assert_eq!(map.compare_range(u64::MAX - 1, &[14, 15]), Ok(()));
+
+    map.write(u64::MAX - 1, &[1, 2]).unwrap();
+    assert_eq!(
+        map.get_inclusive(u64::MAX - 2..=u64::MAX).unwrap(),
+        &[13, 1, 2][..]
+    );
+    map.insert(vec![3], u64::MAX);
+    let doubled = map.map_values(|_, v| v * 2);
+    assert_eq!(
+        doubled.get_inclusive(u64::MAX - 1..=u64::MAX).unwrap(),
+        &[2, 6][..]
+    );
+    // A gap before `A::MAX` itself
+    map.insert(vec![9; 4], 0x10);
+    map.retain_blocks(|range, _| range.start == 0x10);
+    assert_eq!(
+        Vec::from_iter(map.ranges_inclusive()),
+        vec![0x10..=0x13, u64::MAX..=u64::MAX]
+    );
+    assert_eq!(map.get_inclusive(u64::MAX - 1..=u64::MAX), None);
+    assert_eq!(
+        map.read_into_exact(u64::MAX - 1, &mut buf),
+        Err(Unmapped { addr: u64::MAX - 1 })
+    );
+    assert_eq!(map.write(u64::MAX, &[4]), Ok(()));
+    assert_eq!(map.get_inclusive(u64::MAX..=u64::MAX).unwrap(), &[4][..]);
+
+    let mut small = SparseVec::<u8, u16>::default();
+    small.insert(vec![7; 2], u16::MAX - 1);
+    assert_eq!(
+        Vec::from_iter(small.ranges_inclusive()),
+        vec![u16::MAX - 1..=u16::MAX]
+    );
+}
+
+#[test]
+#[should_panic(expected = "3 elements at 0xffffffffffffffff exceed the address space")]
+fn sparsevec_insert_overflow() {
+    SparseVec::new().insert(vec![0u8; 3], u64::MAX);
+}
@@ -0,0 +1,628 @@
+use std::io::{self, IoSlice, Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+
+use crate::journal::JournalOp;
+use crate::{Error, GapPolicy, SparseVec, Unmapped};
+
+/// What a cursor's `read` does when the position is inside a gap.
+///
+/// Reading at or after the end of the stored data always returns `Ok(0)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReadGapPolicy {
+    /// Return `Ok(0)`, as if the data ended at the gap.
+    #[default]
+    Eof,
+    /// Fail with [`io::ErrorKind::UnexpectedEof`].
+    Error,
+    /// Read zeros up to the next stored byte.
+    ZeroFill,
+}
+
+// The canonical pieces `range` consists of, split where mirrors or the address modulus
+// redirect it
+fn pieces(vec: &SparseVec<u8>, range: &Range<u64>) -> Vec<Range<u64>> {
+    vec.mirrored(range).unwrap_or_else(|| vec![range.clone()])
+}
+
+// Whether data is readable at or after `pos`, directly or through an alias window
+fn stored_from(vec: &SparseVec<u8>, pos: u64) -> bool {
+    vec.overlaps(&(pos..u64::MAX))
+        || vec
+            .mirrors()
+            .any(|(window, canonical)| window.end > pos && vec.overlaps(&canonical))
+}
+
+fn read_at(
+    vec: &SparseVec<u8>,
+    pos: u64,
+    gaps: ReadGapPolicy,
+    buf: &mut [u8],
+) -> io::Result<usize> {
+    let range = pos..pos.saturating_add(buf.len() as u64);
+    if range.is_empty() {
+        return Ok(0);
+    }
+    // Bytes up to the first stored one in `range`
+    let mut gap = 0;
+    let mut found = false;
+    for piece in pieces(vec, &range) {
+        match vec.slices(piece.clone()).next() {
+            Some((stored, data)) if stored.start == piece.start => {
+                if gap == 0 {
+                    buf[..data.len()].copy_from_slice(data);
+                    return Ok(data.len());
+                }
+                found = true;
+            }
+            Some((stored, _)) => {
+                gap += stored.start - piece.start;
+                found = true;
+            }
+            None => gap += piece.end - piece.start,
+        }
+        if found {
+            break;
+        }
+    }
+
+    if !found && !stored_from(vec, range.end) {
+        return Ok(0);
+    }
+    match gaps {
+        ReadGapPolicy::Eof => Ok(0),
+        ReadGapPolicy::Error => Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("address {pos:#x} is unmapped"),
+        )),
+        ReadGapPolicy::ZeroFill => {
+            let len = gap as usize;
+            buf[..len].fill(0);
+            Ok(len)
+        }
+    }
+}
+
+fn seek_to(vec: &SparseVec<u8>, pos: &mut u64, seek: SeekFrom) -> io::Result<u64> {
+    let new = match seek {
+        SeekFrom::Start(offset) => Some(offset),
+        SeekFrom::Current(offset) => pos.checked_add_signed(offset),
+        SeekFrom::End(offset) => vec
+            .bounds()
+            .map_or(0, |bounds| bounds.end)
+            .checked_add_signed(offset),
+    };
+    *pos = new.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "seek to a negative or overflowing position",
+        )
+    })?;
+    Ok(*pos)
+}
+
+/// [`Read`] and [`Seek`] over a borrowed `SparseVec<u8>`. Positions are addresses.
+pub struct SparseCursor<'a> {
+    vec: &'a SparseVec<u8>,
+    pos: u64,
+    gaps: ReadGapPolicy,
+}
+
+impl<'a> SparseCursor<'a> {
+    pub fn new(vec: &'a SparseVec<u8>, gaps: ReadGapPolicy) -> Self {
+        Self { vec, pos: 0, gaps }
+    }
+
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl Read for SparseCursor<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = read_at(self.vec, self.pos, self.gaps, buf)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for SparseCursor<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        seek_to(self.vec, &mut self.pos, pos)
+    }
+}
+
+/// Like [`SparseCursor`] but also implements [`Write`].
+///
+/// Writes go through existing coverage. Writing into a gap fails with
+/// [`io::ErrorKind::UnexpectedEof`] unless [`SparseCursorMut::extend_into_gaps`] is set, in which
+/// case the written data is inserted. Writing into a [frozen](SparseVec::freeze) range fails
+/// with [`io::ErrorKind::PermissionDenied`] wrapping [`Error::Frozen`].
+pub struct SparseCursorMut<'a> {
+    vec: &'a mut SparseVec<u8>,
+    pos: u64,
+    gaps: ReadGapPolicy,
+    extend: bool,
+}
+
+impl<'a> SparseCursorMut<'a> {
+    pub fn new(vec: &'a mut SparseVec<u8>, gaps: ReadGapPolicy) -> Self {
+        Self {
+            vec,
+            pos: 0,
+            gaps,
+            extend: false,
+        }
+    }
+
+    pub fn extend_into_gaps(mut self, extend: bool) -> Self {
+        self.extend = extend;
+        self
+    }
+
+    pub fn position(&self) -> u64 {
+        self.pos
+    }
+
+    pub fn set_position(&mut self, pos: u64) {
+        self.pos = pos;
+    }
+}
+
+impl Read for SparseCursorMut<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = read_at(self.vec, self.pos, self.gaps, buf)?;
+        self.pos += len as u64;
+        Ok(len)
+    }
+}
+
+impl Seek for SparseCursorMut<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        seek_to(self.vec, &mut self.pos, pos)
+    }
+}
+
+impl Write for SparseCursorMut<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let max = usize::try_from(u64::MAX - self.pos).unwrap_or(usize::MAX);
+        let buf = &buf[..buf.len().min(max)];
+        if buf.is_empty() {
+            return Err(io::ErrorKind::WriteZero.into());
+        }
+
+        let range = self.pos..self.pos + buf.len() as u64;
+        let pieces = pieces(self.vec, &range);
+        let frozen = |err| io::Error::new(io::ErrorKind::PermissionDenied, Error::from(err));
+        // Only up to the end of the first canonical piece, the rest is left to the next call
+        let stored = match self.vec.slices(pieces[0].clone()).next() {
+            Some((stored, _)) if stored.start == pieces[0].start => Some(stored),
+            _ => None,
+        };
+        let len = match stored {
+            Some(stored) => {
+                self.vec.check_frozen(&stored).map_err(frozen)?;
+                let len = (stored.end - stored.start) as usize;
+                self.vec
+                    .write(self.pos, &buf[..len])
+                    .map_err(|err| io::Error::new(io::ErrorKind::UnexpectedEof, err))?;
+                len
+            }
+            None if self.extend => {
+                for piece in &pieces {
+                    self.vec.check_frozen(piece).map_err(frozen)?;
+                }
+                self.vec.insert(buf.to_vec(), self.pos);
+                buf.len()
+            }
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("address {:#x} is unmapped", self.pos),
+                ))
+            }
+        };
+        self.pos += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl SparseVec<u8> {
+    /// Stored data within `range` as slices for [`Write::write_vectored`], with the address
+    /// range of every slice at the same index.
+    pub fn io_slices(&self, range: Range<u64>) -> (Vec<Range<u64>>, Vec<IoSlice<'_>>) {
+        self.slices(range)
+            .map(|(clipped, slice)| (clipped, IoSlice::new(slice)))
+            .unzip()
+    }
+
+    /// Writes the stored data within `range` to `w` without the gaps, using vectored writes.
+    /// Returns the number of bytes written.
+    pub fn write_vectored_to<W: Write>(&self, range: Range<u64>, w: &mut W) -> io::Result<u64> {
+        let (_, mut slices) = self.io_slices(range);
+        let mut slices = &mut slices[..];
+        let mut written = 0;
+        while !slices.is_empty() {
+            match w.write_vectored(slices) {
+                Ok(0) => return Err(io::ErrorKind::WriteZero.into()),
+                Ok(len) => {
+                    IoSlice::advance_slices(&mut slices, len);
+                    written += len as u64;
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(written)
+    }
+
+    /// Writes the bytes within `range` to `writer` in address order, straight from the
+    /// stored blocks. Gaps are handled according to `gaps`; with [`GapPolicy::Error`] nothing
+    /// is written if `range` has a gap, and the error wraps [`Unmapped`]. Returns the number
+    /// of bytes written.
+    pub fn copy_range_to_writer<W: Write>(
+        &self,
+        range: Range<u64>,
+        writer: &mut W,
+        gaps: GapPolicy,
+    ) -> io::Result<u64> {
+        if gaps == GapPolicy::Error {
+            if let Some(addr) = self.first_unmapped(&range) {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    Unmapped { addr },
+                ));
+            }
+        }
+        let fill = |writer: &mut W, len: u64| -> io::Result<u64> {
+            let GapPolicy::TreatAsValue(value) = gaps else {
+                return Ok(0);
+            };
+            let chunk = [value; 4096];
+            let mut left = len;
+            while left > 0 {
+                let n = left.min(chunk.len() as u64);
+                writer.write_all(&chunk[..n as usize])?;
+                left -= n;
+            }
+            Ok(len)
+        };
+
+        let mut written = 0;
+        let mut pos = range.start;
+        for (clipped, slice) in self.slices(range.clone()) {
+            written += fill(writer, clipped.start - pos)?;
+            writer.write_all(slice)?;
+            written += slice.len() as u64;
+            pos = clipped.end;
+        }
+        written += fill(writer, range.end.saturating_sub(pos))?;
+        Ok(written)
+    }
+
+    /// Reads up to `len` bytes from `reader` to `addr`, directly into the stored block if the
+    /// range is already stored in one, otherwise into a new block. Returns the number of
+    /// bytes read before EOF; only those are stored or overwritten. Bytes read before an
+    /// error are stored as well.
+    pub fn insert_from_reader<R: Read>(
+        &mut self,
+        addr: u64,
+        len: u64,
+        reader: &mut R,
+    ) -> io::Result<u64> {
+        if addr.checked_add(len).is_none() {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        if let Some((key, slice)) = self.find_mut(addr..addr + len) {
+            let block = &mut self.data.get_mut(&key).unwrap()[slice];
+            let mut read = 0;
+            let result = loop {
+                if read == block.len() {
+                    break Ok(());
+                }
+                match reader.read(&mut block[read..]) {
+                    Ok(0) => break Ok(()),
+                    Ok(n) => read += n,
+                    Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                    Err(err) => break Err(err),
+                }
+            };
+            // Journaled as a write of the bytes read, which a mutable slice could not be
+            if self.journal.is_some() && read > 0 {
+                let data = block[..read].to_vec();
+                self.journal(JournalOp::Write, addr, read as u64, &data);
+            }
+            return result.map(|_| read as u64);
+        }
+
+        // Grows with the data actually read instead of trusting `len` up front, and keeps
+        // what was read before an error
+        let mut data = Vec::new();
+        let result = reader.take(len).read_to_end(&mut data);
+        let read = data.len() as u64;
+        self.insert(data, addr);
+        result.map(|_| read)
+    }
+}
+
+#[test]
+fn sparsevec_copy_range_to_writer() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1, 2], 0x10);
+    map.insert(vec![3; 3], 0x14);
+    map.insert(vec![4; 0x2000], 0x20);
+
+    let copy = |range, gaps| {
+        let mut out = Vec::new();
+        map.copy_range_to_writer(range, &mut out, gaps).map(|n| {
+            assert_eq!(n, out.len() as u64);
+            out
+        })
+    };
+    assert_eq!(copy(0x11..0x17, GapPolicy::Skip).unwrap(), [2, 3, 3, 3]);
+    assert_eq!(
+        copy(0x0e..0x18, GapPolicy::TreatAsValue(0xff)).unwrap(),
+        [0xff, 0xff, 1, 2, 0xff, 0xff, 3, 3, 3, 0xff]
+    );
+    let filled = copy(0x16..0x3000, GapPolicy::TreatAsValue(0)).unwrap();
+    assert_eq!(filled.len(), 0x3000 - 0x16);
+    assert_eq!(filled[..0x0b], [3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 4]);
+    assert!(filled[0x200a..].iter().all(|&b| b == 0));
+    assert_eq!(copy(0x14..0x17, GapPolicy::Error).unwrap(), [3; 3]);
+
+    let mut out = Vec::new();
+    let err = map
+        .copy_range_to_writer(0x10..0x17, &mut out, GapPolicy::Error)
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    let unmapped = err.get_ref().unwrap().downcast_ref::<Unmapped>();
+    assert_eq!(unmapped, Some(&Unmapped { addr: 0x12 }));
+    assert!(out.is_empty());
+}
+
+#[test]
+fn sparsevec_insert_from_reader() {
+    let mut map = SparseVec::new();
+    let mut reader = &[1u8, 2, 3, 4, 5, 6][..];
+    assert_eq!(map.insert_from_reader(0x10, 4, &mut reader).unwrap(), 4);
+    // Short read, only the bytes read are mapped
+    assert_eq!(map.insert_from_reader(0x20, 8, &mut reader).unwrap(), 2);
+    assert_eq!(map.insert_from_reader(0x30, 8, &mut reader).unwrap(), 0);
+    assert_eq!(map.insert_from_reader(0x30, 0, &mut &[7u8][..]).unwrap(), 0);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x10..0x14, 0x20..0x22]);
+    assert_eq!(map.get(0x20..0x22).unwrap(), &[5, 6]);
+
+    // Overwrites in place, keeping the rest of a short read
+    map.insert(vec![0; 8], 0x40);
+    assert_eq!(
+        map.insert_from_reader(0x42, 4, &mut &[8u8, 8][..]).unwrap(),
+        2
+    );
+    assert_eq!(map.get(0x40..0x48).unwrap(), &[0, 0, 8, 8, 0, 0, 0, 0]);
+    assert_eq!(
+        map.insert_from_reader(0x46, 4, &mut &[9u8; 4][..]).unwrap(),
+        4
+    );
+    assert_eq!(
+        map.get(0x40..0x4a).unwrap(),
+        &[0, 0, 8, 8, 0, 0, 9, 9, 9, 9]
+    );
+
+    struct Failing(u8);
+    impl Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            self.0 -= 1;
+            buf[0] = 0xff;
+            Ok(1)
+        }
+    }
+    let err = map
+        .insert_from_reader(0x60, 8, &mut Failing(3))
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    assert_eq!(map.get(0x60..0x63).unwrap(), &[0xff; 3]);
+    assert!(map.insert_from_reader(0x40, 8, &mut Failing(1)).is_err());
+    assert_eq!(map.get(0x40..0x42).unwrap(), &[0xff, 0]);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x10..0x14, 0x20..0x22, 0x40..0x4a, 0x60..0x63]
+    );
+    assert_eq!(
+        map.insert_from_reader(u64::MAX, 2, &mut reader)
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::InvalidInput
+    );
+
+    // A huge length is not allocated up front
+    assert_eq!(
+        map.insert_from_reader(0x80, u64::MAX / 2, &mut &[3u8; 3][..])
+            .unwrap(),
+        3
+    );
+    assert_eq!(map.get(0x80..0x83).unwrap(), &[3; 3]);
+}
+
+#[cfg(test)]
+fn cursor_test_vec() -> SparseVec<u8> {
+    let mut map = SparseVec::new();
+    map.insert(vec![1, 2, 3, 4], 2);
+    map.insert(vec![5, 6], 10);
+    map
+}
+
+#[test]
+fn sparsevec_cursor_eof_policy() {
+    let map = cursor_test_vec();
+    let mut cursor = SparseCursor::new(&map, ReadGapPolicy::Eof);
+    let mut buf = [0; 16];
+    assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+
+    cursor.seek(SeekFrom::Start(3)).unwrap();
+    assert_eq!(cursor.read(&mut buf).unwrap(), 3);
+    assert_eq!(buf[..3], [2, 3, 4]);
+    assert_eq!(cursor.position(), 6);
+    assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+
+    cursor.seek(SeekFrom::Current(4)).unwrap();
+    let mut rest = Vec::new();
+    cursor.read_to_end(&mut rest).unwrap();
+    assert_eq!(rest, [5, 6]);
+}
+
+#[test]
+fn sparsevec_cursor_error_policy() {
+    let map = cursor_test_vec();
+    let mut cursor = SparseCursor::new(&map, ReadGapPolicy::Error);
+    let mut buf = [0; 16];
+    let err = cursor.read(&mut buf).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    assert_eq!(cursor.position(), 0);
+
+    cursor.set_position(2);
+    let mut exact = [0; 4];
+    cursor.read_exact(&mut exact).unwrap();
+    assert_eq!(exact, [1, 2, 3, 4]);
+    assert!(cursor.read(&mut buf).is_err());
+
+    cursor.seek(SeekFrom::End(-1)).unwrap();
+    assert_eq!(cursor.read(&mut buf).unwrap(), 1);
+    assert_eq!(buf[0], 6);
+    assert_eq!(cursor.read(&mut buf).unwrap(), 0);
+    assert!(cursor.seek(SeekFrom::Current(-20)).is_err());
+}
+
+#[test]
+fn sparsevec_cursor_zero_fill_policy() {
+    let map = cursor_test_vec();
+    let mut cursor = SparseCursor::new(&map, ReadGapPolicy::ZeroFill);
+    let mut all = Vec::new();
+    cursor.read_to_end(&mut all).unwrap();
+    assert_eq!(all, [0, 0, 1, 2, 3, 4, 0, 0, 0, 0, 5, 6]);
+
+    cursor.set_position(5);
+    let mut buf = [0xff; 3];
+    assert_eq!(cursor.read(&mut buf).unwrap(), 1);
+    assert_eq!(cursor.read(&mut buf).unwrap(), 3);
+    assert_eq!(buf, [0; 3]);
+    assert_eq!(cursor.position(), 9);
+}
+
+#[test]
+fn sparsevec_cursor_write() {
+    let mut map = cursor_test_vec();
+    let mut cursor = SparseCursorMut::new(&mut map, ReadGapPolicy::Eof);
+    cursor.set_position(4);
+    assert_eq!(cursor.write(&[7, 7, 7, 7]).unwrap(), 2);
+    assert_eq!(
+        cursor.write(&[7]).unwrap_err().kind(),
+        io::ErrorKind::UnexpectedEof
+    );
+    assert!(cursor.write_all(&[8; 8]).is_err());
+
+    let mut cursor = cursor.extend_into_gaps(true);
+    cursor.write_all(&[8; 5]).unwrap();
+    cursor.seek(SeekFrom::Start(0)).unwrap();
+    cursor.write_all(&[9, 9]).unwrap();
+    assert_eq!(cursor.position(), 2);
+    assert_eq!(
+        map.get(0..12).unwrap(),
+        &[9, 9, 1, 2, 7, 7, 8, 8, 8, 8, 8, 6]
+    );
+}
+
+#[test]
+fn sparsevec_cursor_frozen_and_mirrored() {
+    let mut map = cursor_test_vec();
+    map.freeze(4..6);
+    let mut cursor = SparseCursorMut::new(&mut map, ReadGapPolicy::Eof).extend_into_gaps(true);
+    cursor.set_position(3);
+    let err = cursor.write(&[7; 2]).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+    assert!(matches!(
+        err.into_inner().unwrap().downcast_ref::<Error>(),
+        Some(Error::Frozen { addr: 4 })
+    ));
+    cursor.set_position(5);
+    assert_eq!(
+        cursor.write(&[7]).unwrap_err().kind(),
+        io::ErrorKind::PermissionDenied
+    );
+    assert_eq!(map.get(2..6).unwrap(), &[1, 2, 3, 4]);
+
+    // Cursors see the same aliases as `get` and `write`
+    let mut map = SparseVec::<u8>::with_address_modulus(0x10);
+    map.insert(vec![1, 2, 3, 4], 2);
+    map.insert(vec![5, 6], 10);
+    let mut cursor = SparseCursorMut::new(&mut map, ReadGapPolicy::ZeroFill);
+    cursor.set_position(0x13);
+    let mut buf = [0xff; 16];
+    assert_eq!(cursor.read(&mut buf).unwrap(), 3);
+    assert_eq!(buf[..3], [2, 3, 4]);
+    assert_eq!(cursor.read(&mut buf).unwrap(), 4);
+    assert_eq!(buf[..4], [0; 4]);
+    cursor.set_position(0x1b);
+    cursor.write_all(&[9]).unwrap();
+    assert!(cursor.write_all(&[9; 2]).is_err());
+    cursor.set_position(0x2a);
+    let mut exact = [0; 8];
+    cursor.read_exact(&mut exact).unwrap();
+    assert_eq!(exact, [5, 9, 0, 0, 0, 0, 0, 0]);
+    assert_eq!(map.get(10..12).unwrap(), &[5, 9]);
+}
+
+#[test]
+fn sparsevec_write_vectored_to() {
+    // Accepts at most three bytes per call, splitting slices
+    struct Short(Vec<u8>, usize);
+    impl Write for Short {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.1 += 1;
+            let len = buf.len().min(3);
+            self.0.extend_from_slice(&buf[..len]);
+            Ok(len)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut map = cursor_test_vec();
+    map.insert(vec![7; 8], 0x20);
+    let (ranges, slices) = map.io_slices(3..0x24);
+    assert_eq!(ranges, vec![3..6, 10..12, 0x20..0x24]);
+    assert_eq!(
+        Vec::from_iter(slices.iter().map(|slice| slice.to_vec())),
+        vec![vec![2, 3, 4], vec![5, 6], vec![7; 4]]
+    );
+
+    let mut wire = Short(Vec::new(), 0);
+    assert_eq!(map.write_vectored_to(3..0x24, &mut wire).unwrap(), 9);
+    let expected = Vec::from_iter(map.iter_range(3..0x24).map(|(_, v)| *v));
+    assert_eq!(wire.0, expected);
+    assert_eq!(wire.1, 4);
+
+    let mut all = Vec::new();
+    assert_eq!(map.write_vectored_to(0..u64::MAX, &mut all).unwrap(), 14);
+    assert_eq!(all, [1, 2, 3, 4, 5, 6, 7, 7, 7, 7, 7, 7, 7, 7]);
+    assert_eq!(map.write_vectored_to(0x40..0x50, &mut all).unwrap(), 0);
+
+    let mut full = [0u8; 4];
+    let err = map
+        .write_vectored_to(0..u64::MAX, &mut &mut full[..])
+        .unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::WriteZero);
+}
@@ -0,0 +1,134 @@
+use alloc::borrow::Cow;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// One `SparseVec` per maximal run of stored addresses, in address order, e.g. to process
+    /// separate regions in parallel. The data is moved, not copied, except where blocks are
+    /// [shared](SparseVec::insert_shared) or an island spans several blocks, which are
+    /// joined into the first one. The stored lengths of the parts add up to
+    /// [`SparseVec::stored_len`].
+    ///
+    /// The parts only keep the data, with the default configuration.
+    pub fn split_at_gaps(self) -> Vec<SparseVec<T, A>> {
+        Vec::from_iter(self.into_raw_parts().into_iter().map(|(range, data)| {
+            let mut part = Self::default();
+            part.push_block((range, data));
+            part
+        }))
+    }
+}
+
+impl<T: Clone, A: Address> SparseVec<T, A> {
+    /// The maximal runs of stored addresses with their data, in address order. With blocks
+    /// merged this is every block, borrowed. An island spanning several blocks, e.g. with
+    /// [auto merging](SparseVec::set_auto_merge) off or a
+    /// [block length limit](SparseVec::with_max_block_len), is copied into one slice.
+    pub fn islands(&self) -> impl Iterator<Item = (Range<A>, Cow<'_, [T]>)> + '_ {
+        let mut blocks = self.blocks().peekable();
+        core::iter::from_fn(move || {
+            let (mut range, first) = blocks.next()?;
+            let mut data = Cow::Borrowed(first);
+            while let Some((next, next_data)) = blocks.next_if(|(next, _)| next.start == range.end)
+            {
+                data.to_mut().extend_from_slice(next_data);
+                range.end = next.end;
+            }
+            Some((range, data))
+        })
+    }
+}
+
+#[test]
+fn sparsevec_split_at_gaps() {
+    let mut map = crate::sparse_vec! {
+        0x100 => [1u8; 4],
+        0x104 => [2; 4],
+        0x200 => [3; 4],
+    };
+    map.set_auto_merge(false);
+    map.insert(vec![4; 4], 0x204);
+    map.insert(vec![5; 2], 0x208);
+    map.insert(vec![6; 2], 0x300);
+
+    let islands = Vec::from_iter(map.islands());
+    assert_eq!(
+        Vec::from_iter(islands.iter().map(|(range, _)| range.clone())),
+        vec![0x100..0x108, 0x200..0x20a, 0x300..0x302]
+    );
+    assert!(matches!(
+        islands[0].1,
+        Cow::Borrowed([1, 1, 1, 1, 2, 2, 2, 2])
+    ));
+    assert!(matches!(islands[2].1, Cow::Borrowed([6, 6])));
+    assert_eq!(&islands[1].1[..], &[3, 3, 3, 3, 4, 4, 4, 4, 5, 5]);
+    drop(islands);
+
+    // Blocks are moved, not copied
+    let pointer = map.get(0x300..0x302).unwrap().as_ptr();
+    let stored_len = map.stored_len();
+    let parts = map.split_at_gaps();
+    assert_eq!(parts.len(), 3);
+    assert_eq!(
+        parts.iter().map(SparseVec::stored_len).sum::<usize>(),
+        stored_len
+    );
+    assert_eq!(Vec::from_iter(parts[1].ranges()), vec![0x200..0x20a]);
+    assert_eq!(
+        parts[1].get(0x200..0x20a).unwrap(),
+        &[3, 3, 3, 3, 4, 4, 4, 4, 5, 5]
+    );
+    assert_eq!(parts[2].get(0x300..0x302).unwrap().as_ptr(), pointer);
+
+    // Up to the end of the address space
+    let mut map = SparseVec::<u8, u16>::default();
+    map.insert(vec![1; 2], 0x10);
+    map.insert(vec![2; 4], 0xfffb);
+    let parts = map.split_at_gaps();
+    assert_eq!(parts.len(), 2);
+    assert_eq!(Vec::from_iter(parts[1].ranges()), vec![0xfffb..0xffff]);
+
+    assert!(SparseVec::<u8>::new().split_at_gaps().is_empty());
+}
+
+#[test]
+fn sparsevec_split_at_gaps_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(200);
+    for _ in 0..100 {
+        let mut map = SparseVec::<u16>::new();
+        map.set_auto_merge(rng.gen_bool(0.5));
+        for _ in 0..rng.gen_range(0..20) {
+            let len = rng.gen_range(1..8);
+            map.insert(vec![rng.gen(); len], rng.gen_range(0..0x100));
+        }
+        let islands = Vec::from_iter(
+            map.islands()
+                .map(|(range, data)| (range, data.into_owned())),
+        );
+        let coverage = crate::SparseSet::from_coverage(&map);
+        assert!(islands
+            .iter()
+            .map(|(range, _)| range.clone())
+            .eq(coverage.ranges()));
+
+        let stored_len = map.stored_len();
+        let parts = map.split_at_gaps();
+        assert_eq!(parts.len(), islands.len());
+        assert_eq!(
+            parts.iter().map(SparseVec::stored_len).sum::<usize>(),
+            stored_len
+        );
+        for (part, (range, data)) in parts.iter().zip(&islands) {
+            part.assert_invariants();
+            assert_eq!(part.blocks().len(), 1);
+            assert_eq!(
+                part.get_block(range.start),
+                Some((range.clone(), &data[..]))
+            );
+        }
+    }
+}
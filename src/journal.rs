@@ -0,0 +1,477 @@
+//! Journal of mutations behind the `std` feature. Without it `SparseVec::journal` does nothing,
+//! so the mutating operations record unconditionally.
+
+#[cfg(feature = "std")]
+use std::fmt;
+#[cfg(feature = "std")]
+use std::io::{self, Read, Write};
+#[cfg(feature = "std")]
+use std::panic::{AssertUnwindSafe, RefUnwindSafe, UnwindSafe};
+#[cfg(all(test, feature = "std"))]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "std")]
+use crate::checksum::crc32;
+use crate::{Address, SparseVec};
+
+// op: u8, addr: u64 LE, len: u64 LE
+#[cfg(feature = "std")]
+const HEADER_LEN: usize = 17;
+
+/// Record types, by their op code.
+#[derive(Clone, Copy)]
+pub(crate) enum JournalOp {
+    /// `len` elements at `addr` follow.
+    Insert = 1,
+    /// The value filling `len` elements at `addr` follows.
+    Fill = 2,
+    /// `len` elements at `addr` follow.
+    Write = 3,
+    /// `len` elements at `addr` were unmapped.
+    Remove = 4,
+    /// The value filling the gaps in `len` elements at `addr` follows.
+    FillGaps = 5,
+}
+
+// Set by `SparseVec::enable_journal`. `bytes` is the identity on `[u8]`, so that mutating
+// does not need `T = u8` everywhere.
+#[cfg(feature = "std")]
+pub(crate) struct Journal<T> {
+    writer: Box<dyn Write + Send + Sync + UnwindSafe + RefUnwindSafe>,
+    bytes: fn(&[T]) -> &[u8],
+    // Only handed back by `disable_journal`, never observed in between
+    error: Option<AssertUnwindSafe<io::Error>>,
+}
+
+#[cfg(feature = "std")]
+impl SparseVec<u8> {
+    /// Appends a record to `writer` for every later insert, fill, write and removed block,
+    /// for [`replay_journal`] to apply to a copy of the current state after a crash. Replaces
+    /// the previous journal without flushing it.
+    ///
+    /// Records are `(op: u8, addr: u64 LE, len: u64 LE, payload, crc32: u32 LE)`, each
+    /// passed to `writer` in one `write_all`. Shared inserts are recorded as inserts and
+    /// evictions by [`SparseVec::with_capacity_limit`] as removals. If writing fails, nothing
+    /// is recorded from then on and [`SparseVec::disable_journal`] returns the error. Changes
+    /// through mutable slices, e.g. [`SparseVec::get_mut`], `blocks_mut` or `with_block_raw`,
+    /// cannot be recorded, so they stop the journal the same way with an
+    /// [`io::ErrorKind::Unsupported`] error instead of letting a replay diverge.
+    pub fn enable_journal<W>(&mut self, writer: W)
+    where
+        W: Write + Send + Sync + UnwindSafe + RefUnwindSafe + 'static,
+    {
+        self.journal = Some(Journal {
+            writer: Box::new(writer),
+            bytes: |data| data,
+            error: None,
+        });
+    }
+
+    /// Stops journaling and flushes the writer, returning the first error writing to it.
+    pub fn disable_journal(&mut self) -> io::Result<()> {
+        let Some(mut journal) = self.journal.take() else {
+            return Ok(());
+        };
+        match journal.error {
+            Some(AssertUnwindSafe(err)) => Err(err),
+            None => journal.writer.flush(),
+        }
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    // Records an operation on `len` elements at `addr`
+    #[cfg(feature = "std")]
+    pub(crate) fn journal(&mut self, op: JournalOp, addr: A, len: u64, payload: &[T]) {
+        let Some(journal) = &mut self.journal else {
+            return;
+        };
+        if journal.error.is_some() {
+            return;
+        }
+        let payload = (journal.bytes)(payload);
+        let mut record = Vec::with_capacity(HEADER_LEN + payload.len() + 4);
+        record.push(op as u8);
+        record.extend_from_slice(&addr.to_u64().to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(payload);
+        record.extend_from_slice(&crc32(&record).to_le_bytes());
+        if let Err(err) = journal.writer.write_all(&record) {
+            journal.error = Some(AssertUnwindSafe(err));
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline]
+    pub(crate) fn journal(&mut self, _: JournalOp, _: A, _: u64, _: &[T]) {}
+
+    // Stops the journal like a failed write, for mutations it cannot record
+    #[cfg(feature = "std")]
+    pub(crate) fn stop_journal(&mut self, what: &str) {
+        if let Some(journal) = &mut self.journal {
+            if journal.error.is_none() {
+                let err = io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("{what} cannot be journaled"),
+                );
+                journal.error = Some(AssertUnwindSafe(err));
+            }
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline]
+    pub(crate) fn stop_journal(&mut self, _: &str) {}
+}
+
+/// Error while replaying a journal, with the byte offset of the record that failed.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub enum ReplayError {
+    /// The reader failed.
+    Io { offset: u64, source: io::Error },
+    /// The record has an unknown op code, or a wrong checksum but more records follow.
+    Corrupt { offset: u64 },
+    /// The record with `len` elements at `addr` does not fit in the address space.
+    Overflow { offset: u64, addr: u64, len: u64 },
+    /// The record writes to `addr`, which is unmapped, so the journal does not belong to
+    /// the base.
+    Unmapped { offset: u64, addr: u64 },
+}
+
+#[cfg(feature = "std")]
+impl ReplayError {
+    /// Byte offset of the start of the failing record in the journal.
+    pub fn offset(&self) -> u64 {
+        match self {
+            ReplayError::Io { offset, .. }
+            | ReplayError::Corrupt { offset }
+            | ReplayError::Overflow { offset, .. }
+            | ReplayError::Unmapped { offset, .. } => *offset,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io { offset, source } => {
+                write!(f, "read error in record at offset {offset}: {source}")
+            }
+            ReplayError::Corrupt { offset } => write!(f, "corrupt record at offset {offset}"),
+            ReplayError::Overflow { offset, addr, len } => write!(
+                f,
+                "record at offset {offset} with {len} bytes at {addr:#x} overflows the address space"
+            ),
+            ReplayError::Unmapped { offset, addr } => write!(
+                f,
+                "record at offset {offset} writes to unmapped address {addr:#x}"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReplayError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// Applies the records written by [`SparseVec::enable_journal`] to `base`, which should
+/// hold the state from when the journal was enabled.
+///
+/// A record cut short by the end of the journal, as left by a crash while writing it, is
+/// ignored along with the rest of the journal. So is a final record with a wrong checksum.
+#[cfg(feature = "std")]
+pub fn replay_journal<R: Read>(
+    mut base: SparseVec<u8>,
+    mut reader: R,
+) -> Result<SparseVec<u8>, ReplayError> {
+    let mut offset = 0u64;
+    loop {
+        let io = |source| ReplayError::Io { offset, source };
+        let mut header = [0; HEADER_LEN];
+        if crate::records::read_full(&mut reader, &mut header).map_err(io)? < HEADER_LEN {
+            return Ok(base);
+        }
+        let addr = u64::from_le_bytes(header[1..9].try_into().unwrap());
+        let len = u64::from_le_bytes(header[9..].try_into().unwrap());
+        let (op, payload_len) = match header[0] {
+            1 => (JournalOp::Insert, len),
+            2 => (JournalOp::Fill, 1),
+            3 => (JournalOp::Write, len),
+            4 => (JournalOp::Remove, 0),
+            5 => (JournalOp::FillGaps, 1),
+            _ => return Err(ReplayError::Corrupt { offset }),
+        };
+        // Inserted and written data may end at `u64::MAX` itself
+        let last = match op {
+            JournalOp::Insert | JournalOp::Write => len.saturating_sub(1),
+            _ => len,
+        };
+        if addr.checked_add(last).is_none() {
+            return Err(ReplayError::Overflow { offset, addr, len });
+        }
+
+        // Grows with the data actually read instead of trusting `len` up front
+        let mut record = header.to_vec();
+        (&mut reader)
+            .take(payload_len.saturating_add(4))
+            .read_to_end(&mut record)
+            .map_err(io)?;
+        if (record.len() - HEADER_LEN) as u64 != payload_len.saturating_add(4) {
+            return Ok(base);
+        }
+        let (record, crc) = record.split_at(record.len() - 4);
+        if crc32(record) != u32::from_le_bytes(crc.try_into().unwrap()) {
+            let more = crate::records::read_full(&mut reader, &mut [0]).map_err(io)?;
+            return match more {
+                0 => Ok(base),
+                _ => Err(ReplayError::Corrupt { offset }),
+            };
+        }
+
+        let payload = &record[HEADER_LEN..];
+        match op {
+            JournalOp::Insert => base.insert(payload.to_vec(), addr),
+            JournalOp::Fill => base.fill(addr..addr + len, payload[0]),
+            JournalOp::Write => base
+                .write(addr, payload)
+                .map_err(|err| ReplayError::Unmapped {
+                    offset,
+                    addr: err.addr,
+                })?,
+            JournalOp::Remove => {
+                let range = addr..addr + len;
+                base.assert_thawed(&range);
+                base.record_history(&range);
+                base.remove_unwatched(range);
+            }
+            JournalOp::FillGaps => base.fill_gaps(addr..addr + len, payload[0]),
+        }
+        offset += (record.len() + 4) as u64;
+    }
+}
+
+// A journal the test can read while the `SparseVec` owns the writer
+#[cfg(all(test, feature = "std"))]
+#[derive(Clone, Default)]
+struct SharedJournal(Arc<Mutex<Vec<u8>>>);
+
+#[cfg(all(test, feature = "std"))]
+impl Write for SharedJournal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+fn journal_contents(map: &SparseVec<u8>) -> Vec<(u64, u8)> {
+    Vec::from_iter(map.iter_range(0..u64::MAX).map(|(addr, v)| (addr, *v)))
+}
+
+#[cfg(all(test, feature = "std"))]
+fn journal_test_vec() -> SparseVec<u8> {
+    crate::sparse_vec! { 0x100 => [1u8; 0x10] }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sparsevec_journal() {
+    let journal = SharedJournal::default();
+    let mut map = journal_test_vec();
+    map.enable_journal(journal.clone());
+    map.insert(vec![2; 4], 0x10c);
+    map.fill(0x200..0x208, 3);
+    map.write(0x202, &[4, 4]).unwrap();
+    assert!(map.write(0x300, &[5]).is_err());
+    map.fill_gaps(0x1f0..0x210, 6);
+    map.insert(vec![7, 8], u64::MAX - 2);
+    map.retain_blocks(|range, _| range.start != 0x100);
+    map.disable_journal().unwrap();
+    map.insert(vec![9], 0);
+
+    // Records in order, each one self-delimiting
+    let bytes = journal.0.lock().unwrap().clone();
+    assert_eq!(bytes[0], JournalOp::Insert as u8);
+    assert_eq!(bytes[1..9], 0x10cu64.to_le_bytes());
+    assert_eq!(bytes[9..17], 4u64.to_le_bytes());
+    assert_eq!(bytes[17..21], [2; 4]);
+    assert_eq!(bytes[21..25], crc32(&bytes[..21]).to_le_bytes());
+    assert_eq!(bytes[25], JournalOp::Fill as u8);
+
+    let replayed = replay_journal(journal_test_vec(), &bytes[..]).unwrap();
+    replayed.assert_invariants();
+    map.retain_blocks(|range, _| range.start != 0);
+    assert_eq!(journal_contents(&replayed), journal_contents(&map));
+    assert_eq!(replayed.get(u64::MAX - 2..u64::MAX), Some(&[7, 8][..]));
+    assert_eq!(
+        Vec::from_iter(replayed.ranges()),
+        vec![0x1f0..0x210, u64::MAX - 2..u64::MAX]
+    );
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sparsevec_journal_untracked() {
+    // SharedJournal inserts and evictions are recorded, in-place reads are written
+    let journal = SharedJournal::default();
+    let mut map = SparseVec::with_capacity_limit(0x10);
+    map.insert(vec![1; 0x10], 0x100);
+    map.enable_journal(journal.clone());
+    map.insert_shared(Arc::from(&[2u8; 8][..]), 0x200);
+    assert_eq!(
+        map.insert_from_reader(0x204, 2, &mut &[3u8, 3][..])
+            .unwrap(),
+        2
+    );
+    map.disable_journal().unwrap();
+    let bytes = journal.0.lock().unwrap().clone();
+    let replayed = replay_journal(journal_test_vec(), &bytes[..]).unwrap();
+    assert_eq!(Vec::from_iter(replayed.ranges()), vec![0x200..0x208]);
+    assert_eq!(journal_contents(&replayed), journal_contents(&map));
+
+    // Mutable slices would let a replay diverge, so they stop the journal
+    let mut map = journal_test_vec();
+    map.enable_journal(SharedJournal::default());
+    assert!(map.get_mut(0x200..0x201).is_none());
+    map.insert(vec![4], 0);
+    map.get_mut(0x100..0x104).unwrap().fill(5);
+    let err = map.disable_journal().unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Unsupported);
+    assert_eq!(err.to_string(), "get_mut cannot be journaled");
+
+    let journal = SharedJournal::default();
+    map.enable_journal(journal.clone());
+    map.with_block_raw(0x100, |_, data| data.push(6));
+    map.insert(vec![7], 1);
+    assert!(map.disable_journal().is_err());
+    assert!(journal.0.lock().unwrap().is_empty());
+    map.enable_journal(SharedJournal::default());
+    map.blocks_mut();
+    assert!(map.disable_journal().is_err());
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sparsevec_journal_torn() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(188);
+    let journal = SharedJournal::default();
+    let mut map = journal_test_vec();
+    map.enable_journal(journal.clone());
+    // Journal length and state after every record
+    let mut states = vec![(0, journal_contents(&map))];
+    for i in 0..60u8 {
+        let addr = rng.gen_range(0xf0..0x180);
+        let len = rng.gen_range(1..0x20);
+        match rng.gen_range(0..5) {
+            0 => map.insert(vec![i; len], addr),
+            1 => map.fill(addr..addr + len as u64, i),
+            2 => map.fill_gaps(addr..addr + len as u64, i),
+            3 => map.retain_blocks(|range, _| range.end - range.start > len as u64),
+            _ => {
+                if map.write(addr, &vec![i; len]).is_err() {
+                    continue;
+                }
+            }
+        }
+        let written = journal.0.lock().unwrap().len();
+        if written > states.last().unwrap().0 {
+            states.push((written, journal_contents(&map)));
+        }
+    }
+    let bytes = journal.0.lock().unwrap().clone();
+
+    // Cutting the journal anywhere recovers up to the last complete record
+    for cut in 0..=bytes.len() {
+        let replayed = replay_journal(journal_test_vec(), &bytes[..cut]).unwrap();
+        let (_, expected) = states.iter().rev().find(|(len, _)| *len <= cut).unwrap();
+        assert_eq!(&journal_contents(&replayed), expected, "cut at {cut}");
+    }
+
+    // So is a garbled final record, but not one followed by more records
+    let (last, _) = states[states.len() - 2];
+    let mut garbled = bytes.clone();
+    *garbled.last_mut().unwrap() ^= 1;
+    let replayed = replay_journal(journal_test_vec(), &garbled[..]).unwrap();
+    assert_eq!(journal_contents(&replayed), states[states.len() - 2].1);
+    garbled[last + 1] ^= 1;
+    garbled.extend_from_slice(&bytes[last..]);
+    assert!(matches!(
+        replay_journal(journal_test_vec(), &garbled[..]),
+        Err(ReplayError::Corrupt { offset }) if offset == last as u64
+    ));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn sparsevec_journal_errors() {
+    let record = |op: u8, addr: u64, len: u64, payload: &[u8]| {
+        let mut record = vec![op];
+        record.extend_from_slice(&addr.to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(payload);
+        record.extend_from_slice(&crc32(&record).to_le_bytes());
+        record
+    };
+    let fill = record(2, 0x10, 4, &[1]);
+    let second = fill.len() as u64;
+
+    let unknown = [fill.clone(), record(9, 0, 0, &[])].concat();
+    let err = replay_journal(journal_test_vec(), &unknown[..]).unwrap_err();
+    assert!(matches!(err, ReplayError::Corrupt { offset } if offset == second));
+    assert_eq!(err.to_string(), "corrupt record at offset 22");
+
+    let overflow = [fill.clone(), record(4, u64::MAX - 1, 2, &[])].concat();
+    let err = replay_journal(journal_test_vec(), &overflow[..]).unwrap_err();
+    assert_eq!(err.offset(), second);
+    assert!(matches!(err, ReplayError::Overflow { len: 2, .. }));
+    let top = [fill.clone(), record(1, u64::MAX - 1, 3, &[1, 2, 3])].concat();
+    let err = replay_journal(journal_test_vec(), &top[..]).unwrap_err();
+    assert!(matches!(err, ReplayError::Overflow { len: 3, .. }));
+    let top = [fill.clone(), record(1, u64::MAX - 1, 2, &[1, 2])].concat();
+    let replayed = replay_journal(journal_test_vec(), &top[..]).unwrap();
+    assert_eq!(
+        replayed.get_inclusive(u64::MAX - 1..=u64::MAX).unwrap(),
+        &[1, 2][..]
+    );
+
+    let unmapped = [fill, record(3, 0x12, 4, &[2; 4])].concat();
+    let err = replay_journal(journal_test_vec(), &unmapped[..]).unwrap_err();
+    assert!(matches!(err, ReplayError::Unmapped { addr: 0x14, .. }));
+
+    // A huge length is not allocated before the data arrives
+    let huge = record(1, 0, u64::MAX, &[]);
+    assert_eq!(
+        journal_contents(&replay_journal(journal_test_vec(), &huge[..]).unwrap()),
+        journal_contents(&journal_test_vec())
+    );
+
+    // Write errors stop the journal
+    struct Failing;
+    impl Write for Failing {
+        fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+            Err(io::Error::other("disk full"))
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+    let mut map = journal_test_vec();
+    map.enable_journal(Failing);
+    map.insert(vec![1], 0);
+    map.insert(vec![2], 1);
+    assert_eq!(map.disable_journal().unwrap_err().to_string(), "disk full");
+    assert!(map.disable_journal().is_ok());
+}
@@ -0,0 +1,31 @@
+use core::hash::{BuildHasherDefault, Hasher};
+
+use hashbrown::HashMap;
+
+// Block keys are small sequential integers handed out by the crate itself, so they only
+// need spreading over the bits hashbrown uses, not protection against collision attacks
+#[derive(Default)]
+pub(crate) struct KeyHasher(u64);
+
+impl Hasher for KeyHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.write_u64(byte as u64);
+        }
+    }
+
+    fn write_u64(&mut self, key: u64) {
+        // Fibonacci hashing, so the high bits of the control bytes differ too
+        self.0 = (self.0 ^ key).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+
+    fn write_usize(&mut self, key: usize) {
+        self.write_u64(key as u64);
+    }
+}
+
+pub(crate) type KeyMap<V> = HashMap<usize, V, BuildHasherDefault<KeyHasher>>;
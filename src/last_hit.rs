@@ -0,0 +1,149 @@
+use core::ops::{Deref, Range};
+use core::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+
+use rangemap::RangeMap;
+
+use crate::{Address, SparseVec};
+
+// The ranges of the blocks with their keys, the only place the ranges are stored. Derefs to
+// the `RangeMap` for reading, while `insert` and `remove` also forget the last hit, so it
+// cannot outlive a change of the block it is for.
+pub(crate) struct BlockMap<A> {
+    ranges: RangeMap<A, usize>,
+    last_hit: LastHit,
+}
+
+impl<A: Address> BlockMap<A> {
+    pub(crate) fn insert(&mut self, range: Range<A>, key: usize) {
+        self.last_hit.clear();
+        self.ranges.insert(range, key);
+    }
+
+    pub(crate) fn remove(&mut self, range: Range<A>) {
+        self.last_hit.clear();
+        self.ranges.remove(range);
+    }
+
+    pub(crate) fn into_ranges(self) -> RangeMap<A, usize> {
+        self.ranges
+    }
+}
+
+impl<A: Address> Default for BlockMap<A> {
+    fn default() -> Self {
+        Self {
+            ranges: RangeMap::new(),
+            last_hit: LastHit::default(),
+        }
+    }
+}
+
+impl<A: Address> Clone for BlockMap<A> {
+    fn clone(&self) -> Self {
+        Self {
+            ranges: self.ranges.clone(),
+            last_hit: LastHit::default(),
+        }
+    }
+}
+
+impl<A> Deref for BlockMap<A> {
+    type Target = RangeMap<A, usize>;
+
+    fn deref(&self) -> &RangeMap<A, usize> {
+        &self.ranges
+    }
+}
+
+// Key plus one and range of the block found by the last lookup, checked before searching the
+// map. Lookups only take `&self`, so this is a seqlock that keeps `SparseVec: Sync`: a write
+// makes `seq` odd while it lasts, and a read retried past a write is a miss. Concurrent
+// writes skip caching rather than wait.
+#[derive(Default)]
+struct LastHit {
+    seq: AtomicUsize,
+    key: AtomicUsize,
+    start: AtomicU64,
+    end: AtomicU64,
+}
+
+impl LastHit {
+    fn get<A: Address>(&self) -> Option<(Range<A>, usize)> {
+        let seq = self.seq.load(Ordering::Acquire);
+        if seq % 2 == 1 {
+            return None;
+        }
+        let key = self.key.load(Ordering::Relaxed);
+        let (start, end) = (
+            self.start.load(Ordering::Relaxed),
+            self.end.load(Ordering::Relaxed),
+        );
+        fence(Ordering::Acquire);
+        if self.seq.load(Ordering::Relaxed) != seq {
+            return None;
+        }
+        let range = A::try_from_u64(start)?..A::try_from_u64(end)?;
+        Some((range, key.checked_sub(1)?))
+    }
+
+    fn set<A: Address>(&self, range: &Range<A>, key: usize) {
+        let seq = self.seq.load(Ordering::Relaxed);
+        if seq % 2 == 1
+            || (self.seq)
+                .compare_exchange(seq, seq + 1, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+        {
+            return;
+        }
+        fence(Ordering::Release);
+        self.key.store(key + 1, Ordering::Relaxed);
+        self.start.store(range.start.to_u64(), Ordering::Relaxed);
+        self.end.store(range.end.to_u64(), Ordering::Relaxed);
+        self.seq.store(seq + 2, Ordering::Release);
+    }
+
+    fn clear(&mut self) {
+        *self.key.get_mut() = 0;
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    // `map.get_key_value(&addr)` for the accessors
+    pub(crate) fn find_block(&self, addr: A) -> Option<(Range<A>, usize)> {
+        if let Some((range, key)) = self.map.last_hit.get() {
+            if range.contains(&addr) {
+                return Some((range, key));
+            }
+        }
+        let (range, key) = self.map.get_key_value(&addr)?;
+        self.map.last_hit.set(range, *key);
+        Some((range.clone(), *key))
+    }
+}
+
+#[test]
+fn sparsevec_last_hit() {
+    use rand::{Rng, SeedableRng};
+
+    fn assert_sync<T: Sync>(_: &T) {}
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(189);
+    let mut map = SparseVec::new();
+    assert_sync(&map);
+    for i in 0..2000u32 {
+        let addr = rng.gen_range(0..0x400);
+        match rng.gen_range(0..4) {
+            0 => map.insert(vec![i; rng.gen_range(1..0x40)], addr),
+            1 => map.split_blocks_at(&[addr]),
+            2 => map.retain_blocks(|range, _| !range.contains(&addr)),
+            _ => {}
+        }
+        // Lookups right after the mutation, and repeated ones hitting the cache
+        for addr in [addr, addr, rng.gen_range(0..0x400), addr + 1] {
+            let expected = map.map.get_key_value(&addr).map(|(r, k)| (r.clone(), *k));
+            assert_eq!(map.find_block(addr), expected);
+            let block = expected.map(|(range, key)| (range, &map.data[&key][..]));
+            assert_eq!(map.get_block(addr), block);
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::{self, Write};
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    pub fn layout(&self) -> LayoutDisplay<'_, T, A> {
+        LayoutDisplay {
+            vec: self,
+            show_gaps: true,
+            hex_sizes: false,
+            max_lines: None,
+        }
+    }
+
+    /// Drawing of the address space for documentation and debugging, see [`LayoutOpts`].
+    pub fn render_layout(&self, opts: LayoutOpts) -> String {
+        let mut out = String::new();
+        match opts {
+            LayoutOpts::Bar { columns } => self.render_bar(&mut out, columns),
+            LayoutOpts::Graphviz => self.render_graphviz(&mut out),
+        }
+        .unwrap();
+        out
+    }
+
+    fn render_bar(&self, out: &mut String, columns: usize) -> fmt::Result {
+        let Some(bounds) = self.bounds() else {
+            return write!(out, "(empty)");
+        };
+        // Runs of the address space drawn to scale, separated by compressed gaps
+        let stored: u64 = self.ranges().map(|r| (r.end - r.start).to_u64()).sum();
+        let mut runs: Vec<Range<A>> = Vec::new();
+        for range in self.ranges() {
+            match runs.last_mut() {
+                Some(run) if (range.start - run.end).to_u64() <= stored => run.end = range.end,
+                _ => runs.push(range),
+            }
+        }
+        let breaks = runs.len() - 1;
+        let visible: u64 = runs.iter().map(|r| (r.end - r.start).to_u64()).sum();
+        // Every run rounds up to whole columns, which costs at most one column each
+        let available = columns
+            .saturating_sub(BREAK.len() * breaks + runs.len())
+            .max(1);
+        let scale = visible.div_ceil(available as u64);
+        writeln!(
+            out,
+            "{:#x}..{:#x}  1 column = {scale} addresses",
+            bounds.start, bounds.end
+        )?;
+        for (i, run) in runs.iter().enumerate() {
+            if i > 0 {
+                out.push_str(BREAK);
+            }
+            let mut start = run.start;
+            while start < run.end {
+                let step = A::try_from_u64(scale).unwrap_or(A::MAX);
+                let end = start.checked_add(step).unwrap_or(A::MAX).min(run.end);
+                // A column is covered if any address in it is
+                let covered = self.ranges_in(start..end).next().is_some();
+                out.push(if covered { '#' } else { '.' });
+                start = end;
+            }
+        }
+        Ok(())
+    }
+
+    fn render_graphviz(&self, out: &mut String) -> fmt::Result {
+        let size = |range: &Range<A>| Size {
+            bytes: (range.end - range.start)
+                .to_u64()
+                .saturating_mul(core::mem::size_of::<T>() as u64),
+            hex: false,
+        };
+        let mut fields = Vec::new();
+        let mut prev_end = None;
+        for range in self.ranges() {
+            if let Some(prev_end) = prev_end {
+                let gap = prev_end..range.start;
+                fields.push(alloc::format!("{{gap|{}}}", size(&gap)));
+            }
+            fields.push(alloc::format!(
+                "{{{:#x}..{:#x}|{}}}",
+                range.start,
+                range.end,
+                size(&range)
+            ));
+            prev_end = Some(range.end);
+        }
+        writeln!(out, "digraph layout {{")?;
+        writeln!(out, "    node [shape=record];")?;
+        writeln!(out, "    layout [label=\"{}\"];", fields.join("|"))?;
+        write!(out, "}}")
+    }
+}
+
+// Drawn for a gap compressed by `LayoutOpts::Bar`
+const BREAK: &str = "//";
+
+/// How [`SparseVec::render_layout`] draws the address space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayoutOpts {
+    /// A header with the scale and a bar at most `columns` wide over [`SparseVec::bounds`],
+    /// `#` for columns with any stored address and `.` for the others. Gaps longer than all
+    /// stored data together are drawn as `//` instead of to scale. Every block takes at
+    /// least one column.
+    Bar { columns: usize },
+    /// A Graphviz node with one record field per block and gap, with their sizes in bytes.
+    Graphviz,
+}
+
+impl Default for LayoutOpts {
+    fn default() -> Self {
+        LayoutOpts::Bar { columns: 80 }
+    }
+}
+
+/// Human readable memory map of a [`SparseVec`], one line per block.
+///
+/// Sizes are given in bytes, i.e. the element count times `size_of::<T>()`, saturating at
+/// `u64::MAX`.
+pub struct LayoutDisplay<'a, T, A = u64> {
+    vec: &'a SparseVec<T, A>,
+    show_gaps: bool,
+    hex_sizes: bool,
+    max_lines: Option<usize>,
+}
+
+impl<T, A> LayoutDisplay<'_, T, A> {
+    /// Print a line for every gap between two blocks. Enabled by default.
+    pub fn gaps(mut self, show: bool) -> Self {
+        self.show_gaps = show;
+        self
+    }
+
+    /// Print sizes in hexadecimal instead of decimal.
+    pub fn hex_sizes(mut self, hex: bool) -> Self {
+        self.hex_sizes = hex;
+        self
+    }
+
+    /// Limit the number of block and gap lines. The footer is always printed.
+    pub fn max_lines(mut self, max: usize) -> Self {
+        self.max_lines = Some(max);
+        self
+    }
+
+    fn size(&self, len: u64) -> Size {
+        Size {
+            bytes: len.saturating_mul(core::mem::size_of::<T>() as u64),
+            hex: self.hex_sizes,
+        }
+    }
+}
+
+struct Size {
+    bytes: u64,
+    hex: bool,
+}
+
+impl fmt::Display for Size {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.hex {
+            write!(f, "{:#x} bytes", self.bytes)
+        } else {
+            write!(f, "{} bytes", self.bytes)
+        }
+    }
+}
+
+enum Line<A> {
+    Block(Range<A>),
+    Gap(Range<A>),
+}
+
+impl<T, A: Address> fmt::Display for LayoutDisplay<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = Vec::new();
+        let mut stored = 0;
+        let mut unmapped = 0;
+        let mut prev_end = None;
+        for range in self.vec.ranges() {
+            if let Some(prev_end) = prev_end {
+                unmapped += (range.start - prev_end).to_u64();
+                if self.show_gaps {
+                    lines.push(Line::Gap(prev_end..range.start));
+                }
+            }
+            stored += (range.end - range.start).to_u64();
+            prev_end = Some(range.end);
+            lines.push(Line::Block(range));
+        }
+
+        let shown = self.max_lines.unwrap_or(usize::MAX).min(lines.len());
+        for line in &lines[..shown] {
+            match line {
+                Line::Block(range) => writeln!(
+                    f,
+                    "{:#010x}..{:#010x}  {}",
+                    range.start,
+                    range.end,
+                    self.size((range.end - range.start).to_u64())
+                )?,
+                Line::Gap(range) => writeln!(
+                    f,
+                    "{:#010x}..{:#010x}  ({} unmapped)",
+                    range.start,
+                    range.end,
+                    self.size((range.end - range.start).to_u64())
+                )?,
+            }
+        }
+        if shown < lines.len() {
+            writeln!(f, "... {} more lines", lines.len() - shown)?;
+        }
+        write!(
+            f,
+            "{} blocks, {} stored, {} in gaps",
+            self.vec.map.len(),
+            self.size(stored),
+            self.size(unmapped)
+        )
+    }
+}
+
+#[test]
+fn sparsevec_layout() {
+    let mut map = SparseVec::new();
+    assert_eq!(
+        map.layout().to_string(),
+        "0 blocks, 0 bytes stored, 0 bytes in gaps"
+    );
+
+    map.insert(vec![0u8; 0x800], 0x1000);
+    map.insert(vec![0u8; 0x10], 0x2000);
+    map.insert(vec![0u8; 0x100], 0x1_0000_0000);
+    assert_eq!(
+        map.layout().to_string(),
+        "\
+0x00001000..0x00001800  2048 bytes
+0x00001800..0x00002000  (2048 bytes unmapped)
+0x00002000..0x00002010  16 bytes
+0x00002010..0x100000000  (4294959088 bytes unmapped)
+0x100000000..0x100000100  256 bytes
+3 blocks, 2320 bytes stored, 4294961136 bytes in gaps"
+    );
+    assert_eq!(
+        map.layout().gaps(false).hex_sizes(true).to_string(),
+        "\
+0x00001000..0x00001800  0x800 bytes
+0x00002000..0x00002010  0x10 bytes
+0x100000000..0x100000100  0x100 bytes
+3 blocks, 0x910 bytes stored, 0xffffe7f0 bytes in gaps"
+    );
+    assert_eq!(
+        map.layout().max_lines(2).to_string(),
+        "\
+0x00001000..0x00001800  2048 bytes
+0x00001800..0x00002000  (2048 bytes unmapped)
+... 3 more lines
+3 blocks, 2320 bytes stored, 4294961136 bytes in gaps"
+    );
+
+    let mut wide = SparseVec::new();
+    wide.insert(vec![0u32; 4], 0x10);
+    assert_eq!(
+        wide.layout().to_string(),
+        "\
+0x00000010..0x00000014  16 bytes
+1 blocks, 16 bytes stored, 0 bytes in gaps"
+    );
+
+    // Sizes in bytes saturate
+    wide.insert(vec![0; 1], u64::MAX - 1);
+    assert!(wide
+        .layout()
+        .to_string()
+        .ends_with("2 blocks, 20 bytes stored, 18446744073709551615 bytes in gaps"));
+    assert!(wide
+        .render_layout(LayoutOpts::Graphviz)
+        .contains("{gap|18446744073709551615 bytes}"));
+}
+
+#[test]
+fn sparsevec_render_layout() {
+    let mut map = SparseVec::new();
+    assert_eq!(map.render_layout(LayoutOpts::default()), "(empty)");
+
+    map.insert(vec![0u8; 0x800], 0x1000);
+    map.insert(vec![0u8; 0x10], 0x2000);
+    map.insert(vec![0u8; 0x100], 0x1_0000_0000);
+    assert_eq!(
+        map.render_layout(LayoutOpts::default()),
+        "\
+0x1000..0x100000100  1 column = 58 addresses
+####################################..................................#//#####"
+    );
+    assert_eq!(
+        map.render_layout(LayoutOpts::Bar { columns: 20 }),
+        "\
+0x1000..0x100000100  1 column = 273 addresses
+########.......#//#"
+    );
+    assert_eq!(
+        map.render_layout(LayoutOpts::Graphviz),
+        "\
+digraph layout {
+    node [shape=record];
+    layout [label=\"{0x1000..0x1800|2048 bytes}|{gap|2048 bytes}|{0x2000..0x2010|16 bytes}|\
+{gap|4294959088 bytes}|{0x100000000..0x100000100|256 bytes}\"];
+}"
+    );
+
+    // Gaps longer than the stored data are compressed, so single elements stay visible
+    let mut sparse = SparseVec::new();
+    for addr in [0, 0x3ff, 0x400, 0x1000] {
+        sparse.insert(vec![1u16], addr);
+    }
+    assert_eq!(
+        sparse.render_layout(LayoutOpts::Bar { columns: 16 }),
+        "\
+0x0..0x1001  1 column = 1 addresses
+#//##//#"
+    );
+}
@@ -1,22 +1,223 @@
-use std::collections::{HashMap, HashSet};
-use std::ops::Range;
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(not(feature = "alloc"))]
+compile_error!("sparse_vec requires the `alloc` feature");
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::{Bound, Range, RangeBounds};
+
+use hashbrown::HashMap;
 
 use itertools::Itertools;
-use rangemap::RangeMap;
+use rangemap::{RangeMap, RangeSet};
+
+mod address;
+mod bits;
+mod block_id;
+mod block_len;
+mod boundaries;
+mod bridge;
+mod builder;
+mod bus;
+#[cfg(feature = "bytes")]
+mod bytes_impl;
+mod capacity;
+mod checksum;
+mod chunks;
+mod compare;
+#[cfg(feature = "compress")]
+mod compress;
+mod coverage;
+mod cstr;
+mod cursor;
+mod dedup;
+mod encoding;
+mod endian;
+mod error;
+mod fallible;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod formats;
+mod free;
+mod freeze;
+mod gather;
+mod hexdump;
+mod history;
+mod holes;
+mod inclusive;
+#[cfg(feature = "std")]
+mod io;
+mod islands;
+mod journal;
+mod keys;
+mod last_hit;
+mod layout;
+mod macros;
+mod map_values;
+mod marks;
+mod merge;
+mod mirror;
+mod modulus;
+mod overlay;
+mod overwrite;
+mod pages;
+mod pattern;
+#[cfg(feature = "bytemuck")]
+mod pod;
+#[cfg(all(feature = "proc-maps", any(target_os = "linux", target_os = "android")))]
+mod proc_maps;
+mod push;
+mod raw;
+mod raw_parts;
+mod rebased;
+#[cfg(feature = "std")]
+mod records;
+mod reduce;
+mod reserve;
+#[cfg(feature = "rkyv")]
+mod rkyv_impl;
+mod runs;
+#[cfg(feature = "serde")]
+mod serde_impl;
+mod set;
+#[cfg(feature = "std")]
+mod shared;
+mod signature;
+#[cfg(feature = "std")]
+mod sparse_file;
+#[cfg(feature = "std")]
+mod stats;
+mod storage;
+#[cfg(feature = "async")]
+mod stream;
+mod tagged;
+mod trace;
+mod unchecked;
+mod view;
+mod watch;
+mod zip;
+
+pub use address::Address;
+pub use bits::SparseBitVec;
+pub use block_id::BlockId;
+pub use builder::{BuildError, SparseVecBuilder};
+pub use bus::{Access, AutoMap, MemFault, MemoryBus};
+pub use checksum::{ChecksumAlgo, ChecksumError, GapPolicy};
+#[cfg(feature = "compress")]
+pub use compress::CompressionStats;
+pub use cstr::CStrError;
+pub use cursor::{Cursor, CursorSegment};
+pub use dedup::DedupStats;
+pub use encoding::{DecodeError, LeBytes};
+pub use error::{CompareDiff, CompareError, Error, Frozen, ReadError, Unmapped, WriteError};
+#[cfg(feature = "object")]
+pub use formats::LoadError;
+pub use formats::{IhexError, IhexErrorKind, SrecError, SrecErrorKind, SrecKind};
+pub use hexdump::HexDump;
+#[cfg(feature = "std")]
+pub use io::{ReadGapPolicy, SparseCursor, SparseCursorMut};
+#[cfg(feature = "std")]
+pub use journal::{replay_journal, ReplayError};
+pub use layout::{LayoutDisplay, LayoutOpts};
+#[doc(hidden)]
+pub use macros::__into_vec;
+pub use map_values::MapError;
+pub use marks::MarkId;
+pub use merge::Conflict;
+pub use overlay::Overlay;
+pub use pages::{PageAlign, PageCoverage, PageView};
+#[cfg(all(feature = "proc-maps", any(target_os = "linux", target_os = "android")))]
+pub use proc_maps::{MapRegion, SkippedRegion};
+pub use raw_parts::RawPartsError;
+pub use rebased::{RebaseError, RebasedView, RebasedViewMut};
+#[cfg(feature = "std")]
+pub use records::ImportError;
+pub use reserve::RegionKind;
+#[cfg(feature = "rkyv")]
+pub use rkyv_impl::ArchivedSparseVec;
+pub use runs::RunLimitExceeded;
+pub use set::SparseSet;
+#[cfg(feature = "std")]
+pub use shared::SharedSparseVec;
+pub use signature::{Signature, SignatureError};
+#[cfg(feature = "std")]
+pub use stats::ByteStats;
+pub use tagged::TaggedSparseVec;
+pub use view::{SparseView, SparseViewMut, ViewError};
+pub use watch::{WatchHit, WatchId, WatchOp};
 
-#[derive(Default, Debug)]
-pub struct SparseVec<T> {
-    map: RangeMap<u64, usize>,
-    data: HashMap<usize, (Range<u64>, Vec<T>)>,
+use journal::JournalOp;
+use keys::KeyMap;
+use storage::Storage;
+
+pub struct SparseVec<T, A = u64> {
+    map: last_hit::BlockMap<A>,
+    data: KeyMap<Storage<T>>,
     key_counter: usize,
+    watch: watch::Watchpoints<A>,
+    history: history::History<T, A>,
+    marks: marks::Marks<A>,
+    frozen: RangeSet<A>,
+    block_align: Option<usize>,
+    max_block_len: Option<u64>,
+    auto_merge: bool,
+    push_origin: A,
+    mirrors: RangeMap<A, mirror::Mirror<A>>,
+    reserved: RangeSet<A>,
+    holes: Option<holes::Holes<T>>,
+    dedup: Option<dedup::DedupIndex<T>>,
+    capacity: Option<capacity::Capacity<T, A>>,
+    // The element at `A::MAX`, which no block range can end after
+    top: Option<T>,
+    #[cfg(feature = "std")]
+    journal: Option<journal::Journal<T>>,
+    #[cfg(feature = "compress")]
+    compression: Option<compress::Compression>,
+}
+
+impl<T, A: Address> Default for SparseVec<T, A> {
+    fn default() -> Self {
+        Self {
+            map: Default::default(),
+            data: KeyMap::default(),
+            key_counter: 0,
+            watch: Default::default(),
+            history: Default::default(),
+            marks: Default::default(),
+            frozen: RangeSet::new(),
+            block_align: None,
+            max_block_len: None,
+            auto_merge: true,
+            push_origin: A::ZERO,
+            mirrors: RangeMap::new(),
+            reserved: RangeSet::new(),
+            holes: None,
+            dedup: None,
+            capacity: None,
+            top: None,
+            #[cfg(feature = "std")]
+            journal: None,
+            #[cfg(feature = "compress")]
+            compression: None,
+        }
+    }
 }
 
-impl<T: Copy> SparseVec<T> {
+impl<T> SparseVec<T> {
+    /// Creates an empty `SparseVec` with `u64` addresses. Use [`Default`] for other address types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
     fn assert_invariants(&self) {
         for (range, key) in self.map.iter() {
-            let (old_range, vec) = &self.data[key];
-            assert_eq!(range, old_range);
-            assert_eq!(vec.len(), (range.end - range.start) as usize);
+            assert_eq!(self.data[key].len(), (range.end - range.start).to_usize());
         }
         let mut duplicates = HashMap::new();
         for (range, key) in self.map.iter() {
@@ -26,140 +227,952 @@ impl<T: Copy> SparseVec<T> {
                 duplicates.insert(*key, range.clone());
             }
         }
+        assert_eq!(self.data.len(), self.map.len(), "data of unmapped blocks");
     }
 
-    fn resize_block(
-        data: &mut HashMap<usize, (Range<u64>, Vec<T>)>,
-        key: &usize,
-        range: &Range<u64>,
-    ) {
-        let (old_range, vec) = data.get_mut(key).unwrap();
-        let new_vec_range: Range<usize> = cast_range(sub_range(range, old_range.start));
-        if new_vec_range.start != 0 {
-            vec.copy_within(new_vec_range.clone(), 0);
-        }
-        vec.truncate(new_vec_range.end - new_vec_range.start);
-        *old_range = range.clone();
+    // Cuts the data of a block that was at `old` down to `range`
+    fn resize_block(data: &mut KeyMap<Storage<T>>, key: &usize, old: &Range<A>, range: &Range<A>) {
+        let vec = data.get_mut(key).unwrap();
+        vec.trim(cast_range(sub_range(range, old.start)));
     }
 
-    fn collect_garbage(&mut self) {
-        let mut used = HashSet::with_capacity(self.map.len());
-        used.extend(self.map.iter().map(|(_, k)| *k));
-        self.data.retain(|k, _| used.contains(k));
+    // Unmaps the block at exactly `range` and drops its data
+    fn remove_block(&mut self, range: Range<A>) {
+        trace::event!(
+            TRACE,
+            block_start = %trace::Hex(range.start),
+            block_end = %trace::Hex(range.end),
+            "overwritten"
+        );
+        let key = *self.map.get(&range.start).unwrap();
+        self.map.remove(range);
+        self.data.remove(&key);
     }
 
-    pub fn get(&self, range: Range<u64>) -> Option<&[T]> {
-        let (found_range, key) = self.map.get_key_value(&range.start)?;
+    /// The stored data of `range`, `None` unless it lies within a single block. An unbounded
+    /// end is the end of the block containing the start, an unbounded start the lowest
+    /// stored address.
+    pub fn get(&self, range: impl RangeBounds<A>) -> Option<&[T]> {
+        let range = self.resolve_range_with(&range, |start| {
+            self.map
+                .get_key_value(&start)
+                .map_or(start, |(block, _)| block.end)
+        });
+        let range = self.unmirror(range)?;
+        let (found_range, key) = self.find_block(range.start)?;
         let slice_range = sub_range(&range, found_range.start);
-        self.data[key].1.get(cast_range(slice_range))
+        let slice = self.data[&key].get(cast_range(slice_range))?;
+        self.touch(key);
+        Some(slice)
     }
 
-    pub fn get_mut(&mut self, range: Range<u64>) -> Option<&mut [T]> {
-        let (found_range, key) = self.map.get_key_value(&range.start)?;
-        let slice_range = sub_range(&range, found_range.start);
-        self.data
-            .get_mut(key)
-            .unwrap()
-            .1
-            .get_mut(cast_range(slice_range))
+    /// Like [`SparseVec::get`], but reports which part of `range` is missing. Empty ranges are
+    /// always readable.
+    pub fn try_get(&self, range: Range<A>) -> Result<&[T], ReadError<A>> {
+        if range.is_empty() {
+            return Ok(&[]);
+        }
+        let Some((found_range, key)) = self.find_block(range.start) else {
+            let next = self.map.overlapping(range.clone()).next();
+            let end = next.map_or(range.end, |(block, _)| block.start);
+            return Err(ReadError::StartUnmapped {
+                gap: range.start..end,
+            });
+        };
+        if found_range.end < range.end {
+            return Err(ReadError::TruncatedAt {
+                first_missing: found_range.end,
+                available: found_range.end - range.start,
+            });
+        }
+        Ok(&self.data[&key][cast_range(sub_range(&range, found_range.start))])
     }
 
-    pub fn overlaps(&self, range: &Range<u64>) -> bool {
+    /// Stops a [journal](SparseVec::enable_journal), which cannot record changes through the
+    /// slice.
+    pub fn get_mut(&mut self, range: Range<A>) -> Option<&mut [T]> {
+        let (key, slice_range) = self.find_mut(range)?;
+        self.stop_journal("get_mut");
+        Some(&mut self.data.get_mut(&key).unwrap()[slice_range])
+    }
+
+    // The block key and slice range of `range` for a mutable borrow, after the checks and
+    // bookkeeping of `get_mut` except for the journal
+    pub(crate) fn find_mut(&mut self, range: Range<A>) -> Option<(usize, Range<usize>)> {
+        let range = self.unmirror(range)?;
+        let (found_range, key) = self.find_block(range.start)?;
+        if range.end > found_range.end || range.start > range.end {
+            return None;
+        }
+        self.assert_thawed(&range);
+        self.record_history(&range);
+        self.watch.notify(&range, WatchOp::GetMut);
+        self.touch(key);
+        Some((key, cast_range(sub_range(&range, found_range.start))))
+    }
+
+    /// The whole block containing `addr` with its range, `None` if `addr` is unmapped.
+    pub fn get_block(&self, addr: A) -> Option<(Range<A>, &[T])> {
+        let (range, key) = self.find_block(addr)?;
+        self.touch(key);
+        Some((range, &self.data[&key]))
+    }
+
+    /// Mutable variant of [`SparseVec::get_block`], treated like [`SparseVec::get_mut`] over
+    /// the whole block.
+    pub fn get_block_mut(&mut self, addr: A) -> Option<(Range<A>, &mut [T])> {
+        let range = self.map.get_key_value(&addr)?.0.clone();
+        let slice = self.get_mut(range.clone())?;
+        Some((range, slice))
+    }
+
+    pub fn overlaps(&self, range: &Range<A>) -> bool {
         self.map.overlaps(range)
     }
 
-    pub fn insert(&mut self, data: Vec<T>, addr: u64) {
-        if data.is_empty() {
+    /// Stores `data` at `addr..addr + data.len()`, overwriting stored values.
+    ///
+    /// Data may end at `A::MAX` itself. No `Range<A>` ends after it, so that last element is
+    /// kept apart from the blocks and only the methods taking inclusive ranges,
+    /// `read_into_exact`, `write`, `compare_range` and `map_values` see it.
+    ///
+    /// Panics if the data does not fit in the address space.
+    pub fn insert(&mut self, data: Vec<T>, addr: A) {
+        if !data.is_empty() {
+            let addr = self.wrap_past_end(addr, data.len());
+            let end = A::try_from_u64(data.len() as u64).and_then(|len| addr.checked_add(len));
+            let Some(end) = end else {
+                return self.insert_through_max(data, addr);
+            };
+            let range = addr..end;
+            if let Some(pieces) = self.mirrored(&range) {
+                let mut offset = 0;
+                for piece in pieces {
+                    let len = (piece.end - piece.start).to_usize();
+                    self.insert(data[offset..offset + len].to_vec(), piece.start);
+                    offset += len;
+                }
+                return;
+            }
+            self.assert_thawed(&range);
+            self.journal(JournalOp::Insert, addr, data.len() as u64, &data);
+            self.watch.notify(&range, WatchOp::Insert);
+            self.record_history(&range);
+            if self.dedup.is_some() && self.block_align.is_none() {
+                self.insert_deduped(data, addr);
+            } else {
+                self.insert_unwatched(data, addr);
+            }
+        }
+    }
+
+    /// Sets every address in `range` to `value`, mapping gaps.
+    pub fn fill(&mut self, range: Range<A>, value: T) {
+        if let Some(pieces) = self.mirrored(&range) {
+            for piece in pieces {
+                self.fill(piece, value);
+            }
             return;
         }
+        if !range.is_empty() {
+            self.assert_thawed(&range);
+            trace::event!(
+                DEBUG,
+                start = %trace::Hex(range.start),
+                end = %trace::Hex(range.end),
+                "fill"
+            );
+            let len = (range.end - range.start).to_u64();
+            self.journal(JournalOp::Fill, range.start, len, &[value]);
+            self.watch.notify(&range, WatchOp::Fill);
+            self.record_history(&range);
+            self.insert_unwatched(
+                vec![value; (range.end - range.start).to_usize()],
+                range.start,
+            );
+        }
+    }
 
-        let insert_range = addr..addr + data.len() as u64;
+    /// Sets every unmapped address in `range` to `value`, keeping the stored values.
+    pub fn fill_gaps(&mut self, range: Range<A>, value: T) {
+        if self.contains_range(&range) {
+            return;
+        }
+        let gaps = Vec::from_iter(self.gaps(range.clone()));
+        for gap in &gaps {
+            self.assert_thawed(gap);
+        }
+        let len = (range.end - range.start).to_u64();
+        self.journal(JournalOp::FillGaps, range.start, len, &[value]);
+        for gap in &gaps {
+            trace::event!(
+                DEBUG,
+                start = %trace::Hex(gap.start),
+                end = %trace::Hex(gap.end),
+                "fill gap"
+            );
+            self.watch.notify(gap, WatchOp::Fill);
+        }
+        self.record_history_in(&gaps);
+        // Each gap merges with its neighbours like an append, so their blocks survive
+        for gap in gaps {
+            self.insert_unwatched(vec![value; (gap.end - gap.start).to_usize()], gap.start);
+        }
+    }
+
+    /// Mutable slice over exactly `range`, filling gaps in it with `default` first.
+    pub fn get_mut_or_fill(&mut self, range: Range<A>, default: T) -> &mut [T] {
+        if range.is_empty() {
+            return &mut [];
+        }
+        self.fill_gaps(range.clone(), default);
+        // Gaps next to shared or unmergeable blocks stay blocks of their own
+        if self.get(range.clone()).is_none() {
+            let data = Vec::from_iter(self.iter_range(range.clone()).map(|(_, v)| *v));
+            self.insert(data, range.start);
+        }
+        self.get_mut(range).unwrap()
+    }
+
+    /// Read-modify-write of `range`. `f` receives the stored data if [`SparseVec::get_mut`]
+    /// would return it, so partially covered ranges are passed as `None`. Data returned by
+    /// `f` is then inserted at `range.start`.
+    pub fn update(&mut self, range: Range<A>, f: impl FnOnce(Option<&mut [T]>) -> Option<Vec<T>>) {
+        let start = range.start;
+        if let Some(data) = f(self.get_mut(range)) {
+            self.insert(data, start);
+        }
+    }
+
+    /// Removes every block for which `f` returns false, as yielded by [`SparseVec::blocks`].
+    /// Blocks are never split, the others stay untouched.
+    ///
+    /// Panics if a removed block is frozen.
+    pub fn retain_blocks(&mut self, mut f: impl FnMut(&Range<A>, &[T]) -> bool) {
+        let removed = Vec::from_iter(
+            self.map
+                .iter()
+                .filter(|(range, key)| !f(range, &self.data[*key]))
+                .map(|(range, _)| range.clone()),
+        );
+        for range in &removed {
+            self.assert_thawed(range);
+        }
+        for range in removed {
+            trace::event!(
+                DEBUG,
+                start = %trace::Hex(range.start),
+                end = %trace::Hex(range.end),
+                "remove block"
+            );
+            let len = (range.end - range.start).to_u64();
+            self.journal(JournalOp::Remove, range.start, len, &[]);
+            self.record_history(&range);
+            self.remove_block(range);
+        }
+    }
+
+    fn insert_unwatched(&mut self, data: Vec<T>, addr: A) {
+        let range = addr..addr + A::from_usize(data.len());
+        let holes = self.hole_runs(&data);
+        if holes.is_empty() {
+            self.insert_storage(Storage::new(data, self.block_align), addr);
+            return self.enforce_capacity(&range);
+        }
+        let mut offset = 0;
+        for hole in holes
+            .into_iter()
+            .chain(core::iter::once(data.len()..data.len()))
+        {
+            if offset < hole.start {
+                let part = data[offset..hole.start].to_vec();
+                self.insert_storage(
+                    Storage::new(part, self.block_align),
+                    addr + A::from_usize(offset),
+                );
+            }
+            self.remove_unwatched(addr + A::from_usize(hole.start)..addr + A::from_usize(hole.end));
+            offset = hole.end;
+        }
+        self.enforce_capacity(&range);
+    }
+
+    fn insert_storage(&mut self, data: Storage<T>, addr: A) {
+        let Some(max) = self.max_block_len else {
+            return self.insert_block(data, addr);
+        };
+        // Blocks never cross a multiple of the limit
+        let first_len = (max - addr.to_u64() % max).min(usize::MAX as u64) as usize;
+        if data.len() <= first_len {
+            return self.insert_block(data, addr);
+        }
+        let chunk_len = max.min(usize::MAX as u64) as usize;
+        let mut offset = 0;
+        let mut len = first_len;
+        while offset < data.len() {
+            let end = data.len().min(offset + len);
+            let chunk = data.slice(offset..end, self.block_align);
+            self.insert_block(chunk, addr + A::from_usize(offset));
+            offset = end;
+            len = chunk_len;
+        }
+    }
+
+    fn insert_block(&mut self, data: Storage<T>, addr: A) {
+        let insert_range = addr..addr + A::from_usize(data.len());
+        trace::event!(
+            DEBUG,
+            start = %trace::Hex(insert_range.start),
+            end = %trace::Hex(insert_range.end),
+            blocks = self.map.len(),
+            "insert"
+        );
+        if !self.reserved.is_empty() {
+            self.reserved.remove(insert_range.clone());
+        }
+
+        // Appending right after the highest block only has to extend it
+        if let Some((last, &key)) = self.map.last_range_value() {
+            if last.end == addr
+                && self.auto_merge
+                && self.same_window(last.start, insert_range.end)
+                && !data.is_shared()
+                && !self.data[&key].is_shared()
+            {
+                let range = last.start..insert_range.end;
+                self.data.get_mut(&key).unwrap().extend_from_slice(&data);
+                self.map.insert(range, key);
+                return;
+            }
+        }
 
         let start_key = self.map.get(&insert_range.start);
         // Will create duplicate key
         if let Some(&key) = start_key {
             if start_key == self.map.get(&insert_range.end) {
-                let (range, vec) = self.data.get(&key).unwrap();
+                let (range, _) = self.map.get_key_value(&insert_range.start).unwrap();
                 let range = range.clone();
                 let lower_range = range.start..insert_range.start;
                 let upper_range = insert_range.end..range.end;
+                trace::event!(
+                    TRACE,
+                    block_start = %trace::Hex(range.start),
+                    block_end = %trace::Hex(range.end),
+                    "split"
+                );
 
                 if !upper_range.is_empty() {
-                    self.map.insert(upper_range.clone(), self.key_counter);
                     let copy_range = sub_range(&upper_range, range.start);
-                    self.data.insert(
-                        self.key_counter,
-                        (upper_range, vec[cast_range(copy_range)].to_vec()),
-                    );
+                    let upper = self.data[&key].slice(cast_range(copy_range), self.block_align);
+                    self.map.insert(upper_range, self.key_counter);
+                    self.data.insert(self.key_counter, upper);
                     self.key_counter += 1;
                 }
 
+                // Removing the inserted part leaves the lower part under `key`. Without one
+                // the block is overwritten below.
                 if !lower_range.is_empty() {
-                    Self::resize_block(&mut self.data, &key, &lower_range);
-                    self.map.insert(lower_range, key);
+                    self.map.remove(insert_range.clone());
+                    Self::resize_block(&mut self.data, &key, &range, &lower_range);
                 }
             }
         }
 
+        // Blocks the insert overlaps, at most one of them starts before or ends after it
+        let overlapped = Vec::from_iter(
+            self.map
+                .overlapping(&insert_range)
+                .map(|(range, key)| (range.clone(), *key)),
+        );
+
         // Insert
         self.map.insert(insert_range.clone(), self.key_counter);
-        self.data.insert(self.key_counter, (insert_range, data));
+        self.data.insert(self.key_counter, data);
         self.key_counter += 1;
 
         // Resize
-        for (range, key) in self.map.iter() {
-            Self::resize_block(&mut self.data, key, range);
+        let mut overwritten = Vec::new();
+        for (old, key) in overlapped {
+            let range = if old.start < insert_range.start {
+                old.start..insert_range.start
+            } else if old.end > insert_range.end {
+                insert_range.end..old.end
+            } else {
+                overwritten.push((old, key));
+                continue;
+            };
+            trace::event!(
+                TRACE,
+                block_start = %trace::Hex(old.start),
+                block_end = %trace::Hex(old.end),
+                start = %trace::Hex(range.start),
+                end = %trace::Hex(range.end),
+                "trimmed"
+            );
+            Self::resize_block(&mut self.data, &key, &old, &range);
         }
 
         // Merge
         loop {
             let mut mergable = None;
-            for ((range, _), (range2, _)) in self.map.iter().tuple_windows() {
-                if range.end == range2.start {
-                    mergable = Some((range.clone(), range2.clone()));
+            for ((range, key), (range2, key2)) in self.map.iter().tuple_windows() {
+                if range.end == range2.start
+                    && self.auto_merge
+                    && self.same_window(range.start, range2.end)
+                    && !self.data[key].is_shared()
+                    && !self.data[key2].is_shared()
+                {
+                    mergable = Some((range.clone(), *key, range2.clone(), *key2));
                     break;
                 }
             }
 
-            if let Some((range, range2)) = mergable {
-                let key1 = *self.map.get(&range.start).unwrap();
-                let key2 = *self.map.get(&range2.start).unwrap();
-
-                let (_, vec2) = self.data.remove(&key2).unwrap();
+            if let Some((range, key1, range2, key2)) = mergable {
+                trace::event!(
+                    TRACE,
+                    start = %trace::Hex(range.start),
+                    at = %trace::Hex(range2.start),
+                    end = %trace::Hex(range2.end),
+                    "merged"
+                );
+                let vec2 = self.data.remove(&key2).unwrap();
                 self.map.remove(range2.clone());
-                let (data_range, vec1) = self.data.get_mut(&key1).unwrap();
-                *data_range = range.start..range2.end;
-                vec1.extend_from_slice(&vec2);
-                self.map.insert(data_range.clone(), key1);
+                self.data.get_mut(&key1).unwrap().extend_from_slice(&vec2);
+                self.map.insert(range.start..range2.end, key1);
             } else {
                 break;
             }
         }
 
-        self.collect_garbage();
+        for (_range, key) in overwritten {
+            trace::event!(
+                TRACE,
+                block_start = %trace::Hex(_range.start),
+                block_end = %trace::Hex(_range.end),
+                "overwritten"
+            );
+            self.data.remove(&key);
+        }
+        trace::event!(DEBUG, blocks = self.map.len(), "inserted");
 
         #[cfg(debug_assertions)]
         self.assert_invariants();
     }
 
-    pub fn ranges(&self) -> impl Iterator<Item = Range<u64>> + '_ {
-        self.map.iter().map(|(range, _)| range.clone())
+    pub fn stored_len(&self) -> usize {
+        self.map.iter().map(|(_, k)| self.data[k].len()).sum()
     }
 
-    pub fn stored_len(&self) -> usize {
-        self.map.iter().map(|(_, k)| self.data[k].1.len()).sum()
+    /// Fills `buf` with the data starting at `addr`. Fails without touching `buf` if any
+    /// part of the range is unmapped.
+    pub fn read_into_exact(&self, addr: A, buf: &mut [T]) -> Result<(), Unmapped<A>> {
+        if buf.is_empty() {
+            return Ok(());
+        }
+        let addr = self.wrap_past_end(addr, buf.len());
+        let Some(end) = A::try_from_u64(buf.len() as u64).and_then(|len| addr.checked_add(len))
+        else {
+            return self.read_through_max(addr, buf);
+        };
+        let range = addr..end;
+        if let Some(pieces) = self.mirrored(&range) {
+            self.first_unmapped_mirrored(addr, &pieces)?;
+            let mut offset = 0;
+            for piece in pieces {
+                let len = (piece.end - piece.start).to_usize();
+                self.read_into_exact(piece.start, &mut buf[offset..offset + len])?;
+                offset += len;
+            }
+            return Ok(());
+        }
+        if let Some(addr) = self.first_unmapped(&range) {
+            return Err(Unmapped { addr });
+        }
+        let mut offset = 0;
+        for (_, slice) in self.slices(range) {
+            buf[offset..offset + slice.len()].copy_from_slice(slice);
+            offset += slice.len();
+        }
+        Ok(())
+    }
+
+    /// The contents of `range`, with `default` in the gaps. Empty for reversed ranges.
+    pub fn read_or(&self, range: Range<A>, default: T) -> Vec<T> {
+        if range.start >= range.end {
+            return Vec::new();
+        }
+        let len = (range.end - range.start).to_usize();
+        let mut result = Vec::with_capacity(len);
+        for (clipped, slice) in self.slices(range.clone()) {
+            result.resize((clipped.start - range.start).to_usize(), default);
+            result.extend_from_slice(slice);
+        }
+        result.resize(len, default);
+        result
+    }
+
+    /// Overwrites stored data starting at `addr`. Only writes through existing coverage; if any
+    /// part of the destination is unmapped nothing is written.
+    pub fn write(&mut self, addr: A, data: &[T]) -> Result<(), Unmapped<A>> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let addr = self.wrap_past_end(addr, data.len());
+        let Some(end) = A::try_from_u64(data.len() as u64).and_then(|len| addr.checked_add(len))
+        else {
+            return self.write_through_max(addr, data);
+        };
+        let range = addr..end;
+        if let Some(pieces) = self.mirrored(&range) {
+            self.first_unmapped_mirrored(addr, &pieces)?;
+            let mut offset = 0;
+            for piece in pieces {
+                let len = (piece.end - piece.start).to_usize();
+                self.write(piece.start, &data[offset..offset + len])?;
+                offset += len;
+            }
+            return Ok(());
+        }
+        self.assert_thawed(&range);
+        if let Some(addr) = self.first_unmapped(&range) {
+            return Err(Unmapped { addr });
+        }
+        self.journal(JournalOp::Write, addr, data.len() as u64, data);
+        self.record_history(&range);
+
+        let mut offset = 0;
+        for (block, key) in self.map.overlapping(&range) {
+            let clipped = clip_range(block, &range);
+            let len = (clipped.end - clipped.start).to_usize();
+            let vec = self.data.get_mut(key).unwrap();
+            vec[cast_range(sub_range(&clipped, block.start))]
+                .copy_from_slice(&data[offset..offset + len]);
+            offset += len;
+        }
+        self.watch.notify(&range, WatchOp::Write);
+        Ok(())
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    // Bulk constructor. Blocks must be non-empty, sorted and non-overlapping.
+    // Adjacent blocks are merged so the result is identical to inserting them one by one.
+    fn from_sorted_blocks(blocks: impl IntoIterator<Item = (A, Vec<T>)>) -> Self {
+        let mut vec = Self::default();
+        let mut current: Option<(Range<A>, Vec<T>)> = None;
+        for (start, data) in blocks {
+            debug_assert!(!data.is_empty());
+            let range = start..start + A::from_usize(data.len());
+            match &mut current {
+                Some((current_range, current_data)) if current_range.end == start => {
+                    current_range.end = range.end;
+                    current_data.extend(data);
+                }
+                _ => {
+                    debug_assert!(current.as_ref().is_none_or(|(r, _)| r.end < start));
+                    if let Some(block) = current.replace((range, data)) {
+                        vec.push_block(block);
+                    }
+                }
+            }
+        }
+        if let Some(block) = current {
+            vec.push_block(block);
+        }
+        vec
+    }
+
+    fn push_block(&mut self, (range, data): (Range<A>, Vec<T>)) {
+        self.map.insert(range, self.key_counter);
+        self.data
+            .insert(self.key_counter, Storage::new(data, self.block_align));
+        self.key_counter += 1;
+    }
+
+    pub fn ranges(&self) -> Ranges<'_, A> {
+        Ranges {
+            map: self.map.iter(),
+            len: self.map.len(),
+        }
+    }
+
+    /// The range from the lowest to the highest stored address, `None` if empty.
+    pub fn bounds(&self) -> Option<Range<A>> {
+        let (first, _) = self.map.first_range_value()?;
+        let (last, _) = self.map.last_range_value()?;
+        Some(first.start..last.end)
+    }
+
+    /// Stored data overlapping `range`, clipped to it, in address order.
+    pub fn slices(
+        &self,
+        range: impl RangeBounds<A>,
+    ) -> impl Iterator<Item = (Range<A>, &[T])> + '_ {
+        let range = self.resolve_range(&range);
+        self.map
+            .overlapping(range.clone())
+            .map(move |(block, key)| {
+                let clipped = clip_range(block, &range);
+                let slice = &self.data[key][cast_range(sub_range(&clipped, block.start))];
+                (clipped, slice)
+            })
+            .filter(|(clipped, _)| !clipped.is_empty())
+    }
+
+    /// All stored elements within `range` with their addresses. Gaps are skipped.
+    pub fn iter_range(&self, range: impl RangeBounds<A>) -> impl Iterator<Item = (A, &T)> + '_ {
+        self.slices(range).flat_map(|(clipped, slice)| {
+            slice
+                .iter()
+                .enumerate()
+                .map(move |(i, v)| (clipped.start + A::from_usize(i), v))
+        })
+    }
+
+    /// New `SparseVec` holding a copy of the data inside `range` at the same addresses.
+    pub fn clone_range(&self, range: Range<A>) -> Self
+    where
+        T: Clone,
+    {
+        Self::from_sorted_blocks(
+            self.slices(range)
+                .map(|(clipped, slice)| (clipped.start, slice.to_vec())),
+        )
+    }
+
+    /// Whether every address in `range` is stored. True for empty ranges.
+    pub fn contains_range<R: RangeBounds<A>>(&self, range: &R) -> bool {
+        self.first_unmapped(&self.resolve_range(range)).is_none()
+    }
+
+    /// Stored ranges overlapping `range`, clipped to it, in address order. Like
+    /// [`SparseVec::slices`] without the data.
+    pub fn ranges_in(&self, range: impl RangeBounds<A>) -> impl Iterator<Item = Range<A>> + '_ {
+        let range = self.resolve_range(&range);
+        self.map
+            .overlapping(range.clone())
+            .map(move |(block, _)| clip_range(block, &range))
+            .filter(|clipped| !clipped.is_empty())
+    }
+
+    /// Unmapped parts of `range`, in address order. Reserved addresses count as unmapped,
+    /// see [`SparseVec::unreserved_gaps`].
+    pub fn gaps(&self, range: impl RangeBounds<A>) -> impl Iterator<Item = Range<A>> + '_ {
+        let range = self.resolve_range(&range);
+        let (mut next, end) = (range.start, range.end);
+        self.map
+            .overlapping(range)
+            .map(|(block, _)| block.clone())
+            .chain(core::iter::once(end..end))
+            .filter_map(move |block| {
+                let gap = next..block.start.min(end);
+                next = next.max(block.end);
+                (!gap.is_empty()).then_some(gap)
+            })
+    }
+
+    // `range` as a half-open range, unbounded ends resolved against `bounds()`
+    fn resolve_range(&self, range: &impl RangeBounds<A>) -> Range<A> {
+        self.resolve_range_with(range, |_| {
+            self.bounds().map_or(A::ZERO, |bounds| bounds.end)
+        })
+    }
+
+    // Like `resolve_range`, with `unbounded_end` computing the end from the start. The end
+    // never lies before the start, so reversed ranges resolve to empty ones.
+    fn resolve_range_with(
+        &self,
+        range: &impl RangeBounds<A>,
+        unbounded_end: impl FnOnce(A) -> A,
+    ) -> Range<A> {
+        let one = A::from_usize(1);
+        let start = match range.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => match start.checked_add(one) {
+                Some(start) => start,
+                None => return A::MAX..A::MAX,
+            },
+            Bound::Unbounded => self.bounds().map_or(A::ZERO, |bounds| bounds.start),
+        };
+        let end = match range.end_bound() {
+            // `A::MAX` itself is kept apart from the blocks, so no range reaches it
+            Bound::Included(&end) => end.checked_add(one).unwrap_or(A::MAX),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => unbounded_end(start),
+        };
+        start..end.max(start)
+    }
+
+    // First unmapped address from `addr` on, or `A::MAX` if everything before it is stored
+    fn unmapped_past_end(&self, addr: A) -> Unmapped<A> {
+        let range = addr..A::MAX;
+        let first = match self.mirrored(&range) {
+            Some(pieces) => self.first_unmapped_mirrored(addr, &pieces).err(),
+            None => self.first_unmapped(&range).map(|addr| Unmapped { addr }),
+        };
+        first.unwrap_or(Unmapped { addr: A::MAX })
+    }
+
+    // First address of `range` that is not covered
+    fn first_unmapped(&self, range: &Range<A>) -> Option<A> {
+        let mut next = range.start;
+        for (block, _) in self.map.overlapping(range) {
+            if block.start > next {
+                return Some(next);
+            }
+            next = block.end;
+        }
+        (next < range.end).then_some(next)
+    }
+
+    pub fn blocks(&self) -> Blocks<'_, T, A> {
+        Blocks {
+            map: self.map.iter(),
+            data: &self.data,
+            len: self.map.len(),
+        }
+    }
+
+    /// Stops a [journal](SparseVec::enable_journal), like [`SparseVec::get_mut`].
+    pub fn blocks_mut(&mut self) -> BlocksMut<'_, T, A> {
+        self.stop_journal("blocks_mut");
+        let mut ranges =
+            KeyMap::from_iter(self.map.iter().map(|(range, key)| (*key, range.clone())));
+        let mut blocks = Vec::from_iter(
+            self.data
+                .iter_mut()
+                .map(|(key, vec)| (ranges.remove(key).unwrap(), &mut vec[..])),
+        );
+        blocks.sort_unstable_by_key(|(range, _)| range.start);
+        BlocksMut {
+            inner: blocks.into_iter(),
+        }
+    }
+}
+
+const DEBUG_BLOCK_ELEMENTS: usize = 16;
+
+impl<T: fmt::Debug, A: Address> fmt::Debug for SparseVec<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alternate = f.alternate();
+        let mut s = f.debug_struct("SparseVec");
+        s.field("blocks", &self.map.len());
+        s.field(
+            "stored",
+            &self.blocks().map(|(_, d)| d.len()).sum::<usize>(),
+        );
+        s.field("ranges", &DebugRanges(self));
+        if alternate {
+            s.field("data", &DebugData(self));
+        }
+        s.finish()
+    }
+}
+
+struct DebugRange<'a, A>(&'a Range<A>);
+
+impl<A: Address> fmt::Debug for DebugRange<'_, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}..{:#x}", self.0.start, self.0.end)
+    }
+}
+
+struct DebugRanges<'a, T, A>(&'a SparseVec<T, A>);
+
+impl<T, A: Address> fmt::Debug for DebugRanges<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.0.map.iter().map(|(range, _)| DebugRange(range)))
+            .finish()
+    }
+}
+
+struct DebugData<'a, T, A>(&'a SparseVec<T, A>);
+
+impl<T: fmt::Debug, A: Address> fmt::Debug for DebugData<'_, T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.0
+                    .map
+                    .iter()
+                    .map(|(range, key)| (DebugRange(range), DebugElements(&self.0.data[key]))),
+            )
+            .finish()
+    }
+}
+
+// Always printed on a single line, integers in hex. Long blocks are truncated.
+struct DebugElements<'a, T>(&'a [T]);
+
+impl<T: fmt::Debug> fmt::Debug for DebugElements<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[")?;
+        for (i, v) in self.0.iter().take(DEBUG_BLOCK_ELEMENTS).enumerate() {
+            if i != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{v:02x?}")?;
+        }
+        if self.0.len() > DEBUG_BLOCK_ELEMENTS {
+            write!(f, ", ... (+{} more)", self.0.len() - DEBUG_BLOCK_ELEMENTS)?;
+        }
+        f.write_str("]")
+    }
+}
+
+pub struct Ranges<'a, A = u64> {
+    map: rangemap::map::Iter<'a, A, usize>,
+    len: usize,
+}
+
+impl<A: Address> Iterator for Ranges<'_, A> {
+    type Item = Range<A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (range, _) = self.map.next()?;
+        self.len -= 1;
+        Some(range.clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<A: Address> DoubleEndedIterator for Ranges<'_, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (range, _) = self.map.next_back()?;
+        self.len -= 1;
+        Some(range.clone())
+    }
+}
+
+impl<A: Address> ExactSizeIterator for Ranges<'_, A> {}
+
+pub struct Blocks<'a, T, A = u64> {
+    map: rangemap::map::Iter<'a, A, usize>,
+    data: &'a KeyMap<Storage<T>>,
+    len: usize,
+}
+
+impl<'a, T, A: Address> Iterator for Blocks<'a, T, A> {
+    type Item = (Range<A>, &'a [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (range, key) = self.map.next()?;
+        self.len -= 1;
+        Some((range.clone(), &self.data[key]))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
     }
 }
 
-fn sub_range(range: &Range<u64>, offset: u64) -> Range<u64> {
+impl<T, A: Address> DoubleEndedIterator for Blocks<'_, T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (range, key) = self.map.next_back()?;
+        self.len -= 1;
+        Some((range.clone(), &self.data[key]))
+    }
+}
+
+impl<T, A: Address> ExactSizeIterator for Blocks<'_, T, A> {}
+
+pub struct BlocksMut<'a, T, A = u64> {
+    inner: alloc::vec::IntoIter<(Range<A>, &'a mut [T])>,
+}
+
+impl<'a, T, A> Iterator for BlocksMut<'a, T, A> {
+    type Item = (Range<A>, &'a mut [T]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T, A> DoubleEndedIterator for BlocksMut<'_, T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl<T, A> ExactSizeIterator for BlocksMut<'_, T, A> {}
+
+pub struct IntoIter<T, A = u64> {
+    map: rangemap::map::IntoIter<A, usize>,
+    data: KeyMap<Storage<T>>,
+}
+
+impl<T, A: Address> Iterator for IntoIter<T, A> {
+    type Item = (A, Vec<T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (range, key) = self.map.next()?;
+        let vec = self.data.remove(&key).unwrap();
+        Some((range.start, vec.into_vec()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.data.len(), Some(self.data.len()))
+    }
+}
+
+impl<T, A: Address> DoubleEndedIterator for IntoIter<T, A> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        let (range, key) = self.map.next_back()?;
+        let vec = self.data.remove(&key).unwrap();
+        Some((range.start, vec.into_vec()))
+    }
+}
+
+impl<T, A: Address> ExactSizeIterator for IntoIter<T, A> {}
+
+impl<T, A: Address> IntoIterator for SparseVec<T, A> {
+    type Item = (A, Vec<T>);
+    type IntoIter = IntoIter<T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            map: self.map.into_ranges().into_iter(),
+            data: self.data,
+        }
+    }
+}
+
+impl<'a, T, A: Address> IntoIterator for &'a SparseVec<T, A> {
+    type Item = (Range<A>, &'a [T]);
+    type IntoIter = Blocks<'a, T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.blocks()
+    }
+}
+
+impl<'a, T, A: Address> IntoIterator for &'a mut SparseVec<T, A> {
+    type Item = (Range<A>, &'a mut [T]);
+    type IntoIter = BlocksMut<'a, T, A>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.blocks_mut()
+    }
+}
+
+fn sub_range<A: Address>(range: &Range<A>, offset: A) -> Range<A> {
     range.start - offset..range.end - offset
 }
 
-fn cast_range<I, O: TryFrom<I>>(range: Range<I>) -> Range<O>
-where
-    O::Error: std::fmt::Debug,
-{
-    range.start.try_into().unwrap()..range.end.try_into().unwrap()
+fn clip_range<A: Address>(range: &Range<A>, window: &Range<A>) -> Range<A> {
+    range.start.max(window.start)..range.end.min(window.end)
+}
+
+fn cast_range<A: Address>(range: Range<A>) -> Range<usize> {
+    range.start.to_usize()..range.end.to_usize()
 }
 
 #[test]
@@ -177,27 +1190,68 @@ fn sparsevec() {
     insert_test(4, 5, 5);
 }
 
-#[test]
-fn sparsevec_fuzz() {
+#[cfg(test)]
+fn fuzz<A: Address>() {
     use rand::{Rng, SeedableRng};
 
-    let mut map = SparseVec::default();
-    let mut insert_test = |n: u8, size: usize, addr: u64| {
+    // Compresses nearly every block that is not read right after its insert
+    #[cfg(feature = "compress")]
+    let mut map = SparseVec::<u8, A>::with_compression(16);
+    #[cfg(not(feature = "compress"))]
+    let mut map = SparseVec::<u8, A>::default();
+    let mut insert_test = |n: u8, size: usize, addr: A, shared: bool| {
         let vec = Vec::from_iter((0..size).map(|v| (v as u8).overflowing_mul(n).0));
-        map.insert(vec.clone(), addr);
+        if shared {
+            #[cfg(feature = "bytes")]
+            if n % 2 == 1 {
+                map.insert_bytes(vec.clone().into(), addr);
+            } else {
+                map.insert_shared(vec.clone().into(), addr);
+            }
+            #[cfg(not(feature = "bytes"))]
+            map.insert_shared(vec.clone().into(), addr);
+        } else {
+            map.insert(vec.clone(), addr);
+        }
         map.assert_invariants();
-        assert_eq!(map.get(addr..addr + size as u64).unwrap(), &vec);
+        assert_eq!(map.get(addr..addr + A::from_usize(size)).unwrap(), &vec);
+        #[cfg(feature = "compress")]
+        map.compress_cold_blocks();
     };
 
     let mut rng = rand::rngs::StdRng::seed_from_u64(0);
     for _ in 0..1_000_000 {
         let n = rng.gen_range(0..255);
         let size = rng.gen_range(0..1000);
-        let addr = rng.gen_range(0..1000);
-        insert_test(n, size, addr);
+        let addr = A::try_from_u64(rng.gen_range(0..1000)).unwrap();
+        insert_test(n, size, addr, rng.gen_ratio(1, 4));
     }
 }
 
+#[test]
+fn sparsevec_fuzz() {
+    fuzz::<u64>();
+}
+
+#[test]
+fn sparsevec_u32_address_fuzz() {
+    fuzz::<u32>();
+}
+
+#[test]
+fn sparsevec_address_types() {
+    let mut small = SparseVec::<u8, u16>::default();
+    small.insert(vec![1; 0x10], 0xffe0);
+    small.insert(vec![2; 0x10], 0xffef);
+    small.assert_invariants();
+    assert_eq!(Vec::from_iter(small.ranges()), vec![0xffe0..0xffff]);
+    assert_eq!(small.get(0xffee..0xfff0).unwrap(), &[1, 2]);
+
+    let mut native = SparseVec::<u8, usize>::default();
+    native.insert(vec![3; 4], 8);
+    assert_eq!(Vec::from_iter(native), vec![(8usize, vec![3; 4])]);
+}
+
 #[test]
 fn sparsevec_u64_fuzz() {
     use rand::{Rng, SeedableRng};
@@ -218,3 +1272,477 @@ fn sparsevec_u64_fuzz() {
         insert_test(n, size, addr);
     }
 }
+
+#[test]
+fn sparsevec_append() {
+    use alloc::sync::Arc;
+
+    let chunk = |i: u64| Vec::from_iter((0..16).map(|j| (i * 16 + j) as u8));
+    for max_block_len in [None, Some(40)] {
+        let new = || match max_block_len {
+            Some(max) => SparseVec::with_max_block_len(max),
+            None => SparseVec::new(),
+        };
+        // Sequential appends take the fast path, inserting backwards never does
+        let (mut appended, mut general) = (new(), new());
+        for vec in [&mut appended, &mut general] {
+            vec.insert(vec![0xff; 4], 0x100);
+            vec.insert_shared(Arc::from(vec![0xee; 4]), 0x1000);
+        }
+        for i in 0..200 {
+            appended.insert(chunk(i), 0x1004 + i * 16);
+        }
+        for i in (0..200).rev() {
+            general.insert(chunk(i), 0x1004 + i * 16);
+        }
+        appended.assert_invariants();
+        assert!(appended.blocks().eq(general.blocks()));
+        assert!(appended.is_shared(0x1000));
+        assert_eq!(appended.get(0x1004..0x1008).unwrap(), &[0, 1, 2, 3]);
+    }
+}
+
+#[test]
+fn sparsevec_retain_blocks() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 0x10], 0x10);
+    map.insert(vec![1u8; 4], 0x14);
+    map.insert(vec![2u8; 2], 0x30);
+    map.insert(vec![3u8; 2], 0x32);
+    map.insert(vec![0u8; 0x20], 0x40);
+    map.insert(vec![4u8; 1], 0x70);
+    map.enable_history(4);
+
+    // Drop small blocks and blocks of padding
+    map.retain_blocks(|range, data| range.end - range.start > 1 && data.iter().any(|&v| v != 0));
+    map.assert_invariants();
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x10..0x20, 0x30..0x34]);
+    assert_eq!(map.get(0x12..0x16).unwrap(), &[0, 0, 1, 1]);
+    assert_eq!(map.get(0x30..0x34).unwrap(), &[2, 2, 3, 3]);
+    assert_eq!(map.stored_len(), 0x14);
+    assert_eq!(map.get_at_version(0x40..0x60, 6).unwrap(), &[0; 0x20][..]);
+
+    map.retain_blocks(|_, _| true);
+    assert_eq!(map.ranges().len(), 2);
+    map.retain_blocks(|_, _| false);
+    assert!(map.ranges().next().is_none());
+}
+
+#[test]
+fn sparsevec_range_bounds() {
+    use core::ops::Bound::{Excluded, Included, Unbounded};
+
+    let empty = SparseVec::<u8>::new();
+    assert_eq!(empty.get(..), None);
+    assert_eq!(empty.slices(..).count(), 0);
+    assert_eq!(empty.gaps(..).count(), 0);
+    assert!(empty.contains_range(&(..)));
+    assert_eq!(empty.ranges_in(..).count(), 0);
+
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8, 2, 3, 4], 0x10);
+    map.insert(vec![5u8, 6], 0x20);
+
+    // `get` ends at the end of the block
+    assert_eq!(map.get(0x12..).unwrap(), &[3, 4]);
+    assert_eq!(map.get(..).unwrap(), &[1, 2, 3, 4]);
+    assert_eq!(map.get(..0x12).unwrap(), &[1, 2]);
+    assert_eq!(map.get(..=0x12).unwrap(), &[1, 2, 3]);
+    assert_eq!(map.get(0x11..=0x13).unwrap(), &[2, 3, 4]);
+    assert_eq!(map.get(0x13..=0x14), None);
+    assert_eq!(map.get((Excluded(0x10), Included(0x11))).unwrap(), &[2]);
+    assert_eq!(map.get((Excluded(0x11), Unbounded)).unwrap(), &[3, 4]);
+    assert_eq!(map.get(0x14..), None);
+    assert_eq!(map.get((Excluded(u64::MAX), Unbounded)), None);
+
+    // The others end at the end of the stored data
+    assert_eq!(map.iter_range(0x13..).count(), 3);
+    assert_eq!(
+        Vec::from_iter(map.iter_range(..=0x11).map(|(addr, v)| (addr, *v))),
+        vec![(0x10, 1), (0x11, 2)]
+    );
+    assert_eq!(
+        Vec::from_iter(map.slices(0x12..).map(|(range, _)| range)),
+        vec![0x12..0x14, 0x20..0x22]
+    );
+    assert_eq!(Vec::from_iter(map.gaps(..)), vec![0x14..0x20]);
+    assert_eq!(
+        Vec::from_iter(map.gaps(..0x30)),
+        vec![0x14..0x20, 0x22..0x30]
+    );
+    assert_eq!(Vec::from_iter(map.gaps(0x8..=0x10)), vec![0x8..0x10]);
+    assert_eq!(Vec::from_iter(map.gaps(0x30..)), vec![]);
+    assert!(map.contains_range(&(0x10..=0x13)));
+    assert!(!map.contains_range(&(0x10..=0x14)));
+    assert!(!map.contains_range(&(..)));
+    assert!(map.contains_range(&(0x21..)));
+    assert_eq!(
+        Vec::from_iter(map.ranges_in((Excluded(0x13), Included(0x20)))),
+        vec![0x20..0x21]
+    );
+    assert_eq!(
+        Vec::from_iter(map.ranges_in(..)),
+        vec![0x10..0x14, 0x20..0x22]
+    );
+}
+
+#[test]
+fn sparsevec_into_iter() {
+    let mut map = SparseVec::new();
+    map.insert(vec![3u8; 4], 300);
+    map.insert(vec![1u8; 4], 100);
+    map.insert(vec![2u8; 4], 200);
+    map.insert(vec![4u8; 2], 302);
+
+    let borrowed = Vec::from_iter(&map);
+    assert_eq!(
+        borrowed,
+        vec![
+            (100..104, &[1u8; 4][..]),
+            (200..204, &[2; 4][..]),
+            (300..304, &[3, 3, 4, 4][..]),
+        ]
+    );
+
+    for (range, slice) in &mut map {
+        slice.fill(range.start as u8);
+    }
+    assert_eq!(map.get(200..204).unwrap(), &[200; 4]);
+
+    let ptrs = Vec::from_iter(map.blocks().map(|(_, slice)| slice.as_ptr()));
+    let owned = Vec::from_iter(map);
+    assert_eq!(
+        Vec::from_iter(owned.iter().map(|(start, _)| *start)),
+        vec![100, 200, 300]
+    );
+    assert_eq!(
+        Vec::from_iter(owned.iter().map(|(_, vec)| vec.as_ptr())),
+        ptrs
+    );
+}
+
+#[test]
+fn sparsevec_debug() {
+    let mut map = SparseVec::new();
+    assert_eq!(
+        format!("{map:?}"),
+        "SparseVec { blocks: 0, stored: 0, ranges: [] }"
+    );
+
+    map.insert(Vec::from_iter(0..20u8), 0x100);
+    map.insert(vec![0xab, 0xcd], 0x10);
+    assert_eq!(
+        format!("{map:?}"),
+        "SparseVec { blocks: 2, stored: 22, ranges: [0x10..0x12, 0x100..0x114] }"
+    );
+    assert_eq!(
+        format!("{map:#?}"),
+        "\
+SparseVec {
+    blocks: 2,
+    stored: 22,
+    ranges: [
+        0x10..0x12,
+        0x100..0x114,
+    ],
+    data: {
+        0x10..0x12: [ab, cd],
+        0x100..0x114: [00, 01, 02, 03, 04, 05, 06, 07, 08, 09, 0a, 0b, 0c, 0d, 0e, 0f, ... (+4 more)],
+    },
+}"
+    );
+}
+
+#[test]
+fn sparsevec_slices_and_write() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 4], 0);
+    map.insert(vec![2u8; 4], 8);
+    map.insert(vec![3u8; 4], 16);
+
+    assert_eq!(
+        Vec::from_iter(map.slices(2..18)),
+        vec![
+            (2..4, &[1u8, 1][..]),
+            (8..12, &[2; 4][..]),
+            (16..18, &[3, 3][..])
+        ]
+    );
+    assert_eq!(map.slices(4..8).count(), 0);
+    assert_eq!(map.slices(9..9).count(), 0);
+    assert_eq!(Vec::from_iter(map.iter_range(3..9)), vec![(3, &1), (8, &2)]);
+    // Reversed ranges are empty, even within a block
+    let reversed = Range { start: 3, end: 1 };
+    assert_eq!(map.slices(reversed.clone()).count(), 0);
+    assert_eq!(map.iter_range(reversed.clone()).count(), 0);
+    assert_eq!(map.runs(reversed).count(), 0);
+
+    assert_eq!(map.write(2, &[5, 5, 5]), Err(Unmapped { addr: 4 }));
+    assert_eq!(map.write(6, &[5, 5, 5]), Err(Unmapped { addr: 6 }));
+    assert_eq!(map.get(0..4).unwrap(), &[1; 4]);
+    assert_eq!(map.write(9, &[6, 7, 8]), Ok(()));
+    assert_eq!(map.get(8..12).unwrap(), &[2, 6, 7, 8]);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0..4, 8..12, 16..20]);
+
+    // Past the end of the address space
+    map.insert(vec![4; 2], u64::MAX - 2);
+    assert_eq!(
+        map.write(u64::MAX - 2, &[5; 3]),
+        Err(Unmapped { addr: u64::MAX })
+    );
+    assert_eq!(
+        map.write(u64::MAX - 3, &[5; 4]),
+        Err(Unmapped { addr: u64::MAX - 3 })
+    );
+    assert_eq!(map.get(u64::MAX - 2..u64::MAX).unwrap(), &[4; 2]);
+}
+
+#[test]
+fn sparsevec_clone_range() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 8], 0x10);
+    map.insert(vec![2u8; 8], 0x20);
+    map.insert(vec![3u8; 8], 0x30);
+
+    let mut clone = map.clone_range(0x14..0x33);
+    assert_eq!(
+        Vec::from_iter(clone.blocks()),
+        vec![
+            (0x14..0x18, &[1; 4][..]),
+            (0x20..0x28, &[2; 8][..]),
+            (0x30..0x33, &[3; 3][..])
+        ]
+    );
+    clone.get_mut(0x20..0x22).unwrap().fill(9);
+    clone.insert(vec![9; 8], 0x18);
+    assert_eq!(map.get(0x20..0x22).unwrap(), &[2, 2]);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x10..0x18, 0x20..0x28, 0x30..0x38]
+    );
+
+    assert_eq!(map.clone_range(0x18..0x20).stored_len(), 0);
+    assert_eq!(map.clone_range(0..0x100).stored_len(), 24);
+}
+
+#[test]
+fn sparsevec_double_ended_iterators() {
+    let mut map = SparseVec::new();
+    for i in 0..5u64 {
+        map.insert(vec![i as u8; 2], i * 0x10);
+    }
+
+    assert_eq!(map.ranges().len(), 5);
+    assert_eq!(
+        Vec::from_iter(map.ranges().rev().take(2)),
+        vec![0x40..0x42, 0x30..0x32]
+    );
+    let mut ranges = map.ranges();
+    assert_eq!(ranges.next(), Some(0..2));
+    assert_eq!(ranges.next_back(), Some(0x40..0x42));
+    assert_eq!(ranges.len(), 3);
+
+    let mut blocks = map.blocks();
+    assert_eq!(blocks.next_back(), Some((0x40..0x42, &[4u8, 4][..])));
+    assert_eq!(blocks.len(), 4);
+    assert_eq!(blocks.next(), Some((0..2, &[0u8, 0][..])));
+    assert_eq!(blocks.len(), 3);
+
+    let mut blocks_mut = map.blocks_mut();
+    assert_eq!(blocks_mut.len(), 5);
+    blocks_mut.next_back().unwrap().1.fill(9);
+    assert_eq!(map.get(0x40..0x42).unwrap(), &[9, 9]);
+
+    let mut iter = map.into_iter();
+    assert_eq!(iter.len(), 5);
+    assert_eq!(iter.next_back(), Some((0x40, vec![9, 9])));
+    assert_eq!(iter.next(), Some((0, vec![0, 0])));
+    assert_eq!(iter.len(), 3);
+    assert_eq!(
+        iter.rev().map(|(addr, _)| addr).collect::<Vec<_>>(),
+        vec![0x30, 0x20, 0x10]
+    );
+}
+
+#[test]
+fn sparsevec_gaps() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 4], 4);
+    map.insert(vec![0u8; 4], 12);
+
+    assert_eq!(Vec::from_iter(map.gaps(0..20)), vec![0..4, 8..12, 16..20]);
+    assert_eq!(Vec::from_iter(map.gaps(5..14)), vec![8..12]);
+    assert_eq!(Vec::from_iter(map.gaps(9..10)), vec![9..10]);
+    assert_eq!(map.gaps(4..8).count(), 0);
+    assert_eq!(map.gaps(3..3).count(), 0);
+
+    assert!(map.contains_range(&(5..8)));
+    assert!(map.contains_range(&(9..9)));
+    assert!(!map.contains_range(&(5..9)));
+    assert!(!map.contains_range(&(3..5)));
+}
+
+#[test]
+fn sparsevec_get_block() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 4], 4);
+    map.insert(vec![2u8; 4], 8);
+    map.insert(vec![3u8; 4], 16);
+
+    // Merged blocks are returned whole
+    assert_eq!(
+        map.get_block(9),
+        Some((4..12, &[1, 1, 1, 1, 2, 2, 2, 2][..]))
+    );
+    assert_eq!(map.get_block(4).unwrap().0, 4..12);
+    assert_eq!(map.get_block(15), None);
+    assert_eq!(map.get_block(12), None);
+    assert_eq!(map.get_block(16).unwrap().0, 16..20);
+    assert_eq!(map.get_block(20), None);
+
+    let (range, slice) = map.get_block_mut(19).unwrap();
+    assert_eq!(range, 16..20);
+    slice.fill(4);
+    assert_eq!(map.get(16..20).unwrap(), &[4; 4]);
+    assert!(map.get_block_mut(3).is_none());
+}
+
+#[test]
+fn sparsevec_update() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 4], 4);
+
+    map.update(5..7, |data| {
+        data.unwrap().fill(2);
+        None
+    });
+    assert_eq!(map.get(4..8).unwrap(), &[1, 2, 2, 1]);
+
+    // Partially covered
+    map.update(6..10, |data| {
+        assert!(data.is_none());
+        Some(vec![3; 4])
+    });
+    assert_eq!(map.get(4..10).unwrap(), &[1, 2, 3, 3, 3, 3]);
+
+    // Replaced even though covered
+    map.update(4..6, |data| {
+        let old = data.unwrap().to_vec();
+        Some(old.into_iter().map(|v| v * 10).collect())
+    });
+    assert_eq!(map.get(4..10).unwrap(), &[10, 20, 3, 3, 3, 3]);
+
+    map.update(0x20..0x22, |data| data.is_none().then(|| vec![4; 2]));
+    assert_eq!(Vec::from_iter(map.ranges()), vec![4..10, 0x20..0x22]);
+}
+
+#[test]
+fn sparsevec_ranges_in() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 4], 4);
+    map.insert(vec![0u8; 4], 12);
+
+    assert_eq!(Vec::from_iter(map.ranges_in(0..20)), vec![4..8, 12..16]);
+    assert_eq!(Vec::from_iter(map.ranges_in(6..14)), vec![6..8, 12..14]);
+    assert_eq!(Vec::from_iter(map.ranges_in(13..15)), vec![13..15]);
+    assert_eq!(map.ranges_in(8..12).count(), 0);
+    assert_eq!(map.ranges_in(16..100).count(), 0);
+    assert_eq!(map.ranges_in(5..5).count(), 0);
+}
+
+#[test]
+fn sparsevec_get_mut_or_fill() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 4], 0x10);
+    map.insert(vec![2u8; 4], 0x18);
+    map.insert(vec![3u8; 4], 0x30);
+
+    let slice = map.get_mut_or_fill(0x12..0x1e, 0);
+    assert_eq!(slice, &[1, 1, 0, 0, 0, 0, 2, 2, 2, 2, 0, 0]);
+    slice.fill(7);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x10..0x1e, 0x30..0x34]);
+    assert_eq!(map.get(0x10..0x13).unwrap(), &[1, 1, 7]);
+
+    assert_eq!(map.get_mut_or_fill(0x31..0x33, 0), &[3, 3]);
+    assert_eq!(map.get_mut_or_fill(0x40..0x42, 5), &[5, 5]);
+    assert_eq!(map.get_mut_or_fill(0x50..0x50, 5).len(), 0);
+
+    map.fill_gaps(0..0x40, 9);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0..0x42]);
+    assert_eq!(map.get(0x1c..0x20).unwrap(), &[7, 7, 9, 9]);
+}
+
+#[test]
+fn sparsevec_fill_gaps_keeps_blocks() {
+    use alloc::sync::Arc;
+
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 4], 0x10);
+    map.insert_shared(Arc::from(&[2u8; 4][..]), 0x18);
+    map.insert(vec![3u8; 4], 0x100);
+    let id = map.block_id_at(0x10).unwrap();
+    let mark = map.mark();
+
+    // Only the gaps are new, so the stored blocks keep their ids and stay shared
+    map.fill_gaps(0x10..0x20, 0);
+    assert_eq!(map.resolve(id).unwrap().0, 0x10..0x18);
+    assert!(map.is_shared(0x18));
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x10..0x18, 0x18..0x1c, 0x1c..0x20, 0x100..0x104]
+    );
+    let changes = map.changes_since(mark);
+    assert_eq!(
+        Vec::from_iter(changes.ranges()),
+        vec![0x14..0x18, 0x1c..0x20]
+    );
+    assert_eq!(changes.get(0x14..0x18).unwrap(), &[0; 4]);
+
+    // Unless a single slice over them is needed
+    assert_eq!(
+        map.get_mut_or_fill(0x16..0x1e, 5),
+        &[0, 0, 2, 2, 2, 2, 0, 0]
+    );
+    assert!(!map.is_shared(0x18));
+    assert_eq!(map.resolve(id).unwrap().0, 0x10..0x20);
+}
+
+#[test]
+fn sparsevec_try_get() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8, 2, 3, 4], 0x10);
+    map.insert(vec![5u8; 4], 0x20);
+
+    assert_eq!(map.try_get(0x11..0x14), Ok(&[2u8, 3, 4][..]));
+    assert_eq!(map.try_get(0x20..0x24), Ok(&[5u8; 4][..]));
+    assert_eq!(map.try_get(0x30..0x30), Ok(&[][..]));
+
+    // Starts in a gap
+    assert_eq!(
+        map.try_get(0x08..0x12),
+        Err(ReadError::StartUnmapped { gap: 0x08..0x10 })
+    );
+    assert_eq!(
+        map.try_get(0x14..0x18),
+        Err(ReadError::StartUnmapped { gap: 0x14..0x18 })
+    );
+    assert_eq!(
+        map.try_get(0x24..u64::MAX),
+        Err(ReadError::StartUnmapped {
+            gap: 0x24..u64::MAX
+        })
+    );
+
+    // Runs off the end of a block
+    assert_eq!(
+        map.try_get(0x12..0x22),
+        Err(ReadError::TruncatedAt {
+            first_missing: 0x14,
+            available: 2
+        })
+    );
+    assert_eq!(
+        map.try_get(0x23..0x25).unwrap_err().to_string(),
+        "address 0x24 is unmapped after 1 readable elements"
+    );
+}
@@ -0,0 +1,81 @@
+use alloc::vec::Vec;
+
+use crate::SparseVec;
+
+/// Builds a [`SparseVec`] from `address => data` entries, where `data` is anything that
+/// converts into a `Vec`, like an array or a `Vec` itself.
+///
+/// ```
+/// # use sparse_vec::{sparse_vec, SparseVec};
+/// let data = vec![7u8; 4];
+/// let map: SparseVec<u8> = sparse_vec! {
+///     0x100 => [1, 2, 3],
+///     0x200 => [0; 16],
+///     0x300 => data,
+/// };
+/// assert_eq!(map.get(0x100..0x103).unwrap(), &[1, 2, 3]);
+/// ```
+///
+/// Entries can be in any order. Adjacent entries are merged into one block, as with
+/// [`SparseVec::insert`]. Panics with the address if two entries overlap.
+#[macro_export]
+macro_rules! sparse_vec {
+    ($($addr:expr => $data:expr),* $(,)?) => {
+        $crate::SparseVec::__from_entries([$(($addr, $crate::__into_vec($data))),*])
+    };
+}
+
+#[doc(hidden)]
+pub fn __into_vec<T>(data: impl Into<Vec<T>>) -> Vec<T> {
+    data.into()
+}
+
+impl<T> SparseVec<T> {
+    #[doc(hidden)]
+    pub fn __from_entries<const N: usize>(entries: [(u64, Vec<T>); N]) -> Self {
+        let mut entries = Vec::from(entries);
+        entries.retain(|(_, data)| !data.is_empty());
+        entries.sort_unstable_by_key(|(addr, _)| *addr);
+        for pair in entries.windows(2) {
+            let ((addr, data), (next, _)) = (&pair[0], &pair[1]);
+            assert!(
+                addr + data.len() as u64 <= *next,
+                "sparse_vec! entries overlap at {next:#x}"
+            );
+        }
+        Self::from_sorted_blocks(entries)
+    }
+}
+
+#[test]
+fn sparsevec_macro() {
+    let vec = vec![5u16; 3];
+    let map = crate::sparse_vec! {
+        0x300 => vec,
+        0x100 => [1, 2, 3],
+        0x200 => [0; 0x10],
+        0x103 => alloc::vec![4],
+        0x400 => [],
+    };
+    map.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x104, 0x200..0x210, 0x300..0x303]
+    );
+    assert_eq!(map.get(0x100..0x104).unwrap(), &[1, 2, 3, 4]);
+    assert_eq!(map.get(0x300..0x303).unwrap(), &[5; 3]);
+
+    let empty: SparseVec<u8> = crate::sparse_vec! {};
+    assert_eq!(empty.ranges().len(), 0);
+    let top = crate::sparse_vec! { u64::MAX - 2 => [1u8, 2] };
+    assert_eq!(top.bounds(), Some(u64::MAX - 2..u64::MAX));
+}
+
+#[test]
+#[should_panic(expected = "sparse_vec! entries overlap at 0x108")]
+fn sparsevec_macro_overlap() {
+    let _ = crate::sparse_vec! {
+        0x100 => [0u8; 0x10],
+        0x108 => [1u8],
+    };
+}
@@ -0,0 +1,196 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::keys::KeyMap;
+use crate::storage::Storage;
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// New `SparseVec` with the same ranges, holding `f(addr, value)` for every element. `f` is
+    /// called in address order.
+    pub fn map_values<U>(&self, mut f: impl FnMut(A, &T) -> U) -> SparseVec<U, A> {
+        let mut data = KeyMap::with_capacity_and_hasher(self.map.len(), Default::default());
+        for (range, key) in self.map.iter() {
+            let values = self.data[key]
+                .iter()
+                .enumerate()
+                .map(|(i, v)| f(range.start + A::from_usize(i), v))
+                .collect();
+            data.insert(*key, values);
+        }
+        let top = self.top.as_ref().map(|v| f(A::MAX, v));
+        self.with_data(data, top)
+    }
+
+    /// Consuming variant of [`SparseVec::map_values`]. The block allocations are reused where
+    /// the standard library can collect in place, e.g. when `T` and `U` have the same size and
+    /// alignment.
+    pub fn map_values_into<U>(mut self, mut f: impl FnMut(A, T) -> U) -> SparseVec<U, A> {
+        let mut data = KeyMap::with_capacity_and_hasher(self.map.len(), Default::default());
+        for (range, key) in self.map.iter() {
+            let values = self.data.remove(key).unwrap();
+            let values: Vec<U> = values
+                .into_vec()
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| f(range.start + A::from_usize(i), v))
+                .collect();
+            data.insert(*key, values);
+        }
+        let top = self.top.take().map(|v| f(A::MAX, v));
+        self.with_data(data, top)
+    }
+
+    /// Fallible variant of [`SparseVec::map_values`] that stops at the first error.
+    pub fn try_map_values<U, E>(
+        &self,
+        mut f: impl FnMut(A, &T) -> Result<U, E>,
+    ) -> Result<SparseVec<U, A>, MapError<E, A>> {
+        let mut data = KeyMap::with_capacity_and_hasher(self.map.len(), Default::default());
+        for (range, key) in self.map.iter() {
+            let values = self.data[key]
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let addr = range.start + A::from_usize(i);
+                    f(addr, v).map_err(|error| MapError { addr, error })
+                })
+                .collect::<Result<_, _>>()?;
+            data.insert(*key, values);
+        }
+        let top = self.top.as_ref().map(|v| f(A::MAX, v)).transpose();
+        let top = top.map_err(|error| MapError {
+            addr: A::MAX,
+            error,
+        })?;
+        Ok(self.with_data(data, top))
+    }
+
+    // Same layout with other blocks, which must use the keys of `self`
+    fn with_data<U>(&self, data: KeyMap<Vec<U>>, top: Option<U>) -> SparseVec<U, A> {
+        let data = data
+            .into_iter()
+            .map(|(key, values)| (key, Storage::new(values, self.block_align)))
+            .collect();
+        SparseVec {
+            map: self.map.clone(),
+            data,
+            key_counter: self.key_counter,
+            watch: Default::default(),
+            history: Default::default(),
+            marks: Default::default(),
+            frozen: Default::default(),
+            block_align: self.block_align,
+            max_block_len: self.max_block_len,
+            auto_merge: self.auto_merge,
+            push_origin: self.push_origin,
+            mirrors: self.mirrors.clone(),
+            reserved: self.reserved.clone(),
+            holes: None,
+            dedup: None,
+            capacity: None,
+            top,
+            #[cfg(feature = "std")]
+            journal: None,
+            #[cfg(feature = "compress")]
+            compression: None,
+        }
+    }
+}
+
+/// Error returned by the closure of [`SparseVec::try_map_values`] for the element at `addr`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapError<E, A = u64> {
+    pub addr: A,
+    pub error: E,
+}
+
+impl<E: fmt::Display, A: Address> fmt::Display for MapError<E, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "at address {:#x}: {}", self.addr, self.error)
+    }
+}
+
+impl<E: core::error::Error + 'static, A: Address> core::error::Error for MapError<E, A> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+#[test]
+fn sparsevec_map_values() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8, 2, 3], 0x10);
+    map.insert(vec![4u8; 5], 0x20);
+    map.insert(vec![9u8], 0x13);
+    map.insert(vec![5u8; 2], 0x1000);
+
+    let mut calls = Vec::new();
+    let mapped = map.map_values(|addr, v| {
+        calls.push(addr);
+        (addr, *v as u32 * 10)
+    });
+    assert_eq!(
+        Vec::from_iter(mapped.ranges()),
+        Vec::from_iter(map.ranges())
+    );
+    assert!(calls.windows(2).all(|w| w[0] < w[1]));
+    assert_eq!(mapped.get(0x12..0x14).unwrap(), &[(0x12, 30), (0x13, 90)]);
+    assert_eq!(mapped.get(0x1001..0x1002).unwrap(), &[(0x1001, 50)]);
+
+    let ranges = Vec::from_iter(map.ranges());
+    let signed = map.map_values_into(|addr, v| v as i8 - addr as i8);
+    assert_eq!(Vec::from_iter(signed.ranges()), ranges);
+    assert_eq!(signed.get(0x20..0x22).unwrap(), &[4 - 0x20, 4 - 0x21]);
+
+    let mut signed = signed;
+    signed.insert(vec![0; 4], 0x14);
+    assert_eq!(
+        Vec::from_iter(signed.ranges()),
+        vec![0x10..0x18, 0x20..0x25, 0x1000..0x1002]
+    );
+}
+
+#[test]
+fn sparsevec_try_map_values() {
+    let mut map = SparseVec::new();
+    map.insert(b"12a4".to_vec(), 0x10);
+    map.insert(b"5678".to_vec(), 0x20);
+
+    let digit = |_, b: &u8| (*b as char).to_digit(10).ok_or(*b);
+    let err = |addr, error| Err(MapError { addr, error });
+    assert_eq!(map.try_map_values(digit).map(|_| ()), err(0x12, b'a'));
+
+    map.write(0x12, b"3").unwrap();
+    let digits = map.try_map_values(digit).unwrap();
+    assert_eq!(digits.get(0x10..0x14).unwrap(), &[1, 2, 3, 4]);
+    assert_eq!(
+        Vec::from_iter(digits.ranges()),
+        vec![0x10..0x14, 0x20..0x24]
+    );
+
+    map.write(0x20, b"x").unwrap();
+    assert_eq!(map.try_map_values(digit).map(|_| ()), err(0x20, b'x'));
+    map.write(0x20, b"5").unwrap();
+    map.write(0x23, b"y").unwrap();
+    assert_eq!(map.try_map_values(digit).map(|_| ()), err(0x23, b'y'));
+
+    let mut calls = 0;
+    let _ = map.try_map_values(|addr, _| {
+        calls += 1;
+        if addr == 0x10 {
+            Err(())
+        } else {
+            Ok(())
+        }
+    });
+    assert_eq!(calls, 1);
+    assert_eq!(
+        MapError {
+            addr: 0x23u64,
+            error: "bad digit"
+        }
+        .to_string(),
+        "at address 0x23: bad digit"
+    );
+}
@@ -0,0 +1,145 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use rangemap::RangeSet;
+
+use crate::{Address, SparseVec};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct MarkId(u64);
+
+// Ranges touched since each live mark, oldest first
+pub(crate) struct Marks<A> {
+    marks: Vec<(MarkId, RangeSet<A>)>,
+    next_id: u64,
+}
+
+impl<A> Default for Marks<A> {
+    fn default() -> Self {
+        Self {
+            marks: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<A: Address> Marks<A> {
+    pub(crate) fn touch(&mut self, range: &Range<A>) {
+        if range.is_empty() {
+            return;
+        }
+        for (_, touched) in &mut self.marks {
+            touched.insert(range.clone());
+        }
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Starts tracking the ranges touched by `insert`, `fill`, `fill_gaps`, `write` and
+    /// `get_mut`, for [`SparseVec::changes_since`].
+    pub fn mark(&mut self) -> MarkId {
+        let id = MarkId(self.marks.next_id);
+        self.marks.next_id += 1;
+        self.marks.marks.push((id, RangeSet::new()));
+        id
+    }
+
+    /// Stops tracking for every mark older than `mark`.
+    pub fn clear_marks_before(&mut self, mark: MarkId) {
+        self.marks.marks.retain(|(id, _)| *id >= mark);
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Copy of the data in every range touched since `mark`, at its addresses. Inserting
+    /// its blocks into a copy taken at the mark reproduces the current content, except for
+    /// blocks removed by [`SparseVec::retain_blocks`]. Changes made through
+    /// [`SparseVec::blocks_mut`] are not tracked.
+    ///
+    /// Panics if `mark` was cleared.
+    pub fn changes_since(&self, mark: MarkId) -> SparseVec<T, A> {
+        let (_, touched) = self
+            .marks
+            .marks
+            .iter()
+            .find(|(id, _)| *id == mark)
+            .expect("mark was cleared");
+        Self::from_sorted_blocks(touched.iter().flat_map(|range| {
+            self.slices(range.clone())
+                .map(|(clipped, slice)| (clipped.start, slice.to_vec()))
+        }))
+    }
+}
+
+#[test]
+fn sparsevec_changes_since() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 0x20], 0x10);
+    let mark = map.mark();
+    map.write(0x14, &[2, 2]).unwrap();
+    map.fill(0x1e..0x24, 3);
+    let later = map.mark();
+    map.insert(vec![4; 2], 0x40);
+
+    let delta = map.changes_since(mark);
+    assert_eq!(
+        Vec::from_iter(delta.ranges()),
+        vec![0x14..0x16, 0x1e..0x24, 0x40..0x42]
+    );
+    assert_eq!(delta.get(0x1e..0x24).unwrap(), &[3; 6]);
+    assert_eq!(
+        Vec::from_iter(map.changes_since(later).ranges()),
+        vec![0x40..0x42]
+    );
+
+    map.clear_marks_before(later);
+    assert_eq!(map.changes_since(later).ranges().len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "mark was cleared")]
+fn sparsevec_changes_since_cleared() {
+    let mut map = SparseVec::<u8>::new();
+    let mark = map.mark();
+    let later = map.mark();
+    map.clear_marks_before(later);
+    map.changes_since(mark);
+}
+
+#[test]
+fn sparsevec_changes_since_round_trip() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(165);
+    let mut map = SparseVec::new();
+    for _ in 0..20 {
+        map.insert(
+            vec![rng.gen(); rng.gen_range(1..0x40)],
+            rng.gen_range(0..0x400),
+        );
+    }
+    for _ in 0..50 {
+        let mark = map.mark();
+        let mut receiver = map.clone_range(0..u64::MAX);
+        for _ in 0..rng.gen_range(0..10) {
+            let addr = rng.gen_range(0..0x400);
+            let len = rng.gen_range(0..0x40);
+            let value = rng.gen::<u8>();
+            match rng.gen_range(0..4) {
+                0 => map.insert(vec![value; len], addr),
+                1 => map.fill(addr..addr + len as u64, value),
+                2 => map.fill_gaps(addr..addr + len as u64, value),
+                _ => {
+                    if let Some(data) = map.get_mut(addr..addr + len as u64) {
+                        data.fill(value);
+                    }
+                }
+            }
+        }
+        for (start, data) in map.changes_since(mark) {
+            receiver.insert(data, start);
+        }
+        assert!(receiver.blocks().eq(map.blocks()));
+        map.clear_marks_before(mark);
+    }
+}
@@ -0,0 +1,202 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+// Appends `data` at `addr`, extending the last block if it ends there
+fn push_run<T: Clone, A: Address>(blocks: &mut Vec<(A, Vec<T>)>, addr: A, data: &[T]) {
+    match blocks.last_mut() {
+        Some((start, block)) if *start + A::from_usize(block.len()) == addr => {
+            block.extend_from_slice(data)
+        }
+        _ => blocks.push((addr, data.to_vec())),
+    }
+}
+
+fn at<T>(side: Option<&[T]>, i: usize) -> Option<&T> {
+    side.map(|side| &side[i])
+}
+
+/// Addresses changed differently by both sides of a [`SparseVec::merge3`], with what each
+/// version stores there. `None` means the range is unmapped in that version.
+///
+/// The coverage of each version is the same over the whole range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict<T, A = u64> {
+    pub range: Range<A>,
+    pub base: Option<Vec<T>>,
+    pub ours: Option<Vec<T>>,
+    pub theirs: Option<Vec<T>>,
+}
+
+impl<T, A: Address> fmt::Display for Conflict<T, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = |side: &Option<Vec<T>>| if side.is_some() { "mapped" } else { "unmapped" };
+        write!(
+            f,
+            "conflicting changes at {:#x}..{:#x} (base {}, ours {}, theirs {})",
+            self.range.start,
+            self.range.end,
+            status(&self.base),
+            status(&self.ours),
+            status(&self.theirs)
+        )
+    }
+}
+
+impl<T: fmt::Debug, A: Address> core::error::Error for Conflict<T, A> {}
+
+impl<T: Copy + PartialEq, A: Address> SparseVec<T, A> {
+    /// Three-way merge of two versions derived from `base`.
+    ///
+    /// Addresses changed by only one side take its value, addresses changed identically by
+    /// both are taken as well. Adding or removing coverage relative to `base` counts as a
+    /// change. Addresses changed differently by both sides are conflicts, which are all
+    /// returned in address order if there are any.
+    pub fn merge3(base: &Self, ours: &Self, theirs: &Self) -> Result<Self, Vec<Conflict<T, A>>> {
+        // Within two neighbouring boundaries, the coverage of every version is uniform
+        let mut bounds = Vec::from_iter(
+            [base, ours, theirs]
+                .iter()
+                .flat_map(|vec| vec.ranges())
+                .flat_map(|range| [range.start, range.end]),
+        );
+        bounds.sort_unstable();
+        bounds.dedup();
+
+        let mut blocks = Vec::new();
+        let mut conflicts: Vec<Conflict<T, A>> = Vec::new();
+        for bound in bounds.windows(2) {
+            let (start, end) = (bound[0], bound[1]);
+            let segment = start..end;
+            let [b, o, t] = [base, ours, theirs].map(|vec| vec.get(segment.clone()));
+            if o == b {
+                if let Some(t) = t {
+                    push_run(&mut blocks, start, t);
+                }
+                continue;
+            }
+            if t == b || t == o {
+                if let Some(o) = o {
+                    push_run(&mut blocks, start, o);
+                }
+                continue;
+            }
+            let len = (end - start).to_usize();
+            let conflicting =
+                |i| at(o, i) != at(b, i) && at(t, i) != at(b, i) && at(o, i) != at(t, i);
+            let mut i = 0;
+            while i < len {
+                if !conflicting(i) {
+                    let changed_ours = at(o, i) != at(b, i);
+                    if let Some(value) = if changed_ours { at(o, i) } else { at(t, i) } {
+                        push_run(
+                            &mut blocks,
+                            start + A::from_usize(i),
+                            core::slice::from_ref(value),
+                        );
+                    }
+                    i += 1;
+                    continue;
+                }
+                let from = i;
+                while i < len && conflicting(i) {
+                    i += 1;
+                }
+                let copy = |side: Option<&[T]>| side.map(|side| side[from..i].to_vec());
+                conflicts.push(Conflict {
+                    range: start + A::from_usize(from)..start + A::from_usize(i),
+                    base: copy(b),
+                    ours: copy(o),
+                    theirs: copy(t),
+                });
+            }
+        }
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+        Ok(Self::from_sorted_blocks(blocks))
+    }
+}
+
+#[test]
+fn sparsevec_merge3() {
+    let mut base = SparseVec::new();
+    base.insert(vec![0u8; 0x10], 0x10);
+    base.insert(vec![0u8; 4], 0x40);
+
+    let mut ours = base.clone_range(0..u64::MAX);
+    ours.write(0x10, &[1, 1]).unwrap();
+    ours.write(0x18, &[3, 3]).unwrap();
+    ours.insert(vec![5; 4], 0x30);
+    let mut theirs = base.clone_range(0..u64::MAX);
+    theirs.write(0x14, &[2, 2]).unwrap();
+    theirs.write(0x18, &[3, 3]).unwrap();
+    theirs.insert(vec![6; 2], 0x20);
+
+    // One-sided, identical and adjacent changes merge cleanly
+    let merged = SparseVec::merge3(&base, &ours, &theirs).unwrap();
+    assert_eq!(
+        Vec::from_iter(merged.ranges()),
+        vec![0x10..0x22, 0x30..0x34, 0x40..0x44]
+    );
+    assert_eq!(
+        merged.get(0x10..0x22).unwrap(),
+        &[1, 1, 0, 0, 2, 2, 0, 0, 3, 3, 0, 0, 0, 0, 0, 0, 6, 6]
+    );
+    assert_eq!(merged.get(0x30..0x34).unwrap(), &[5; 4]);
+    let unchanged = SparseVec::merge3(&base, &base, &base).unwrap();
+    assert!(unchanged.blocks().eq(base.blocks()));
+
+    // Removing coverage is a change too
+    let removed = base.clone_range(0x10..0x20);
+    let merged = SparseVec::merge3(&base, &removed, &base).unwrap();
+    assert_eq!(Vec::from_iter(merged.ranges()), vec![0x10..0x20]);
+}
+
+#[test]
+fn sparsevec_merge3_conflicts() {
+    let mut base = SparseVec::new();
+    base.insert(vec![0u8; 8], 0x10);
+    base.insert(vec![0u8; 4], 0x40);
+
+    let mut ours = base.clone_range(0..u64::MAX);
+    ours.write(0x10, &[1, 1, 1, 0, 1]).unwrap();
+    ours.insert(vec![3; 2], 0x30);
+    ours.write(0x40, &[4; 4]).unwrap();
+    let mut theirs = base.clone_range(0..u64::MAX);
+    theirs.write(0x11, &[2, 1, 2]).unwrap();
+    theirs.insert(vec![5; 4], 0x2f);
+    // Removed by theirs while ours changed it
+    theirs = SparseVec::merge3(&base, &theirs, &base.clone_range(0..0x40)).unwrap();
+
+    let conflicts = SparseVec::merge3(&base, &ours, &theirs).unwrap_err();
+    assert_eq!(
+        conflicts,
+        vec![
+            Conflict {
+                range: 0x11..0x12,
+                base: Some(vec![0]),
+                ours: Some(vec![1]),
+                theirs: Some(vec![2]),
+            },
+            Conflict {
+                range: 0x30..0x32,
+                base: None,
+                ours: Some(vec![3, 3]),
+                theirs: Some(vec![5, 5]),
+            },
+            Conflict {
+                range: 0x40..0x44,
+                base: Some(vec![0; 4]),
+                ours: Some(vec![4; 4]),
+                theirs: None,
+            },
+        ]
+    );
+    assert_eq!(
+        conflicts[2].to_string(),
+        "conflicting changes at 0x40..0x44 (base mapped, ours mapped, theirs unmapped)"
+    );
+}
@@ -0,0 +1,198 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, SparseVec, Unmapped};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Mirror<A> {
+    canonical: Range<A>,
+    // Start of the alias window, which maps to `canonical.start`
+    base: A,
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Makes `alias_base..alias_base + alias_len` an alias of `canonical`, repeating it as
+    /// often as it fits. `get`, `get_mut`, `insert`, `fill`, `write` and `read_into_exact`
+    /// redirect addresses in the window to `canonical` without storing anything twice.
+    /// Everything else, e.g. [`SparseVec::ranges`], only sees the canonical data.
+    ///
+    /// Panics if `canonical` is empty, if the window overlaps `canonical`, another alias
+    /// window or stored data, or if an alias window and a mirrored range overlap.
+    pub fn add_mirror(&mut self, canonical: Range<A>, alias_base: A, alias_len: A) {
+        assert!(!canonical.is_empty(), "mirrored range must not be empty");
+        let window = alias_base..alias_base + alias_len;
+        if window.is_empty() {
+            return;
+        }
+        let disjoint = |a: &Range<A>, b: &Range<A>| a.end <= b.start || b.end <= a.start;
+        assert!(
+            !self.mirrors.overlaps(&window)
+                && !self.map.overlaps(&window)
+                && disjoint(&window, &canonical),
+            "alias window {window:x?} overlaps existing data or mirrors"
+        );
+        // Aliases of aliases could redirect forever
+        assert!(
+            !self.mirrors.overlaps(&canonical)
+                && self
+                    .mirrors
+                    .iter()
+                    .all(|(_, mirror)| disjoint(&mirror.canonical, &window)),
+            "mirrored ranges must not be alias windows"
+        );
+        self.mirrors.insert(
+            window,
+            Mirror {
+                canonical,
+                base: alias_base,
+            },
+        );
+    }
+
+    /// Alias windows with the canonical range they mirror, in address order.
+    pub fn mirrors(&self) -> impl Iterator<Item = (Range<A>, Range<A>)> + '_ {
+        self.mirrors
+            .iter()
+            .map(|(window, mirror)| (window.clone(), mirror.canonical.clone()))
+    }
+
+    // The canonical ranges `range` consists of in order, `None` if it does not touch an
+    // alias window
+    pub(crate) fn mirrored(&self, range: &Range<A>) -> Option<Vec<Range<A>>> {
+        if self.mirrors.is_empty() || !self.mirrors.overlaps(range) {
+            return None;
+        }
+        let mut pieces = Vec::new();
+        let mut addr = range.start;
+        while addr < range.end {
+            match self.mirrors.get_key_value(&addr) {
+                Some((window, Mirror { canonical, base })) => {
+                    let len = canonical.end - canonical.start;
+                    let offset = A::from_usize(((addr - *base).to_u64() % len.to_u64()) as usize);
+                    let end = range.end.min(window.end).min(addr + (len - offset));
+                    let start = canonical.start + offset;
+                    pieces.push(start..start + (end - addr));
+                    addr = end;
+                }
+                None => {
+                    let next = self.mirrors.overlapping(addr..range.end).next();
+                    let end = next.map_or(range.end, |(window, _)| window.start);
+                    pieces.push(addr..end);
+                    addr = end;
+                }
+            }
+        }
+        Some(pieces)
+    }
+
+    // The first unmapped address of the pieces of the range at `start`, as seen from `start`
+    pub(crate) fn first_unmapped_mirrored(
+        &self,
+        start: A,
+        pieces: &[Range<A>],
+    ) -> Result<(), Unmapped<A>> {
+        let mut offset = A::ZERO;
+        for piece in pieces {
+            if let Some(addr) = self.first_unmapped(piece) {
+                let addr = start + offset + (addr - piece.start);
+                return Err(Unmapped { addr });
+            }
+            offset = offset + (piece.end - piece.start);
+        }
+        Ok(())
+    }
+
+    // Where `len` elements at `addr` go: `addr` itself, or its canonical address if they
+    // would run past `A::MAX` from an alias window reaching it, e.g. with an address modulus,
+    // so that they wrap around instead. `A::MAX` counts as part of such a window, although
+    // no range can contain it.
+    pub(crate) fn wrap_past_end(&self, addr: A, len: usize) -> A {
+        let fits = A::try_from_u64(len as u64).and_then(|len| addr.checked_add(len));
+        if fits.is_some() {
+            return addr;
+        }
+        match self.mirrors.iter().last() {
+            Some((window, Mirror { canonical, base }))
+                if window.end == A::MAX && addr >= window.start =>
+            {
+                let len = canonical.end - canonical.start;
+                let offset = A::from_usize(((addr - *base).to_u64() % len.to_u64()) as usize);
+                canonical.start + offset
+            }
+            _ => addr,
+        }
+    }
+
+    // `range` moved into the canonical range if it does not cross a repetition
+    pub(crate) fn unmirror(&self, range: Range<A>) -> Option<Range<A>> {
+        match self.mirrored(&range) {
+            None => Some(range),
+            Some(pieces) if pieces.len() == 1 => pieces.into_iter().next(),
+            Some(_) => None,
+        }
+    }
+}
+
+#[test]
+fn sparsevec_mirror() {
+    // 2 KiB mirrored through 0x0000..0x2000
+    let mut map = SparseVec::new();
+    map.add_mirror(0..0x800, 0x800, 0x1800);
+    map.insert(vec![1u8; 0x10], 0x1800);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0..0x10]);
+    assert_eq!(
+        Vec::from_iter(map.mirrors()),
+        vec![(0x800..0x2000, 0..0x800)]
+    );
+    for alias in [0, 0x800, 0x1000, 0x1800] {
+        assert_eq!(map.get(alias + 4..alias + 8).unwrap(), &[1; 4]);
+    }
+
+    // Writes wrap around within the window, up to its edge
+    map.insert(vec![2u8; 4], 0x7fe);
+    map.write(0xffe, &[3, 3, 3, 3]).unwrap();
+    assert_eq!(map.get(0x7fe..0x800).unwrap(), &[3, 3]);
+    assert_eq!(map.get(0..4).unwrap(), &[3, 3, 1, 1]);
+    assert_eq!(map.write(0x1ffe, &[4; 4]), Err(Unmapped { addr: 0x2000 }));
+    assert_eq!(map.get(0x1ffe..0x2000).unwrap(), &[3, 3]);
+    map.insert(vec![4; 4], 0x1ffe);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0..0x10, 0x7fe..0x800, 0x2000..0x2002]
+    );
+    assert_eq!(map.get(0x7fe..0x800).unwrap(), &[4, 4]);
+
+    // Reads crossing a repetition are stitched where possible
+    assert_eq!(map.get(0xffe..0x1002), None);
+    let mut buf = [0; 4];
+    map.read_into_exact(0xffe, &mut buf).unwrap();
+    assert_eq!(buf, [4, 4, 3, 3]);
+    assert_eq!(
+        map.read_into_exact(0x1004, &mut [0; 0x10]),
+        Err(Unmapped { addr: 0x1010 })
+    );
+
+    map.fill(0x810..0x814, 5);
+    map.get_mut(0x1013..0x1014).unwrap()[0] = 6;
+    assert_eq!(map.get(0x10..0x14).unwrap(), &[5, 5, 5, 6]);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0..0x14, 0x7fe..0x800, 0x2000..0x2002]
+    );
+}
+
+#[test]
+#[should_panic(expected = "overlaps existing data or mirrors")]
+fn sparsevec_mirror_overlap() {
+    let mut map = SparseVec::<u8>::new();
+    map.add_mirror(0..0x800, 0x800, 0x800);
+    map.add_mirror(0..0x100, 0xf00, 0x200);
+}
+
+#[test]
+#[should_panic(expected = "mirrored ranges must not be alias windows")]
+fn sparsevec_mirror_chain() {
+    let mut map = SparseVec::<u8>::new();
+    map.add_mirror(0..0x10, 0x10, 0x10);
+    map.add_mirror(0x10..0x20, 0x20, 0x10);
+}
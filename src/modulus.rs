@@ -0,0 +1,97 @@
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Empty `SparseVec` whose addresses wrap at `modulus`, e.g. `0x10000` to emulate a 16 bit
+    /// target. Every address from `modulus` on, `A::MAX` included, is an alias of
+    /// `addr % modulus`, as if `0..modulus` was [mirrored](SparseVec::add_mirror) over the rest
+    /// of the address space: inserts, fills and writes crossing `modulus` or the end of the
+    /// address space continue at zero, and [`SparseVec::read_into_exact`] reads across them.
+    /// Only reduced addresses are stored, so e.g. [`SparseVec::ranges`] reports ranges below
+    /// `modulus`.
+    ///
+    /// Panics if `modulus` is zero or not below `A::MAX`.
+    pub fn with_address_modulus(modulus: u64) -> Self {
+        let end = A::try_from_u64(modulus).filter(|&end| end != A::ZERO && end < A::MAX);
+        let Some(end) = end else {
+            panic!("address modulus {modulus:#x} must be positive and below the maximum address");
+        };
+        let mut vec = Self::default();
+        vec.add_mirror(A::ZERO..end, end, A::MAX - end);
+        vec
+    }
+
+    /// The modulus passed to [`SparseVec::with_address_modulus`], if any.
+    pub fn address_modulus(&self) -> Option<u64> {
+        // Alias windows are disjoint, so a wrapping one is the last
+        let (window, canonical) = self.mirrors().last()?;
+        let wraps = window.end == A::MAX && canonical == (A::ZERO..window.start);
+        wraps.then(|| window.start.to_u64())
+    }
+}
+
+#[test]
+fn sparsevec_address_modulus() {
+    let mut map = SparseVec::<u8>::with_address_modulus(0x10000);
+    assert_eq!(map.address_modulus(), Some(0x10000));
+    assert_eq!(SparseVec::<u8>::new().address_modulus(), None);
+
+    // Crossing the wrap boundary continues at zero
+    map.insert(Vec::from_iter(0..0x20), 0xfff0);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0..0x10, 0xfff0..0x10000]);
+    assert_eq!(map.get(0..4).unwrap(), &[0x10, 0x11, 0x12, 0x13]);
+    let mut buf = [0; 0x10];
+    map.read_into_exact(0xfff8, &mut buf).unwrap();
+    assert!(buf.iter().copied().eq(8..0x18));
+    assert_eq!(map.get(0xfff8..0x10008), None);
+
+    // Addresses are reduced everywhere
+    assert_eq!(map.get(0x1_0002..0x1_0004).unwrap(), &[0x12, 0x13]);
+    map.write(0x3_fffe, &[0xaa, 0xbb, 0xcc]).unwrap();
+    assert_eq!(map.get(0xfffe..0x10000).unwrap(), &[0xaa, 0xbb]);
+    assert_eq!(map.get(0..1).unwrap(), &[0xcc]);
+    assert_eq!(
+        map.write(0xffff, &[0; 0x12]),
+        Err(crate::Unmapped { addr: 0x10010 })
+    );
+    map.fill(0x2_0010..0x2_0012, 0xdd);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0..0x12, 0xfff0..0x10000]);
+    assert_eq!(
+        map.read_into_exact(0x10, &mut buf),
+        Err(crate::Unmapped { addr: 0x12 })
+    );
+
+    // Including across the end of the address space
+    map.insert(vec![9, 8], u64::MAX);
+    assert_eq!(map.get(0xffff..0x10000).unwrap(), &[9]);
+    assert_eq!(map.get(0..1).unwrap(), &[8]);
+    map.write(u64::MAX - 1, &[7; 3]).unwrap();
+    let mut buf = [0; 3];
+    map.read_into_exact(u64::MAX, &mut buf).unwrap();
+    assert_eq!(buf, [7, 7, 0x11]);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0..0x12, 0xfff0..0x10000]);
+}
+
+#[test]
+fn sparsevec_address_modulus_non_power_of_two() {
+    let mut map = SparseVec::<u16, u32>::with_address_modulus(1000);
+    assert_eq!(map.address_modulus(), Some(1000));
+    map.insert(Vec::from_iter(0..10), 995);
+    map.insert(vec![7; 3], 2_500);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0..5, 500..503, 995..1000]
+    );
+    assert_eq!(map.get(1_997..1_999).unwrap(), &[2, 3]);
+
+    // Longer than the modulus, only the last repetition is kept
+    map.insert(Vec::from_iter(0..2500), 0);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0..1000]);
+    assert_eq!(map.get(0..2).unwrap(), &[2000, 2001]);
+    assert_eq!(map.get(499..501).unwrap(), &[2499, 1500]);
+}
+
+#[test]
+#[should_panic(expected = "address modulus 0x0 must be positive")]
+fn sparsevec_address_modulus_zero() {
+    SparseVec::<u8>::with_address_modulus(0);
+}
@@ -0,0 +1,202 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, SparseVec, Unmapped};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Layer for local modifications on top of `self`, which stays untouched.
+    pub fn overlay(&self) -> Overlay<'_, T, A> {
+        Overlay {
+            base: self,
+            top: SparseVec::default(),
+        }
+    }
+}
+
+/// Modifications layered over a borrowed base [`SparseVec`], created by
+/// [`SparseVec::overlay`].
+///
+/// Reads see the top layer where it has data and fall through to the base everywhere else.
+/// Writes always go to the top layer.
+pub struct Overlay<'a, T, A = u64> {
+    base: &'a SparseVec<T, A>,
+    top: SparseVec<T, A>,
+}
+
+impl<'a, T, A: Address> Overlay<'a, T, A> {
+    pub fn base(&self) -> &'a SparseVec<T, A> {
+        self.base
+    }
+
+    /// The modifications alone.
+    pub fn top(&self) -> &SparseVec<T, A> {
+        &self.top
+    }
+
+    pub fn into_top(self) -> SparseVec<T, A> {
+        self.top
+    }
+
+    /// Composite data overlapping `range`, clipped to it, in address order. Data from the two
+    /// layers is never combined into one slice.
+    pub fn slices(&self, range: Range<A>) -> impl Iterator<Item = (Range<A>, &[T])> + '_ {
+        let base = self.base;
+        let mut next = range.start;
+        self.top
+            .slices(range.clone())
+            .map(Some)
+            .chain(core::iter::once(None))
+            .flat_map(move |top| {
+                // Base data between the previous and this top slice
+                let end = top.as_ref().map_or(range.end, |(clipped, _)| clipped.start);
+                let below = base.slices(next..end.max(next));
+                if let Some((clipped, _)) = &top {
+                    next = clipped.end;
+                }
+                below.chain(top)
+            })
+    }
+
+    /// All composite elements within `range` with their addresses. Gaps are skipped.
+    pub fn iter_range(&self, range: Range<A>) -> impl Iterator<Item = (A, &T)> + '_ {
+        self.slices(range).flat_map(|(clipped, slice)| {
+            slice
+                .iter()
+                .enumerate()
+                .map(move |(i, v)| (clipped.start + A::from_usize(i), v))
+        })
+    }
+
+    /// Whether every address in `range` is stored in either layer. True for empty ranges.
+    pub fn contains_range(&self, range: &Range<A>) -> bool {
+        self.top
+            .gaps(range.clone())
+            .all(|gap| self.base.contains_range(&gap))
+    }
+}
+
+impl<T: Copy, A: Address> Overlay<'_, T, A> {
+    /// The composite contents of `range`, with `default` wherever neither layer has data.
+    /// Empty for reversed ranges.
+    pub fn read_or(&self, range: Range<A>, default: T) -> Vec<T> {
+        if range.start >= range.end {
+            return Vec::new();
+        }
+        let len = (range.end - range.start).to_usize();
+        let mut result = Vec::with_capacity(len);
+        for (clipped, slice) in self.slices(range.clone()) {
+            result.resize((clipped.start - range.start).to_usize(), default);
+            result.extend_from_slice(slice);
+        }
+        result.resize(len, default);
+        result
+    }
+
+    pub fn insert(&mut self, data: Vec<T>, addr: A) {
+        self.top.insert(data, addr);
+    }
+
+    pub fn fill(&mut self, range: Range<A>, value: T) {
+        self.top.fill(range, value);
+    }
+
+    /// Overwrites composite data starting at `addr`, like [`SparseVec::write`]. Nothing is
+    /// written if any part of the destination is unmapped in both layers.
+    pub fn write(&mut self, addr: A, data: &[T]) -> Result<(), Unmapped<A>> {
+        let range = addr..addr + A::from_usize(data.len());
+        if let Some(gap) = self
+            .top
+            .gaps(range.clone())
+            .find(|gap| !self.base.contains_range(gap))
+        {
+            let addr = self.base.first_unmapped(&gap).unwrap();
+            return Err(Unmapped { addr });
+        }
+        self.top.insert(data.to_vec(), addr);
+        Ok(())
+    }
+
+    /// The composite as a single `SparseVec`.
+    pub fn flatten(&self) -> SparseVec<T, A> {
+        SparseVec::from_sorted_blocks(
+            self.slices(A::ZERO..A::MAX)
+                .map(|(clipped, slice)| (clipped.start, slice.to_vec())),
+        )
+    }
+}
+
+#[test]
+fn sparsevec_overlay() {
+    let mut base = SparseVec::new();
+    base.insert(vec![1u8; 0x10], 0x10);
+    base.insert(vec![2u8; 0x10], 0x30);
+
+    let mut overlay = base.overlay();
+    // Top blocks straddle the base block boundaries and the gap between them
+    overlay.insert(vec![7; 0x1c], 0x18);
+    overlay.fill(0x3c..0x44, 8);
+    assert_eq!(
+        Vec::from_iter(
+            overlay
+                .slices(0..0x100)
+                .map(|(range, s)| (range, s[0], s.len()))
+        ),
+        vec![
+            (0x10..0x18, 1, 8),
+            (0x18..0x34, 7, 0x1c),
+            (0x34..0x3c, 2, 8),
+            (0x3c..0x44, 8, 8)
+        ]
+    );
+    assert_eq!(
+        Vec::from_iter(overlay.slices(0x1a..0x36).map(|(range, _)| range)),
+        vec![0x1a..0x34, 0x34..0x36]
+    );
+    assert_eq!(
+        Vec::from_iter(overlay.iter_range(0x16..0x1a).map(|(a, v)| (a, *v))),
+        vec![(0x16, 1), (0x17, 1), (0x18, 7), (0x19, 7)]
+    );
+    assert_eq!(overlay.read_or(0x0e..0x12, 0), vec![0, 0, 1, 1]);
+    assert!(overlay
+        .read_or(
+            Range {
+                start: 0x12,
+                end: 0x0e
+            },
+            0
+        )
+        .is_empty());
+    assert!(overlay
+        .base()
+        .read_or(
+            Range {
+                start: 0x12,
+                end: 0x0e
+            },
+            0
+        )
+        .is_empty());
+    assert_eq!(
+        overlay.read_or(0x3a..0x46, 0),
+        [2, 2, 8, 8, 8, 8, 8, 8, 8, 8, 0, 0]
+    );
+    assert!(overlay.contains_range(&(0x10..0x44)));
+    assert!(!overlay.contains_range(&(0x10..0x45)));
+
+    // Writes need coverage in either layer
+    assert_eq!(overlay.write(0x42, &[9; 4]), Err(Unmapped { addr: 0x44 }));
+    overlay.write(0x12, &[9; 4]).unwrap();
+    assert_eq!(overlay.read_or(0x10..0x18, 0), [1, 1, 9, 9, 9, 9, 1, 1]);
+    assert_eq!(base.get(0x10..0x18).unwrap(), &[1; 8]);
+
+    let flat = overlay.flatten();
+    assert_eq!(Vec::from_iter(flat.ranges()), vec![0x10..0x44]);
+    assert_eq!(
+        flat.get(0x10..0x44).unwrap(),
+        &overlay.read_or(0x10..0x44, 0)[..]
+    );
+    assert_eq!(
+        Vec::from_iter(overlay.into_top().ranges()),
+        vec![0x12..0x16, 0x18..0x34, 0x3c..0x44]
+    );
+}
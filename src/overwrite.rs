@@ -0,0 +1,92 @@
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Copies the data `other` stores in `range` to the same addresses here, overwriting what
+    /// is stored. Addresses `other` does not store keep their data here. Parts already
+    /// stored here are written in place, the others inserted.
+    pub fn overwrite_from(&mut self, other: &SparseVec<T, A>, range: Range<A>) {
+        for (clipped, slice) in other.slices(range) {
+            if self.contains_range(&clipped) {
+                self.write(clipped.start, slice).unwrap();
+            } else {
+                self.insert(slice.to_vec(), clipped.start);
+            }
+        }
+    }
+}
+
+#[test]
+fn sparsevec_overwrite_from() {
+    let mut map = crate::sparse_vec! {
+        0x100 => [1u8; 0x10],
+        0x120 => [2; 0x10],
+        0x200 => [3; 4],
+    };
+    let other = crate::sparse_vec! {
+        0x0f8 => [4u8; 0x0c],
+        0x108 => [5; 4],
+        0x112 => [6; 0x14],
+        0x200 => [7; 8],
+    };
+    map.overwrite_from(&other, 0x0fc..0x202);
+    map.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x0fc..0x110, 0x112..0x130, 0x200..0x204]
+    );
+    assert_eq!(
+        map.get(0x0fc..0x110).unwrap(),
+        &[4, 4, 4, 4, 4, 4, 4, 4, 1, 1, 1, 1, 5, 5, 5, 5, 1, 1, 1, 1]
+    );
+    assert_eq!(map.get(0x124..0x128).unwrap(), &[6, 6, 2, 2]);
+    assert_eq!(map.get(0x200..0x204).unwrap(), &[7, 7, 3, 3]);
+
+    // Covered parts are written in place
+    let mut covered = crate::sparse_vec! { 0x100 => [1u8; 0x10] };
+    let ptr = |map: &SparseVec<u8>| map.get_block(0x100).unwrap().1.as_ptr();
+    let before = ptr(&covered);
+    covered.overwrite_from(&other, 0x104..0x110);
+    assert_eq!(ptr(&covered), before);
+    assert_eq!(
+        covered.get(0x100..0x110).unwrap(),
+        &[1, 1, 1, 1, 1, 1, 1, 1, 5, 5, 5, 5, 1, 1, 1, 1]
+    );
+    covered.overwrite_from(&other, 0x100..0x100);
+    assert_eq!(Vec::from_iter(covered.ranges()), vec![0x100..0x110]);
+}
+
+#[test]
+fn sparsevec_overwrite_from_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(190);
+    let random = |rng: &mut rand::rngs::StdRng, model: &mut [Option<u32>], tag: u32| {
+        let mut map = SparseVec::new();
+        for i in 0..rng.gen_range(0..12) {
+            let start = rng.gen_range(0..0x1c0);
+            let len = rng.gen_range(1..0x40);
+            map.insert(vec![tag + i; len], start as u64);
+            model[start..][..len].fill(Some(tag + i));
+        }
+        map
+    };
+    for _ in 0..200 {
+        let (mut model, mut other_model) = ([None; 0x200], [None; 0x200]);
+        let mut map = random(&mut rng, &mut model, 0);
+        let other = random(&mut rng, &mut other_model, 100);
+        let start = rng.gen_range(0..0x200);
+        let end = rng.gen_range(start..=0x200);
+        map.overwrite_from(&other, start as u64..end as u64);
+        for addr in start..end {
+            model[addr] = other_model[addr].or(model[addr]);
+        }
+        map.assert_invariants();
+        let stored = Vec::from_iter(map.iter_range(0..0x200).map(|(addr, v)| (addr, *v)));
+        let expected = Vec::from_iter(
+            (0..0x200u64).filter_map(|addr| model[addr as usize].map(|v| (addr, v))),
+        );
+        assert_eq!(stored, expected);
+    }
+}
@@ -0,0 +1,186 @@
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+/// Where the pages of [`SparseVec::pages`] start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageAlign {
+    /// At multiples of the page size. The first and last page may extend past the range.
+    Absolute,
+    /// At the start of the range plus multiples of the page size.
+    RangeStart,
+}
+
+/// How much of a [`PageView`] is stored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageCoverage {
+    Full,
+    Partial,
+    Empty,
+}
+
+/// A page yielded by [`SparseVec::pages`].
+pub struct PageView<'a, T, A = u64> {
+    vec: &'a SparseVec<T, A>,
+    base: A,
+    range: Range<A>,
+    coverage: PageCoverage,
+}
+
+impl<'a, T, A: Address> PageView<'a, T, A> {
+    /// The first address of the page, which is before [`PageView::range`] if the page starts
+    /// before the iterated range.
+    pub fn base(&self) -> A {
+        self.base
+    }
+
+    /// The addresses of the page within the iterated range.
+    pub fn range(&self) -> Range<A> {
+        self.range.clone()
+    }
+
+    /// Coverage of [`PageView::range`].
+    pub fn coverage(&self) -> PageCoverage {
+        self.coverage
+    }
+
+    /// Stored data within [`PageView::range`], in address order.
+    pub fn slices(&self) -> impl Iterator<Item = (Range<A>, &'a [T])> + 'a {
+        self.vec.slices(self.range.clone())
+    }
+
+    /// Unmapped parts of [`PageView::range`], in address order.
+    pub fn gaps(&self) -> impl Iterator<Item = Range<A>> + 'a {
+        self.vec.gaps(self.range.clone())
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Every page of `page_size` elements overlapping `range`, with how much of it is
+    /// stored, including empty pages. Pages are clipped to `range`, so with
+    /// [`PageAlign::Absolute`] coverage only counts the part inside it.
+    ///
+    /// # Panics
+    ///
+    /// If `page_size` is zero.
+    pub fn pages(
+        &self,
+        page_size: A,
+        range: Range<A>,
+        align: PageAlign,
+    ) -> impl Iterator<Item = PageView<'_, T, A>> + '_ {
+        let size = page_size.to_u64();
+        assert!(size != 0, "page size must not be zero");
+        let (start, end) = (range.start.to_u64(), range.end.to_u64());
+        let first = match align {
+            PageAlign::Absolute => start - start % size,
+            PageAlign::RangeStart => start,
+        };
+        // Stepping stops at the end of the address space
+        let bases = core::iter::successors(Some(first), move |base| base.checked_add(size))
+            .take_while(move |base| *base < end);
+        bases.map(move |base| {
+            let page_end = base.saturating_add(size).min(end);
+            let range =
+                A::try_from_u64(base.max(start)).unwrap()..A::try_from_u64(page_end).unwrap();
+            let stored: u64 = self
+                .slices(range.clone())
+                .map(|(clipped, _)| (clipped.end - clipped.start).to_u64())
+                .sum();
+            let coverage = match stored {
+                0 => PageCoverage::Empty,
+                len if len == (range.end - range.start).to_u64() => PageCoverage::Full,
+                _ => PageCoverage::Partial,
+            };
+            PageView {
+                vec: self,
+                base: A::try_from_u64(base).unwrap(),
+                range,
+                coverage,
+            }
+        })
+    }
+}
+
+#[test]
+fn sparsevec_pages() {
+    // Blocks straddling page edges on both sides
+    let map = crate::sparse_vec! {
+        0x0f0 => [1u8; 0x20],
+        0x180 => [2; 0x100],
+        0x2f8 => [3; 0x10],
+    };
+    let summary = |align| {
+        Vec::from_iter(
+            map.pages(0x100, 0x000..0x400, align)
+                .map(|page| (page.base(), page.range(), page.coverage())),
+        )
+    };
+    assert_eq!(
+        summary(PageAlign::Absolute),
+        vec![
+            (0x000, 0x000..0x100, PageCoverage::Partial),
+            (0x100, 0x100..0x200, PageCoverage::Partial),
+            (0x200, 0x200..0x300, PageCoverage::Partial),
+            (0x300, 0x300..0x400, PageCoverage::Partial),
+        ]
+    );
+    let page = map
+        .pages(0x100, 0..0x400, PageAlign::Absolute)
+        .nth(1)
+        .unwrap();
+    assert_eq!(
+        Vec::from_iter(page.slices()),
+        vec![
+            (0x100..0x110, &[1; 0x10][..]),
+            (0x180..0x200, &[2; 0x80][..])
+        ]
+    );
+    assert_eq!(Vec::from_iter(page.gaps()), vec![0x110..0x180]);
+
+    // Pages following the range start, cut by its end
+    let pages = Vec::from_iter(map.pages(0x80, 0x180..0x310, PageAlign::RangeStart));
+    assert_eq!(
+        Vec::from_iter(pages.iter().map(|page| (page.range(), page.coverage()))),
+        vec![
+            (0x180..0x200, PageCoverage::Full),
+            (0x200..0x280, PageCoverage::Full),
+            (0x280..0x300, PageCoverage::Partial),
+            (0x300..0x310, PageCoverage::Partial),
+        ]
+    );
+    assert_eq!(
+        Vec::from_iter(pages[2].slices()),
+        vec![(0x2f8..0x300, &[3; 8][..])]
+    );
+    assert_eq!(Vec::from_iter(pages[2].gaps()), vec![0x280..0x2f8]);
+    assert_eq!(Vec::from_iter(pages[3].gaps()), vec![0x308..0x310]);
+
+    // Absolute pages are clipped to the range
+    let pages = Vec::from_iter(map.pages(0x100, 0x0f8..0x108, PageAlign::Absolute));
+    assert_eq!(
+        Vec::from_iter(
+            pages
+                .iter()
+                .map(|page| (page.base(), page.range(), page.coverage()))
+        ),
+        vec![
+            (0x000, 0x0f8..0x100, PageCoverage::Full),
+            (0x100, 0x100..0x108, PageCoverage::Full),
+        ]
+    );
+    assert_eq!(
+        map.pages(0x100, 0x500..0x600, PageAlign::Absolute)
+            .map(|page| page.coverage())
+            .collect::<Vec<_>>(),
+        vec![PageCoverage::Empty]
+    );
+    assert_eq!(map.pages(0x10, 0x40..0x40, PageAlign::Absolute).count(), 0);
+
+    let mut top = SparseVec::<u8>::new();
+    top.insert(vec![4; 0x10], u64::MAX - 0x10);
+    let pages = Vec::from_iter(top.pages(0x100, u64::MAX - 0x100..u64::MAX, PageAlign::Absolute));
+    assert_eq!(pages.len(), 2);
+    assert_eq!(pages[1].range(), u64::MAX - 0xff..u64::MAX);
+    assert_eq!(pages[1].coverage(), PageCoverage::Partial);
+}
@@ -0,0 +1,110 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{cast_range, clip_range, sub_range, trace, Address, SparseVec, WatchOp};
+
+// Fills `dst` with repetitions of `pattern`, starting at `pattern[phase]`
+fn tile<T: Copy>(dst: &mut [T], pattern: &[T], phase: usize) {
+    let head = dst.len().min(pattern.len() - phase);
+    let (first, rest) = dst.split_at_mut(head);
+    first.copy_from_slice(&pattern[phase..phase + head]);
+    for chunk in rest.chunks_mut(pattern.len()) {
+        chunk.copy_from_slice(&pattern[..chunk.len()]);
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Covers `range` with repetitions of `pattern`, so that `range.start + k` gets
+    /// `pattern[k % pattern.len()]`, overwriting stored values like [`SparseVec::fill`].
+    /// Stored blocks are written in place, only the gaps are allocated.
+    ///
+    /// Panics if `pattern` is empty.
+    pub fn fill_pattern(&mut self, range: Range<A>, pattern: &[T]) {
+        assert!(!pattern.is_empty(), "fill pattern must not be empty");
+        let Some(pieces) = self.mirrored(&range) else {
+            return self.fill_pattern_at(range, pattern, 0);
+        };
+        let mut phase = 0;
+        for piece in pieces {
+            let len = (piece.end - piece.start).to_usize();
+            self.fill_pattern_at(piece, pattern, phase);
+            phase = (phase + len % pattern.len()) % pattern.len();
+        }
+    }
+
+    fn fill_pattern_at(&mut self, range: Range<A>, pattern: &[T], phase: usize) {
+        if range.is_empty() {
+            return;
+        }
+        self.assert_thawed(&range);
+        trace::event!(
+            DEBUG,
+            start = %trace::Hex(range.start),
+            end = %trace::Hex(range.end),
+            "fill pattern"
+        );
+        self.watch.notify(&range, WatchOp::Fill);
+        self.record_history(&range);
+
+        let phase_at =
+            |addr: A| (phase + (addr - range.start).to_usize() % pattern.len()) % pattern.len();
+        let blocks = Vec::from_iter(
+            self.map
+                .overlapping(range.clone())
+                .map(|(block, &key)| (clip_range(block, &range), block.start, key)),
+        );
+        for (clipped, block_start, key) in blocks {
+            let slice =
+                &mut self.data.get_mut(&key).unwrap()[cast_range(sub_range(&clipped, block_start))];
+            tile(slice, pattern, phase_at(clipped.start));
+        }
+        let gaps = Vec::from_iter(self.gaps(range.clone()));
+        for gap in gaps {
+            let mut data = Vec::with_capacity((gap.end - gap.start).to_usize());
+            // Pushing pattern-sized chunks avoids initializing the gap twice
+            let mut offset = phase_at(gap.start);
+            while data.len() < data.capacity() {
+                let len = (data.capacity() - data.len()).min(pattern.len() - offset);
+                data.extend_from_slice(&pattern[offset..offset + len]);
+                offset = 0;
+            }
+            self.insert_unwatched(data, gap.start);
+        }
+    }
+}
+
+#[test]
+fn sparsevec_fill_pattern() {
+    let mut map = SparseVec::new();
+    map.insert(vec![9u8; 2], 0x12);
+    map.insert(vec![9u8; 1], 0x17);
+    map.insert(vec![8u8; 4], 0x30);
+    // 11 bytes, not a multiple of the pattern, which is longer than the blocks it overwrites
+    map.fill_pattern(0x10..0x1b, &[1, 2, 3, 4]);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x10..0x1b, 0x30..0x34]);
+    assert_eq!(
+        map.get(0x10..0x1b).unwrap(),
+        &[1, 2, 3, 4, 1, 2, 3, 4, 1, 2, 3]
+    );
+
+    // The phase follows the start of the range, not the address
+    map.fill_pattern(0x2e..0x33, &[0xde, 0xad, 0xbe]);
+    assert_eq!(
+        map.get(0x2e..0x34).unwrap(),
+        &[0xde, 0xad, 0xbe, 0xde, 0xad, 8]
+    );
+    map.fill_pattern(0x11..0x12, &[7, 6]);
+    assert_eq!(map.get(0x10..0x13).unwrap(), &[1, 7, 3]);
+
+    // Phase carries over between the pieces of a mirrored range
+    let mut mirrored = SparseVec::new();
+    mirrored.add_mirror(0..4, 4, 4);
+    mirrored.fill_pattern(2..7, &[1u8, 2, 3]);
+    assert_eq!(mirrored.get(0..4).unwrap(), &[3, 1, 2, 2]);
+}
+
+#[test]
+#[should_panic(expected = "fill pattern must not be empty")]
+fn sparsevec_fill_pattern_empty() {
+    SparseVec::<u8>::new().fill_pattern(0..4, &[]);
+}
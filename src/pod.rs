@@ -0,0 +1,92 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use bytemuck::Pod;
+
+use crate::{Address, SparseVec, Unmapped};
+
+impl<A: Address> SparseVec<u8, A> {
+    /// Reads a `U` from the bytes at `addr`, which need not be aligned.
+    /// `None` if any byte is unmapped.
+    pub fn read_pod<U: Pod>(&self, addr: A) -> Option<U> {
+        let mut value = U::zeroed();
+        self.read_into_exact(addr, bytemuck::bytes_of_mut(&mut value))
+            .ok()?;
+        Some(value)
+    }
+
+    /// Reads `count` consecutive `U`s starting at `addr`. `None` if any byte is unmapped.
+    pub fn read_pod_slice<U: Pod>(&self, addr: A, count: usize) -> Option<Vec<U>> {
+        let len = count.checked_mul(core::mem::size_of::<U>())?;
+        let end = A::try_from_u64(len as u64).and_then(|len| addr.checked_add(len))?;
+        // Coverage first, so a huge `count` over unmapped bytes allocates nothing
+        let range = addr..end;
+        let covered = match self.mirrored(&range) {
+            Some(pieces) => self.first_unmapped_mirrored(addr, &pieces).is_ok(),
+            None => self.contains_range(&range),
+        };
+        if !covered {
+            return None;
+        }
+        let mut values = vec![U::zeroed(); count];
+        self.read_into_exact(addr, bytemuck::cast_slice_mut(&mut values))
+            .ok()?;
+        Some(values)
+    }
+
+    /// Writes the bytes of `value` through existing coverage.
+    pub fn write_pod<U: Pod>(&mut self, addr: A, value: &U) -> Result<(), Unmapped<A>> {
+        self.write(addr, bytemuck::bytes_of(value))
+    }
+}
+
+#[test]
+fn sparsevec_pod() {
+    #[repr(C)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    struct Header {
+        magic: u32,
+        version: u16,
+        flags: u16,
+        entry: u64,
+    }
+    unsafe impl bytemuck::Zeroable for Header {}
+    unsafe impl bytemuck::Pod for Header {}
+
+    let header = Header {
+        magic: 0x7f45_4c46,
+        version: 2,
+        flags: 0x8001,
+        entry: 0x0040_1000,
+    };
+    let bytes = bytemuck::bytes_of(&header);
+
+    let mut map = SparseVec::new();
+    map.insert(bytes[..5].to_vec(), 0x1001);
+    map.insert(bytes[5..].to_vec(), 0x1006);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x1001..0x1011]);
+    assert_eq!(map.read_pod::<Header>(0x1001), Some(header));
+    assert_eq!(map.read_pod::<Header>(0x1002), None);
+    assert_eq!(map.read_pod::<u32>(0x1001), Some(0x7f45_4c46));
+
+    let updated = Header { flags: 0, ..header };
+    map.write_pod(0x1001, &updated).unwrap();
+    assert_eq!(map.read_pod::<Header>(0x1001), Some(updated));
+    assert_eq!(
+        map.write_pod(0x1002, &updated),
+        Err(Unmapped { addr: 0x1011 })
+    );
+    assert_eq!(map.read_pod::<Header>(0x1001), Some(updated));
+
+    map.insert(bytemuck::cast_slice(&[1u32, 2, 3]).to_vec(), 0x2003);
+    assert_eq!(map.read_pod_slice::<u32>(0x2003, 3), Some(vec![1, 2, 3]));
+    assert_eq!(map.read_pod_slice::<u32>(0x2003, 4), None);
+    assert_eq!(map.read_pod_slice::<u32>(0x2003, 0), Some(vec![]));
+    assert_eq!(map.read_pod_slice::<u64>(0x2003, usize::MAX), None);
+    assert_eq!(map.read_pod_slice::<u8>(0x2003, usize::MAX / 2), None);
+    assert_eq!(map.read_pod_slice::<u8>(u64::MAX - 1, 2), None);
+
+    let mut map = SparseVec::<u8>::with_address_modulus(0x100);
+    map.insert(bytemuck::cast_slice(&[1u16, 2]).to_vec(), 0xfe);
+    assert_eq!(map.read_pod_slice::<u16>(0x1fe, 2), Some(vec![1, 2]));
+}
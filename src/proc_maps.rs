@@ -0,0 +1,162 @@
+use std::fs::{self, File};
+use std::io;
+use std::ops::Range;
+use std::os::unix::fs::FileExt;
+
+use crate::SparseVec;
+
+/// A line of `/proc/<pid>/maps`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapRegion {
+    pub range: Range<u64>,
+    pub readable: bool,
+    pub writable: bool,
+    pub executable: bool,
+    /// `s` instead of `p` in the permissions.
+    pub shared: bool,
+    /// Offset into the mapped file.
+    pub offset: u64,
+    /// The mapped file, a pseudo path like `[heap]` or `[stack]`, or empty for anonymous
+    /// mappings.
+    pub path: String,
+}
+
+/// A region [`SparseVec::from_pid`] selected but could not read, which is left unmapped.
+#[derive(Debug)]
+pub struct SkippedRegion {
+    pub region: MapRegion,
+    pub error: io::Error,
+}
+
+impl SparseVec<u8> {
+    /// Snapshot of the memory of process `pid` in the regions of `/proc/<pid>/maps` that
+    /// `filter` selects, read from `/proc/<pid>/mem`. Needs the same permissions as
+    /// `ptrace`, so usually the same user or `CAP_SYS_PTRACE`.
+    ///
+    /// Regions that are not readable, or fail to read because the process unmapped them in
+    /// the meantime, are left unmapped and returned with the error instead of failing the
+    /// snapshot. Regions read while the process runs are not consistent with each other.
+    pub fn from_pid(
+        pid: i32,
+        filter: impl Fn(&MapRegion) -> bool,
+    ) -> io::Result<(Self, Vec<SkippedRegion>)> {
+        let regions = parse_maps(&fs::read_to_string(format!("/proc/{pid}/maps"))?)?;
+        let mem = File::open(format!("/proc/{pid}/mem"))?;
+        let mut blocks = Vec::new();
+        let mut skipped = Vec::new();
+        for region in regions.into_iter().filter(|region| filter(region)) {
+            if region.range.is_empty() {
+                continue;
+            }
+            match read_region(&mem, &region) {
+                Ok(data) => blocks.push((region.range.start, data)),
+                Err(error) => skipped.push(SkippedRegion { region, error }),
+            }
+        }
+        Ok((Self::from_sorted_blocks(blocks), skipped))
+    }
+}
+
+fn read_region(mem: &File, region: &MapRegion) -> io::Result<Vec<u8>> {
+    if !region.readable {
+        return Err(io::ErrorKind::PermissionDenied.into());
+    }
+    let len = usize::try_from(region.range.end - region.range.start)
+        .map_err(|_| io::ErrorKind::OutOfMemory)?;
+    let mut data = vec![0; len];
+    // Fails with `UnexpectedEof` or `EIO` if the region is gone
+    mem.read_exact_at(&mut data, region.range.start)?;
+    Ok(data)
+}
+
+fn parse_maps(maps: &str) -> io::Result<Vec<MapRegion>> {
+    maps.lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            parse_line(line).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("malformed maps line {line:?}"),
+                )
+            })
+        })
+        .collect()
+}
+
+// `start-end perms offset dev inode path`, with the path padded to a column
+fn parse_line(line: &str) -> Option<MapRegion> {
+    let mut fields = line.splitn(6, ' ');
+    let (start, end) = fields.next()?.split_once('-')?;
+    let perms = fields.next()?.as_bytes();
+    let offset = fields.next()?;
+    let _dev = fields.next()?;
+    let _inode = fields.next()?;
+    let path = fields.next().unwrap_or("").trim_start();
+    if perms.len() != 4 {
+        return None;
+    }
+    Some(MapRegion {
+        range: u64::from_str_radix(start, 16).ok()?..u64::from_str_radix(end, 16).ok()?,
+        readable: perms[0] == b'r',
+        writable: perms[1] == b'w',
+        executable: perms[2] == b'x',
+        shared: perms[3] == b's',
+        offset: u64::from_str_radix(offset, 16).ok()?,
+        path: path.into(),
+    })
+}
+
+#[test]
+fn sparsevec_parse_maps() {
+    let maps = "\
+55d0c3a1e000-55d0c3a20000 r--p 00000000 08:01 1234                       /usr/bin/cat
+55d0c3a20000-55d0c3a25000 r-xp 00002000 08:01 1234                       /usr/bin/my prog (deleted)
+55d0c4b3f000-55d0c4b60000 rw-p 00000000 00:00 0                          [heap]
+7f1b2c000000-7f1b2c021000 rw-s 00000000 00:05 99                         
+7ffd1e5f0000-7ffd1e611000 rw-p 00000000 00:00 0                          [stack]
+ffffffffff600000-ffffffffff601000 --xp 00000000 00:00 0                  [vsyscall]
+";
+    let regions = parse_maps(maps).unwrap();
+    assert_eq!(regions.len(), 6);
+    assert_eq!(
+        regions[1],
+        MapRegion {
+            range: 0x55d0c3a20000..0x55d0c3a25000,
+            readable: true,
+            writable: false,
+            executable: true,
+            shared: false,
+            offset: 0x2000,
+            path: "/usr/bin/my prog (deleted)".into(),
+        }
+    );
+    assert_eq!(regions[2].path, "[heap]");
+    assert!(regions[3].shared && regions[3].path.is_empty());
+    assert_eq!(regions[5].range.end, 0xffffffffff601000);
+    assert!(!regions[5].readable);
+    assert!(parse_maps("55d0c3a1e000 r--p 0 08:01 1").is_err());
+}
+
+#[test]
+fn sparsevec_from_pid() {
+    let data = Box::new(*b"sparse_vec snapshot of its own process");
+    let addr = data.as_ptr() as u64;
+    let pid = std::process::id() as i32;
+    let (snapshot, skipped) = SparseVec::from_pid(pid, |region| {
+        region.range.contains(&addr) || region.path == "[vsyscall]"
+    })
+    .unwrap();
+    assert_eq!(
+        snapshot.get(addr..addr + data.len() as u64).unwrap(),
+        &data[..]
+    );
+    assert_eq!(snapshot.ranges().len(), 1);
+    // The vsyscall page is execute only, or missing entirely
+    assert!(skipped
+        .iter()
+        .all(|skipped| skipped.region.path == "[vsyscall]"));
+
+    let (empty, skipped) = SparseVec::from_pid(pid, |_| false).unwrap();
+    assert_eq!((empty.ranges().len(), skipped.len()), (0, 0));
+    assert!(SparseVec::from_pid(-1, |_| true).is_err());
+}
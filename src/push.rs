@@ -0,0 +1,94 @@
+use alloc::vec::Vec;
+
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Where [`SparseVec::push_back`] places data while the `SparseVec` is empty, zero by
+    /// default.
+    pub fn set_push_origin(&mut self, origin: A) {
+        self.push_origin = origin;
+    }
+
+    pub fn push_origin(&self) -> A {
+        self.push_origin
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Inserts `data` right after the highest stored address, or at the
+    /// [push origin](SparseVec::set_push_origin) if nothing is stored, and returns the
+    /// address it was placed at. Takes the append fast path of [`SparseVec::insert`],
+    /// extending the highest block in place.
+    ///
+    /// Panics if the data does not fit in the address space.
+    pub fn push_back(&mut self, data: Vec<T>) -> A {
+        self.push_back_with_gap(data, A::ZERO)
+    }
+
+    /// Like [`SparseVec::push_back`], but leaves `gap` addresses unmapped before the data.
+    pub fn push_back_with_gap(&mut self, data: Vec<T>, gap: A) -> A {
+        let end = self.bounds().map_or(self.push_origin, |bounds| bounds.end);
+        let len = A::try_from_u64(data.len() as u64);
+        let addr = end
+            .checked_add(gap)
+            .filter(|addr| len.and_then(|len| addr.checked_add(len)).is_some())
+            .expect("push_back past the end of the address space");
+        self.insert(data, addr);
+        addr
+    }
+}
+
+#[test]
+fn sparsevec_push_back() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(182);
+    let mut map = SparseVec::new();
+    map.set_push_origin(0x1000);
+    let mut pushed = Vec::new();
+    let mut expected = 0x1000;
+    for i in 0..5000u32 {
+        let data = vec![i; rng.gen_range(1..8)];
+        let addr = map.push_back(data.clone());
+        assert_eq!(addr, expected);
+        expected += data.len() as u64;
+        pushed.push((addr, data));
+    }
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x1000..expected]);
+    for (addr, data) in pushed {
+        assert_eq!(map.get(addr..addr + data.len() as u64).unwrap(), &data[..]);
+    }
+}
+
+#[test]
+fn sparsevec_push_back_layout() {
+    let mut map = SparseVec::<u8>::with_max_block_len(0x10);
+    assert_eq!(map.push_back_with_gap(vec![1; 4], 0x100), 0x100);
+    assert_eq!(map.push_back(vec![2; 0x20]), 0x104);
+    assert_eq!(map.push_back_with_gap(vec![3; 2], 0x0c), 0x130);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x110, 0x110..0x120, 0x120..0x124, 0x130..0x132]
+    );
+
+    // Data inserted above or removed from the top moves the next address
+    map.insert(vec![4; 4], 0x200);
+    assert_eq!(map.push_back(vec![5]), 0x204);
+    map.retain_blocks(|range, _| range.start < 0x180);
+    assert_eq!(map.push_back(vec![6]), 0x132);
+    map.set_auto_merge(false);
+    assert_eq!(map.push_back(vec![7; 2]), 0x133);
+    assert_eq!(map.ranges().next_back(), Some(0x133..0x135));
+    assert_eq!(
+        Vec::from_iter(map.iter_range(0x130..0x135).map(|(_, v)| *v)),
+        vec![3, 3, 6, 7, 7]
+    );
+}
+
+#[test]
+#[should_panic(expected = "push_back past the end of the address space")]
+fn sparsevec_push_back_full() {
+    let mut map = SparseVec::<u8>::new();
+    map.insert(vec![0; 2], u64::MAX - 2);
+    map.push_back(vec![1]);
+}
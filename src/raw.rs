@@ -0,0 +1,232 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::storage::Storage;
+use crate::{Address, SparseVec, WatchOp};
+
+// Puts the data back into the block if `f` panics, or removes the block if its length
+// already changed
+struct Restore<'a, T, A: Address> {
+    vec: &'a mut SparseVec<T, A>,
+    range: Range<A>,
+    key: usize,
+    data: Option<Vec<T>>,
+}
+
+impl<T, A: Address> Drop for Restore<'_, T, A> {
+    fn drop(&mut self) {
+        let Some(data) = self.data.take() else {
+            return;
+        };
+        if data.len() == (self.range.end - self.range.start).to_usize() {
+            self.vec.put_back(self.key, data);
+        } else {
+            self.vec.map.remove(self.range.clone());
+            self.vec.data.remove(&self.key);
+        }
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Calls `f` with the range and data of the block containing `addr`, for what the rest
+    /// of the API does not cover. Shared and aligned blocks are copied into a `Vec` first.
+    /// Returns `None` if `addr` is unmapped.
+    ///
+    /// `f` may change the length of the data. The block keeps its start, so:
+    /// - Shrinking unmaps the addresses past the new end, or the whole block if empty.
+    /// - Growing maps the addresses past the old end, which must be unmapped, not frozen and
+    ///   in the address space. Otherwise this panics, with the data truncated back to its old
+    ///   length. The grown block is not merged with adjacent blocks, and not split by
+    ///   [`SparseVec::with_max_block_len`].
+    ///
+    /// If `f` panics, the block keeps the data, or is removed if the length changed.
+    ///
+    /// Panics if the block is frozen.
+    pub fn with_block_raw<R>(
+        &mut self,
+        addr: A,
+        f: impl FnOnce(&Range<A>, &mut Vec<T>) -> R,
+    ) -> Option<R> {
+        let (range, key) = self.map.get_key_value(&addr)?;
+        let (range, key) = (range.clone(), *key);
+        self.assert_thawed(&range);
+        self.stop_journal("with_block_raw");
+        self.record_history(&range);
+        self.touch(key);
+        let storage = self.data.get_mut(&key).unwrap();
+        let data = core::mem::replace(storage, Storage::Vec(Vec::new())).into_vec();
+
+        let mut restore = Restore {
+            vec: self,
+            range: range.clone(),
+            key,
+            data: Some(data),
+        };
+        let result = f(&range, restore.data.as_mut().unwrap());
+        let mut data = restore.data.take().unwrap();
+        drop(restore);
+
+        let old_len = (range.end - range.start).to_usize();
+        let len = data.len();
+        if len <= old_len {
+            let new = range.start..range.start + A::from_usize(len);
+            if len < old_len {
+                self.map.remove(new.end..range.end);
+            }
+            if new.is_empty() {
+                self.data.remove(&key);
+            } else {
+                self.put_back(key, data);
+            }
+            self.watch.notify(&range, WatchOp::GetMut);
+            return Some(result);
+        }
+
+        let end = A::try_from_u64(len as u64).and_then(|len| range.start.checked_add(len));
+        let Some(end) = end else {
+            data.truncate(old_len);
+            self.put_back(key, data);
+            panic!(
+                "with_block_raw grew the block at {:#x} past the end of the address space",
+                range.start
+            );
+        };
+        let grown = range.end..end;
+        if let Some((stored, _)) = self.map.overlapping(&grown).next() {
+            let stored = stored.start;
+            data.truncate(old_len);
+            self.put_back(key, data);
+            panic!(
+                "with_block_raw grew the block at {:#x} into stored data at {stored:#x}",
+                range.start
+            );
+        }
+        if let Err(err) = self.check_frozen(&grown) {
+            data.truncate(old_len);
+            self.put_back(key, data);
+            panic!("{err}");
+        }
+        self.record_history(&grown);
+        let new = range.start..end;
+        self.map.insert(new.clone(), key);
+        self.put_back(key, data);
+        self.watch.notify(&new, WatchOp::GetMut);
+        self.enforce_capacity(&new);
+        Some(result)
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    fn put_back(&mut self, key: usize, data: Vec<T>) {
+        *self.data.get_mut(&key).unwrap() = Storage::new(data, self.block_align);
+    }
+}
+
+#[test]
+fn sparsevec_with_block_raw() {
+    let mut map = crate::sparse_vec! {
+        0x100 => [1u8, 2, 3, 4],
+        0x200 => [5; 4],
+        0x208 => [6; 4],
+    };
+    assert_eq!(map.with_block_raw(0x180, |_, _| ()), None);
+
+    // Same length
+    let seen = map.with_block_raw(0x102, |range, data| {
+        data.reverse();
+        range.clone()
+    });
+    assert_eq!(seen, Some(0x100..0x104));
+    assert_eq!(map.get(0x100..0x104).unwrap(), &[4, 3, 2, 1]);
+
+    // Shrinking unmaps the end
+    map.with_block_raw(0x100, |_, data| data.truncate(1));
+    map.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x101, 0x200..0x204, 0x208..0x20c]
+    );
+    assert_eq!(map.get(0x100..0x101).unwrap(), &[4]);
+    map.with_block_raw(0x100, |_, data| data.clear());
+    map.assert_invariants();
+    assert_eq!(map.ranges().len(), 2);
+
+    // Growing up to the next block, which stays separate
+    map.with_block_raw(0x203, |_, data| data.extend([7; 4]));
+    map.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x200..0x208, 0x208..0x20c]
+    );
+    assert_eq!(map.get(0x200..0x208).unwrap(), &[5, 5, 5, 5, 7, 7, 7, 7]);
+    assert_eq!(
+        Vec::from_iter(map.iter_range(0x206..0x20a).map(|(_, v)| *v)),
+        vec![7, 7, 6, 6]
+    );
+
+    // Shared data is copied
+    let shared: alloc::sync::Arc<[u8]> = alloc::sync::Arc::from([8; 4]);
+    map.insert_shared(shared.clone(), 0x300);
+    map.with_block_raw(0x300, |_, data| data.push(9));
+    assert!(!map.is_shared(0x300));
+    assert_eq!(map.get(0x300..0x305).unwrap(), &[8, 8, 8, 8, 9]);
+    assert_eq!(*shared, [8; 4]);
+}
+
+#[test]
+fn sparsevec_with_block_raw_invalid() {
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    let mut map = crate::sparse_vec! {
+        0x100 => [1u8; 4],
+        0x106 => [2; 2],
+        u64::MAX - 2 => [3; 2],
+    };
+    fn raw(map: &mut SparseVec<u8>, addr: u64, f: fn(&mut Vec<u8>)) -> Result<(), String> {
+        catch_unwind(AssertUnwindSafe(|| {
+            map.with_block_raw(addr, |_, data| f(data));
+        }))
+        .map_err(|err| {
+            err.downcast::<String>()
+                .map_or_else(|_| String::new(), |msg| *msg)
+        })
+    }
+
+    // Growing into stored data or past the address space keeps the old length
+    assert_eq!(
+        raw(&mut map, 0x100, |data| data.extend([0; 3])),
+        Err("with_block_raw grew the block at 0x100 into stored data at 0x106".into())
+    );
+    assert_eq!(
+        raw(&mut map, u64::MAX - 2, |data| data.extend([0; 3])),
+        Err(format!(
+            "with_block_raw grew the block at {:#x} past the end of the address space",
+            u64::MAX - 2
+        ))
+    );
+    // A panicking closure keeps the block if the length is unchanged
+    assert!(raw(&mut map, 0x100, |data| {
+        data[0] = 4;
+        panic!()
+    })
+    .is_err());
+    assert!(raw(&mut map, 0x106, |data| {
+        data.pop();
+        panic!()
+    })
+    .is_err());
+    map.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x104, u64::MAX - 2..u64::MAX]
+    );
+    assert_eq!(map.get(0x100..0x104).unwrap(), &[4, 1, 1, 1]);
+    assert_eq!(map.get(u64::MAX - 2..u64::MAX).unwrap(), &[3; 2]);
+
+    // Frozen addresses cannot be grown into
+    map.freeze(0x104..0x105);
+    assert!(raw(&mut map, 0x100, |data| data.push(0)).is_err());
+    assert_eq!(map.get(0x100..0x104).unwrap(), &[4, 1, 1, 1]);
+    map.with_block_raw(0x100, |_, data| data.truncate(3));
+    map.assert_invariants();
+}
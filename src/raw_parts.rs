@@ -0,0 +1,224 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// The blocks with their ranges, in address order and with adjacent blocks merged, for
+    /// taking the data apart without copying unshared blocks. The inverse of
+    /// [`SparseVec::from_raw_parts`].
+    pub fn into_raw_parts(mut self) -> Vec<(Range<A>, Vec<T>)> {
+        let mut parts: Vec<(Range<A>, Vec<T>)> = Vec::with_capacity(self.map.len());
+        for (range, key) in self.map.iter() {
+            let data = self.data.remove(key).unwrap().into_vec();
+            match parts.last_mut() {
+                Some((last, last_data)) if last.end == range.start => {
+                    last.end = range.end;
+                    last_data.extend(data);
+                }
+                _ => parts.push((range.clone(), data)),
+            }
+        }
+        parts
+    }
+
+    /// `SparseVec` storing every part as a block, without copying the data of parts not
+    /// adjacent to the previous one. Adjacent parts are merged into the first of them, and
+    /// parts with empty ranges are skipped.
+    ///
+    /// # Safety
+    /// The parts must be sorted by address and must not overlap, and the data of every part
+    /// must have as many elements as its range has addresses, as
+    /// [`SparseVec::try_from_raw_parts`] checks. Methods like [`SparseVec::get_unchecked`]
+    /// rely on it. Debug builds panic otherwise.
+    pub unsafe fn from_raw_parts(parts: Vec<(Range<A>, Vec<T>)>) -> Self {
+        if cfg!(debug_assertions) {
+            if let Err(err) = check_parts(&parts) {
+                panic!("invalid raw parts: {err}");
+            }
+        }
+        Self::from_sorted_blocks(
+            parts
+                .into_iter()
+                .filter(|(range, _)| !range.is_empty())
+                .map(|(range, data)| (range.start, data)),
+        )
+    }
+
+    /// Like [`SparseVec::from_raw_parts`], but checks the parts first.
+    pub fn try_from_raw_parts(parts: Vec<(Range<A>, Vec<T>)>) -> Result<Self, RawPartsError<A>> {
+        check_parts(&parts)?;
+        // SAFETY: just checked
+        Ok(unsafe { Self::from_raw_parts(parts) })
+    }
+}
+
+fn check_parts<T, A: Address>(parts: &[(Range<A>, Vec<T>)]) -> Result<(), RawPartsError<A>> {
+    let mut previous_end = None;
+    for (index, (range, data)) in parts.iter().enumerate() {
+        if range.start > range.end || (range.end - range.start).to_u64() != data.len() as u64 {
+            return Err(RawPartsError::LengthMismatch {
+                index,
+                range: range.clone(),
+                len: data.len(),
+            });
+        }
+        if range.is_empty() {
+            continue;
+        }
+        if let Some(previous_end) = previous_end.filter(|&end| range.start < end) {
+            return Err(RawPartsError::Overlap {
+                index,
+                start: range.start,
+                previous_end,
+            });
+        }
+        previous_end = Some(range.end);
+    }
+    Ok(())
+}
+
+/// Why [`SparseVec::try_from_raw_parts`] rejected its parts, with the index of the first
+/// invalid part.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawPartsError<A = u64> {
+    /// The part starts before the end of the previous part, so they are unsorted or overlap.
+    Overlap {
+        index: usize,
+        start: A,
+        previous_end: A,
+    },
+    /// The part has `len` elements, but its range has a different number of addresses.
+    LengthMismatch {
+        index: usize,
+        range: Range<A>,
+        len: usize,
+    },
+}
+
+impl<A: Address> fmt::Display for RawPartsError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RawPartsError::Overlap {
+                index,
+                start,
+                previous_end,
+            } => write!(
+                f,
+                "part {index} at {start:#x} starts before the end of the previous part at \
+                 {previous_end:#x}"
+            ),
+            RawPartsError::LengthMismatch { index, range, len } => write!(
+                f,
+                "part {index} has {len} elements for range {:#x}..{:#x}",
+                range.start, range.end
+            ),
+        }
+    }
+}
+
+impl<A: Address> core::error::Error for RawPartsError<A> {}
+
+#[test]
+fn sparsevec_raw_parts() {
+    let mut map = crate::sparse_vec! {
+        0x100 => [1u8; 4],
+        0x200 => [2; 4],
+    };
+    map.set_auto_merge(false);
+    map.insert(vec![3; 2], 0x204);
+    map.insert(vec![4, 5], u64::MAX - 2);
+    let shared: alloc::sync::Arc<[u8]> = alloc::sync::Arc::from([6; 4]);
+    map.insert_shared(shared, 0x300);
+    assert_eq!(map.ranges().len(), 5);
+
+    // Adjacent blocks come out merged
+    let parts = map.into_raw_parts();
+    assert_eq!(
+        parts,
+        vec![
+            (0x100..0x104, vec![1; 4]),
+            (0x200..0x206, vec![2, 2, 2, 2, 3, 3]),
+            (0x300..0x304, vec![6; 4]),
+            (u64::MAX - 2..u64::MAX, vec![4, 5]),
+        ]
+    );
+
+    // The data is moved, not copied
+    let expected = parts.clone();
+    let ptr = parts[0].1.as_ptr();
+    let map = SparseVec::try_from_raw_parts(parts).unwrap();
+    map.assert_invariants();
+    assert_eq!(map.get_block(0x100).unwrap().1.as_ptr(), ptr);
+    let parts = map.into_raw_parts();
+    assert_eq!(parts, expected);
+    assert_eq!(parts[0].1.as_ptr(), ptr);
+
+    let mut split = parts.clone();
+    split.insert(2, (0x206..0x206, Vec::new()));
+    split.insert(3, (0x206..0x208, vec![7; 2]));
+    let map = SparseVec::try_from_raw_parts(split).unwrap();
+    map.assert_invariants();
+    assert_eq!(map.get(0x204..0x208).unwrap(), &[3, 3, 7, 7]);
+    assert!(SparseVec::<u8>::try_from_raw_parts(Vec::new())
+        .unwrap()
+        .into_raw_parts()
+        .is_empty());
+}
+
+#[test]
+fn sparsevec_raw_parts_invalid() {
+    let parts = |ranges: &[Range<u64>]| {
+        Vec::from_iter(
+            ranges
+                .iter()
+                .map(|r| (r.clone(), vec![0u8; (r.end - r.start) as usize])),
+        )
+    };
+    let err = SparseVec::try_from_raw_parts(parts(&[0x10..0x20, 0x1c..0x30])).unwrap_err();
+    assert_eq!(
+        err,
+        RawPartsError::Overlap {
+            index: 1,
+            start: 0x1c,
+            previous_end: 0x20
+        }
+    );
+    assert_eq!(
+        err.to_string(),
+        "part 1 at 0x1c starts before the end of the previous part at 0x20"
+    );
+    assert!(matches!(
+        SparseVec::try_from_raw_parts(parts(&[0x10..0x20, 0x30..0x40, 0x00..0x08])),
+        Err(RawPartsError::Overlap { index: 2, .. })
+    ));
+
+    let mut short = parts(&[0x10..0x20, 0x20..0x30]);
+    short[1].1.pop();
+    let err = SparseVec::try_from_raw_parts(short).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "part 1 has 15 elements for range 0x20..0x30"
+    );
+    let reversed = vec![(
+        Range::<u64> {
+            start: 0x20,
+            end: 0x10,
+        },
+        Vec::<u8>::new(),
+    )];
+    assert!(matches!(
+        SparseVec::try_from_raw_parts(reversed),
+        Err(RawPartsError::LengthMismatch { index: 0, .. })
+    ));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "invalid raw parts: part 1 at 0x1c starts before")]
+fn sparsevec_from_raw_parts_debug_check() {
+    let parts = vec![(0x10u64..0x20, vec![0u8; 0x10]), (0x1c..0x20, vec![0; 4])];
+    // SAFETY: not upheld, which debug builds catch before anything relies on it
+    let _ = unsafe { SparseVec::from_raw_parts(parts) };
+}
@@ -0,0 +1,186 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::SparseVec;
+
+impl<T: Copy> SparseVec<T> {
+    /// View addressing the structure with signed offsets relative to `base`.
+    pub fn rebased_view(&self, base: u64) -> RebasedView<'_, T> {
+        RebasedView { vec: self, base }
+    }
+
+    /// Mutable variant of [`SparseVec::rebased_view`].
+    pub fn rebased_view_mut(&mut self, base: u64) -> RebasedViewMut<'_, T> {
+        RebasedViewMut { vec: self, base }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RebaseError {
+    /// The offsets translate to addresses outside of `0..u64::MAX`.
+    OutOfRange { start: i64, end: i64 },
+    /// A write needed data at `offset`, which is not stored.
+    Unmapped { offset: i64 },
+}
+
+impl fmt::Display for RebaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebaseError::OutOfRange { start, end } => {
+                write!(f, "offsets {start}..{end} are outside the address space")
+            }
+            RebaseError::Unmapped { offset } => write!(f, "offset {offset} is unmapped"),
+        }
+    }
+}
+
+impl core::error::Error for RebaseError {}
+
+fn to_addresses(base: u64, range: &Range<i64>) -> Result<Range<u64>, RebaseError> {
+    let translate = |offset: i64| u64::try_from(base as i128 + offset as i128).ok();
+    translate(range.start)
+        .zip(translate(range.end))
+        .map(|(start, end)| start..end)
+        .ok_or(RebaseError::OutOfRange {
+            start: range.start,
+            end: range.end,
+        })
+}
+
+// Only used for addresses that were translated from a valid offset.
+fn to_offset(base: u64, addr: u64) -> i64 {
+    (addr as i128 - base as i128) as i64
+}
+
+pub struct RebasedView<'a, T> {
+    vec: &'a SparseVec<T>,
+    base: u64,
+}
+
+impl<'a, T: Copy> RebasedView<'a, T> {
+    pub fn base(&self) -> u64 {
+        self.base
+    }
+
+    pub fn get(&self, range: Range<i64>) -> Result<Option<&'a [T]>, RebaseError> {
+        Ok(self.vec.get(to_addresses(self.base, &range)?))
+    }
+
+    pub fn slices(
+        &self,
+        range: Range<i64>,
+    ) -> Result<impl Iterator<Item = (Range<i64>, &'a [T])> + 'a, RebaseError> {
+        let base = self.base;
+        Ok(self
+            .vec
+            .slices(to_addresses(base, &range)?)
+            .map(move |(range, slice)| {
+                (
+                    to_offset(base, range.start)..to_offset(base, range.end),
+                    slice,
+                )
+            }))
+    }
+
+    pub fn iter_range(
+        &self,
+        range: Range<i64>,
+    ) -> Result<impl Iterator<Item = (i64, &'a T)> + 'a, RebaseError> {
+        let base = self.base;
+        Ok(self
+            .vec
+            .iter_range(to_addresses(base, &range)?)
+            .map(move |(addr, v)| (to_offset(base, addr), v)))
+    }
+}
+
+pub struct RebasedViewMut<'a, T> {
+    vec: &'a mut SparseVec<T>,
+    base: u64,
+}
+
+impl<T: Copy> RebasedViewMut<'_, T> {
+    pub fn as_view(&self) -> RebasedView<'_, T> {
+        RebasedView {
+            vec: self.vec,
+            base: self.base,
+        }
+    }
+
+    pub fn get_mut(&mut self, range: Range<i64>) -> Result<Option<&mut [T]>, RebaseError> {
+        Ok(self.vec.get_mut(to_addresses(self.base, &range)?))
+    }
+
+    pub fn insert(&mut self, data: Vec<T>, offset: i64) -> Result<(), RebaseError> {
+        let range = to_addresses(self.base, &self.offsets(offset, data.len())?)?;
+        self.vec.insert(data, range.start);
+        Ok(())
+    }
+
+    pub fn write(&mut self, offset: i64, data: &[T]) -> Result<(), RebaseError> {
+        let range = to_addresses(self.base, &self.offsets(offset, data.len())?)?;
+        self.vec
+            .write(range.start, data)
+            .map_err(|err| RebaseError::Unmapped {
+                offset: to_offset(self.base, err.addr),
+            })
+    }
+
+    fn offsets(&self, offset: i64, len: usize) -> Result<Range<i64>, RebaseError> {
+        let end = i64::try_from(len)
+            .ok()
+            .and_then(|len| offset.checked_add(len));
+        end.map(|end| offset..end).ok_or(RebaseError::OutOfRange {
+            start: offset,
+            end: i64::MAX,
+        })
+    }
+}
+
+#[test]
+fn sparsevec_rebased_view() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8, 2, 3, 4], 0x1000);
+    map.insert(vec![5u8, 6], 0x1010);
+
+    let view = map.rebased_view(0x1008);
+    assert_eq!(view.get(-8..-6), Ok(Some(&[1u8, 2][..])));
+    assert_eq!(view.get(-2..0), Ok(None));
+    assert_eq!(
+        Vec::from_iter(view.slices(-7..9).unwrap()),
+        vec![(-7..-4, &[2u8, 3, 4][..]), (8..9, &[5][..])]
+    );
+    assert_eq!(
+        Vec::from_iter(view.iter_range(-5..9).unwrap()),
+        vec![(-5, &4), (8, &5)]
+    );
+    assert_eq!(
+        view.get(-0x1009..0).unwrap_err(),
+        RebaseError::OutOfRange {
+            start: -0x1009,
+            end: 0
+        }
+    );
+
+    let top = map.rebased_view(u64::MAX - 1);
+    assert!(top.get(0..1).is_ok());
+    assert!(top.get(0..2).is_err());
+
+    let mut view = map.rebased_view_mut(0x1010);
+    view.insert(vec![9, 9], -1).unwrap();
+    view.write(-16, &[7, 7]).unwrap();
+    assert_eq!(
+        view.write(-13, &[0, 0]),
+        Err(RebaseError::Unmapped { offset: -12 })
+    );
+    assert_eq!(
+        view.insert(vec![0], i64::MIN),
+        Err(RebaseError::OutOfRange {
+            start: i64::MIN,
+            end: i64::MIN + 1
+        })
+    );
+    assert_eq!(view.as_view().get(-1..2), Ok(Some(&[9u8, 9, 6][..])));
+    assert_eq!(map.get(0x1000..0x1004).unwrap(), &[7, 7, 3, 4]);
+}
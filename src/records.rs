@@ -0,0 +1,201 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::SparseVec;
+
+const HEADER_LEN: usize = 12;
+
+/// Error while reading a record stream, with the byte offset of the record that failed.
+#[derive(Debug)]
+pub enum ImportError {
+    /// The reader failed.
+    Io { offset: u64, source: io::Error },
+    /// The stream ended inside the record.
+    Truncated { offset: u64 },
+    /// The record at `addr` with `len` bytes does not fit in the address space.
+    Overflow { offset: u64, addr: u64, len: u32 },
+}
+
+impl ImportError {
+    /// Byte offset of the start of the failing record in the stream.
+    pub fn offset(&self) -> u64 {
+        match self {
+            ImportError::Io { offset, .. }
+            | ImportError::Truncated { offset }
+            | ImportError::Overflow { offset, .. } => *offset,
+        }
+    }
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::Io { offset, source } => {
+                write!(f, "read error in record at offset {offset}: {source}")
+            }
+            ImportError::Truncated { offset } => {
+                write!(f, "stream truncated in record at offset {offset}")
+            }
+            ImportError::Overflow { offset, addr, len } => write!(
+                f,
+                "record at offset {offset} with {len} bytes at {addr:#x} overflows the address space"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Fills `buf` unless the stream ends first, returning the number of bytes read
+pub(crate) fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(read)
+}
+
+impl SparseVec<u8> {
+    /// Reads a stream of `(addr: u64 LE, len: u32 LE, bytes)` records, as written by
+    /// [`SparseVec::write_records_to`]. Later records overwrite earlier ones where they
+    /// overlap. See [`SparseVec::extend_from_reader`].
+    pub fn read_records_from<R: Read>(reader: R) -> Result<Self, ImportError> {
+        let mut vec = Self::new();
+        vec.extend_from_reader(reader)?;
+        Ok(vec)
+    }
+
+    /// Inserts every record of a stream in the format of [`SparseVec::read_records_from`]
+    /// until the reader is exhausted. Records are inserted as they are read and only one is
+    /// held in memory at a time. On error, the records before the failing one stay inserted.
+    pub fn extend_from_reader<R: Read>(&mut self, mut reader: R) -> Result<(), ImportError> {
+        let mut offset = 0u64;
+        loop {
+            let io = |source| ImportError::Io { offset, source };
+            let mut header = [0; HEADER_LEN];
+            match read_full(&mut reader, &mut header).map_err(io)? {
+                0 => return Ok(()),
+                HEADER_LEN => {}
+                _ => return Err(ImportError::Truncated { offset }),
+            }
+            let addr = u64::from_le_bytes(header[..8].try_into().unwrap());
+            let len = u32::from_le_bytes(header[8..].try_into().unwrap());
+            if addr.checked_add(len as u64).is_none() {
+                return Err(ImportError::Overflow { offset, addr, len });
+            }
+
+            // Grows with the data actually read instead of trusting `len` up front
+            let mut data = Vec::new();
+            (&mut reader)
+                .take(len as u64)
+                .read_to_end(&mut data)
+                .map_err(io)?;
+            if data.len() != len as usize {
+                return Err(ImportError::Truncated { offset });
+            }
+            self.insert(data, addr);
+            offset += (HEADER_LEN + len as usize) as u64;
+        }
+    }
+
+    /// Writes the stored data as records in address order, splitting blocks longer than
+    /// `u32::MAX` bytes.
+    pub fn write_records_to<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        for (range, data) in self.blocks() {
+            for (i, chunk) in data.chunks(u32::MAX as usize).enumerate() {
+                let addr = range.start + i as u64 * u32::MAX as u64;
+                writer.write_all(&addr.to_le_bytes())?;
+                writer.write_all(&(chunk.len() as u32).to_le_bytes())?;
+                writer.write_all(chunk)?;
+            }
+        }
+        writer.flush()
+    }
+}
+
+#[test]
+fn sparsevec_records() {
+    use std::io::Cursor;
+
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 0x10], 0x10);
+    map.insert(vec![2u8; 3], u64::MAX - 3);
+    map.insert(vec![3u8; 0x100], 0x8000_0000);
+
+    let mut stream = Vec::new();
+    map.write_records_to(&mut stream).unwrap();
+    assert_eq!(stream.len(), 3 * HEADER_LEN + 0x10 + 3 + 0x100);
+    assert_eq!(
+        stream[..HEADER_LEN],
+        [0x10, 0, 0, 0, 0, 0, 0, 0, 0x10, 0, 0, 0]
+    );
+    let read = SparseVec::read_records_from(Cursor::new(&stream)).unwrap();
+    assert!(read.blocks().eq(map.blocks()));
+    assert!(SparseVec::read_records_from(io::empty())
+        .unwrap()
+        .ranges()
+        .next()
+        .is_none());
+
+    // Later records overwrite, existing data is kept
+    let mut patch = Vec::new();
+    patch.extend_from_slice(&0x18u64.to_le_bytes());
+    patch.extend_from_slice(&4u32.to_le_bytes());
+    patch.extend_from_slice(&[4; 4]);
+    let mut extended = read;
+    extended.extend_from_reader(Cursor::new(&patch)).unwrap();
+    assert_eq!(extended.get(0x16..0x1e).unwrap(), &[1, 1, 4, 4, 4, 4, 1, 1]);
+}
+
+#[test]
+fn sparsevec_records_errors() {
+    use std::io::Cursor;
+
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 0x10], 0x10);
+    map.insert(vec![2u8; 0x10], 0x40);
+    let mut stream = Vec::new();
+    map.write_records_to(&mut stream).unwrap();
+    let second = (HEADER_LEN + 0x10) as u64;
+
+    // Cut inside the data and inside the header of the second record
+    for cut in [stream.len() - 1, second as usize + 5] {
+        let mut partial = SparseVec::new();
+        let err = partial
+            .extend_from_reader(Cursor::new(&stream[..cut]))
+            .unwrap_err();
+        assert!(matches!(err, ImportError::Truncated { offset } if offset == second));
+        assert_eq!(err.to_string(), "stream truncated in record at offset 28");
+        assert_eq!(Vec::from_iter(partial.ranges()), vec![0x10..0x20]);
+    }
+
+    let mut overflow = stream[..second as usize].to_vec();
+    overflow.extend_from_slice(&(u64::MAX - 1).to_le_bytes());
+    overflow.extend_from_slice(&2u32.to_le_bytes());
+    let err = SparseVec::read_records_from(Cursor::new(&overflow)).unwrap_err();
+    assert_eq!(err.offset(), second);
+    assert!(matches!(
+        err,
+        ImportError::Overflow { addr, len: 2, .. } if addr == u64::MAX - 1
+    ));
+
+    // A huge length is not allocated before the data arrives
+    let mut huge = 0u64.to_le_bytes().to_vec();
+    huge.extend_from_slice(&u32::MAX.to_le_bytes());
+    assert!(matches!(
+        SparseVec::read_records_from(Cursor::new(&huge)),
+        Err(ImportError::Truncated { offset: 0 })
+    ));
+}
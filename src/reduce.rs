@@ -0,0 +1,147 @@
+use core::iter::Sum;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Folds the elements stored within `range` in address order. Gaps are simply not visited.
+    pub fn fold_range<B>(&self, range: Range<A>, init: B, mut f: impl FnMut(B, A, &T) -> B) -> B {
+        self.slices(range).fold(init, |acc, (range, slice)| {
+            slice
+                .iter()
+                .enumerate()
+                .fold(acc, |acc, (i, v)| f(acc, range.start + A::from_usize(i), v))
+        })
+    }
+
+    /// Sum of the elements stored within `range`, zero if nothing is stored. Gaps are not
+    /// visited. Overflow behaves like [`Iterator::sum`].
+    pub fn sum_range(&self, range: Range<A>) -> T
+    where
+        T: Copy + Sum<T>,
+    {
+        // Summing each slice on its own keeps the inner loop simple enough to vectorize
+        self.slices(range)
+            .map(|(_, slice)| slice.iter().copied().sum::<T>())
+            .sum()
+    }
+
+    /// Smallest element stored within `range`, `None` if nothing is stored.
+    pub fn min_range(&self, range: Range<A>) -> Option<T>
+    where
+        T: Copy + Ord,
+    {
+        self.slices(range)
+            .filter_map(|(_, slice)| slice.iter().copied().min())
+            .min()
+    }
+
+    /// Largest element stored within `range`, `None` if nothing is stored.
+    pub fn max_range(&self, range: Range<A>) -> Option<T>
+    where
+        T: Copy + Ord,
+    {
+        self.slices(range)
+            .filter_map(|(_, slice)| slice.iter().copied().max())
+            .max()
+    }
+
+    /// Maximal runs of consecutive equal elements stored within `range`, in address order.
+    /// Runs end at gaps and at the edges of `range`.
+    pub fn runs(&self, range: Range<A>) -> impl Iterator<Item = (Range<A>, &T)> + '_
+    where
+        T: PartialEq,
+    {
+        self.slices(range).flat_map(|(clipped, slice)| {
+            let mut start = clipped.start;
+            slice.chunk_by(|a, b| a == b).map(move |run| {
+                let end = start + A::from_usize(run.len());
+                let item = (start..end, &run[0]);
+                start = end;
+                item
+            })
+        })
+    }
+}
+
+#[test]
+fn sparsevec_reductions() {
+    let map = crate::sparse_vec! {
+        0x10 => [5u32, 1, 7],
+        0x20 => [2; 4],
+        0x30 => [9],
+        0x1000 => [3, 4],
+    };
+
+    assert_eq!(map.sum_range(0..u64::MAX), 5 + 1 + 7 + 8 + 9 + 7);
+    assert_eq!(map.sum_range(0x11..0x22), 1 + 7 + 2 + 2);
+    assert_eq!(map.sum_range(0x13..0x20), 0);
+    assert_eq!(map.min_range(0..u64::MAX), Some(1));
+    assert_eq!(map.min_range(0x12..0x1001), Some(2));
+    assert_eq!(map.max_range(0..0x30), Some(7));
+    assert_eq!(map.max_range(0..0x31), Some(9));
+    assert_eq!(map.max_range(0x31..0x1000), None);
+    assert_eq!(map.min_range(0x40..0x40), None);
+
+    let visited = map.fold_range(0x12..0x1001, Vec::new(), |mut acc, addr, v| {
+        acc.push((addr, *v));
+        acc
+    });
+    assert_eq!(
+        visited,
+        vec![
+            (0x12, 7),
+            (0x20, 2),
+            (0x21, 2),
+            (0x22, 2),
+            (0x23, 2),
+            (0x30, 9),
+            (0x1000, 3)
+        ]
+    );
+    let weighted = map.fold_range(0..u64::MAX, 0u64, |acc, addr, v| acc + addr * *v as u64);
+    assert_eq!(
+        weighted,
+        0x10 * 5
+            + 0x11
+            + 0x12 * 7
+            + (0x20 + 0x21 + 0x22 + 0x23) * 2
+            + 0x30 * 9
+            + 0x1000 * 3
+            + 0x1001 * 4
+    );
+
+    let empty = SparseVec::<i8>::new();
+    assert_eq!(empty.sum_range(0..u64::MAX), 0);
+    assert_eq!(empty.fold_range(0..u64::MAX, 1, |acc, _, v| acc * *v), 1);
+}
+
+#[test]
+fn sparsevec_runs() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 0x1000], 0x1000);
+    map.insert(vec![1, 1, 2, 0, 0], 0x2000);
+    map.insert(vec![0u8; 4], 0x2008);
+
+    assert_eq!(
+        Vec::from_iter(map.runs(0..u64::MAX)),
+        vec![
+            (0x1000..0x2000, &0),
+            (0x2000..0x2002, &1),
+            (0x2002..0x2003, &2),
+            (0x2003..0x2005, &0),
+            // Equal values do not join across the gap
+            (0x2008..0x200c, &0),
+        ]
+    );
+    // Runs are clipped to the window
+    assert_eq!(
+        Vec::from_iter(map.runs(0x1ffe..0x2001)),
+        vec![(0x1ffe..0x2000, &0), (0x2000..0x2001, &1)]
+    );
+    assert_eq!(
+        Vec::from_iter(map.runs(0x2004..0x200a)),
+        vec![(0x2004..0x2005, &0), (0x2008..0x200a, &0)]
+    );
+    assert_eq!(map.runs(0x2005..0x2008).count(), 0);
+}
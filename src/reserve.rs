@@ -0,0 +1,143 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+/// What an address holds, see [`SparseVec::region_kind`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RegionKind {
+    /// Data is stored.
+    Populated,
+    /// Nothing is stored, but the address is reserved.
+    Reserved,
+    /// Nothing is stored or reserved.
+    Unmapped,
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Marks the unpopulated addresses in `range` as valid but not yet populated. Storing data
+    /// at a reserved address consumes its reservation.
+    ///
+    /// Gap-oriented methods like [`SparseVec::gaps`] and [`SparseVec::find_free_range`] treat
+    /// reserved addresses as unmapped. [`SparseVec::unreserved_gaps`] and
+    /// [`SparseVec::find_unreserved_range`] skip them.
+    pub fn reserve(&mut self, range: Range<A>) {
+        if range.is_empty() {
+            return;
+        }
+        self.reserved.insert(range.clone());
+        for (block, _) in self.map.overlapping(&range) {
+            self.reserved.remove(block.clone());
+        }
+    }
+
+    /// Removes the reservation of `range`. Stored data is not affected.
+    pub fn unreserve(&mut self, range: Range<A>) {
+        if !range.is_empty() {
+            self.reserved.remove(range);
+        }
+    }
+
+    pub fn is_reserved(&self, addr: A) -> bool {
+        self.reserved.contains(&addr)
+    }
+
+    /// Reserved ranges in address order, coalesced. They never overlap stored data.
+    pub fn reserved_ranges(&self) -> impl Iterator<Item = Range<A>> + '_ {
+        self.reserved.iter().cloned()
+    }
+
+    pub fn region_kind(&self, addr: A) -> RegionKind {
+        if self.map.contains_key(&addr) {
+            RegionKind::Populated
+        } else if self.reserved.contains(&addr) {
+            RegionKind::Reserved
+        } else {
+            RegionKind::Unmapped
+        }
+    }
+
+    /// Like [`SparseVec::gaps`], but without the reserved parts.
+    pub fn unreserved_gaps(&self, range: Range<A>) -> impl Iterator<Item = Range<A>> + '_ {
+        self.gaps(range)
+            .flat_map(|gap| Vec::from_iter(self.reserved.gaps(&gap)))
+    }
+
+    /// Like [`SparseVec::find_free_range`], but the range must not be reserved either.
+    pub fn find_unreserved_range(&self, len: A, align: A, search: Range<A>) -> Option<A> {
+        let mut start = search.start;
+        loop {
+            let addr = self.find_free_range(len, align, start..search.end)?;
+            // No fit can start before the end of a reservation overlapping this one
+            match self.reserved.overlapping(&(addr..addr + len)).last() {
+                Some(reserved) => start = reserved.end,
+                None => return Some(addr),
+            }
+        }
+    }
+
+    /// Like [`SparseVec::find_free_range_last`], but the range must not be reserved either.
+    pub fn find_unreserved_range_last(&self, len: A, align: A, search: Range<A>) -> Option<A> {
+        let mut end = search.end;
+        loop {
+            let addr = self.find_free_range_last(len, align, search.start..end)?;
+            match self.reserved.overlapping(&(addr..addr + len)).next() {
+                Some(reserved) => end = reserved.start,
+                None => return Some(addr),
+            }
+        }
+    }
+}
+
+#[test]
+fn sparsevec_reserve() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 0x10], 0x1010);
+    map.reserve(0x1000..0x1100);
+    assert_eq!(
+        Vec::from_iter(map.reserved_ranges()),
+        vec![0x1000..0x1010, 0x1020..0x1100]
+    );
+    assert_eq!(map.region_kind(0x1000), RegionKind::Reserved);
+    assert_eq!(map.region_kind(0x1010), RegionKind::Populated);
+    assert_eq!(map.region_kind(0x1100), RegionKind::Unmapped);
+
+    // Inserting consumes the covered part of the reservation
+    map.insert(vec![2; 0x20], 0x1080);
+    map.fill(0x10f0..0x1110, 3);
+    assert_eq!(
+        Vec::from_iter(map.reserved_ranges()),
+        vec![0x1000..0x1010, 0x1020..0x1080, 0x10a0..0x10f0]
+    );
+    assert!(!map.is_reserved(0x1090));
+    map.unreserve(0x1040..0x1080);
+    assert!(map.is_reserved(0x103f));
+    assert!(!map.is_reserved(0x1040));
+
+    // Gap-oriented queries see reserved space as unmapped unless asked otherwise
+    assert_eq!(
+        Vec::from_iter(map.gaps(0x1000..0x10a8)),
+        vec![0x1000..0x1010, 0x1020..0x1080, 0x10a0..0x10a8]
+    );
+    assert_eq!(
+        Vec::from_iter(map.unreserved_gaps(0x1000..0x10a8)),
+        vec![0x1040..0x1080]
+    );
+    assert_eq!(map.find_free_range(0x10, 0x10, 0..u64::MAX), Some(0));
+    assert_eq!(
+        map.find_unreserved_range(0x10, 0x10, 0x1000..u64::MAX),
+        Some(0x1040)
+    );
+    assert_eq!(
+        map.find_unreserved_range(0x50, 0x10, 0x1000..u64::MAX),
+        Some(0x1110)
+    );
+    assert_eq!(
+        map.find_unreserved_range_last(0x10, 0x10, 0..0x1100),
+        Some(0x1070)
+    );
+    assert_eq!(
+        map.find_unreserved_range_last(0x10, 1, 0x1000..0x1040),
+        None
+    );
+}
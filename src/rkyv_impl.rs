@@ -0,0 +1,250 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use rkyv::bytecheck::{CheckBytes, Verify};
+use rkyv::munge::munge;
+use rkyv::rancor::{Fallible, Source};
+use rkyv::ser::{Allocator, Writer};
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::{Archive, Archived, Deserialize, Place, Portable, Serialize};
+
+use crate::{DecodeError, SparseVec};
+
+// A block as archived. Blocks are serialized from `BlockRef`, the tests use this to archive
+// lists that no `SparseVec` produces.
+#[derive(Archive)]
+#[cfg_attr(test, derive(Serialize))]
+#[cfg_attr(not(test), allow(dead_code))]
+#[rkyv(archived = ArchivedBlock)]
+struct Block<T> {
+    start: u64,
+    data: Vec<T>,
+}
+
+struct BlockRef<'a, T> {
+    start: u64,
+    data: &'a [T],
+}
+
+impl<T: Archive> Archive for BlockRef<'_, T> {
+    type Archived = ArchivedBlock<T>;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: VecResolver, out: Place<ArchivedBlock<T>>) {
+        munge!(let ArchivedBlock { start, data } = out);
+        self.start.resolve((), start);
+        ArchivedVec::resolve_from_slice(self.data, resolver, data);
+    }
+}
+
+impl<T, S> Serialize<S> for BlockRef<'_, T>
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<VecResolver, S::Error> {
+        ArchivedVec::serialize_from_slice(self.data, serializer)
+    }
+}
+
+/// A [`SparseVec`] archived with `rkyv`, as its blocks in address order. Reads look up
+/// blocks in the archive directly, so a memory mapped archive is never copied.
+///
+/// Validating the archive checks that the blocks are sorted, do not overlap and fit in the
+/// address space.
+#[derive(Portable, CheckBytes)]
+#[bytecheck(crate = rkyv::bytecheck, verify)]
+#[repr(C)]
+pub struct ArchivedSparseVec<T: Archive> {
+    blocks: ArchivedVec<ArchivedBlock<T>>,
+}
+
+impl<T: Archive> ArchivedSparseVec<T> {
+    /// Like [`SparseVec::get`], the data in `range` if one block contains all of it.
+    pub fn get(&self, range: Range<u64>) -> Option<&[Archived<T>]> {
+        let blocks = self.blocks.as_slice();
+        let i = blocks.partition_point(|block| block.start.to_native() <= range.start);
+        let block = &blocks[i.checked_sub(1)?];
+        let start = block.start.to_native();
+        let (offset, end) = (range.start - start, range.end.checked_sub(start)?);
+        block
+            .data
+            .get(usize::try_from(offset).ok()?..usize::try_from(end).ok()?)
+    }
+
+    /// Ranges and data of all blocks, in address order.
+    pub fn blocks(&self) -> impl Iterator<Item = (Range<u64>, &[Archived<T>])> + '_ {
+        self.blocks
+            .iter()
+            .filter(|block| !block.data.is_empty())
+            .map(|block| {
+                let start = block.start.to_native();
+                (
+                    start..start + block.data.len() as u64,
+                    block.data.as_slice(),
+                )
+            })
+    }
+}
+
+unsafe impl<T, C> Verify<C> for ArchivedSparseVec<T>
+where
+    T: Archive,
+    C: Fallible + ?Sized,
+    C::Error: Source,
+{
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        let mut prev_end = 0;
+        for block in self.blocks.iter() {
+            let (start, len) = (block.start.to_native(), block.data.len() as u64);
+            let Some(end) = start.checked_add(len) else {
+                return Err(Source::new(DecodeError::Overflow { start, len }));
+            };
+            if start < prev_end {
+                return Err(Source::new(DecodeError::Overlap { start, prev_end }));
+            }
+            prev_end = end;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Archive> Archive for SparseVec<T> {
+    type Archived = ArchivedSparseVec<T>;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: VecResolver, out: Place<ArchivedSparseVec<T>>) {
+        munge!(let ArchivedSparseVec { blocks } = out);
+        ArchivedVec::resolve_from_len(self.map.len(), resolver, blocks);
+    }
+}
+
+impl<T, S> Serialize<S> for SparseVec<T>
+where
+    T: Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<VecResolver, S::Error> {
+        let blocks = Vec::from_iter(self.blocks().map(|(range, data)| BlockRef {
+            start: range.start,
+            data,
+        }));
+        ArchivedVec::<ArchivedBlock<T>>::serialize_from_iter::<BlockRef<'_, T>, _, _>(
+            blocks.iter(),
+            serializer,
+        )
+    }
+}
+
+impl<T, D> Deserialize<SparseVec<T>, D> for ArchivedSparseVec<T>
+where
+    T: Archive,
+    ArchivedVec<Archived<T>>: Deserialize<Vec<T>, D>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize(&self, deserializer: &mut D) -> Result<SparseVec<T>, D::Error> {
+        let mut blocks = Vec::new();
+        for block in self.blocks.iter().filter(|block| !block.data.is_empty()) {
+            blocks.push((
+                block.start.to_native(),
+                block.data.deserialize(deserializer)?,
+            ));
+        }
+        Ok(SparseVec::from_sorted_blocks(blocks))
+    }
+}
+
+#[test]
+fn sparsevec_rkyv() {
+    use rkyv::rancor::Error;
+
+    let mut map = crate::sparse_vec! {
+        0x10 => [1u8, 2, 3],
+        0x1000 => [4; 0x20],
+        u64::MAX - 2 => [5, 6],
+    };
+    map.set_auto_merge(false);
+    map.insert(vec![7; 4], 0x13);
+    let bytes = rkyv::to_bytes::<Error>(&map).unwrap();
+
+    let archived = rkyv::access::<ArchivedSparseVec<u8>, Error>(&bytes).unwrap();
+    assert!(archived.blocks().eq(map.blocks()));
+    assert_eq!(archived.get(0x11..0x13), Some(&[2, 3][..]));
+    assert_eq!(archived.get(0x12..0x14), None);
+    assert_eq!(archived.get(0x13..0x17), Some(&[7; 4][..]));
+    assert_eq!(archived.get(0x1010..0x1020), Some(&[4; 0x10][..]));
+    assert_eq!(archived.get(0x1010..0x1021), None);
+    assert_eq!(archived.get(u64::MAX - 1..u64::MAX), Some(&[6][..]));
+    assert_eq!(archived.get(0x0f..0x10), None);
+    assert_eq!(archived.get(0x17..0x18), None);
+
+    let decoded = rkyv::deserialize::<SparseVec<u8>, Error>(archived).unwrap();
+    // Adjacent blocks are merged again
+    decoded.assert_invariants();
+    assert_eq!(
+        Vec::from_iter(decoded.ranges()),
+        vec![0x10..0x17, 0x1000..0x1020, u64::MAX - 2..u64::MAX]
+    );
+    assert!(decoded
+        .iter_range(0..u64::MAX)
+        .eq(map.iter_range(0..u64::MAX)));
+    let empty = rkyv::from_bytes::<SparseVec<u16>, Error>(
+        &rkyv::to_bytes::<Error>(&SparseVec::<u16>::new()).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(empty.ranges().len(), 0);
+
+    let wide = crate::sparse_vec! { 0x100 => [0x1234u32, 0x5678] };
+    let bytes = rkyv::to_bytes::<Error>(&wide).unwrap();
+    let archived = rkyv::access::<ArchivedSparseVec<u32>, Error>(&bytes).unwrap();
+    assert_eq!(archived.get(0x101..0x102).unwrap()[0].to_native(), 0x5678);
+}
+
+#[test]
+fn sparsevec_rkyv_validation() {
+    use rkyv::rancor::Error;
+    use rkyv::util::AlignedVec;
+
+    let access = |bytes: &[u8]| {
+        let mut aligned = AlignedVec::<16>::new();
+        aligned.extend_from_slice(bytes);
+        rkyv::access::<ArchivedSparseVec<u8>, Error>(&aligned)
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    };
+    let blocks = |blocks: &[(u64, &[u8])]| {
+        let blocks = Vec::from_iter(blocks.iter().map(|(start, data)| Block {
+            start: *start,
+            data: data.to_vec(),
+        }));
+        rkyv::to_bytes::<Error>(&blocks).unwrap()
+    };
+
+    // The archive of a block list has the same layout
+    assert_eq!(access(&blocks(&[(0x10, &[1; 4]), (0x14, &[2])])), Ok(()));
+    assert_eq!(
+        access(&blocks(&[(0x10, &[1; 4]), (0x12, &[2])])),
+        Err(DecodeError::Overlap {
+            start: 0x12,
+            prev_end: 0x14
+        }
+        .to_string())
+    );
+    assert!(access(&blocks(&[(0x20, &[1]), (0x10, &[2])])).is_err());
+    assert_eq!(
+        access(&blocks(&[(u64::MAX - 1, &[1; 2])])),
+        Err(DecodeError::Overflow {
+            start: u64::MAX - 1,
+            len: 2
+        }
+        .to_string())
+    );
+
+    // Pointers and lengths outside of the buffer
+    let mut bytes = blocks(&[(0x10, &[1; 4])]).to_vec();
+    let len = bytes.len();
+    assert!(access(&bytes[..len - 1]).is_err());
+    bytes[len - 4..].copy_from_slice(&0x100u32.to_le_bytes());
+    assert!(access(&bytes).is_err());
+    assert!(access(&[0xff; 8]).is_err());
+}
@@ -0,0 +1,121 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use itertools::Itertools;
+use rangemap::RangeMap;
+
+use crate::{Address, SparseVec};
+
+/// [`SparseVec::from_value_runs`] would have stored more elements than its limit. `range` is
+/// the run that went over it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunLimitExceeded<A = u64> {
+    pub range: Range<A>,
+    pub limit: usize,
+}
+
+impl<A: Address> fmt::Display for RunLimitExceeded<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "run {:#x}..{:#x} exceeds the limit of {} elements",
+            self.range.start, self.range.end, self.limit
+        )
+    }
+}
+
+impl<A: Address> core::error::Error for RunLimitExceeded<A> {}
+
+impl<T: Eq + Clone, A: Address> SparseVec<T, A> {
+    /// Every maximal run of equal stored values, mapped to its value. Runs separated by a gap
+    /// stay separate even if their values are equal.
+    pub fn to_value_runs(&self) -> RangeMap<A, T> {
+        let mut runs = RangeMap::new();
+        for (range, data) in self.blocks() {
+            let mut start = range.start;
+            for (len, value) in data.iter().dedup_with_count() {
+                let end = start + A::from_usize(len);
+                runs.insert(start..end, value.clone());
+                start = end;
+            }
+        }
+        runs
+    }
+
+    /// Stores every run of `runs` as that many copies of its value, failing if they hold
+    /// more than `limit` elements in total.
+    pub fn from_value_runs(
+        runs: &RangeMap<A, T>,
+        limit: usize,
+    ) -> Result<Self, RunLimitExceeded<A>> {
+        let mut total = 0usize;
+        let mut blocks = Vec::with_capacity(runs.len());
+        for (range, value) in runs.iter() {
+            let len = (range.end - range.start).to_u64();
+            if len > (limit - total) as u64 {
+                return Err(RunLimitExceeded {
+                    range: range.clone(),
+                    limit,
+                });
+            }
+            let len = len as usize;
+            total += len;
+            blocks.push((range.start, vec![value.clone(); len]));
+        }
+        Ok(Self::from_sorted_blocks(blocks))
+    }
+}
+
+#[test]
+fn sparsevec_value_runs() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 4], 0x10);
+    map.insert(vec![1, 1, 2, 0], 0x14);
+    // Equal values on both sides of a gap
+    map.insert(vec![0; 2], 0x19);
+    map.insert(vec![3], 0x100);
+
+    let runs = map.to_value_runs();
+    assert_eq!(
+        Vec::from_iter(runs.iter().map(|(range, value)| (range.clone(), *value))),
+        vec![
+            (0x10..0x14, 0),
+            (0x14..0x16, 1),
+            (0x16..0x17, 2),
+            (0x17..0x18, 0),
+            (0x19..0x1b, 0),
+            (0x100..0x101, 3),
+        ]
+    );
+
+    let back = SparseVec::from_value_runs(&runs, 0x100).unwrap();
+    assert!(back.blocks().eq(map.blocks()));
+    assert_eq!(
+        Vec::from_iter(back.ranges()),
+        vec![0x10..0x18, 0x19..0x1b, 0x100..0x101]
+    );
+    assert!(SparseVec::<u8>::default().to_value_runs().is_empty());
+}
+
+#[test]
+fn sparsevec_value_runs_limit() {
+    let mut runs = RangeMap::new();
+    runs.insert(0x10..0x20u64, 'a');
+    runs.insert(0x1000..u64::MAX, 'b');
+    assert_eq!(
+        SparseVec::from_value_runs(&runs, 0x1000).unwrap_err(),
+        RunLimitExceeded {
+            range: 0x1000..u64::MAX,
+            limit: 0x1000,
+        }
+    );
+
+    // The limit is on the total
+    runs.insert(0x1000..0x1010, 'b');
+    runs.remove(0x1010..u64::MAX);
+    assert!(SparseVec::from_value_runs(&runs, 0x1f).is_err());
+    let map = SparseVec::from_value_runs(&runs, 0x20).unwrap();
+    assert_eq!(map.get(0x1000..0x1010).unwrap(), &['b'; 0x10]);
+}
@@ -0,0 +1,117 @@
+use alloc::vec::Vec;
+
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{Address, SparseVec};
+
+// The serialized form is the ordered list of blocks, independent of the internal layout.
+#[derive(Serialize)]
+struct BlockRef<'a, T, A> {
+    start: A,
+    data: &'a [T],
+}
+
+#[derive(Deserialize)]
+struct Block<T, A> {
+    start: A,
+    data: Vec<T>,
+}
+
+impl<T: Serialize, A: Address + Serialize> Serialize for SparseVec<T, A> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.blocks().map(|(range, data)| BlockRef {
+            start: range.start,
+            data,
+        }))
+    }
+}
+
+impl<'de, T: Deserialize<'de>, A: Address + Deserialize<'de>> Deserialize<'de> for SparseVec<T, A> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut blocks = Vec::<Block<T, A>>::deserialize(deserializer)?;
+        blocks.retain(|block| !block.data.is_empty());
+        blocks.sort_by_key(|block| block.start);
+
+        let mut prev_end = None;
+        for block in &blocks {
+            let end = A::try_from_u64(block.data.len() as u64)
+                .and_then(|len| block.start.checked_add(len))
+                .ok_or_else(|| {
+                    D::Error::custom(format_args!(
+                        "block at {:#x} with length {} overflows the address space",
+                        block.start,
+                        block.data.len()
+                    ))
+                })?;
+            if let Some(prev_end) = prev_end.filter(|&prev_end| prev_end > block.start) {
+                return Err(D::Error::custom(format_args!(
+                    "block at {:#x} overlaps previous block ending at {:#x}",
+                    block.start, prev_end
+                )));
+            }
+            prev_end = Some(end);
+        }
+
+        Ok(SparseVec::from_sorted_blocks(
+            blocks.into_iter().map(|block| (block.start, block.data)),
+        ))
+    }
+}
+
+#[test]
+fn sparsevec_serde_roundtrip() {
+    let empty = SparseVec::<u8>::new();
+    assert_eq!(serde_json::to_string(&empty).unwrap(), "[]");
+    let empty: SparseVec<u8> = serde_json::from_str("[]").unwrap();
+    assert_eq!(empty.ranges().count(), 0);
+
+    let map = crate::sparse_vec! {
+        0x10 => [1u16, 2, 3],
+        0x13 => [4, 5],
+        u64::MAX - 5 => [6; 4],
+    };
+    let json = serde_json::to_string(&map).unwrap();
+    assert_eq!(
+        json,
+        r#"[{"start":16,"data":[1,2,3,4,5]},{"start":18446744073709551610,"data":[6,6,6,6]}]"#
+    );
+
+    let from_json: SparseVec<u16> = serde_json::from_str(&json).unwrap();
+    let from_bincode: SparseVec<u16> =
+        bincode::deserialize(&bincode::serialize(&map).unwrap()).unwrap();
+    for decoded in [from_json, from_bincode] {
+        decoded.assert_invariants();
+        assert_eq!(
+            Vec::from_iter(decoded.blocks()),
+            Vec::from_iter(map.blocks())
+        );
+        assert_eq!(
+            decoded.ranges().next_back(),
+            Some(u64::MAX - 5..u64::MAX - 1)
+        );
+    }
+
+    // Adjacent blocks are merged
+    let merged: SparseVec<u8> =
+        serde_json::from_str(r#"[{"start":4,"data":[3]},{"start":0,"data":[1,1,1,1]}]"#).unwrap();
+    merged.assert_invariants();
+    assert_eq!(Vec::from_iter(merged.ranges()), vec![0..5]);
+}
+
+#[test]
+fn sparsevec_serde_invalid() {
+    let overlap = serde_json::from_str::<SparseVec<u8>>(
+        r#"[{"start":0,"data":[1,2,3]},{"start":2,"data":[4]}]"#,
+    );
+    assert!(overlap.unwrap_err().to_string().contains("overlaps"));
+
+    let overflow =
+        serde_json::from_str::<SparseVec<u8>>(r#"[{"start":18446744073709551615,"data":[1,2]}]"#);
+    assert!(overflow.unwrap_err().to_string().contains("overflows"));
+
+    // Longer than the address space of small addresses
+    let long = format!(r#"[{{"start":0,"data":{:?}}}]"#, vec![0u8; 0x10001]);
+    let overflow = serde_json::from_str::<SparseVec<u8, u16>>(&long);
+    assert!(overflow.unwrap_err().to_string().contains("overflows"));
+}
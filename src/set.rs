@@ -0,0 +1,225 @@
+use core::fmt;
+use core::ops::Range;
+
+use rangemap::RangeSet;
+
+use crate::{Address, SparseVec};
+
+/// The coverage of a [`SparseVec`] without data, e.g. to track visited addresses. Stores
+/// only the ranges, so memory does not grow with their length, where even a `SparseVec<()>`
+/// keeps block bookkeeping next to them.
+///
+/// Adjacent and overlapping ranges are merged, as [`SparseVec`] merges blocks by default.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SparseSet<A = u64> {
+    ranges: RangeSet<A>,
+}
+
+impl<A: Address> Default for SparseSet<A> {
+    fn default() -> Self {
+        Self {
+            ranges: RangeSet::new(),
+        }
+    }
+}
+
+impl<A: Address> fmt::Debug for SparseSet<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.ranges()).finish()
+    }
+}
+
+impl SparseSet {
+    /// Creates an empty `SparseSet` with `u64` addresses. Use [`Default`] for other address
+    /// types.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<A: Address> SparseSet<A> {
+    /// The addresses `vec` stores.
+    pub fn from_coverage<T>(vec: &SparseVec<T, A>) -> Self {
+        Self {
+            ranges: vec.map.iter().map(|(range, _)| range.clone()).collect(),
+        }
+    }
+
+    /// Adds every address in `range`.
+    pub fn insert_range(&mut self, range: Range<A>) {
+        if !range.is_empty() {
+            self.ranges.insert(range);
+        }
+    }
+
+    /// Removes every address in `range`.
+    pub fn remove(&mut self, range: Range<A>) {
+        if !range.is_empty() {
+            self.ranges.remove(range);
+        }
+    }
+
+    pub fn contains(&self, addr: A) -> bool {
+        self.ranges.contains(&addr)
+    }
+
+    /// Whether every address in `range` is in the set. True for empty ranges.
+    pub fn contains_range(&self, range: &Range<A>) -> bool {
+        range.is_empty()
+            || self
+                .ranges
+                .get(&range.start)
+                .is_some_and(|found| range.end <= found.end)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Number of addresses in the set.
+    pub fn covered_len(&self) -> u64 {
+        self.ranges.iter().map(|r| (r.end - r.start).to_u64()).sum()
+    }
+
+    /// Ranges in the set, in address order.
+    pub fn ranges(&self) -> impl DoubleEndedIterator<Item = Range<A>> + '_ {
+        self.ranges.iter().cloned()
+    }
+
+    /// Ranges not in the set within `range`, in address order.
+    pub fn gaps<'a>(&'a self, range: &'a Range<A>) -> impl Iterator<Item = Range<A>> + 'a {
+        self.ranges.gaps(range)
+    }
+
+    /// Addresses in `self` or `other`.
+    pub fn union(&self, other: &Self) -> Self {
+        let mut union = self.clone();
+        for range in other.ranges() {
+            union.ranges.insert(range);
+        }
+        union
+    }
+
+    /// Addresses in both `self` and `other`.
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut left = self.ranges.iter().peekable();
+        let mut right = other.ranges.iter().peekable();
+        let mut result = Self::default();
+        while let (Some(a), Some(b)) = (left.peek(), right.peek()) {
+            result.insert_range(a.start.max(b.start)..a.end.min(b.end));
+            if a.end <= b.end {
+                left.next();
+            } else {
+                right.next();
+            }
+        }
+        result
+    }
+
+    /// Addresses in `self` but not in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut difference = self.clone();
+        for range in other.ranges() {
+            difference.ranges.remove(range);
+        }
+        difference
+    }
+}
+
+impl<A: Address> FromIterator<Range<A>> for SparseSet<A> {
+    fn from_iter<I: IntoIterator<Item = Range<A>>>(iter: I) -> Self {
+        let mut set = Self::default();
+        for range in iter {
+            set.insert_range(range);
+        }
+        set
+    }
+}
+
+#[test]
+fn sparsevec_sparse_set() {
+    let mut set = SparseSet::new();
+    set.insert_range(0x10..0x20);
+    set.insert_range(0x20..0x28);
+    set.insert_range(0x40..0x48);
+    set.insert_range(0x44..0x50);
+    set.insert_range(0x60..0x60);
+    assert_eq!(Vec::from_iter(set.ranges()), vec![0x10..0x28, 0x40..0x50]);
+    assert!(set.contains(0x27) && !set.contains(0x28));
+    assert!(set.contains_range(&(0x18..0x28)) && !set.contains_range(&(0x18..0x29)));
+    assert!(set.contains_range(&(0x30..0x30)));
+    assert_eq!(set.covered_len(), 0x28);
+    assert_eq!(
+        Vec::from_iter(set.gaps(&(0..0x48))),
+        vec![0..0x10, 0x28..0x40]
+    );
+
+    // Removing splits ranges
+    set.remove(0x14..0x18);
+    set.remove(0x48..0x100);
+    assert_eq!(
+        Vec::from_iter(set.ranges()),
+        vec![0x10..0x14, 0x18..0x28, 0x40..0x48]
+    );
+
+    // Same coverage as a `SparseVec` given the same inserts
+    let mut map = SparseVec::new();
+    for range in [0x10..0x20, 0x20..0x28, 0x40..0x48, 0x44..0x50] {
+        map.insert(vec![(); (range.end - range.start) as usize], range.start);
+    }
+    let from_map = SparseSet::from_coverage(&map);
+    assert!(from_map.ranges().eq(map.ranges()));
+
+    let other = SparseSet::from_iter([0x00..0x12, 0x20..0x44]);
+    assert_eq!(
+        Vec::from_iter(set.union(&other).ranges()),
+        vec![0x00..0x14, 0x18..0x48]
+    );
+    assert_eq!(
+        Vec::from_iter(set.intersection(&other).ranges()),
+        vec![0x10..0x12, 0x20..0x28, 0x40..0x44]
+    );
+    assert_eq!(
+        Vec::from_iter(set.difference(&other).ranges()),
+        vec![0x12..0x14, 0x18..0x20, 0x44..0x48]
+    );
+    assert!(set.difference(&set).is_empty());
+}
+
+#[test]
+fn sparsevec_sparse_set_random() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(193);
+    let mut random = || {
+        let mut set = SparseSet::<u16>::default();
+        let mut model = [false; 0x200];
+        for _ in 0..rng.gen_range(0..16) {
+            let start = rng.gen_range(0..0x1c0);
+            let end = start + rng.gen_range(0..0x40);
+            let insert = rng.gen_bool(0.7);
+            if insert {
+                set.insert_range(start..end);
+            } else {
+                set.remove(start..end);
+            }
+            model[start as usize..end as usize].fill(insert);
+        }
+        (set, model)
+    };
+    for _ in 0..100 {
+        let ((a, a_model), (b, b_model)) = (random(), random());
+        let check = |set: SparseSet<u16>, f: fn(bool, bool) -> bool| {
+            let expected = (0..0x200).filter(|&i| f(a_model[i], b_model[i]));
+            let stored = set.ranges().flatten().map(usize::from);
+            assert!(stored.eq(expected));
+        };
+        check(a.union(&b), |a, b| a || b);
+        check(a.intersection(&b), |a, b| a && b);
+        check(a.difference(&b), |a, b| a && !b);
+        // Every range is maximal
+        for (r, s) in a.ranges().zip(a.ranges().skip(1)) {
+            assert!(r.end < s.start);
+        }
+    }
+}
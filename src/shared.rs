@@ -0,0 +1,322 @@
+use std::ops::Range;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::{SparseVec, Unmapped};
+
+/// A `SparseVec` that can be used from several threads at once.
+///
+/// The address space is cut into regions of a fixed length, which are spread over a fixed
+/// number of shards, each behind its own [`RwLock`]. Operations only lock the shards of the
+/// regions they touch, so accesses to different regions mostly run in parallel. Operations
+/// spanning several shards lock them in shard order, which keeps them atomic and free of
+/// deadlocks.
+pub struct SharedSparseVec<T> {
+    region_len: u64,
+    shards: Vec<RwLock<SparseVec<T>>>,
+}
+
+impl<T> SharedSparseVec<T> {
+    /// Empty `SharedSparseVec` with `shards` locks over regions of `region_len` addresses.
+    ///
+    /// Panics if either is zero.
+    pub fn new(region_len: u64, shards: usize) -> Self {
+        assert!(
+            region_len > 0 && shards > 0,
+            "region length and shard count must not be zero"
+        );
+        Self {
+            region_len,
+            shards: Vec::from_iter((0..shards).map(|_| RwLock::new(SparseVec::new()))),
+        }
+    }
+
+    pub fn region_len(&self) -> u64 {
+        self.region_len
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// The data of all shards as a single `SparseVec`.
+    pub fn into_inner(self) -> SparseVec<T>
+    where
+        T: Copy,
+    {
+        let mut vec = SparseVec::new();
+        for shard in self.shards {
+            for (start, data) in shard.into_inner().unwrap() {
+                vec.insert(data, start);
+            }
+        }
+        vec
+    }
+
+    fn shard_of(&self, addr: u64) -> usize {
+        ((addr / self.region_len) % self.shards.len() as u64) as usize
+    }
+
+    // `range` cut at region boundaries
+    fn pieces(&self, range: Range<u64>) -> impl Iterator<Item = Range<u64>> + '_ {
+        let mut addr = range.start;
+        core::iter::from_fn(move || {
+            if addr >= range.end {
+                return None;
+            }
+            let region_end = (addr / self.region_len + 1).saturating_mul(self.region_len);
+            let end = range.end.min(region_end);
+            let piece = addr..end;
+            addr = end;
+            Some(piece)
+        })
+    }
+
+    // Shards holding `range` in lock order
+    fn involved(&self, range: &Range<u64>) -> Vec<usize> {
+        if range.is_empty() {
+            return Vec::from_iter([self.shard_of(range.start)]);
+        }
+        let regions = (range.end - 1) / self.region_len - range.start / self.region_len + 1;
+        if regions >= self.shards.len() as u64 {
+            return Vec::from_iter(0..self.shards.len());
+        }
+        let mut shards = Vec::from_iter(self.pieces(range.clone()).map(|p| self.shard_of(p.start)));
+        shards.sort_unstable();
+        shards.dedup();
+        shards
+    }
+
+    fn read(&self, range: &Range<u64>) -> Vec<Option<RwLockReadGuard<'_, SparseVec<T>>>> {
+        let mut guards = Vec::from_iter(self.shards.iter().map(|_| None));
+        for i in self.involved(range) {
+            guards[i] = Some(self.shards[i].read().unwrap());
+        }
+        guards
+    }
+
+    fn write_lock(&self, range: &Range<u64>) -> Vec<Option<RwLockWriteGuard<'_, SparseVec<T>>>> {
+        let mut guards = Vec::from_iter(self.shards.iter().map(|_| None));
+        for i in self.involved(range) {
+            guards[i] = Some(self.shards[i].write().unwrap());
+        }
+        guards
+    }
+
+    /// Whether every address in `range` is stored. True for empty ranges.
+    pub fn contains_range(&self, range: &Range<u64>) -> bool {
+        let guards = self.read(range);
+        self.pieces(range.clone()).all(|piece| {
+            let shard = guards[self.shard_of(piece.start)].as_ref().unwrap();
+            shard.contains_range(&piece)
+        })
+    }
+
+    /// Calls `f` with the stored data overlapping `range`, clipped to it, in address order,
+    /// while the shards are locked.
+    pub fn visit_slices(&self, range: Range<u64>, mut f: impl FnMut(Range<u64>, &[T])) {
+        let guards = self.read(&range);
+        for piece in self.pieces(range.clone()) {
+            let shard = guards[self.shard_of(piece.start)].as_ref().unwrap();
+            for (clipped, slice) in shard.slices(piece) {
+                f(clipped, slice);
+            }
+        }
+    }
+
+    /// Stored ranges in address order, coalesced across region boundaries.
+    pub fn ranges(&self) -> Vec<Range<u64>> {
+        let guards = Vec::from_iter(self.shards.iter().map(|shard| shard.read().unwrap()));
+        let mut ranges = Vec::from_iter(guards.iter().flat_map(|shard| shard.ranges()));
+        ranges.sort_unstable_by_key(|range| range.start);
+        ranges.dedup_by(|next, range| {
+            let adjacent = range.end == next.start;
+            if adjacent {
+                range.end = next.end;
+            }
+            adjacent
+        });
+        ranges
+    }
+}
+
+impl<T: Copy> SharedSparseVec<T> {
+    /// Like [`SparseVec::insert`], atomically for the whole range.
+    ///
+    /// Panics if the data does not fit in the address space.
+    pub fn insert(&self, data: Vec<T>, addr: u64) {
+        let Some(end) = addr.checked_add(data.len() as u64) else {
+            panic!(
+                "{} elements at {addr:#x} exceed the address space",
+                data.len()
+            );
+        };
+        let range = addr..end;
+        let mut guards = self.write_lock(&range);
+        let mut offset = 0;
+        for piece in self.pieces(range) {
+            let len = (piece.end - piece.start) as usize;
+            let shard = guards[self.shard_of(piece.start)].as_mut().unwrap();
+            shard.insert(data[offset..offset + len].to_vec(), piece.start);
+            offset += len;
+        }
+    }
+
+    /// Copy of exactly `range`, `None` if any part of it is unmapped or it is reversed.
+    pub fn get(&self, range: Range<u64>) -> Option<Vec<T>> {
+        if range.start > range.end {
+            return None;
+        }
+        let guards = self.read(&range);
+        // Grows with the data found, as an unmapped piece ends the copy
+        let mut data = Vec::new();
+        for piece in self.pieces(range) {
+            let shard = guards[self.shard_of(piece.start)].as_ref().unwrap();
+            data.extend_from_slice(shard.get(piece)?);
+        }
+        Some(data)
+    }
+
+    /// Like [`SparseVec::write`], atomically for the whole range.
+    pub fn write(&self, addr: u64, data: &[T]) -> Result<(), Unmapped> {
+        let end = addr.checked_add(data.len() as u64);
+        // Up to `u64::MAX`, which `insert` never stores here, if the data does not fit
+        let range = addr..end.unwrap_or(u64::MAX);
+        let mut guards = self.write_lock(&range);
+        for piece in self.pieces(range.clone()) {
+            let shard = guards[self.shard_of(piece.start)].as_ref().unwrap();
+            if let Some(addr) = shard.gaps(piece).next().map(|gap| gap.start) {
+                return Err(Unmapped { addr });
+            }
+        }
+        if end.is_none() {
+            return Err(Unmapped { addr: u64::MAX });
+        }
+        let mut offset = 0;
+        for piece in self.pieces(range) {
+            let len = (piece.end - piece.start) as usize;
+            let shard = guards[self.shard_of(piece.start)].as_mut().unwrap();
+            shard.write(piece.start, &data[offset..offset + len])?;
+            offset += len;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn sparsevec_shared() {
+    let map = SharedSparseVec::new(0x10, 3);
+    map.insert(vec![1u8; 0x30], 0x08);
+    map.insert(vec![2u8; 4], 0x50);
+    assert_eq!(map.ranges(), vec![0x08..0x38, 0x50..0x54]);
+    assert_eq!(map.get(0x0e..0x12).unwrap(), [1; 4]);
+    assert_eq!(map.get(0x30..0x40), None);
+    assert!(map.contains_range(&(0x08..0x38)));
+    assert!(!map.contains_range(&(0x08..0x39)));
+
+    assert_eq!(map.write(0x34, &[3; 8]), Err(Unmapped { addr: 0x38 }));
+    map.write(0x1e, &[3; 4]).unwrap();
+    let mut slices = Vec::new();
+    map.visit_slices(0x1c..0x24, |range, slice| {
+        slices.push((range, slice.to_vec()))
+    });
+    assert_eq!(
+        slices,
+        vec![
+            (0x1c..0x20, vec![1, 1, 3, 3]),
+            (0x20..0x24, vec![3, 3, 1, 1])
+        ]
+    );
+
+    // Huge, reversed and overflowing ranges fail
+    assert_eq!(map.get(0..u64::MAX), None);
+    assert_eq!(
+        map.get(Range {
+            start: 0x0a,
+            end: 0x09
+        }),
+        None
+    );
+    map.insert(vec![4; 2], u64::MAX - 2);
+    assert_eq!(
+        map.write(u64::MAX - 3, &[5; 4]),
+        Err(Unmapped { addr: u64::MAX - 3 })
+    );
+    assert_eq!(
+        map.write(u64::MAX - 2, &[5; 3]),
+        Err(Unmapped { addr: u64::MAX })
+    );
+    assert_eq!(map.get(u64::MAX - 2..u64::MAX).unwrap(), [4; 2]);
+
+    let vec = map.into_inner();
+    assert_eq!(
+        Vec::from_iter(vec.ranges()),
+        vec![0x08..0x38, 0x50..0x54, u64::MAX - 2..u64::MAX]
+    );
+}
+
+#[test]
+#[should_panic(expected = "4 elements at 0xfffffffffffffffe exceed the address space")]
+fn sparsevec_shared_insert_overflow() {
+    SharedSparseVec::new(0x10, 3).insert(vec![1u8; 4], u64::MAX - 1);
+}
+
+#[test]
+fn sparsevec_shared_threads() {
+    use rand::{Rng, SeedableRng};
+    use std::thread;
+
+    let map = SharedSparseVec::new(0x40, 4);
+    // Spans covering several shards, always written whole with a single value
+    let spans = [0x00..0x100, 0x100..0x1c0, 0x3f0..0x430];
+    for span in &spans {
+        map.insert(vec![0u32; (span.end - span.start) as usize], span.start);
+    }
+    thread::scope(|scope| {
+        // Disjoint areas, each checked against its own model
+        for t in 0..4u64 {
+            let map = &map;
+            scope.spawn(move || {
+                let mut rng = rand::rngs::StdRng::seed_from_u64(t);
+                let base = 0x1000 + t * 0x1000;
+                let mut model = vec![None; 0x400];
+                for i in 0..2000u32 {
+                    let start = rng.gen_range(0..0x380);
+                    let len = rng.gen_range(0..0x80);
+                    let range = base + start as u64..base + (start + len) as u64;
+                    if rng.gen_bool(0.5) || !map.contains_range(&range) {
+                        map.insert(vec![i; len], range.start);
+                        model[start..start + len].fill(Some(i));
+                    } else {
+                        map.write(range.start, &vec![i; len]).unwrap();
+                        model[start..start + len].fill(Some(i));
+                    }
+                    let expected: Option<Vec<u32>> =
+                        model[start..start + len].iter().copied().collect();
+                    assert_eq!(map.get(range), expected);
+                }
+            });
+        }
+        // Writers of the same spans and readers that must never see a partial write
+        for t in 0..4u32 {
+            let (map, spans) = (&map, &spans);
+            scope.spawn(move || {
+                let mut rng = rand::rngs::StdRng::seed_from_u64(100 + t as u64);
+                for i in 0..500 {
+                    let span = spans[rng.gen_range(0..spans.len())].clone();
+                    if t % 2 == 0 {
+                        let len = (span.end - span.start) as usize;
+                        map.write(span.start, &vec![t * 1000 + i; len]).unwrap();
+                    } else {
+                        let data = map.get(span).unwrap();
+                        assert!(data.iter().all(|&v| v == data[0]));
+                    }
+                }
+            });
+        }
+    });
+    let vec = map.into_inner();
+    for span in spans {
+        let data = vec.get(span).unwrap();
+        assert!(data.iter().all(|&v| v == data[0]));
+    }
+}
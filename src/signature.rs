@@ -0,0 +1,258 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+
+use crate::SparseVec;
+
+/// A byte pattern with wildcards, parsed from the usual `"48 8B ?? ?? 90"` syntax.
+///
+/// Tokens are separated by whitespace, each either two hex digits or `??` (or `?`) for any byte.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Signature(Vec<Option<u8>>);
+
+impl Signature {
+    pub fn new(pattern: Vec<Option<u8>>) -> Self {
+        Self(pattern)
+    }
+
+    pub fn pattern(&self) -> &[Option<u8>] {
+        &self.0
+    }
+}
+
+/// Why a string is not a valid [`Signature`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The string has no tokens.
+    Empty,
+    /// Token number `index` is neither a hex byte nor a wildcard.
+    InvalidToken { index: usize },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Empty => write!(f, "empty signature"),
+            SignatureError::InvalidToken { index } => {
+                write!(f, "signature token {index} is not a hex byte or wildcard")
+            }
+        }
+    }
+}
+
+impl core::error::Error for SignatureError {}
+
+impl FromStr for Signature {
+    type Err = SignatureError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let pattern = s
+            .split_whitespace()
+            .enumerate()
+            .map(|(index, token)| match token {
+                "?" | "??" => Ok(None),
+                // `from_str_radix` alone would take a sign, e.g. `+f`
+                _ if token.len() == 2 && token.bytes().all(|c| c.is_ascii_hexdigit()) => {
+                    u8::from_str_radix(token, 16)
+                        .map(Some)
+                        .map_err(|_| SignatureError::InvalidToken { index })
+                }
+                _ => Err(SignatureError::InvalidToken { index }),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if pattern.is_empty() {
+            return Err(SignatureError::Empty);
+        }
+        Ok(Self(pattern))
+    }
+}
+
+fn matches(data: &[u8], pattern: &[Option<u8>]) -> bool {
+    data.iter()
+        .zip(pattern)
+        .all(|(byte, expected)| expected.is_none_or(|expected| expected == *byte))
+}
+
+// Offset and length of the longest run of literal bytes in `pattern`
+fn anchor(pattern: &[Option<u8>]) -> (usize, usize) {
+    let mut best = (0, 0);
+    let mut start = 0;
+    for (i, byte) in pattern.iter().enumerate() {
+        if byte.is_none() {
+            start = i + 1;
+        } else if i + 1 - start > best.1 {
+            best = (start, i + 1 - start);
+        }
+    }
+    best
+}
+
+// Start offsets of every match of `pattern` in `data`, in order. The longest literal run is
+// searched with Horspool's algorithm and only its hits are checked against the whole pattern.
+fn find_in(data: &[u8], pattern: &[Option<u8>]) -> Vec<usize> {
+    let n = pattern.len();
+    if data.len() < n {
+        return Vec::new();
+    }
+    let (offset, len) = anchor(pattern);
+    if len == 0 {
+        return Vec::from_iter(0..=data.len() - n);
+    }
+    let needle = Vec::from_iter(pattern[offset..offset + len].iter().map(|b| b.unwrap()));
+    let mut skip = [len; 256];
+    for (i, &byte) in needle[..len - 1].iter().enumerate() {
+        skip[byte as usize] = len - 1 - i;
+    }
+
+    let mut found = Vec::new();
+    // Only anchor positions that leave room for the whole pattern
+    let mut pos = offset;
+    while pos - offset + n <= data.len() {
+        let window = &data[pos..pos + len];
+        if window == needle && matches(&data[pos - offset..pos - offset + n], pattern) {
+            found.push(pos - offset);
+        }
+        pos += skip[window[len - 1] as usize];
+    }
+    found
+}
+
+impl SparseVec<u8> {
+    /// Addresses in `range` where `pattern` matches, in order, with `None` matching any
+    /// byte. Matches may overlap but never span a gap or leave `range`. An empty pattern
+    /// never matches.
+    pub fn find_signature(
+        &self,
+        pattern: &[Option<u8>],
+        range: Range<u64>,
+    ) -> impl Iterator<Item = u64> + '_ {
+        let pattern = pattern.to_vec();
+        let mut slices = self.slices(range).peekable();
+        // Contiguous runs of stored data, which may consist of several blocks
+        let runs = core::iter::from_fn(move || {
+            let first = slices.next()?;
+            let mut run = Vec::from_iter([first]);
+            while let Some(next) =
+                slices.next_if(|(next, _)| next.start == run.last().unwrap().0.end)
+            {
+                run.push(next);
+            }
+            Some(run)
+        });
+        runs.flat_map(move |run| Self::find_in_run(&run, &pattern))
+    }
+
+    fn find_in_run(run: &[(Range<u64>, &[u8])], pattern: &[Option<u8>]) -> Vec<u64> {
+        if pattern.is_empty() {
+            return Vec::new();
+        }
+        let n = pattern.len() as u64;
+        let mut found = Vec::new();
+        for (i, (range, data)) in run.iter().enumerate() {
+            found.extend(
+                find_in(data, pattern)
+                    .into_iter()
+                    .map(|s| range.start + s as u64),
+            );
+            // Matches crossing into the next block that start after the previous boundary
+            let Some((next, _)) = run.get(i + 1) else {
+                continue;
+            };
+            let from = range.start.max(next.start.saturating_sub(n - 1));
+            let to = next.start + (n - 1);
+            let mut window = Vec::new();
+            for (range, data) in run[i..].iter().take_while(|(range, _)| range.start < to) {
+                let clipped = range.start.max(from)..range.end.min(to);
+                if clipped.start < clipped.end {
+                    let slice = clipped.start - range.start..clipped.end - range.start;
+                    window.extend_from_slice(&data[slice.start as usize..slice.end as usize]);
+                }
+            }
+            found.extend(
+                find_in(&window, pattern)
+                    .into_iter()
+                    .map(|s| from + s as u64)
+                    .filter(|&s| s < next.start),
+            );
+        }
+        found.sort_unstable();
+        found
+    }
+}
+
+#[test]
+fn sparsevec_signature_parse() {
+    let signature: Signature = "48 8b ?? ? 90".parse().unwrap();
+    assert_eq!(
+        signature.pattern(),
+        &[Some(0x48), Some(0x8b), None, None, Some(0x90)]
+    );
+    assert_eq!(" \t".parse::<Signature>(), Err(SignatureError::Empty));
+    assert_eq!(
+        "48 8 90".parse::<Signature>(),
+        Err(SignatureError::InvalidToken { index: 1 })
+    );
+    assert_eq!(
+        "+f 01".parse::<Signature>(),
+        Err(SignatureError::InvalidToken { index: 0 })
+    );
+    assert_eq!(
+        "48 8bc".parse::<Signature>().unwrap_err().to_string(),
+        "signature token 1 is not a hex byte or wildcard"
+    );
+}
+
+#[test]
+fn sparsevec_find_signature() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0x48, 0x8b, 0x05, 0x11, 0x90, 0x48, 0x8b], 0x100);
+    map.insert(vec![0x00, 0x00, 0x90, 0x48, 0x8b, 0x48, 0x8b], 0x200);
+    map.insert(vec![0x00, 0x90, 0x90], 0x207);
+    let find = |s: &str, range| {
+        let signature: Signature = s.parse().unwrap();
+        Vec::from_iter(map.find_signature(signature.pattern(), range))
+    };
+    assert_eq!(find("48 8B ?? ?? 90", 0..u64::MAX), vec![0x100, 0x205]);
+    // Matches never span the gap or leave the range
+    assert_eq!(find("8B ?? ?? 90", 0..u64::MAX), vec![0x101, 0x206]);
+    assert_eq!(find("8B ?? ?? 90", 0..0x209), vec![0x101]);
+    // Wildcards at either end
+    assert_eq!(find("?? 48 8b", 0..u64::MAX), vec![0x104, 0x202, 0x204]);
+    assert_eq!(find("48 8b ??", 0..u64::MAX), vec![0x100, 0x203, 0x205]);
+    assert_eq!(find("?? ??", 0x105..0x10a), vec![0x105]);
+    assert_eq!(find("48", 0x101..0x106), vec![0x105]);
+    // Adjacent to the gaps
+    assert_eq!(find("90 48 8b", 0..u64::MAX), vec![0x104, 0x202]);
+    assert_eq!(find("00 00", 0..u64::MAX), vec![0x200]);
+}
+
+#[test]
+fn sparsevec_find_signature_fuzz() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(160);
+    for _ in 0..200 {
+        // Unmergeable neighbours make matches cross block boundaries
+        let mut map = SparseVec::<u8>::with_max_block_len(rng.gen_range(1..8));
+        for _ in 0..10 {
+            let len = rng.gen_range(0..40);
+            let data = Vec::from_iter((0..len).map(|_| rng.gen_range(0..3)));
+            map.insert(data, rng.gen_range(0..200));
+        }
+        let pattern = Vec::from_iter(
+            (0..rng.gen_range(1..6)).map(|_| rng.gen_bool(0.7).then(|| rng.gen_range(0..3))),
+        );
+        let range = rng.gen_range(0..100)..rng.gen_range(100..300);
+        let expected = Vec::from_iter(range.clone().filter(|&addr| {
+            let mut buf = vec![0; pattern.len()];
+            addr + pattern.len() as u64 <= range.end
+                && map.read_into_exact(addr, &mut buf).is_ok()
+                && matches(&buf, &pattern)
+        }));
+        assert_eq!(
+            Vec::from_iter(map.find_signature(&pattern, range)),
+            expected
+        );
+    }
+}
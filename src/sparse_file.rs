@@ -0,0 +1,183 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::SparseVec;
+
+/// Zero runs at least this long are treated as holes when the OS cannot report them.
+const ZERO_RUN: u64 = 4096;
+
+impl SparseVec<u8> {
+    /// Writes every block at the file offset equal to its address, so that the gaps become holes
+    /// on filesystems that support sparse files. The file is truncated to `bounds().end`.
+    pub fn write_sparse_file(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        for (range, data) in self.blocks() {
+            file.seek(SeekFrom::Start(range.start))?;
+            file.write_all(data)?;
+        }
+        file.set_len(self.bounds().map_or(0, |bounds| bounds.end))?;
+        file.sync_all()
+    }
+
+    /// Reads a file written by [`SparseVec::write_sparse_file`], using file offsets as addresses.
+    ///
+    /// On Linux and Android the data regions are queried with `SEEK_DATA`/`SEEK_HOLE`. They are
+    /// filesystem block granular, so stored blocks may include zero padding around the data that
+    /// was written. If the filesystem does not support these queries, or on other platforms, the
+    /// file is scanned instead: zeros before the first and after the last non-zero byte and runs
+    /// of at least 4096 zeros are treated as holes.
+    pub fn read_sparse_file(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        if let Some(vec) = read_data_regions(&file)? {
+            return Ok(vec);
+        }
+        file.rewind()?;
+        read_scanning(file)
+    }
+}
+
+// Returns `None` if the filesystem does not support `SEEK_DATA`
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn read_data_regions(file: &File) -> io::Result<Option<SparseVec<u8>>> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::FileExt;
+
+    let len = file.metadata()?.len();
+    // `Ok(None)` if there is no data at or after `pos`
+    let seek = |pos: u64, whence| -> io::Result<Option<u64>> {
+        let pos = i64::try_from(pos).map_err(|_| io::ErrorKind::InvalidInput)?;
+        // SAFETY: lseek only operates on the descriptor, which `file` keeps open.
+        let res = unsafe { libc::lseek64(file.as_raw_fd(), pos, whence) };
+        if res >= 0 {
+            return Ok(Some(res as u64));
+        }
+        let err = io::Error::last_os_error();
+        match err.raw_os_error() {
+            Some(libc::ENXIO) => Ok(None),
+            _ => Err(err),
+        }
+    };
+
+    let mut vec = SparseVec::new();
+    let mut pos = 0;
+    while pos < len {
+        let start = match seek(pos, libc::SEEK_DATA) {
+            Ok(Some(start)) => start,
+            Ok(None) => break,
+            Err(err) if pos == 0 && err.raw_os_error() == Some(libc::EINVAL) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let end = seek(start, libc::SEEK_HOLE)?.unwrap_or(len).min(len);
+        let mut data =
+            vec![0; usize::try_from(end - start).map_err(|_| io::ErrorKind::OutOfMemory)?];
+        file.read_exact_at(&mut data, start)?;
+        vec.insert(data, start);
+        pos = end;
+    }
+    Ok(Some(vec))
+}
+
+fn read_scanning(file: File) -> io::Result<SparseVec<u8>> {
+    let mut reader = BufReader::with_capacity(1 << 16, file);
+    let mut vec = SparseVec::new();
+    let mut block: Option<(u64, Vec<u8>)> = None;
+    let mut zeros = 0;
+    let mut pos = 0;
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        for run in buf.chunk_by(|a, b| (*a == 0) == (*b == 0)) {
+            if run[0] == 0 {
+                zeros += run.len() as u64;
+            } else {
+                match &mut block {
+                    Some((_, data)) if zeros < ZERO_RUN => {
+                        data.resize(data.len() + zeros as usize, 0);
+                        data.extend_from_slice(run);
+                    }
+                    _ => {
+                        if let Some((start, data)) = block.replace((pos, run.to_vec())) {
+                            vec.insert(data, start);
+                        }
+                    }
+                }
+                zeros = 0;
+            }
+            pos += run.len() as u64;
+        }
+        let len = buf.len();
+        reader.consume(len);
+    }
+    if let Some((start, data)) = block {
+        vec.insert(data, start);
+    }
+    Ok(vec)
+}
+
+#[cfg(test)]
+fn sparse_file_test_vec(offset: u64) -> SparseVec<u8> {
+    let mut map = SparseVec::new();
+    map.insert(vec![1, 2, 3], 0);
+    map.insert(vec![7; 5000], 0x2_0000);
+    map.insert(vec![4, 0, 0, 5], offset);
+    map.insert(vec![9; 100], offset + 0x3_0000);
+    map
+}
+
+// Every written block must be read back exactly, anything else may only be zero padding.
+#[cfg(test)]
+fn assert_sparse_file_contents(written: &SparseVec<u8>, read: &SparseVec<u8>) {
+    for (range, data) in written.blocks() {
+        assert_eq!(read.get(range).unwrap(), data);
+    }
+    for (addr, v) in read.iter_range(0..u64::MAX) {
+        assert!(*v == 0 || written.get(addr..addr + 1).is_some());
+    }
+}
+
+#[cfg(test)]
+fn sparse_file_test_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("sparse_vec_{name}_{}", std::process::id()))
+}
+
+#[test]
+fn sparsevec_sparse_file_roundtrip() {
+    let path = sparse_file_test_path("roundtrip");
+    let map = sparse_file_test_vec(0x4000_0000);
+    map.write_sparse_file(&path).unwrap();
+    assert_eq!(std::fs::metadata(&path).unwrap().len(), 0x4003_0064);
+
+    let read = SparseVec::read_sparse_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_sparse_file_contents(&map, &read);
+}
+
+#[test]
+fn sparsevec_sparse_file_scanning() {
+    let path = sparse_file_test_path("scanning");
+    let map = sparse_file_test_vec(0x400_0000);
+    map.write_sparse_file(&path).unwrap();
+
+    let read = read_scanning(File::open(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_sparse_file_contents(&map, &read);
+    assert_eq!(
+        Vec::from_iter(read.ranges()),
+        vec![
+            0..3,
+            0x2_0000..0x2_1388,
+            0x400_0000..0x400_0004,
+            0x403_0000..0x403_0064
+        ]
+    );
+
+    let empty = SparseVec::new();
+    empty.write_sparse_file(&path).unwrap();
+    let read = SparseVec::read_sparse_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(read.stored_len(), 0);
+}
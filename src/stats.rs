@@ -0,0 +1,91 @@
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+/// Distribution of the stored bytes in a range, see [`SparseVec::byte_stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteStats {
+    /// Number of stored bytes with each value.
+    pub histogram: [u64; 256],
+    /// Number of stored bytes in the range.
+    pub covered: u64,
+    /// Shannon entropy of the stored bytes in bits per byte, from 0.0 to 8.0. 0.0 if nothing
+    /// is stored.
+    pub entropy: f64,
+}
+
+impl<A: Address> SparseVec<u8, A> {
+    /// Histogram and entropy of the bytes stored within `range`. Gaps are not counted.
+    pub fn byte_stats(&self, range: Range<A>) -> ByteStats {
+        let mut histogram = [0u64; 256];
+        for (_, slice) in self.slices(range) {
+            for &byte in slice {
+                histogram[byte as usize] += 1;
+            }
+        }
+        let covered: u64 = histogram.iter().sum();
+        let entropy = histogram
+            .iter()
+            .filter(|&&count| count > 0)
+            .map(|&count| {
+                let p = count as f64 / covered as f64;
+                -p * p.log2()
+            })
+            .sum::<f64>()
+            // Avoids -0.0 for a single value
+            .max(0.0);
+        ByteStats {
+            histogram,
+            covered,
+            entropy,
+        }
+    }
+
+    /// Shannon entropy of the bytes stored within `range`, see [`SparseVec::byte_stats`].
+    pub fn entropy(&self, range: Range<A>) -> f64 {
+        self.byte_stats(range).entropy
+    }
+}
+
+#[test]
+fn sparsevec_byte_stats() {
+    use rand::{Rng, SeedableRng};
+
+    let mut rng = rand::rngs::StdRng::seed_from_u64(155);
+    let mut map = SparseVec::new();
+    let mut model = vec![None; 0x1000];
+    for _ in 0..50 {
+        let start = rng.gen_range(0..0xf00);
+        let data = Vec::from_iter((0..rng.gen_range(0..0x100)).map(|_| rng.gen_range(0..16u8)));
+        model[start..start + data.len()]
+            .iter_mut()
+            .zip(&data)
+            .for_each(|(m, v)| *m = Some(*v));
+        map.insert(data, start as u64);
+    }
+
+    for window in [0..0x1000, 0x123..0x456, 0xff0..0xfff] {
+        let stats = map.byte_stats(window.start as u64..window.end as u64);
+        let bytes = Vec::from_iter(model[window].iter().flatten().copied());
+        let mut histogram = [0; 256];
+        bytes.iter().for_each(|&b| histogram[b as usize] += 1);
+        let entropy: f64 = (0..=255u8)
+            .map(|v| bytes.iter().filter(|&&b| b == v).count() as f64 / bytes.len() as f64)
+            .filter(|&p| p > 0.0)
+            .map(|p| -p * p.log2())
+            .sum();
+        assert_eq!(stats.histogram, histogram);
+        assert_eq!(stats.covered, bytes.len() as u64);
+        assert!((stats.entropy - entropy).abs() < 1e-9);
+    }
+
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 0x1000], 0x1000);
+    map.insert(Vec::from_iter(0..=255u8), 0x3000);
+    assert_eq!(map.entropy(0x1000..0x2000), 0.0);
+    assert_eq!(map.entropy(0x3000..0x3100), 8.0);
+    assert_eq!(map.entropy(0x3000..0x3002), 1.0);
+    let gaps = map.byte_stats(0x2000..0x3000);
+    assert_eq!((gaps.covered, gaps.entropy), (0, 0.0));
+    assert!(gaps.histogram.iter().all(|&c| c == 0));
+}
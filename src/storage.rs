@@ -0,0 +1,489 @@
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, realloc, Layout};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::{Deref, DerefMut, Range};
+use core::ptr::{self, NonNull};
+
+use crate::journal::JournalOp;
+use crate::{Address, SparseVec, WatchOp};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Empty `SparseVec` whose blocks each start at a memory address that is a multiple of
+    /// `align` bytes, e.g. for SIMD loads or DMA. The alignment is kept when blocks are
+    /// split, resized or merged. Blocks are copied into aligned buffers when inserted, so
+    /// `insert` no longer takes over the allocation of its `Vec`.
+    ///
+    /// Panics if `align` is not a power of two.
+    pub fn with_block_alignment(align: usize) -> Self {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        Self {
+            block_align: Some(align),
+            ..Self::default()
+        }
+    }
+
+    /// The alignment passed to [`SparseVec::with_block_alignment`], if any.
+    pub fn block_alignment(&self) -> Option<usize> {
+        self.block_align
+    }
+
+    /// Same as [`SparseVec::blocks`]. With [`SparseVec::with_block_alignment`], the pointer of
+    /// every slice is a multiple of the block alignment, also for empty `T`.
+    pub fn blocks_aligned(&self) -> impl Iterator<Item = (Range<A>, &[T])> + '_ {
+        self.blocks().inspect(|(_, slice)| {
+            debug_assert!(self
+                .block_align
+                .is_none_or(|align| slice.as_ptr() as usize % align == 0));
+        })
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// Like [`SparseVec::insert`], but keeps referencing `data` instead of copying it, e.g.
+    /// for large read-only images. Splitting and trimming such a block by later inserts
+    /// keeps referencing `data`; a block is only copied once it is mutated through
+    /// `get_mut`, `write` or `blocks_mut`.
+    ///
+    /// Shared blocks are not merged with adjacent data, so [`SparseVec::get`] over their edges
+    /// returns `None` until they are copied. With [`SparseVec::with_block_alignment`], `data`
+    /// is copied right away.
+    pub fn insert_shared(&mut self, data: Arc<[T]>, addr: A) {
+        if data.is_empty() {
+            return;
+        }
+        let range = addr..addr + A::from_usize(data.len());
+        self.assert_thawed(&range);
+        self.journal(JournalOp::Insert, addr, data.len() as u64, &data);
+        self.watch.notify(&range, WatchOp::Insert);
+        self.record_history(&range);
+        let storage = match self.block_align {
+            Some(align) => Storage::new(data.to_vec(), Some(align)),
+            None => Storage::shared(data),
+        };
+        self.insert_storage(storage, addr);
+        self.enforce_capacity(&range);
+    }
+
+    /// Whether the block containing `addr` still references data passed to
+    /// [`SparseVec::insert_shared`] or `insert_bytes`.
+    pub fn is_shared(&self, addr: A) -> bool {
+        self.map
+            .get(&addr)
+            .is_some_and(|key| self.data[key].is_shared())
+    }
+}
+
+/// The data of one block.
+pub(crate) enum Storage<T> {
+    Vec(Vec<T>),
+    Aligned(AlignedVec<T>),
+    /// Part `range` of data that is not owned. `to_vec` copies it, so that owning it does not
+    /// need `T: Clone` everywhere.
+    Shared {
+        data: Arc<[T]>,
+        range: Range<usize>,
+        to_vec: fn(&[T]) -> Vec<T>,
+    },
+    /// Like `Shared`, referencing a `Bytes`. Only built for `Storage<u8>`, where `view` is
+    /// its `Deref`, so the generic code can see the data as `[T]`.
+    #[cfg(feature = "bytes")]
+    Bytes {
+        data: bytes::Bytes,
+        view: fn(&bytes::Bytes) -> &[T],
+        to_vec: fn(&[T]) -> Vec<T>,
+    },
+    /// Compressed by [`SparseVec::compress_cold_blocks`], decompressed on access.
+    #[cfg(feature = "compress")]
+    Compressed(alloc::boxed::Box<crate::compress::Compressed<T>>),
+}
+
+impl<T> Storage<T> {
+    /// `data` as is, or copied to a buffer whose start is aligned to `align` bytes.
+    pub(crate) fn new(data: Vec<T>, align: Option<usize>) -> Self {
+        match align {
+            None => Storage::Vec(data),
+            Some(align) => Storage::Aligned(AlignedVec::from_vec(data, align)),
+        }
+    }
+
+    pub(crate) fn into_vec(self) -> Vec<T> {
+        match self {
+            Storage::Vec(vec) => vec,
+            Storage::Aligned(vec) => vec.into_vec(),
+            Storage::Shared {
+                data,
+                range,
+                to_vec,
+            } => to_vec(&data[range]),
+            #[cfg(feature = "bytes")]
+            Storage::Bytes { data, view, to_vec } => to_vec(view(&data)),
+            #[cfg(feature = "compress")]
+            Storage::Compressed(data) => data.into_vec(),
+        }
+    }
+
+    pub(crate) fn is_shared(&self) -> bool {
+        match self {
+            Storage::Shared { .. } => true,
+            #[cfg(feature = "bytes")]
+            Storage::Bytes { .. } => true,
+            _ => false,
+        }
+    }
+
+    fn truncate(&mut self, len: usize) {
+        match self {
+            Storage::Vec(vec) => vec.truncate(len),
+            Storage::Aligned(vec) => vec.truncate(len),
+            Storage::Shared { range, .. } => range.end = range.end.min(range.start + len),
+            #[cfg(feature = "bytes")]
+            Storage::Bytes { data, .. } => data.truncate(len),
+            #[cfg(feature = "compress")]
+            Storage::Compressed(_) => {
+                self.make_owned();
+                self.truncate(len);
+            }
+        }
+    }
+
+    // Copies shared data and decompresses compressed data so it can be modified
+    fn make_owned(&mut self) {
+        match self {
+            Storage::Shared {
+                data,
+                range,
+                to_vec,
+            } => *self = Storage::Vec(to_vec(&data[range.clone()])),
+            #[cfg(feature = "bytes")]
+            Storage::Bytes { data, view, to_vec } => *self = Storage::Vec(to_vec(view(data))),
+            #[cfg(feature = "compress")]
+            Storage::Compressed(_) => {
+                let Storage::Compressed(data) = mem::replace(self, Storage::Vec(Vec::new())) else {
+                    unreachable!()
+                };
+                *self = Storage::Vec(data.into_vec());
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<T: Copy> Storage<T> {
+    pub(crate) fn shared(data: Arc<[T]>) -> Self {
+        Storage::Shared {
+            range: 0..data.len(),
+            data,
+            to_vec: <[T]>::to_vec,
+        }
+    }
+
+    /// Copy of `range`, or another reference to it for shared data.
+    pub(crate) fn slice(&self, range: Range<usize>, align: Option<usize>) -> Self {
+        match self {
+            Storage::Shared {
+                data,
+                range: shared,
+                to_vec,
+            } => Storage::Shared {
+                data: data.clone(),
+                range: shared.start + range.start..shared.start + range.end,
+                to_vec: *to_vec,
+            },
+            #[cfg(feature = "bytes")]
+            Storage::Bytes { data, view, to_vec } => Storage::Bytes {
+                data: data.slice(range),
+                view: *view,
+                to_vec: *to_vec,
+            },
+            _ => Storage::new(self[range].to_vec(), align),
+        }
+    }
+
+    /// Keeps only the elements in `keep`.
+    pub(crate) fn trim(&mut self, keep: Range<usize>) {
+        match self {
+            Storage::Shared { range, .. } => {
+                *range = range.start + keep.start..range.start + keep.end;
+                return;
+            }
+            #[cfg(feature = "bytes")]
+            Storage::Bytes { data, .. } => {
+                *data = data.slice(keep);
+                return;
+            }
+            _ => {}
+        }
+        if keep.start != 0 {
+            self.copy_within(keep.clone(), 0);
+        }
+        self.truncate(keep.end - keep.start);
+    }
+
+    pub(crate) fn extend_from_slice(&mut self, data: &[T]) {
+        self.make_owned();
+        match self {
+            Storage::Vec(vec) => vec.extend_from_slice(data),
+            Storage::Aligned(vec) => vec.extend_from_slice(data),
+            _ => unreachable!(),
+        }
+    }
+}
+
+#[cfg(feature = "bytes")]
+impl Storage<u8> {
+    pub(crate) fn bytes(data: bytes::Bytes) -> Self {
+        Storage::Bytes {
+            data,
+            view: Deref::deref,
+            to_vec: <[u8]>::to_vec,
+        }
+    }
+
+    /// Another reference to the data, copied into a new `Bytes` unless it already is one.
+    pub(crate) fn to_bytes(&self) -> bytes::Bytes {
+        match self {
+            Storage::Bytes { data, .. } => data.clone(),
+            _ => bytes::Bytes::copy_from_slice(self),
+        }
+    }
+}
+
+impl<T> Deref for Storage<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Storage::Vec(vec) => vec,
+            Storage::Aligned(vec) => vec,
+            Storage::Shared { data, range, .. } => &data[range.clone()],
+            #[cfg(feature = "bytes")]
+            Storage::Bytes { data, view, .. } => view(data),
+            #[cfg(feature = "compress")]
+            Storage::Compressed(data) => data.get(),
+        }
+    }
+}
+
+impl<T> DerefMut for Storage<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.make_owned();
+        match self {
+            Storage::Vec(vec) => vec,
+            Storage::Aligned(vec) => vec,
+            _ => unreachable!(),
+        }
+    }
+}
+
+/// Growable buffer like `Vec<T>` whose allocation is aligned to `align` bytes.
+pub(crate) struct AlignedVec<T> {
+    ptr: NonNull<T>,
+    len: usize,
+    cap: usize,
+    align: usize,
+}
+
+// Owns its elements like `Vec<T>`
+unsafe impl<T: Send> Send for AlignedVec<T> {}
+unsafe impl<T: Sync> Sync for AlignedVec<T> {}
+
+impl<T> AlignedVec<T> {
+    fn from_vec(mut vec: Vec<T>, align: usize) -> Self {
+        assert!(align.is_power_of_two(), "alignment must be a power of two");
+        let align = align.max(mem::align_of::<T>());
+        let mut aligned = Self {
+            // Dangling but aligned until something is allocated
+            ptr: NonNull::new(align as *mut T).unwrap(),
+            len: 0,
+            cap: if mem::size_of::<T>() == 0 {
+                usize::MAX
+            } else {
+                0
+            },
+            align,
+        };
+        aligned.reserve(vec.len());
+        unsafe {
+            // Moves the elements, `vec` only frees its allocation
+            ptr::copy_nonoverlapping(vec.as_ptr(), aligned.ptr.as_ptr(), vec.len());
+            aligned.len = vec.len();
+            vec.set_len(0);
+        }
+        aligned
+    }
+
+    fn into_vec(mut self) -> Vec<T> {
+        let mut vec = Vec::with_capacity(self.len);
+        unsafe {
+            ptr::copy_nonoverlapping(self.ptr.as_ptr(), vec.as_mut_ptr(), self.len);
+            vec.set_len(self.len);
+            self.len = 0;
+        }
+        vec
+    }
+
+    fn layout(&self, cap: usize) -> Layout {
+        Layout::array::<T>(cap)
+            .and_then(|layout| layout.align_to(self.align))
+            .expect("capacity overflow")
+    }
+
+    fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if needed <= self.cap {
+            return;
+        }
+        let cap = needed.max(self.cap.saturating_mul(2));
+        let layout = self.layout(cap);
+        let ptr = unsafe {
+            if self.cap == 0 {
+                alloc(layout)
+            } else {
+                realloc(
+                    self.ptr.as_ptr().cast(),
+                    self.layout(self.cap),
+                    layout.size(),
+                )
+            }
+        };
+        // `realloc` keeps the alignment of the old layout
+        self.ptr = NonNull::new(ptr.cast()).unwrap_or_else(|| handle_alloc_error(layout));
+        self.cap = cap;
+    }
+
+    fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            let tail = unsafe { self.ptr.as_ptr().add(len) };
+            let tail = ptr::slice_from_raw_parts_mut(tail, self.len - len);
+            self.len = len;
+            unsafe { ptr::drop_in_place(tail) };
+        }
+    }
+}
+
+impl<T: Copy> AlignedVec<T> {
+    fn extend_from_slice(&mut self, data: &[T]) {
+        self.reserve(data.len());
+        unsafe {
+            let end = self.ptr.as_ptr().add(self.len);
+            ptr::copy_nonoverlapping(data.as_ptr(), end, data.len());
+        }
+        self.len += data.len();
+    }
+}
+
+impl<T> Deref for AlignedVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> DerefMut for AlignedVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T> Drop for AlignedVec<T> {
+    fn drop(&mut self) {
+        self.truncate(0);
+        if self.cap != 0 && mem::size_of::<T>() != 0 {
+            unsafe { dealloc(self.ptr.as_ptr().cast(), self.layout(self.cap)) };
+        }
+    }
+}
+
+#[test]
+fn sparsevec_block_alignment() {
+    use rand::{Rng, SeedableRng};
+
+    let align = 64;
+    let mut rng = rand::rngs::StdRng::seed_from_u64(143);
+    let mut map = SparseVec::with_block_alignment(align);
+    let mut model = vec![None; 0x400];
+    for i in 0..500u32 {
+        let start = rng.gen_range(0..0x3c0);
+        let len = rng.gen_range(0..0x40);
+        if rng.gen_bool(0.8) {
+            map.insert(vec![i; len], start);
+        } else {
+            map.fill(start..start + len as u64, i);
+        }
+        model[start as usize..][..len].fill(Some(i));
+
+        for (range, slice) in map.blocks_aligned() {
+            assert_eq!(slice.as_ptr() as usize % align, 0, "block {range:x?}");
+            let expected = &model[range.start as usize..range.end as usize];
+            assert!(slice
+                .iter()
+                .map(Some)
+                .eq(expected.iter().map(Option::as_ref)));
+        }
+    }
+    assert_eq!(map.block_alignment(), Some(align));
+    assert_eq!(SparseVec::<u8>::new().block_alignment(), None);
+
+    // Blocks keep their contents when taken out
+    for (start, vec) in map {
+        let expected = &model[start as usize..][..vec.len()];
+        assert!(vec.iter().map(Some).eq(expected.iter().map(Option::as_ref)));
+    }
+}
+
+#[test]
+fn sparsevec_insert_shared() {
+    let rom: Arc<[u8]> = Vec::from_iter(0..0x40).into();
+    let mut map = SparseVec::new();
+    map.insert(vec![0xff; 4], 0x0c);
+    map.insert_shared(rom.clone(), 0x10);
+    map.insert_shared(rom.clone(), 0x1000);
+    assert!(map.is_shared(0x10) && map.is_shared(0x104f - 0x10));
+    assert_eq!(Arc::strong_count(&rom), 3);
+
+    // Splitting and trimming keep referencing the data
+    map.insert(vec![0xee; 4], 0x20);
+    map.fill(0x0c..0x14, 0xdd);
+    let ranges = Vec::from_iter(map.ranges());
+    assert_eq!(
+        ranges,
+        vec![
+            0x0c..0x14,
+            0x14..0x20,
+            0x20..0x24,
+            0x24..0x50,
+            0x1000..0x1040
+        ]
+    );
+    assert!(map.is_shared(0x14) && map.is_shared(0x24) && !map.is_shared(0x20));
+    assert_eq!(Arc::strong_count(&rom), 4);
+    assert_eq!(map.get(0x1e..0x20).unwrap(), &[0x0e, 0x0f]);
+    assert_eq!(map.get(0x24..0x26).unwrap(), &[0x14, 0x15]);
+    // Shared blocks are not merged
+    assert_eq!(map.get(0x1e..0x22), None);
+
+    // Mutation copies the block, which then merges again
+    map.write(0x1001, &[0xcc]).unwrap();
+    assert!(!map.is_shared(0x1000));
+    assert_eq!(map.get(0x1000..0x1003).unwrap(), &[0, 0xcc, 2]);
+    map.get_mut(0x14..0x15).unwrap()[0] = 0xbb;
+    map.get_mut(0x24..0x25).unwrap()[0] = 0xbb;
+    map.insert(vec![1], 0x50);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x0c..0x51, 0x1000..0x1040]
+    );
+    assert_eq!(Arc::strong_count(&rom), 1);
+    assert_eq!(rom[4], 4);
+
+    // Values taken out are owned copies
+    let mut map = SparseVec::<_>::with_max_block_len(0x10);
+    map.insert_shared(rom.clone(), 0x08);
+    assert_eq!(map.ranges().len(), 5);
+    assert!(map.is_shared(0x08) && map.is_shared(0x47));
+    let blocks = Vec::from_iter(map);
+    assert_eq!(blocks[1], (0x10, Vec::from_iter(0x08..0x18)));
+    assert_eq!(Arc::strong_count(&rom), 1);
+}
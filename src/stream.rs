@@ -0,0 +1,233 @@
+use alloc::vec::Vec;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll};
+
+use futures_core::Stream;
+
+use crate::{Address, SparseVec};
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// The blocks as a [`Stream`] of owned copies, in address order, e.g. for uploading.
+    /// Every item is ready immediately, so backpressure is up to the consumer.
+    pub fn block_stream(&self) -> impl Stream<Item = (A, Vec<T>)> + Unpin + '_ {
+        self.block_stream_chunked(usize::MAX)
+    }
+
+    /// Like [`SparseVec::block_stream`], but splits blocks into chunks of at most `max_len`
+    /// elements.
+    ///
+    /// # Panics
+    ///
+    /// If `max_len` is zero.
+    pub fn block_stream_chunked(
+        &self,
+        max_len: usize,
+    ) -> impl Stream<Item = (A, Vec<T>)> + Unpin + '_ {
+        assert!(max_len != 0, "chunk size must not be zero");
+        IterStream(self.blocks().flat_map(move |(range, data)| {
+            data.chunks(max_len)
+                .enumerate()
+                .map(move |(i, chunk)| (range.start + A::from_usize(i * max_len), chunk.to_vec()))
+        }))
+    }
+
+    /// Collects the chunks of `stream` into a `SparseVec`, stopping at the first error.
+    ///
+    /// Chunks are stored like [`SparseVec::insert`] in the order they arrive, so where
+    /// chunks overlap the later one wins, and adjacent chunks are merged. While chunks
+    /// arrive in address order without overlapping, they are only collected and the blocks
+    /// are built at the end, like [`SparseVecBuilder`](crate::SparseVecBuilder) does.
+    ///
+    /// Panics if a chunk does not fit in the address space.
+    pub async fn from_stream<S, E>(stream: S) -> Result<Self, E>
+    where
+        S: Stream<Item = Result<(A, Vec<T>), E>>,
+    {
+        let mut stream = pin!(stream);
+        let mut sorted: Vec<(A, Vec<T>)> = Vec::new();
+        let mut sorted_end = A::ZERO;
+        // Once a chunk arrives out of order
+        let mut vec: Option<Self> = None;
+        while let Some(item) = core::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            let (start, data) = item?;
+            if data.is_empty() {
+                continue;
+            }
+            if vec.is_none() && start >= sorted_end {
+                // Data past the end of the address space goes on to panic in `insert`
+                if let Some(end) = start.checked_add(A::from_usize(data.len())) {
+                    sorted_end = end;
+                    sorted.push((start, data));
+                    continue;
+                }
+            }
+            vec.get_or_insert_with(|| Self::from_sorted_blocks(core::mem::take(&mut sorted)))
+                .insert(data, start);
+        }
+        Ok(vec.unwrap_or_else(|| Self::from_sorted_blocks(sorted)))
+    }
+}
+
+// Stream over an iterator, ready on every poll
+struct IterStream<I>(I);
+
+// The iterator is never pinned
+impl<I> Unpin for IterStream<I> {}
+
+impl<I: Iterator> Stream for IterStream<I> {
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.get_mut().0.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+#[cfg(test)]
+fn block_on<F: core::future::Future>(future: F) -> F::Output {
+    use core::task::{RawWaker, RawWakerVTable, Waker};
+
+    const VTABLE: RawWakerVTable = RawWakerVTable::new(
+        |_| RawWaker::new(core::ptr::null(), &VTABLE),
+        |_| {},
+        |_| {},
+        |_| {},
+    );
+    // SAFETY: the vtable ignores the data pointer
+    let waker = unsafe { Waker::from_raw(RawWaker::new(core::ptr::null(), &VTABLE)) };
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+    }
+}
+
+// Pending on every other poll, as a network stream would be
+#[cfg(test)]
+struct Pending<I> {
+    items: I,
+    ready: bool,
+}
+
+#[cfg(test)]
+impl<I: Iterator + Unpin> Stream for Pending<I> {
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        let this = self.get_mut();
+        this.ready = !this.ready;
+        if this.ready {
+            Poll::Ready(this.items.next())
+        } else {
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+#[test]
+fn sparsevec_block_stream() {
+    let map = crate::sparse_vec! {
+        0x100 => Vec::from_iter(0..10u8),
+        0x200 => [1; 2],
+    };
+    assert_eq!(
+        block_on(collect_stream(map.block_stream())),
+        vec![(0x100, Vec::from_iter(0..10)), (0x200, vec![1; 2])]
+    );
+    assert_eq!(
+        block_on(collect_stream(map.block_stream_chunked(4))),
+        vec![
+            (0x100, vec![0, 1, 2, 3]),
+            (0x104, vec![4, 5, 6, 7]),
+            (0x108, vec![8, 9]),
+            (0x200, vec![1; 2])
+        ]
+    );
+    let empty = SparseVec::<u8>::new();
+    assert!(block_on(collect_stream(empty.block_stream())).is_empty());
+}
+
+#[cfg(test)]
+async fn collect_stream<S: Stream + Unpin>(mut stream: S) -> Vec<S::Item> {
+    let mut items = Vec::new();
+    while let Some(item) = core::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+        items.push(item);
+    }
+    items
+}
+
+#[test]
+fn sparsevec_from_stream() {
+    use rand::seq::SliceRandom;
+    use rand::SeedableRng;
+
+    let from = |chunks: Vec<(u64, Vec<u8>)>| {
+        let stream = Pending {
+            items: chunks.into_iter().map(Ok::<_, ()>),
+            ready: false,
+        };
+        let map = block_on(SparseVec::from_stream(stream)).unwrap();
+        map.assert_invariants();
+        map
+    };
+
+    // In order, adjacent chunks are merged
+    let map = from(vec![
+        (0x100, vec![1; 4]),
+        (0x104, vec![2; 4]),
+        (0x200, vec![3]),
+    ]);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x108, 0x200..0x201]
+    );
+    assert_eq!(map.get(0x102..0x106).unwrap(), &[1, 1, 2, 2]);
+
+    // Out of order and overlapping, the later chunk wins
+    let map = from(vec![
+        (0x200, vec![3; 4]),
+        (0x100, vec![1; 8]),
+        (0x104, vec![2; 2]),
+        (0x1fe, vec![4; 4]),
+        (0x300, Vec::new()),
+        (u64::MAX - 2, vec![5; 2]),
+    ]);
+    assert_eq!(
+        Vec::from_iter(map.ranges()),
+        vec![0x100..0x108, 0x1fe..0x204, u64::MAX - 2..u64::MAX]
+    );
+    assert_eq!(map.get(0x100..0x108).unwrap(), &[1, 1, 1, 1, 2, 2, 1, 1]);
+    assert_eq!(map.get(0x1fe..0x204).unwrap(), &[4, 4, 4, 4, 3, 3]);
+    assert_eq!(map.get(u64::MAX - 2..u64::MAX).unwrap(), &[5, 5]);
+
+    // Any order of disjoint chunks gives the same blocks
+    let source = crate::sparse_vec! {
+        0x0 => Vec::from_iter(0..0x40u8),
+        0x80 => Vec::from_iter(0x40..0x60u8),
+    };
+    let mut chunks = block_on(collect_stream(source.block_stream_chunked(8)));
+    let mut rng = rand::rngs::StdRng::seed_from_u64(195);
+    for _ in 0..10 {
+        chunks.shuffle(&mut rng);
+        let map = from(chunks.clone());
+        assert!(map.blocks().eq(source.blocks()));
+    }
+
+    // Errors stop the stream
+    let items = [
+        Ok((0u64, vec![1u8])),
+        Err("lost connection"),
+        Ok((1, vec![2])),
+    ];
+    let stream = IterStream(items.into_iter());
+    assert_eq!(
+        block_on(SparseVec::from_stream(stream)).unwrap_err(),
+        "lost connection"
+    );
+}
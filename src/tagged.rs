@@ -0,0 +1,134 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use rangemap::RangeMap;
+
+use crate::{Address, SparseVec, Unmapped};
+
+/// A [`SparseVec`] where every stored address also carries a metadata value, e.g. the file a
+/// segment was loaded from.
+///
+/// Overwriting part of a region splits its tag onto the remaining parts. Adjacent regions are
+/// only reported as one block by [`TaggedSparseVec::blocks`] if their tags are equal.
+pub struct TaggedSparseVec<T, M, A = u64> {
+    data: SparseVec<T, A>,
+    tags: RangeMap<A, M>,
+}
+
+impl<T, M: Eq + Clone, A: Address> Default for TaggedSparseVec<T, M, A> {
+    fn default() -> Self {
+        Self {
+            data: SparseVec::default(),
+            tags: RangeMap::new(),
+        }
+    }
+}
+
+impl<T, M: Eq + Clone> TaggedSparseVec<T, M> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T, M: Eq + Clone, A: Address> TaggedSparseVec<T, M, A> {
+    /// The data without tags.
+    pub fn data(&self) -> &SparseVec<T, A> {
+        &self.data
+    }
+
+    pub fn into_data(self) -> SparseVec<T, A> {
+        self.data
+    }
+
+    pub fn tag_at(&self, addr: A) -> Option<&M> {
+        self.tags.get(&addr)
+    }
+
+    /// Runs of stored data with equal tags, in address order.
+    pub fn blocks(&self) -> impl Iterator<Item = (Range<A>, &[T], &M)> + '_ {
+        self.tags.iter().map(|(range, tag)| {
+            // Tags cover exactly the stored data and blocks are always merged
+            let slice = self.data.slices(range.clone()).next().unwrap().1;
+            (range.clone(), slice, tag)
+        })
+    }
+}
+
+impl<T: Copy, M: Eq + Clone, A: Address> TaggedSparseVec<T, M, A> {
+    pub fn get(&self, range: Range<A>) -> Option<&[T]> {
+        self.data.get(range)
+    }
+
+    /// Inserts `data` at `addr` like [`SparseVec::insert`] and tags its range with `meta`.
+    pub fn insert_tagged(&mut self, data: Vec<T>, addr: A, meta: M) {
+        if data.is_empty() {
+            return;
+        }
+        self.tags
+            .insert(addr..addr + A::from_usize(data.len()), meta);
+        self.data.insert(data, addr);
+    }
+
+    /// Sets every address in `range` to `value` and tags it with `meta`.
+    pub fn fill_tagged(&mut self, range: Range<A>, value: T, meta: M) {
+        if range.is_empty() {
+            return;
+        }
+        self.tags.insert(range.clone(), meta);
+        self.data.fill(range, value);
+    }
+
+    /// Overwrites stored data like [`SparseVec::write`], keeping the tags.
+    pub fn write(&mut self, addr: A, data: &[T]) -> Result<(), Unmapped<A>> {
+        self.data.write(addr, data)
+    }
+}
+
+#[test]
+fn sparsevec_tagged() {
+    let mut map = TaggedSparseVec::new();
+    map.insert_tagged(vec![1u8; 0x10], 0x10, "a.elf");
+    map.insert_tagged(vec![2u8; 0x10], 0x20, "b.elf");
+    // Splits the first region
+    map.insert_tagged(vec![3u8; 4], 0x14, "patch");
+    map.fill_tagged(0x30..0x34, 0, "a.elf");
+    map.insert_tagged(vec![4u8; 4], 0x34, "a.elf");
+
+    assert_eq!(
+        Vec::from_iter(
+            map.blocks()
+                .map(|(range, data, tag)| (range, data[0], *tag))
+        ),
+        vec![
+            (0x10..0x14, 1, "a.elf"),
+            (0x14..0x18, 3, "patch"),
+            (0x18..0x20, 1, "a.elf"),
+            (0x20..0x30, 2, "b.elf"),
+            // Equal tags merge
+            (0x30..0x38, 0, "a.elf"),
+        ]
+    );
+    assert_eq!(map.blocks().nth(4).unwrap().1, &[0, 0, 0, 0, 4, 4, 4, 4]);
+    assert_eq!(map.tag_at(0x13), Some(&"a.elf"));
+    assert_eq!(map.tag_at(0x17), Some(&"patch"));
+    assert_eq!(map.tag_at(0x38), None);
+    assert_eq!(Vec::from_iter(map.data().ranges()), vec![0x10..0x38]);
+
+    map.write(0x1e, &[5; 4]).unwrap();
+    assert_eq!(map.tag_at(0x1f), Some(&"a.elf"));
+    assert_eq!(map.get(0x1e..0x22).unwrap(), &[5; 4]);
+    assert_eq!(map.write(0x36, &[5; 4]), Err(Unmapped { addr: 0x38 }));
+
+    // The tag overwritten entirely disappears
+    map.insert_tagged(vec![6u8; 8], 0x12, "b.elf");
+    assert_eq!(
+        Vec::from_iter(map.blocks().map(|(range, _, tag)| (range, *tag))),
+        vec![
+            (0x10..0x12, "a.elf"),
+            (0x12..0x1a, "b.elf"),
+            (0x1a..0x20, "a.elf"),
+            (0x20..0x30, "b.elf"),
+            (0x30..0x38, "a.elf"),
+        ]
+    );
+}
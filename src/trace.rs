@@ -0,0 +1,118 @@
+//! Instrumentation behind the `tracing` feature. Without it the macro expands to nothing and
+//! its arguments are never evaluated.
+
+#[cfg(feature = "tracing")]
+use core::fmt;
+
+#[cfg(feature = "tracing")]
+use crate::Address;
+
+#[cfg(feature = "tracing")]
+macro_rules! event {
+    ($level:ident, $($args:tt)*) => {
+        tracing::event!(tracing::Level::$level, $($args)*)
+    };
+}
+
+#[cfg(not(feature = "tracing"))]
+macro_rules! event {
+    ($($args:tt)*) => {};
+}
+
+pub(crate) use event;
+
+/// Records an address field as `0x...`.
+#[cfg(feature = "tracing")]
+pub(crate) struct Hex<A>(pub(crate) A);
+
+#[cfg(feature = "tracing")]
+impl<A: Address> fmt::Display for Hex<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn sparsevec_tracing_events() {
+    use std::fmt::Debug;
+    use std::sync::{Arc, Mutex};
+
+    use tracing::field::{Field, Visit};
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::{Event, Metadata, Subscriber};
+
+    use crate::SparseVec;
+
+    // Every event as its fields in order, e.g. `message=merge start=0x10 ...`
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if !self.0.is_empty() {
+                self.0.push(' ');
+            }
+            self.0 += &format!("{}={:?}", field.name(), value);
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+        fn new_span(&self, _: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+        fn record(&self, _: &Id, _: &Record<'_>) {}
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+        fn enter(&self, _: &Id) {}
+        fn exit(&self, _: &Id) {}
+    }
+
+    let recorder = Recorder::default();
+    tracing::subscriber::with_default(recorder.clone(), || {
+        let mut map = SparseVec::new();
+        map.insert(vec![0u8; 0x10], 0x10);
+        map.insert(vec![0u8; 0x10], 0x30);
+        // Trims the first block, then merges everything
+        map.insert(vec![1u8; 0x18], 0x18);
+        // Splits the merged block
+        map.insert(vec![2u8; 0x2], 0x20);
+        map.fill(0x00..0x04, 3);
+        map.insert(vec![4u8; 0x4], 0x00);
+    });
+    let events = recorder.0.lock().unwrap().clone();
+    assert_eq!(
+        events,
+        vec![
+            "message=insert start=0x10 end=0x20 blocks=0",
+            "message=inserted blocks=1",
+            "message=insert start=0x30 end=0x40 blocks=1",
+            "message=inserted blocks=2",
+            "message=insert start=0x18 end=0x30 blocks=2",
+            "message=trimmed block_start=0x10 block_end=0x20 start=0x10 end=0x18",
+            "message=merged start=0x10 at=0x18 end=0x30",
+            "message=merged start=0x10 at=0x30 end=0x40",
+            "message=inserted blocks=1",
+            "message=insert start=0x20 end=0x22 blocks=1",
+            "message=split block_start=0x10 block_end=0x40",
+            "message=merged start=0x10 at=0x20 end=0x22",
+            "message=merged start=0x10 at=0x22 end=0x40",
+            "message=inserted blocks=1",
+            "message=fill start=0x0 end=0x4",
+            "message=insert start=0x0 end=0x4 blocks=1",
+            "message=inserted blocks=2",
+            "message=insert start=0x0 end=0x4 blocks=2",
+            "message=overwritten block_start=0x0 block_end=0x4",
+            "message=inserted blocks=2",
+        ]
+    );
+}
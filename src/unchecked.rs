@@ -0,0 +1,136 @@
+use core::ops::Range;
+
+use crate::{cast_range, sub_range, Address, SparseVec, WatchOp};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// [`SparseVec::get`] without checking that `range` is stored, for hot loops over
+    /// addresses validated before. Mirrors are not resolved.
+    ///
+    /// # Safety
+    /// `range.start` must be stored, and `range` must not be reversed or extend past the end
+    /// of the block containing it. Debug builds panic otherwise.
+    pub unsafe fn get_unchecked(&self, range: Range<A>) -> &[T] {
+        let (block, key) = unsafe { self.find_unchecked(&range) };
+        let data = unsafe { self.data.get(&key).unwrap_unchecked() };
+        // SAFETY: the block contains `range`
+        unsafe { data.get_unchecked(cast_range(sub_range(&range, block.start))) }
+    }
+
+    /// The element at `addr`, like [`SparseVec::get_unchecked`] of `addr..addr + 1`.
+    ///
+    /// # Safety
+    /// `addr` must be stored in a block, so it cannot be `A::MAX`, which never is. Debug
+    /// builds panic otherwise.
+    pub unsafe fn get_value_unchecked(&self, addr: A) -> &T {
+        unsafe { &self.get_unchecked(value_range(addr))[0] }
+    }
+
+    // The block containing `range`, which the caller guarantees exists
+    unsafe fn find_unchecked(&self, range: &Range<A>) -> (Range<A>, usize) {
+        let found = self.find_block(range.start);
+        debug_assert!(
+            found
+                .as_ref()
+                .is_some_and(|(block, _)| range.start <= range.end && range.end <= block.end),
+            "range {:#x}..{:#x} is not within one block",
+            range.start,
+            range.end
+        );
+        // SAFETY: guaranteed by the caller
+        let (block, key) = unsafe { found.unwrap_unchecked() };
+        self.touch(key);
+        (block, key)
+    }
+}
+
+impl<T: Copy, A: Address> SparseVec<T, A> {
+    /// [`SparseVec::get_mut`] without checking that `range` is stored. Freezing, history and
+    /// watchpoints still apply.
+    ///
+    /// # Safety
+    /// Same as [`SparseVec::get_unchecked`].
+    pub unsafe fn get_mut_unchecked(&mut self, range: Range<A>) -> &mut [T] {
+        let (block, key) = unsafe { self.find_unchecked(&range) };
+        self.assert_thawed(&range);
+        self.record_history(&range);
+        self.watch.notify(&range, WatchOp::GetMut);
+        let data = unsafe { self.data.get_mut(&key).unwrap_unchecked() };
+        // SAFETY: the block contains `range`
+        unsafe { data.get_unchecked_mut(cast_range(sub_range(&range, block.start))) }
+    }
+
+    /// Mutable variant of [`SparseVec::get_value_unchecked`].
+    ///
+    /// # Safety
+    /// Same as [`SparseVec::get_value_unchecked`].
+    pub unsafe fn get_value_mut_unchecked(&mut self, addr: A) -> &mut T {
+        unsafe { &mut self.get_mut_unchecked(value_range(addr))[0] }
+    }
+}
+
+// `addr..addr + 1`, empty at `A::MAX` so that debug builds report it as unmapped
+fn value_range<A: Address>(addr: A) -> Range<A> {
+    addr..addr.checked_add(A::from_usize(1)).unwrap_or(addr)
+}
+
+#[test]
+fn sparsevec_get_unchecked() {
+    let mut map = crate::sparse_vec! {
+        0x100 => [1u8, 2, 3, 4],
+        0x200 => [5; 4],
+    };
+    map.set_auto_merge(false);
+    map.insert(vec![6; 4], 0x204);
+    unsafe {
+        assert_eq!(map.get_unchecked(0x101..0x104), &[2, 3, 4]);
+        assert!(map.get_unchecked(0x103..0x103).is_empty());
+        assert_eq!(*map.get_value_unchecked(0x207), 6);
+        map.get_mut_unchecked(0x200..0x202).fill(7);
+        *map.get_value_mut_unchecked(0x100) = 8;
+    }
+    assert_eq!(map.get(0x100..0x104).unwrap(), &[8, 2, 3, 4]);
+    assert_eq!(map.get(0x200..0x204).unwrap(), &[7, 7, 5, 5]);
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "range 0x202..0x206 is not within one block")]
+fn sparsevec_get_unchecked_across_blocks() {
+    let mut map = crate::sparse_vec! { 0x200 => [5u8; 4] };
+    map.set_auto_merge(false);
+    map.insert(vec![6; 4], 0x204);
+    unsafe {
+        map.get_unchecked(0x202..0x206);
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "range 0x180..0x181 is not within one block")]
+fn sparsevec_get_unchecked_unmapped() {
+    let mut map = crate::sparse_vec! { 0x100 => [1u8; 4] };
+    unsafe {
+        *map.get_value_mut_unchecked(0x180) = 0;
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "range 0xffffffffffffffff..0xffffffffffffffff is not within one block")]
+fn sparsevec_get_unchecked_max() {
+    let map = crate::sparse_vec! { u64::MAX - 4 => [1u8; 4] };
+    unsafe {
+        map.get_value_unchecked(u64::MAX);
+    }
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "range 0x102..0x101 is not within one block")]
+fn sparsevec_get_unchecked_reversed() {
+    let map = crate::sparse_vec! { 0x100 => [1u8; 4] };
+    #[allow(clippy::reversed_empty_ranges)]
+    unsafe {
+        map.get_unchecked(0x102..0x101);
+    }
+}
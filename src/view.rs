@@ -0,0 +1,285 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+use crate::{clip_range, Address, SparseVec, Unmapped};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Read-only view of the data inside `window`. Everything outside of it appears unmapped.
+    pub fn view(&self, window: Range<A>) -> SparseView<'_, T, A> {
+        SparseView {
+            vec: self,
+            window,
+            relative: false,
+        }
+    }
+
+    /// Mutable view that rejects every operation touching addresses outside of `window`.
+    pub fn view_mut(&mut self, window: Range<A>) -> SparseViewMut<'_, T, A> {
+        SparseViewMut { vec: self, window }
+    }
+}
+
+/// Borrowed window onto a [`SparseVec`], created by [`SparseVec::view`].
+pub struct SparseView<'a, T, A = u64> {
+    vec: &'a SparseVec<T, A>,
+    window: Range<A>,
+    relative: bool,
+}
+
+impl<'a, T, A: Address> SparseView<'a, T, A> {
+    /// Take and report addresses as offsets from the start of the window.
+    pub fn relative(mut self, relative: bool) -> Self {
+        self.relative = relative;
+        self
+    }
+
+    pub fn window(&self) -> Range<A> {
+        self.window.clone()
+    }
+
+    pub fn slices(&self, range: Range<A>) -> impl Iterator<Item = (Range<A>, &'a [T])> + 'a {
+        let (vec, offset) = (self.vec, self.offset());
+        vec.slices(self.clip(&range))
+            .map(move |(range, slice)| (range.start - offset..range.end - offset, slice))
+    }
+
+    pub fn iter_range(&self, range: Range<A>) -> impl Iterator<Item = (A, &'a T)> + 'a {
+        let (vec, offset) = (self.vec, self.offset());
+        vec.iter_range(self.clip(&range))
+            .map(move |(addr, v)| (addr - offset, v))
+    }
+
+    pub fn contains_range(&self, range: &Range<A>) -> bool {
+        match self.to_absolute(range) {
+            Some(range) if range.is_empty() => true,
+            Some(range) => self.inside(&range) && self.vec.contains_range(&range),
+            None => false,
+        }
+    }
+
+    /// Unmapped parts of `range`, including everything outside of the window.
+    pub fn gaps(&self, range: Range<A>) -> impl Iterator<Item = Range<A>> + 'a {
+        let offset = self.offset();
+        let window = self.window.clone();
+        let absolute = self.to_absolute(&range);
+        // Parts of the range before and after the window are gaps as well
+        let (before, after) = match &absolute {
+            Some(range) => (
+                range.start..range.end.min(window.start),
+                range.start.max(window.end)..range.end,
+            ),
+            None => (range.start..range.start, range.start..range.start),
+        };
+        let before = (!before.is_empty()).then_some(before);
+        let after = (!after.is_empty()).then_some(after);
+        before
+            .into_iter()
+            .chain(self.vec.gaps(self.clip(&range)))
+            .chain(after)
+            .map(move |gap| gap.start - offset..gap.end - offset)
+    }
+
+    fn offset(&self) -> A {
+        if self.relative {
+            self.window.start
+        } else {
+            A::ZERO
+        }
+    }
+
+    // `None` if a relative range does not fit the address space
+    fn to_absolute(&self, range: &Range<A>) -> Option<Range<A>> {
+        let offset = self.offset();
+        Some(range.start.checked_add(offset)?..range.end.checked_add(offset)?)
+    }
+
+    fn inside(&self, range: &Range<A>) -> bool {
+        range.start >= self.window.start && range.end <= self.window.end
+    }
+
+    // Absolute part of `range` inside the window, possibly empty
+    fn clip(&self, range: &Range<A>) -> Range<A> {
+        match self.to_absolute(range) {
+            Some(range) => {
+                let clipped = clip_range(&range, &self.window);
+                if clipped.is_empty() {
+                    clipped.start..clipped.start
+                } else {
+                    clipped
+                }
+            }
+            None => self.window.start..self.window.start,
+        }
+    }
+}
+
+impl<'a, T: Copy, A: Address> SparseView<'a, T, A> {
+    pub fn get(&self, range: Range<A>) -> Option<&'a [T]> {
+        let range = self.to_absolute(&range)?;
+        if !self.inside(&range) {
+            return None;
+        }
+        self.vec.get(range)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ViewError<A = u64> {
+    /// The operation would touch `start..end`, which is not inside the window.
+    OutOfWindow { start: A, end: A },
+    /// A write needed data at `addr`, which is not stored.
+    Unmapped { addr: A },
+}
+
+impl<A: Address> fmt::Display for ViewError<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ViewError::OutOfWindow { start, end } => {
+                write!(f, "range {start:#x}..{end:#x} is outside the view")
+            }
+            ViewError::Unmapped { addr } => write!(f, "address {addr:#x} is unmapped"),
+        }
+    }
+}
+
+impl<A: Address> core::error::Error for ViewError<A> {}
+
+/// Mutable window onto a [`SparseVec`], created by [`SparseVec::view_mut`].
+pub struct SparseViewMut<'a, T, A = u64> {
+    vec: &'a mut SparseVec<T, A>,
+    window: Range<A>,
+}
+
+impl<T: Copy, A: Address> SparseViewMut<'_, T, A> {
+    pub fn as_view(&self) -> SparseView<'_, T, A> {
+        self.vec.view(self.window.clone())
+    }
+
+    pub fn window(&self) -> Range<A> {
+        self.window.clone()
+    }
+
+    pub fn get_mut(&mut self, range: Range<A>) -> Result<Option<&mut [T]>, ViewError<A>> {
+        self.check(range.start, range.end)?;
+        Ok(self.vec.get_mut(range))
+    }
+
+    pub fn write(&mut self, addr: A, data: &[T]) -> Result<(), ViewError<A>> {
+        let range = self.check_len(addr, data.len())?;
+        self.vec
+            .write(range.start, data)
+            .map_err(|Unmapped { addr }| ViewError::Unmapped { addr })
+    }
+
+    pub fn fill(&mut self, range: Range<A>, value: T) -> Result<(), ViewError<A>> {
+        self.check(range.start, range.end)?;
+        self.vec.fill(range, value);
+        Ok(())
+    }
+
+    pub fn insert(&mut self, data: Vec<T>, addr: A) -> Result<(), ViewError<A>> {
+        self.check_len(addr, data.len())?;
+        self.vec.insert(data, addr);
+        Ok(())
+    }
+
+    fn check(&self, start: A, end: A) -> Result<(), ViewError<A>> {
+        if start > end || start < self.window.start || end > self.window.end {
+            return Err(ViewError::OutOfWindow { start, end });
+        }
+        Ok(())
+    }
+
+    fn check_len(&self, addr: A, len: usize) -> Result<Range<A>, ViewError<A>> {
+        let end = A::try_from_u64(len as u64)
+            .and_then(|len| addr.checked_add(len))
+            .unwrap_or(A::MAX);
+        self.check(addr, end)?;
+        Ok(addr..end)
+    }
+}
+
+#[test]
+fn sparsevec_view() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 0x10], 0x100);
+    map.insert(vec![2u8; 0x10], 0x120);
+    map.insert(vec![3u8; 0x10], 0x140);
+
+    // Both edge blocks straddle the window
+    let view = map.view(0x108..0x148);
+    assert_eq!(view.get(0x108..0x110), Some(&[1u8; 8][..]));
+    assert_eq!(view.get(0x104..0x110), None);
+    assert_eq!(view.get(0x140..0x149), None);
+    assert_eq!(
+        Vec::from_iter(view.slices(0..0x200)),
+        vec![
+            (0x108..0x110, &[1u8; 8][..]),
+            (0x120..0x130, &[2; 16][..]),
+            (0x140..0x148, &[3; 8][..])
+        ]
+    );
+    assert_eq!(view.iter_range(0x100..0x109).count(), 1);
+    assert_eq!(view.iter_range(0x147..0x150).count(), 1);
+    assert_eq!(view.slices(0x200..0x300).count(), 0);
+    assert!(view.contains_range(&(0x120..0x130)));
+    assert!(!view.contains_range(&(0x100..0x110)));
+    assert!(!view.contains_range(&(0x144..0x14c)));
+    assert_eq!(
+        Vec::from_iter(view.gaps(0x100..0x150)),
+        vec![0x100..0x108, 0x110..0x120, 0x130..0x140, 0x148..0x150]
+    );
+
+    let view = map.view(0x108..0x148).relative(true);
+    assert_eq!(view.window(), 0x108..0x148);
+    assert_eq!(view.get(0..8), Some(&[1u8; 8][..]));
+    assert_eq!(view.get(0x3f..0x41), None);
+    assert_eq!(
+        Vec::from_iter(view.slices(0x10..0x40).map(|(range, _)| range)),
+        vec![0x18..0x28, 0x38..0x40]
+    );
+    assert_eq!(view.iter_range(0..1).next(), Some((0, &1)));
+    assert!(view.contains_range(&(0x18..0x28)));
+    assert!(!view.contains_range(&(u64::MAX - 1..u64::MAX)));
+    assert_eq!(
+        Vec::from_iter(view.gaps(0..0x50)),
+        vec![0x8..0x18, 0x28..0x38, 0x40..0x50]
+    );
+    assert_eq!(view.slices(u64::MAX - 1..u64::MAX).count(), 0);
+}
+
+#[test]
+fn sparsevec_view_mut() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 0x10], 0x100);
+    map.insert(vec![2u8; 0x10], 0x120);
+
+    let mut view = map.view_mut(0x108..0x128);
+    let out = |start, end| Err(ViewError::OutOfWindow { start, end });
+    assert_eq!(view.insert(vec![9; 4], 0x106), out(0x106, 0x10a));
+    assert_eq!(view.insert(vec![9; 4], 0x126), out(0x126, 0x12a));
+    assert_eq!(view.insert(vec![9; 0x30], 0x100), out(0x100, 0x130));
+    assert_eq!(view.fill(0x127..0x129, 9), out(0x127, 0x129));
+    assert_eq!(view.write(0x104, &[9; 8]), out(0x104, 0x10c));
+    assert_eq!(view.get_mut(0x100..0x10a).map(|_| ()), out(0x100, 0x10a));
+    assert_eq!(view.insert(vec![9], u64::MAX), out(u64::MAX, u64::MAX));
+    assert_eq!(view.as_view().slices(0..u64::MAX).count(), 2);
+
+    assert_eq!(
+        view.write(0x10e, &[7; 4]),
+        Err(ViewError::Unmapped { addr: 0x110 })
+    );
+    view.insert(vec![5; 4], 0x110).unwrap();
+    view.write(0x10e, &[7; 4]).unwrap();
+    view.fill(0x114..0x120, 6).unwrap();
+    view.get_mut(0x126..0x128).unwrap().unwrap().fill(8);
+
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x100..0x130]);
+    assert_eq!(map.get(0x100..0x108).unwrap(), &[1; 8]);
+    assert_eq!(
+        map.get(0x10c..0x116).unwrap(),
+        &[1, 1, 7, 7, 7, 7, 5, 5, 6, 6]
+    );
+    assert_eq!(map.get(0x124..0x12a).unwrap(), &[2, 2, 8, 8, 2, 2]);
+}
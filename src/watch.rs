@@ -0,0 +1,122 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::{Address, SparseVec};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct WatchId(u64);
+
+/// The operation that touched a watched range.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchOp {
+    Insert,
+    Write,
+    Fill,
+    GetMut,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchHit<A = u64> {
+    pub id: WatchId,
+    /// The part of the watched range that was touched.
+    pub range: Range<A>,
+    pub op: WatchOp,
+}
+
+pub(crate) struct Watchpoints<A> {
+    watches: Vec<(WatchId, Range<A>)>,
+    hits: Vec<WatchHit<A>>,
+    next_id: u64,
+}
+
+impl<A> Default for Watchpoints<A> {
+    fn default() -> Self {
+        Self {
+            watches: Vec::new(),
+            hits: Vec::new(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<A: Address> Watchpoints<A> {
+    pub(crate) fn notify(&mut self, range: &Range<A>, op: WatchOp) {
+        for (id, watched) in &self.watches {
+            let range = crate::clip_range(range, watched);
+            if !range.is_empty() {
+                self.hits.push(WatchHit { id: *id, range, op });
+            }
+        }
+    }
+}
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Records a [`WatchHit`] whenever `insert`, `write`, `fill` or `get_mut` touches `range`.
+    /// The range does not have to be mapped.
+    pub fn add_watchpoint(&mut self, range: Range<A>) -> WatchId {
+        let id = WatchId(self.watch.next_id);
+        self.watch.next_id += 1;
+        self.watch.watches.push((id, range));
+        id
+    }
+
+    /// Returns `false` if there is no watchpoint with this id. Hits that were already recorded
+    /// are kept.
+    pub fn remove_watchpoint(&mut self, id: WatchId) -> bool {
+        let len = self.watch.watches.len();
+        self.watch.watches.retain(|(watch, _)| *watch != id);
+        self.watch.watches.len() != len
+    }
+
+    /// Hits in the order they were recorded, one per touched watchpoint and operation.
+    pub fn take_watch_hits(&mut self) -> Vec<WatchHit<A>> {
+        core::mem::take(&mut self.watch.hits)
+    }
+}
+
+#[test]
+fn sparsevec_watchpoints() {
+    let mut map = SparseVec::new();
+    map.insert(vec![0u8; 0x10], 0x100);
+    let a = map.add_watchpoint(0x104..0x108);
+    let b = map.add_watchpoint(0x106..0x120);
+    assert!(map.take_watch_hits().is_empty());
+
+    let hit = |id, range, op| WatchHit { id, range, op };
+    map.write(0x100, &[1; 5]).unwrap();
+    map.write(0x108, &[1; 1]).unwrap();
+    assert!(map.write(0x10f, &[1; 2]).is_err());
+    map.get_mut(0x100..0x104).unwrap();
+    assert!(map.get_mut(0x110..0x112).is_none());
+    assert_eq!(
+        map.take_watch_hits(),
+        vec![
+            hit(a, 0x104..0x105, WatchOp::Write),
+            hit(b, 0x108..0x109, WatchOp::Write)
+        ]
+    );
+
+    // Spanning the gap after the block
+    map.insert(vec![2u8; 4], 0x11e);
+    map.fill(0x105..0x118, 3);
+    map.get_mut(0x106..0x107).unwrap();
+    assert_eq!(
+        map.take_watch_hits(),
+        vec![
+            hit(b, 0x11e..0x120, WatchOp::Insert),
+            hit(a, 0x105..0x108, WatchOp::Fill),
+            hit(b, 0x106..0x118, WatchOp::Fill),
+            hit(a, 0x106..0x107, WatchOp::GetMut),
+            hit(b, 0x106..0x107, WatchOp::GetMut)
+        ]
+    );
+
+    assert!(map.remove_watchpoint(a));
+    assert!(!map.remove_watchpoint(a));
+    map.insert(vec![4u8; 0x20], 0xf0);
+    assert_eq!(
+        map.take_watch_hits(),
+        vec![hit(b, 0x106..0x110, WatchOp::Insert)]
+    );
+    assert_eq!(map.get(0x10e..0x112).unwrap(), &[4, 4, 3, 3]);
+}
@@ -0,0 +1,82 @@
+use core::ops::Range;
+
+use crate::{cast_range, clip_range, sub_range, Address, SparseVec};
+
+impl<T, A: Address> SparseVec<T, A> {
+    /// Maximal runs covered by both `self` and `other`, in address order, with the data of
+    /// both.
+    pub fn zip_slices<'a, U>(
+        &'a self,
+        other: &'a SparseVec<U, A>,
+    ) -> impl Iterator<Item = (Range<A>, &'a [T], &'a [U])> + 'a {
+        let mut left = self.blocks().peekable();
+        let mut right = other.blocks().peekable();
+        core::iter::from_fn(move || loop {
+            let (a, a_data) = left.peek()?;
+            let (b, b_data) = right.peek()?;
+            let common = clip_range(a, b);
+            let result = (!common.is_empty()).then(|| {
+                (
+                    common.clone(),
+                    &a_data[cast_range(sub_range(&common, a.start))],
+                    &b_data[cast_range(sub_range(&common, b.start))],
+                )
+            });
+            if a.end <= b.end {
+                left.next();
+            } else {
+                right.next();
+            }
+            if result.is_some() {
+                return result;
+            }
+        })
+    }
+
+    /// Every address covered by both `self` and `other` with both values, in address order.
+    pub fn zip_with<'a, U>(
+        &'a self,
+        other: &'a SparseVec<U, A>,
+    ) -> impl Iterator<Item = (A, &'a T, &'a U)> + 'a {
+        self.zip_slices(other).flat_map(|(range, a, b)| {
+            a.iter()
+                .zip(b)
+                .enumerate()
+                .map(move |(i, (a, b))| (range.start + A::from_usize(i), a, b))
+        })
+    }
+}
+
+#[test]
+fn sparsevec_zip() {
+    let mut left = SparseVec::new();
+    left.insert(vec![1u8, 2, 3, 4, 5, 6], 0x10);
+    left.insert(vec![7u8, 8], 0x20);
+    left.insert(vec![9u8; 4], 0x30);
+
+    let mut right = SparseVec::new();
+    right.insert(vec!['a', 'b'], 0x0e);
+    right.insert(vec!['c', 'd', 'e'], 0x13);
+    right.insert(vec!['f'; 0x20], 0x18);
+
+    assert_eq!(
+        Vec::from_iter(left.zip_slices(&right)),
+        vec![
+            (0x13..0x16, &[4u8, 5, 6][..], &['c', 'd', 'e'][..]),
+            (0x20..0x22, &[7, 8][..], &['f', 'f'][..]),
+            (0x30..0x34, &[9; 4][..], &['f'; 4][..]),
+        ]
+    );
+    assert_eq!(
+        Vec::from_iter(left.zip_with(&right).take(4)),
+        vec![
+            (0x13, &4, &'c'),
+            (0x14, &5, &'d'),
+            (0x15, &6, &'e'),
+            (0x20, &7, &'f')
+        ]
+    );
+    assert_eq!(left.zip_with(&right).count(), 9);
+    assert_eq!(right.zip_slices(&left).count(), 3);
+    assert_eq!(left.zip_slices(&SparseVec::<u8>::new()).count(), 0);
+}
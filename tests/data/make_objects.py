@@ -0,0 +1,59 @@
+#!/usr/bin/env python3
+"""Writes the tiny ELF and PE images used by the `object` loader tests."""
+import struct
+from pathlib import Path
+
+here = Path(__file__).parent
+
+
+def elf():
+    # (vaddr, file bytes, memory size); the third segment overlaps the first
+    segments = [
+        (0x400000, bytes(range(1, 9)), 8),
+        (0x401000, b"\xaa\xbb\xcc\xdd", 0x10),
+        (0x400004, b"\xee\xee", 2),
+    ]
+    phoff, phnum = 64, len(segments) + 1
+    offset = phoff + 56 * phnum
+    header = struct.pack(
+        "<4sBBBBB7xHHIQQQIHHHHHH",
+        b"\x7fELF", 2, 1, 1, 0, 0, 2, 0x3E, 1, 0x400002, phoff, 0, 0, 64, 56, phnum, 64, 0, 0,
+    )
+    phdrs, data = b"", b""
+    for vaddr, body, memsz in segments:
+        phdrs += struct.pack("<IIQQQQQQ", 1, 5, offset + len(data), vaddr, vaddr, len(body), memsz, 0x1000)
+        data += body
+    # A PT_NOTE segment that must not be loaded
+    phdrs += struct.pack("<IIQQQQQQ", 4, 4, offset, 0x500000, 0x500000, 2, 2, 4)
+    return header + phdrs + data
+
+
+def pe():
+    image_base = 0x140000000
+    # (name, virtual address, virtual size, raw bytes)
+    sections = [
+        (b".text", 0x1000, 8, bytes(range(0x10, 0x18))),
+        (b".bss", 0x2000, 0x10, b""),
+        (b".data", 0x3000, 4, b"\x99\x88\x77\x66\x55\x44"),
+    ]
+    dos = b"MZ" + bytes(58) + struct.pack("<I", 0x40)
+    coff = struct.pack("<4sHHIIIHH", b"PE\0\0", 0x8664, len(sections), 0, 0, 0, 240, 0x22)
+    optional = struct.pack(
+        "<HBBIIIII", 0x20B, 0, 0, 0x200, 0x200, 0x10, 0x1004, 0x1000
+    ) + struct.pack(
+        "<QIIHHHHHHIIIIHHQQQQII",
+        image_base, 0x1000, 0x200, 6, 0, 0, 0, 6, 0, 0, 0x4000, 0x200, 0, 3, 0,
+        0x100000, 0x1000, 0x100000, 0x1000, 0, 16,
+    ) + bytes(16 * 8)
+    headers, raw = b"", b""
+    for name, va, vsize, body in sections:
+        ptr = 0x200 + len(raw) if body else 0
+        size = (len(body) + 0x1FF) & ~0x1FF
+        headers += struct.pack("<8sIIIIIIHHI", name, vsize, va, size, ptr, 0, 0, 0, 0, 0x60000020)
+        raw += body.ljust(size, b"\0")
+    image = (dos + coff + optional + headers).ljust(0x200, b"\0")
+    return image + raw
+
+
+(here / "tiny.elf").write_bytes(elf())
+(here / "tiny.exe").write_bytes(pe())
@@ -0,0 +1,20 @@
+// Uses the crate from a `#![no_std]` crate. Run with
+// `cargo test --no-default-features --features alloc --test no_std` to also build the
+// library itself without std.
+#![no_std]
+
+extern crate alloc;
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use sparse_vec::SparseVec;
+
+#[test]
+fn sparsevec_no_std() {
+    let mut map = SparseVec::new();
+    map.insert(vec![1u8; 8], 0x100);
+    map.insert(vec![2u8; 8], 0x104);
+    assert_eq!(map.get(0x102..0x106).unwrap(), &[1, 1, 2, 2]);
+    assert_eq!(Vec::from_iter(map.ranges()), vec![0x100..0x10c]);
+}
@@ -0,0 +1,51 @@
+// Counts the bytes allocated while building a `SparseSet`, which must not depend on the
+// length of its ranges. A test binary of its own, as it replaces the global allocator.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use sparse_vec::SparseSet;
+
+struct Counting;
+
+thread_local! {
+    static ALLOCATED: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.with(|allocated| allocated.set(allocated.get() + layout.size()));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocated_by(f: impl FnOnce()) -> usize {
+    let before = ALLOCATED.with(Cell::get);
+    f();
+    ALLOCATED.with(Cell::get) - before
+}
+
+#[test]
+fn sparsevec_sparse_set_allocations() {
+    let build = |len: u64| {
+        allocated_by(|| {
+            let mut set = SparseSet::new();
+            for i in 0..1000 {
+                set.insert_range(i << 42..(i << 42) + len);
+            }
+            set.remove(0..len / 2);
+            assert_eq!(set.ranges().count(), 1000);
+        })
+    };
+    let short = build(2);
+    assert_eq!(build(1 << 40), short);
+    // Just the ranges and the tree nodes holding them
+    assert!(short < 1000 * 64, "{short} bytes for 1000 ranges");
+}